@@ -41,7 +41,7 @@ pub(crate) fn add_explicit_type(mut ctx: AssistCtx<impl HirDatabase>) -> Option<
 }
 
 /// Returns true if any type parameter is unknown
-fn is_unknown(ty: &Ty) -> bool {
+pub(crate) fn is_unknown(ty: &Ty) -> bool {
     match ty {
         Ty::Unknown => true,
         Ty::Apply(a_ty) => a_ty.parameters.iter().any(is_unknown),
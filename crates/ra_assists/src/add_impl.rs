@@ -9,28 +9,11 @@ use crate::{Assist, AssistCtx, AssistId};
 
 pub(crate) fn add_impl(mut ctx: AssistCtx<impl HirDatabase>) -> Option<Assist> {
     let nominal = ctx.node_at_offset::<ast::NominalDef>()?;
-    let name = nominal.name()?;
+    let header = impl_header(nominal, None)?;
     ctx.add_action(AssistId("add_impl"), "add impl", |edit| {
         edit.target(nominal.syntax().range());
-        let type_params = nominal.type_param_list();
         let start_offset = nominal.syntax().range().end();
-        let mut buf = String::new();
-        buf.push_str("\n\nimpl");
-        if let Some(type_params) = type_params {
-            type_params.syntax().text().push_to(&mut buf);
-        }
-        buf.push_str(" ");
-        buf.push_str(name.text().as_str());
-        if let Some(type_params) = type_params {
-            let lifetime_params = type_params
-                .lifetime_params()
-                .filter_map(|it| it.lifetime_token())
-                .map(|it| it.text());
-            let type_params =
-                type_params.type_params().filter_map(|it| it.name()).map(|it| it.text());
-            join(lifetime_params.chain(type_params)).surround_with("<", ">").to_buf(&mut buf);
-        }
-        buf.push_str(" {\n");
+        let mut buf = format!("\n\n{} {{\n", header);
         edit.set_cursor(start_offset + TextUnit::of_str(&buf));
         buf.push_str("\n}");
         edit.insert(start_offset, buf);
@@ -39,6 +22,36 @@ pub(crate) fn add_impl(mut ctx: AssistCtx<impl HirDatabase>) -> Option<Assist> {
     ctx.build()
 }
 
+/// The header of an impl block for the type, like `impl<T> Foo<T>`, or
+/// `impl<T> Trait for Foo<T>` for a trait impl, with the where clause of the
+/// type.
+pub(crate) fn impl_header(nominal: &ast::NominalDef, trait_: Option<&str>) -> Option<String> {
+    let name = nominal.name()?;
+    let type_params = nominal.type_param_list();
+    let mut buf = String::new();
+    buf.push_str("impl");
+    if let Some(type_params) = type_params {
+        type_params.syntax().text().push_to(&mut buf);
+    }
+    buf.push_str(" ");
+    if let Some(trait_) = trait_ {
+        buf.push_str(trait_);
+        buf.push_str(" for ");
+    }
+    buf.push_str(name.text().as_str());
+    if let Some(type_params) = type_params {
+        let lifetime_params =
+            type_params.lifetime_params().filter_map(|it| it.lifetime_token()).map(|it| it.text());
+        let type_params = type_params.type_params().filter_map(|it| it.name()).map(|it| it.text());
+        join(lifetime_params.chain(type_params)).surround_with("<", ">").to_buf(&mut buf);
+    }
+    if let Some(where_clause) = nominal.where_clause() {
+        buf.push_str(" ");
+        where_clause.syntax().text().push_to(&mut buf);
+    }
+    Some(buf)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -57,6 +70,11 @@ mod tests {
             "struct Foo<'a, T: Foo<'a>> {<|>}",
             "struct Foo<'a, T: Foo<'a>> {}\n\nimpl<'a, T: Foo<'a>> Foo<'a, T> {\n<|>\n}",
         );
+        check_assist(
+            add_impl,
+            "struct Foo<T> where T: Clone {<|>}",
+            "struct Foo<T> where T: Clone {}\n\nimpl<T> Foo<T> where T: Clone {\n<|>\n}",
+        );
     }
 
     #[test]
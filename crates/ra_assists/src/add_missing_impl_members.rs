@@ -83,15 +83,7 @@ fn add_missing_impl_members_inner(
             ast_editor.make_multiline();
         }
 
-        for item in missing_items {
-            let it = match item.kind() {
-                ImplItemKind::FnDef(def) => {
-                    strip_docstring(ImplItem::cast(add_body(def).syntax()).unwrap())
-                }
-                _ => strip_docstring(item),
-            };
-            ast_editor.append_item(&it)
-        }
+        append_items(&mut ast_editor, missing_items.into_iter());
 
         let first_new_item = ast_editor.ast().impl_items().nth(n_existing_items).unwrap();
         let cursor_poisition = first_new_item.syntax().range().start();
@@ -103,6 +95,38 @@ fn add_missing_impl_members_inner(
     ctx.build()
 }
 
+/// The text of an impl block with `header`, holding `items` added like the
+/// missing members of an impl.
+pub(crate) fn impl_with_items<'a>(
+    header: &str,
+    items: impl Iterator<Item = &'a ast::ImplItem>,
+) -> String {
+    let impl_block = AstBuilder::<ast::ImplBlock>::from_header(header);
+    let item_list = match impl_block.item_list() {
+        Some(it) => it,
+        None => return format!("{} {{}}", header),
+    };
+    let mut ast_editor = AstEditor::new(item_list);
+    ast_editor.make_multiline();
+    append_items(&mut ast_editor, items);
+    format!("{} {}", header, ast_editor.ast().syntax().text())
+}
+
+fn append_items<'a>(
+    ast_editor: &mut AstEditor<ast::ItemList>,
+    items: impl Iterator<Item = &'a ast::ImplItem>,
+) {
+    for item in items {
+        let it = match item.kind() {
+            ImplItemKind::FnDef(def) => {
+                strip_docstring(ImplItem::cast(add_body(def).syntax()).unwrap())
+            }
+            _ => strip_docstring(item),
+        };
+        ast_editor.append_item(&it)
+    }
+}
+
 fn strip_docstring(item: &ast::ImplItem) -> TreeArc<ast::ImplItem> {
     let mut ast_editor = AstEditor::new(item);
     ast_editor.strip_attrs_and_docs();
@@ -340,5 +364,4 @@ impl Foo for S {
 }",
         )
     }
-
 }
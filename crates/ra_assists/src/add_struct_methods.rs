@@ -0,0 +1,330 @@
+use hir::{db::HirDatabase, source_binder, HirDisplay};
+use itertools::Itertools;
+use ra_db::FileId;
+use ra_fmt::{leading_indent, reindent};
+use ra_syntax::{
+    ast::{self, AstNode, FnDefOwner, NameOwner, TypeAscriptionOwner, VisibilityOwner},
+    SmolStr,
+};
+
+use crate::{
+    add_explicit_type::is_unknown,
+    add_impl::impl_header,
+    assist_ctx::AssistBuilder,
+    ast_editor::{AstBuilder, AstEditor},
+    Assist, AssistCtx, AssistId,
+};
+
+/// Generates a `new` function taking all the fields of the struct.
+pub(crate) fn add_new(mut ctx: AssistCtx<impl HirDatabase>) -> Option<Assist> {
+    let struct_def = ctx.node_at_offset::<ast::StructDef>()?;
+    let fields = match struct_def.kind() {
+        ast::StructKind::Named(field_list) => field_list.fields().collect::<Vec<_>>(),
+        _ => return None,
+    };
+    if has_method(struct_def, "new") {
+        return None;
+    }
+    let mut params = Vec::new();
+    let mut names = Vec::new();
+    for field in fields {
+        let name = field.name()?.text().clone();
+        let ty = field_type(ctx.db, ctx.frange.file_id, struct_def, field)?;
+        params.push(format!("{}: {}", name, ty));
+        names.push(name);
+    }
+    let init = if names.is_empty() {
+        "Self {}".to_string()
+    } else {
+        format!("Self {{ {} }}", names.iter().join(", "))
+    };
+    let method = format!(
+        "{}fn new({}) -> Self {{\n    {}\n}}",
+        visibility(struct_def),
+        params.iter().join(", "),
+        init
+    );
+
+    ctx.add_action(AssistId("add_new"), "add constructor", |edit| {
+        edit.target(struct_def.syntax().range());
+        insert_method(edit, struct_def, &method);
+    });
+
+    ctx.build()
+}
+
+/// Generates a method returning a reference to the field under the cursor.
+pub(crate) fn add_getter(mut ctx: AssistCtx<impl HirDatabase>) -> Option<Assist> {
+    let field = ctx.node_at_offset::<ast::NamedFieldDef>()?;
+    let struct_def = parent_struct(field)?;
+    let name = field.name()?.text().clone();
+    if has_method(struct_def, &name) {
+        return None;
+    }
+    let ty = field_type(ctx.db, ctx.frange.file_id, struct_def, field)?;
+    let method = format!(
+        "{}fn {}(&self) -> &{} {{\n    &self.{}\n}}",
+        visibility(struct_def),
+        name,
+        ty,
+        name
+    );
+
+    ctx.add_action(AssistId("add_getter"), "add getter", |edit| {
+        edit.target(struct_def.syntax().range());
+        insert_method(edit, struct_def, &method);
+    });
+
+    ctx.build()
+}
+
+/// Generates a method replacing the value of the field under the cursor.
+pub(crate) fn add_setter(mut ctx: AssistCtx<impl HirDatabase>) -> Option<Assist> {
+    let field = ctx.node_at_offset::<ast::NamedFieldDef>()?;
+    let struct_def = parent_struct(field)?;
+    let name = field.name()?.text().clone();
+    let method_name = format!("set_{}", name);
+    if has_method(struct_def, &method_name) {
+        return None;
+    }
+    let ty = field_type(ctx.db, ctx.frange.file_id, struct_def, field)?;
+    let method = format!(
+        "{}fn {}(&mut self, {}: {}) {{\n    self.{} = {};\n}}",
+        visibility(struct_def),
+        method_name,
+        name,
+        ty,
+        name,
+        name
+    );
+
+    ctx.add_action(AssistId("add_setter"), "add setter", |edit| {
+        edit.target(struct_def.syntax().range());
+        insert_method(edit, struct_def, &method);
+    });
+
+    ctx.build()
+}
+
+fn parent_struct(field: &ast::NamedFieldDef) -> Option<&ast::StructDef> {
+    field.syntax().parent()?.parent().and_then(ast::StructDef::cast)
+}
+
+/// The generated methods are as visible as the struct.
+fn visibility(struct_def: &ast::StructDef) -> String {
+    match struct_def.visibility() {
+        Some(vis) => format!("{} ", vis.syntax().text()),
+        None => String::new(),
+    }
+}
+
+/// The type of the field, as lowered by hir. The declared type is used
+/// instead when the lowered one is unknown, or when its display would lose
+/// lifetimes or path qualifiers of the declaration.
+fn field_type(
+    db: &impl HirDatabase,
+    file_id: FileId,
+    struct_def: &ast::StructDef,
+    field: &ast::NamedFieldDef,
+) -> Option<String> {
+    let declared = field.ascribed_type()?.syntax().text().to_string();
+    if declared.contains('\'') || declared.contains("::") {
+        return Some(declared);
+    }
+    let module = source_binder::module_from_child_node(db, file_id, struct_def.syntax())?;
+    let hir_struct = source_binder::struct_from_module(db, module, struct_def);
+    let name = field.name()?.text().to_string();
+    let hir_field = hir_struct.fields(db).into_iter().find(|it| it.name(db).to_string() == name)?;
+    let ty = hir_field.ty(db);
+    if is_unknown(&ty) {
+        Some(declared)
+    } else {
+        Some(ty.display(db).to_string())
+    }
+}
+
+/// The inherent impl blocks of the struct, next to its definition.
+fn inherent_impls(struct_def: &ast::StructDef) -> Vec<&ast::ImplBlock> {
+    let name = match struct_def.name() {
+        Some(it) => it.text().clone(),
+        None => return Vec::new(),
+    };
+    let container = match struct_def.syntax().parent() {
+        Some(it) => it,
+        None => return Vec::new(),
+    };
+    container
+        .children()
+        .filter_map(ast::ImplBlock::cast)
+        .filter(|it| it.target_trait().is_none())
+        .filter(|it| it.target_type().map_or(false, |ty| type_name(ty) == name))
+        .collect()
+}
+
+fn type_name(type_ref: &ast::TypeRef) -> SmolStr {
+    let text = type_ref.syntax().text().to_string();
+    text.split('<').next().unwrap_or("").trim().into()
+}
+
+fn has_method(struct_def: &ast::StructDef, name: &str) -> bool {
+    inherent_impls(struct_def)
+        .into_iter()
+        .filter_map(|it| it.item_list())
+        .flat_map(|it| it.functions())
+        .any(|it| it.name().map_or(false, |it| it.text() == name))
+}
+
+/// Appends the method to the first inherent impl block of the struct, or adds
+/// a new impl block after the struct.
+fn insert_method(edit: &mut AssistBuilder, struct_def: &ast::StructDef, method: &str) {
+    let item_list = inherent_impls(struct_def).into_iter().find_map(|it| it.item_list());
+    if let Some(item_list) = item_list {
+        let indent = match item_list.impl_items().last() {
+            Some(it) => leading_indent(it.syntax()).unwrap_or("").to_string(),
+            None => format!("    {}", leading_indent(item_list.syntax()).unwrap_or("")),
+        };
+        let method = AstBuilder::<ast::ImplItem>::from_text(&reindent(method, &indent));
+        let mut ast_editor = AstEditor::new(item_list);
+        if item_list.impl_items().next().is_none() {
+            ast_editor.make_multiline();
+        }
+        ast_editor.append_item(&method);
+        ast_editor.into_text_edit(edit.text_edit_builder());
+        return;
+    }
+
+    let header =
+        match ast::NominalDef::cast(struct_def.syntax()).and_then(|it| impl_header(it, None)) {
+            Some(it) => it,
+            None => return,
+        };
+    let indent = leading_indent(struct_def.syntax()).unwrap_or("");
+    let impl_block = format!("{} {{\n    {}\n}}", header, reindent(method, "    "));
+    let offset = struct_def.syntax().range().end();
+    edit.insert(offset, format!("\n\n{}{}", indent, reindent(&impl_block, indent)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::{check_assist, check_assist_not_applicable, check_assist_target};
+
+    #[test]
+    fn add_new_creates_impl() {
+        check_assist(
+            add_new,
+            "
+pub struct Foo<'a, T> {<|>
+    name: &'a str,
+    value: T,
+    count: u32,
+}",
+            "
+pub struct Foo<'a, T> {<|>
+    name: &'a str,
+    value: T,
+    count: u32,
+}
+
+impl<'a, T> Foo<'a, T> {
+    pub fn new(name: &'a str, value: T, count: u32) -> Self {
+        Self { name, value, count }
+    }
+}",
+        );
+    }
+
+    #[test]
+    fn add_new_appends_to_impl() {
+        check_assist(
+            add_new,
+            "
+struct Foo { <|>x: i32 }
+
+impl Foo {
+    fn x(&self) -> i32 {
+        self.x
+    }
+}",
+            "
+struct Foo { <|>x: i32 }
+
+impl Foo {
+    fn x(&self) -> i32 {
+        self.x
+    }
+    fn new(x: i32) -> Self {
+        Self { x }
+    }
+}",
+        );
+    }
+
+    #[test]
+    fn add_new_not_applicable() {
+        check_assist_not_applicable(add_new, "struct Foo(<|>i32);");
+        check_assist_not_applicable(
+            add_new,
+            "
+struct Foo { <|>x: i32 }
+
+impl Foo {
+    fn new() -> Foo { Foo { x: 0 } }
+}",
+        );
+    }
+
+    #[test]
+    fn add_getter_and_setter() {
+        check_assist(
+            add_getter,
+            "
+mod m {
+    struct Foo { <|>x: i32 }
+
+    impl Foo {}
+}",
+            "
+mod m {
+    struct Foo { <|>x: i32 }
+
+    impl Foo {
+        fn x(&self) -> &i32 {
+            &self.x
+        }
+    }
+}",
+        );
+        check_assist(
+            add_setter,
+            "
+struct Foo { x: i32, <|>y: Vec<i32> }",
+            "
+struct Foo { x: i32, <|>y: Vec<i32> }
+
+impl Foo {
+    fn set_y(&mut self, y: Vec<i32>) {
+        self.y = y;
+    }
+}",
+        );
+        check_assist_target(
+            add_getter,
+            "struct Foo { x: i32, <|>y: u8 }",
+            "struct Foo { x: i32, y: u8 }",
+        );
+    }
+
+    #[test]
+    fn add_getter_not_applicable_when_exists() {
+        check_assist_not_applicable(
+            add_getter,
+            "
+struct Foo { <|>x: i32 }
+
+impl Foo {
+    fn x(&self) -> &i32 { &self.x }
+}",
+        );
+    }
+}
@@ -0,0 +1,339 @@
+use hir::{db::HirDatabase, source_binder};
+use itertools::Itertools;
+use ra_fmt::{leading_indent, reindent};
+use ra_syntax::{
+    ast::{self, AstNode, AttrsOwner, NameOwner, TypeAscriptionOwner},
+    SyntaxKind::IDENT,
+};
+
+use crate::{
+    add_impl::impl_header, add_missing_impl_members::impl_with_items, assist_ctx::AssistBuilder,
+    ast_editor::AstBuilder, Assist, AssistCtx, AssistId,
+};
+
+/// Generates a `From` impl for an enum variant with a single field, wrapping
+/// the converted value into the variant.
+pub(crate) fn add_from_impl(mut ctx: AssistCtx<impl HirDatabase>) -> Option<Assist> {
+    let variant = ctx.node_at_offset::<ast::EnumVariant>()?;
+    let enum_def = variant.parent_enum();
+    let nominal = ast::NominalDef::cast(enum_def.syntax())?;
+    let enum_name = enum_def.name()?.text().clone();
+    let variant_name = variant.name()?.text().clone();
+    let (ty, value) = match variant.kind() {
+        ast::StructKind::Tuple(field_list) => {
+            let (field,) = field_list.fields().collect_tuple()?;
+            (field.type_ref()?, format!("{}::{}(v)", enum_name, variant_name))
+        }
+        ast::StructKind::Named(field_list) => {
+            let (field,) = field_list.fields().collect_tuple()?;
+            let field_name = field.name()?.text().clone();
+            let value = format!("{}::{} {{ {}: v }}", enum_name, variant_name, field_name);
+            (field.ascribed_type()?, value)
+        }
+        ast::StructKind::Unit => return None,
+    };
+    let trait_ = format!("From<{}>", ty.syntax().text());
+    if has_trait_impl(nominal, &trait_) {
+        return None;
+    }
+    let header = impl_header(nominal, Some(&trait_))?;
+    let from = AstBuilder::<ast::ImplItem>::from_text(&format!(
+        "fn from(v: {}) -> Self {{\n        {}\n    }}",
+        ty.syntax().text(),
+        value
+    ));
+    let impl_block = impl_with_items(&header, std::iter::once(&*from));
+
+    ctx.add_action(AssistId("add_from_impl"), "add `From` impl", |edit| {
+        edit.target(variant.syntax().range());
+        insert_impl(edit, nominal, &impl_block);
+    });
+
+    ctx.build()
+}
+
+/// Generates a `Default` impl for a struct, using the default of each field.
+pub(crate) fn add_default_impl(mut ctx: AssistCtx<impl HirDatabase>) -> Option<Assist> {
+    let nominal = ctx.node_at_offset::<ast::NominalDef>()?;
+    let struct_def = match nominal.kind() {
+        ast::NominalDefKind::StructDef(it) => it,
+        ast::NominalDefKind::EnumDef(_) => return None,
+    };
+    if is_derived(nominal, "Default") || has_trait_impl(nominal, "Default") {
+        return None;
+    }
+    let value = match struct_def.kind() {
+        ast::StructKind::Named(field_list) => {
+            let fields = field_list
+                .fields()
+                .filter_map(|it| it.name())
+                .map(|it| format!("{}: Default::default()", it.text()))
+                .collect::<Vec<_>>();
+            if fields.is_empty() {
+                "Self {}".to_string()
+            } else {
+                format!("Self {{ {} }}", fields.join(", "))
+            }
+        }
+        ast::StructKind::Tuple(field_list) => {
+            let fields = field_list.fields().map(|_| "Default::default()").join(", ");
+            format!("Self({})", fields)
+        }
+        ast::StructKind::Unit => "Self".to_string(),
+    };
+    let header = impl_header(nominal, Some("Default"))?;
+    let default = AstBuilder::<ast::ImplItem>::from_text(&format!(
+        "fn default() -> Self {{\n        {}\n    }}",
+        value
+    ));
+    let impl_block = impl_with_items(&header, std::iter::once(&*default));
+
+    ctx.add_action(AssistId("add_default_impl"), "add `Default` impl", |edit| {
+        edit.target(nominal.syntax().range());
+        insert_impl(edit, nominal, &impl_block);
+    });
+
+    ctx.build()
+}
+
+/// Generates a `Display` impl skeleton, matching over the variants of an enum.
+pub(crate) fn add_display_impl(mut ctx: AssistCtx<impl HirDatabase>) -> Option<Assist> {
+    let nominal = ctx.node_at_offset::<ast::NominalDef>()?;
+    if is_derived(nominal, "Display") || has_trait_impl(nominal, "Display") {
+        return None;
+    }
+    let body = match nominal.kind() {
+        ast::NominalDefKind::EnumDef(enum_def) => {
+            let enum_name = enum_def.name()?.text().clone();
+            let mut buf = String::from("match self {");
+            for variant in enum_def.variant_list()?.variants() {
+                let name = match variant.name() {
+                    Some(it) => it.text().clone(),
+                    None => continue,
+                };
+                let fields = match variant.kind() {
+                    ast::StructKind::Tuple(_) => "(..)",
+                    ast::StructKind::Named(_) => " { .. }",
+                    ast::StructKind::Unit => "",
+                };
+                buf.push_str(&format!(
+                    "\n    {}::{}{} => write!(f, \"{}\"),",
+                    enum_name, name, fields, name
+                ));
+            }
+            buf.push_str("\n}");
+            buf
+        }
+        ast::NominalDefKind::StructDef(_) => {
+            AstBuilder::<ast::Expr>::unimplemented().syntax().text().to_string()
+        }
+    };
+    // Uses `fmt::` if the module imports `fmt`
+    let module =
+        source_binder::module_from_child_node(ctx.db, ctx.frange.file_id, nominal.syntax())?;
+    let fmt = if module.scope(ctx.db).entries().any(|(name, _)| name.to_string() == "fmt") {
+        "fmt"
+    } else {
+        "std::fmt"
+    };
+    let header = impl_header(nominal, Some(&format!("{}::Display", fmt)))?;
+    let fmt_fn = AstBuilder::<ast::ImplItem>::from_text(&format!(
+        "fn fmt(&self, f: &mut {}::Formatter) -> {}::Result {{\n        {}\n    }}",
+        fmt,
+        fmt,
+        reindent(&body, "        ")
+    ));
+    let impl_block = impl_with_items(&header, std::iter::once(&*fmt_fn));
+
+    ctx.add_action(AssistId("add_display_impl"), "add `Display` impl", |edit| {
+        edit.target(nominal.syntax().range());
+        insert_impl(edit, nominal, &impl_block);
+    });
+
+    ctx.build()
+}
+
+/// Whether the type derives the trait.
+fn is_derived(nominal: &ast::NominalDef, trait_name: &str) -> bool {
+    nominal
+        .attrs()
+        .filter_map(|it| it.as_call())
+        .filter(|(name, _)| name.as_str() == "derive")
+        .flat_map(|(_, token_tree)| token_tree.syntax().children_with_tokens())
+        .filter_map(|it| it.as_token())
+        .any(|it| it.kind() == IDENT && it.text().as_str() == trait_name)
+}
+
+/// Whether the trait is already implemented for the type, next to its
+/// definition. Paths to the trait are compared by their last segment, and
+/// whitespace is ignored.
+fn has_trait_impl(nominal: &ast::NominalDef, trait_: &str) -> bool {
+    let name = match nominal.name() {
+        Some(it) => it.text().to_string(),
+        None => return false,
+    };
+    let container = match nominal.syntax().parent() {
+        Some(it) => it,
+        None => return false,
+    };
+    let normalize = |text: String| {
+        let text = text.split_whitespace().collect::<String>();
+        let prefix_len = text.split('<').next().unwrap_or("").rfind("::").map_or(0, |it| it + 2);
+        text[prefix_len..].to_string()
+    };
+    let trait_ = normalize(trait_.to_string());
+    container.children().filter_map(ast::ImplBlock::cast).any(|impl_block| {
+        let (target_trait, target_type) =
+            match (impl_block.target_trait(), impl_block.target_type()) {
+                (Some(target_trait), Some(target_type)) => (target_trait, target_type),
+                _ => return false,
+            };
+        let type_name = target_type.syntax().text().to_string();
+        type_name.split('<').next().unwrap_or("").trim() == name
+            && normalize(target_trait.syntax().text().to_string()) == trait_
+    })
+}
+
+fn insert_impl(edit: &mut AssistBuilder, nominal: &ast::NominalDef, impl_block: &str) {
+    let indent = leading_indent(nominal.syntax()).unwrap_or("");
+    let offset = nominal.syntax().range().end();
+    edit.insert(offset, format!("\n\n{}{}", indent, reindent(impl_block, indent)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::{check_assist, check_assist_not_applicable, check_assist_target};
+
+    #[test]
+    fn add_from_impl_for_variant() {
+        check_assist(
+            add_from_impl,
+            "
+enum Error {
+    <|>Io(io::Error),
+    Other { message: String },
+}",
+            "
+enum Error {
+    <|>Io(io::Error),
+    Other { message: String },
+}
+
+impl From<io::Error> for Error {
+    fn from(v: io::Error) -> Self {
+        Error::Io(v)
+    }
+}",
+        );
+        check_assist(
+            add_from_impl,
+            "enum Error<T> { Other { <|>message: T } }",
+            "enum Error<T> { Other { <|>message: T } }
+
+impl<T> From<T> for Error<T> {
+    fn from(v: T) -> Self {
+        Error::Other { message: v }
+    }
+}",
+        );
+        check_assist_target(add_from_impl, "enum E { <|>A(u32), B }", "A(u32)");
+    }
+
+    #[test]
+    fn add_from_impl_not_applicable() {
+        check_assist_not_applicable(add_from_impl, "enum E { A(u32, u32)<|> }");
+        check_assist_not_applicable(add_from_impl, "enum E { <|>A }");
+        check_assist_not_applicable(
+            add_from_impl,
+            "
+enum E { <|>A(u32) }
+impl From<u32> for E {
+    fn from(v: u32) -> E { E::A(v) }
+}",
+        );
+    }
+
+    #[test]
+    fn add_default_impl_for_struct() {
+        check_assist(
+            add_default_impl,
+            "struct Foo { <|>a: u32, b: String }",
+            "struct Foo { <|>a: u32, b: String }
+
+impl Default for Foo {
+    fn default() -> Self {
+        Self { a: Default::default(), b: Default::default() }
+    }
+}",
+        );
+        check_assist(
+            add_default_impl,
+            "
+mod m {
+    struct Foo<|>(u32, u8);
+}",
+            "
+mod m {
+    struct Foo<|>(u32, u8);
+
+    impl Default for Foo {
+        fn default() -> Self {
+            Self(Default::default(), Default::default())
+        }
+    }
+}",
+        );
+        check_assist_not_applicable(add_default_impl, "#[derive(Debug, Default)] struct Foo<|>;");
+    }
+
+    #[test]
+    fn add_default_impl_keeps_generics_and_where_clause() {
+        check_assist(
+            add_default_impl,
+            "struct Foo<'a, T> where T: Default { <|>a: &'a str, t: T }",
+            "struct Foo<'a, T> where T: Default { <|>a: &'a str, t: T }
+
+impl<'a, T> Default for Foo<'a, T> where T: Default {
+    fn default() -> Self {
+        Self { a: Default::default(), t: Default::default() }
+    }
+}",
+        );
+    }
+
+    #[test]
+    fn add_display_impl_for_enum() {
+        check_assist(
+            add_display_impl,
+            "
+enum E {<|>
+    A,
+    B(u32),
+    C { x: u32 },
+}",
+            r#"
+enum E {<|>
+    A,
+    B(u32),
+    C { x: u32 },
+}
+
+impl std::fmt::Display for E {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            E::A => write!(f, "A"),
+            E::B(..) => write!(f, "B"),
+            E::C { .. } => write!(f, "C"),
+        }
+    }
+}"#,
+        );
+        check_assist_not_applicable(
+            add_display_impl,
+            "
+struct S<|>;
+impl fmt::Display for S {}",
+        );
+    }
+}
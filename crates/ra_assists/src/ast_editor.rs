@@ -271,6 +271,18 @@ impl AstBuilder<ast::Expr> {
     }
 }
 
+impl AstBuilder<ast::ImplItem> {
+    pub fn from_text(text: &str) -> TreeArc<ast::ImplItem> {
+        ast_node_from_file_text(&format!("impl S {{ {} }}", text))
+    }
+}
+
+impl AstBuilder<ast::ImplBlock> {
+    pub fn from_header(header: &str) -> TreeArc<ast::ImplBlock> {
+        ast_node_from_file_text(&format!("{} {{}}", header))
+    }
+}
+
 impl AstBuilder<ast::NameRef> {
     pub fn new(text: &str) -> TreeArc<ast::NameRef> {
         ast_node_from_file_text(&format!("fn f() {{ {}; }}", text))
@@ -327,5 +339,4 @@ mod tokens {
             self.0.syntax().first_child_or_token().unwrap().as_token().unwrap()
         }
     }
-
 }
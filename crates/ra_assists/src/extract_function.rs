@@ -0,0 +1,888 @@
+use hir::{
+    db::HirDatabase, AdtDef, Either, HasSource, HirDisplay, Mutability, SourceAnalyzer, Ty,
+    TypeCtor,
+};
+use ra_fmt::leading_indent;
+use ra_syntax::{
+    ast::{self, AstNode, AttrsOwner, NameOwner},
+    SmolStr, SyntaxElement,
+    SyntaxKind::*,
+    SyntaxNode, TextRange, TextUnit, T,
+};
+
+use crate::{add_explicit_type::is_unknown, Assist, AssistCtx, AssistId};
+
+/// Extracts the selected statements or expression of a function body into a
+/// new function. The locals the selection uses become parameters, and the
+/// locals it defines which are used afterwards are returned. Locals which would
+/// be moved into the new function but are used afterwards are borrowed.
+pub(crate) fn extract_function(mut ctx: AssistCtx<impl HirDatabase>) -> Option<Assist> {
+    if ctx.frange.range.is_empty() {
+        return None;
+    }
+    let range = trimmed_range(&ctx)?;
+    let selection = selection(element_node(ctx.covering_node_for_range(range)), range)?;
+    let fn_def = selection.nodes[0].ancestors().find_map(ast::FnDef::cast)?;
+    let in_closure = selection.nodes[0]
+        .ancestors()
+        .find(|it| it.kind() == FN_DEF || it.kind() == LAMBDA_EXPR)?
+        .kind()
+        == LAMBDA_EXPR;
+    let reaches_end = !in_closure
+        && selection.block.is_some()
+        && selection.block == fn_def.body().and_then(|it| it.block())
+        && selection.is_block_end;
+
+    let db = ctx.db;
+    let analyzer = SourceAnalyzer::new(db, ctx.frange.file_id, selection.nodes[0], None);
+    let root = fn_def.syntax().ancestors().last()?;
+
+    let mut params: Vec<Param> = Vec::new();
+    let mut self_uses = Vec::new();
+    for path_expr in selection.descendants().filter_map(ast::PathExpr::cast) {
+        let path = match path_expr.path() {
+            Some(path) if path.qualifier().is_none() => path,
+            _ => continue,
+        };
+        let name_ref = match path.segment().and_then(|it| it.kind()) {
+            Some(ast::PathSegmentKind::Name(name_ref)) => name_ref,
+            Some(ast::PathSegmentKind::SelfKw) => {
+                self_uses.push(path_expr.syntax());
+                continue;
+            }
+            _ => continue,
+        };
+        let pat_ptr = match analyzer.resolve_local_name(name_ref).map(|it| it.ptr()) {
+            Some(Either::A(ptr)) => ptr,
+            _ => continue,
+        };
+        // Locals defined inside of the selection stay locals
+        if pat_ptr.syntax_node_ptr().range().is_subrange(&selection.range) {
+            continue;
+        }
+        match params.iter_mut().find(|it| it.pat == pat_ptr.syntax_node_ptr().range()) {
+            Some(param) => param.uses.push(path_expr.syntax()),
+            None => {
+                let pat = pat_ptr.to_node(root);
+                let used_after = ast::BindPat::cast(pat.syntax()).map_or(false, |it| {
+                    analyzer
+                        .find_all_refs(it)
+                        .iter()
+                        .any(|it| it.range.start() >= selection.range.end())
+                });
+                params.push(Param {
+                    name: name_ref.text().clone(),
+                    pat: pat.syntax().range(),
+                    ty: analyzer.type_of_pat(db, pat),
+                    uses: vec![path_expr.syntax()],
+                    used_after,
+                    pass: Pass::Value,
+                })
+            }
+        }
+    }
+    for param in params.iter_mut() {
+        let is_ref = param.ty.as_ref().map_or(false, |it| it.as_reference().is_some());
+        param.pass = if is_ref {
+            Pass::Value
+        } else if param.uses.iter().any(|it| is_mutated(db, &analyzer, it)) {
+            Pass::MutRef
+        } else if param.used_after && !param.ty.as_ref().map_or(true, |it| is_copy(db, it)) {
+            Pass::Ref
+        } else {
+            Pass::Value
+        };
+    }
+    let self_param = if self_uses.is_empty() {
+        None
+    } else {
+        // Only methods can pass `self` along
+        fn_def.param_list().and_then(|it| it.self_param())?;
+        if self_uses.iter().any(|it| is_mutated(db, &analyzer, it)) {
+            Some("&mut self")
+        } else {
+            Some("&self")
+        }
+    };
+
+    let outputs: Vec<&ast::BindPat> = selection
+        .descendants()
+        .filter_map(ast::BindPat::cast)
+        .filter(|pat| {
+            analyzer.find_all_refs(pat).iter().any(|it| it.range.start() >= selection.range.end())
+        })
+        .collect();
+    if !outputs.is_empty() && selection.tail.is_some() {
+        return None;
+    }
+
+    let mut has_return = false;
+    let mut has_try = false;
+    for node in selection.descendants() {
+        match node.kind() {
+            RETURN_EXPR | TRY_EXPR if !is_nested(node, selection.range, is_fn_like) => {
+                if node.kind() == RETURN_EXPR {
+                    has_return = true;
+                } else {
+                    has_try = true;
+                }
+            }
+            BREAK_EXPR | CONTINUE_EXPR if !jumps_inside(node, selection.range) => return None,
+            _ => (),
+        }
+    }
+    let flow = if has_return || has_try {
+        if reaches_end {
+            let ret_type = fn_def.ret_type().and_then(|it| it.type_ref());
+            Flow::Exit(ret_type.map(|it| it.syntax().text().to_string()))
+        } else if has_return || in_closure {
+            return None;
+        } else {
+            Flow::Try(TryKind::of_fn(fn_def)?)
+        }
+    } else {
+        Flow::Plain
+    };
+
+    let ret_value = match selection.tail {
+        Some(tail) => {
+            let ty = analyzer.type_of(db, tail);
+            if ty == Some(Ty::unit()) {
+                None
+            } else {
+                Some(type_text(db, ty))
+            }
+        }
+        None => match outputs.as_slice() {
+            [] => None,
+            [output] => Some(type_text(db, analyzer.type_of_pat(db, (*output).into()))),
+            outputs => {
+                let types = outputs
+                    .iter()
+                    .map(|it| type_text(db, analyzer.type_of_pat(db, (*it).into())))
+                    .collect::<Vec<_>>();
+                Some(format!("({})", types.join(", ")))
+            }
+        },
+    };
+    let ret_type = match &flow {
+        Flow::Plain => ret_value,
+        Flow::Exit(ret_type) => ret_type.clone(),
+        Flow::Try(kind) => Some(kind.ret_type(ret_value.as_ref().map_or("()", String::as_str))),
+    };
+
+    let anchor = if self_param.is_some() {
+        fn_def.syntax()
+    } else {
+        match fn_def.syntax().parent().and_then(|it| it.parent()) {
+            Some(container) if container.kind() == IMPL_BLOCK || container.kind() == TRAIT_DEF => {
+                container
+            }
+            _ => fn_def.syntax(),
+        }
+    };
+    let indent = leading_indent(anchor).unwrap_or("");
+
+    let output_names = outputs
+        .iter()
+        .map(|it| it.name().map(|it| it.text().to_string()).unwrap_or_default())
+        .collect::<Vec<_>>();
+
+    ctx.add_action(AssistId("extract_function"), "extract into function", |edit| {
+        // The call replacing the selection
+        let mut call = String::new();
+        let bindings = outputs
+            .iter()
+            .zip(output_names.iter())
+            .map(
+                |(pat, name)| if pat.is_mutable() { format!("mut {}", name) } else { name.clone() },
+            )
+            .collect::<Vec<_>>();
+        match bindings.as_slice() {
+            [] => (),
+            [binding] => call.push_str(&format!("let {} = ", binding)),
+            bindings => call.push_str(&format!("let ({}) = ", bindings.join(", "))),
+        }
+        if self_param.is_some() {
+            call.push_str("self.");
+        }
+        let cursor_offset = TextUnit::of_str(&call);
+        let args =
+            params.iter().map(|it| format!("{}{}", it.pass.prefix(), it.name)).collect::<Vec<_>>();
+        call.push_str(&format!("fun_name({})", args.join(", ")));
+        if let Flow::Try(_) = flow {
+            call.push_str("?");
+        }
+        let is_exit = if let Flow::Exit(_) = flow { true } else { false };
+        if selection.tail.is_none() && !is_exit {
+            call.push_str(";");
+        }
+
+        // The new function
+        let mut rewrites = Vec::new();
+        if let (Flow::Try(kind), Some(tail)) = (&flow, selection.tail) {
+            let range = tail.syntax().range();
+            rewrites.push((
+                TextRange::offset_len(range.start(), 0.into()),
+                format!("{}(", kind.wrapper()),
+            ));
+            rewrites.push((TextRange::offset_len(range.end(), 0.into()), ")".to_string()));
+        }
+        for param in params.iter().filter(|it| it.pass != Pass::Value) {
+            for &use_ in param.uses.iter().filter(|it| !is_auto_derefed(it)) {
+                let start = use_.range().start();
+                match use_.parent().and_then(ast::RefExpr::cast) {
+                    // `&local` is the reference passed in
+                    Some(ref_expr) if param.pass == Pass::Ref && !ref_expr.is_mut() => {
+                        let range = TextRange::from_to(ref_expr.syntax().range().start(), start);
+                        rewrites.push((range, String::new()));
+                    }
+                    _ => rewrites.push((TextRange::offset_len(start, 0.into()), "*".to_string())),
+                }
+            }
+        }
+        rewrites.sort_by_key(|&(range, _)| range.start());
+        let mut body = String::new();
+        let mut last = selection.range.start();
+        for (range, text) in rewrites {
+            body.push_str(&root.text().slice(last..range.start()).to_string());
+            body.push_str(&text);
+            last = range.end();
+        }
+        body.push_str(&root.text().slice(last..selection.range.end()).to_string());
+
+        let body_indent = format!("{}    ", indent);
+        let old_indent =
+            selection.nodes[0].ancestors().find_map(leading_indent).unwrap_or_default();
+        let mut body = reindent(&body, old_indent, &body_indent);
+        if selection.tail.is_none() && !is_exit {
+            let value = match output_names.as_slice() {
+                [] => None,
+                [name] => Some(name.clone()),
+                names => Some(format!("({})", names.join(", "))),
+            };
+            let value = match &flow {
+                Flow::Try(kind) => Some(format!(
+                    "{}({})",
+                    kind.wrapper(),
+                    value.as_ref().map_or("()", String::as_str)
+                )),
+                _ => value,
+            };
+            if let Some(value) = value {
+                body.push_str(&format!("\n{}{}", body_indent, value));
+            }
+        }
+
+        let mut all_params = Vec::new();
+        all_params.extend(self_param.map(String::from));
+        all_params.extend(params.iter().map(|it| {
+            format!("{}: {}{}", it.name, it.pass.prefix(), type_text(db, it.ty.clone()))
+        }));
+        let ret = ret_type.as_ref().map(|it| format!(" -> {}", it)).unwrap_or_default();
+        let function = format!(
+            "\n\n{indent}fn fun_name({params}){ret} {{\n{body}\n{indent}}}",
+            indent = indent,
+            params = all_params.join(", "),
+            ret = ret,
+            body = body,
+        );
+
+        edit.target(selection.range);
+        edit.replace(selection.range, call);
+        edit.insert(anchor.range().end(), function);
+        edit.set_cursor(selection.range.start() + cursor_offset);
+    });
+
+    ctx.build()
+}
+
+/// The selected code: whole statements of a block, possibly followed by its
+/// tail expression, or a single expression.
+struct Selection<'a> {
+    nodes: Vec<&'a SyntaxNode>,
+    /// The expression giving the value of the selected code.
+    tail: Option<&'a ast::Expr>,
+    block: Option<&'a ast::Block>,
+    /// Whether the selection runs to the end of `block`.
+    is_block_end: bool,
+    range: TextRange,
+}
+
+impl<'a> Selection<'a> {
+    fn descendants(&self) -> impl Iterator<Item = &'a SyntaxNode> + '_ {
+        self.nodes.iter().flat_map(|it| it.descendants())
+    }
+}
+
+/// A local of the enclosing function used by the selection.
+struct Param<'a> {
+    name: SmolStr,
+    /// The range of the pattern defining the local.
+    pat: TextRange,
+    ty: Option<Ty>,
+    uses: Vec<&'a SyntaxNode>,
+    /// Whether the local is used after the selection.
+    used_after: bool,
+    pass: Pass,
+}
+
+/// How a local is passed to the new function.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Pass {
+    Value,
+    /// The local is used afterwards and is not `Copy`, so it is passed as `&`.
+    Ref,
+    /// The selection mutates the local, so it is passed as `&mut`.
+    MutRef,
+}
+
+impl Pass {
+    fn prefix(self) -> &'static str {
+        match self {
+            Pass::Value => "",
+            Pass::Ref => "&",
+            Pass::MutRef => "&mut ",
+        }
+    }
+}
+
+/// How the control flows out of the selected code.
+enum Flow {
+    Plain,
+    /// The code exits from the enclosing function with `return` or `?`, and
+    /// runs to its end: the new function returns the same type.
+    Exit(Option<String>),
+    /// The code exits from the enclosing function with `?`, so the value of
+    /// the new function is wrapped and unwrapped again with `?`.
+    Try(TryKind),
+}
+
+enum TryKind {
+    Option,
+    Result { err: String },
+}
+
+impl TryKind {
+    fn of_fn(fn_def: &ast::FnDef) -> Option<TryKind> {
+        let type_ref = fn_def.ret_type()?.type_ref()?;
+        let segment = match type_ref.kind() {
+            ast::TypeRefKind::PathType(it) => it.path()?.segment()?,
+            _ => return None,
+        };
+        let args = segment.type_arg_list()?.type_args().collect::<Vec<_>>();
+        match (segment.name_ref()?.text().as_str(), args.as_slice()) {
+            ("Option", [_]) => Some(TryKind::Option),
+            ("Result", [_, err]) => Some(TryKind::Result { err: err.syntax().text().to_string() }),
+            _ => None,
+        }
+    }
+
+    fn ret_type(&self, ty: &str) -> String {
+        match self {
+            TryKind::Option => format!("Option<{}>", ty),
+            TryKind::Result { err } => format!("Result<{}, {}>", ty, err),
+        }
+    }
+
+    fn wrapper(&self) -> &'static str {
+        match self {
+            TryKind::Option => "Some",
+            TryKind::Result { .. } => "Ok",
+        }
+    }
+}
+
+/// The selected range without surrounding whitespace.
+fn trimmed_range(ctx: &AssistCtx<impl HirDatabase>) -> Option<TextRange> {
+    let range = ctx.frange.range;
+    let text = element_node(ctx.covering_element()).text().slice(range).to_string();
+    let start = range.start() + TextUnit::of_str(&text[..text.len() - text.trim_start().len()]);
+    let end = range.end() - TextUnit::of_str(&text[text.trim_end().len()..]);
+    if start < end {
+        Some(TextRange::from_to(start, end))
+    } else {
+        None
+    }
+}
+
+fn element_node<'a>(element: SyntaxElement<'a>) -> &'a SyntaxNode {
+    match element {
+        SyntaxElement::Node(node) => node,
+        SyntaxElement::Token(token) => token.parent(),
+    }
+}
+
+fn selection(node: &SyntaxNode, range: TextRange) -> Option<Selection> {
+    if let Some(block) = ast::Block::cast(node) {
+        return block_selection(block, range);
+    }
+    if node.range() != range {
+        return None;
+    }
+    // A statement has the same range as its expression when there is no `;`
+    let node =
+        node.ancestors().take_while(|it| it.range() == range && it.kind() != BLOCK).last()?;
+    if let Some(block) = node.parent().and_then(ast::Block::cast) {
+        return block_selection(block, range);
+    }
+    let expr = ast::Expr::cast(node)?;
+    // Extracting a plain local or literal is pointless
+    if node.kind() == PATH_EXPR || node.kind() == LITERAL || is_assignee(node) {
+        return None;
+    }
+    Some(Selection { nodes: vec![node], tail: Some(expr), block: None, is_block_end: false, range })
+}
+
+/// Selects the statements of `block` covered by `range`, which must not cut
+/// through any of them.
+fn block_selection(block: &ast::Block, range: TextRange) -> Option<Selection> {
+    let l_curly = block.syntax().first_child_or_token()?;
+    let r_curly = block.syntax().last_child_or_token()?;
+    if l_curly.kind() != T!['{'] || r_curly.kind() != T!['}'] {
+        return None;
+    }
+    if range.start() < l_curly.range().end() || r_curly.range().start() < range.end() {
+        return None;
+    }
+    let tail = block.expr();
+    let children = block
+        .statements()
+        .map(AstNode::syntax)
+        .chain(tail.map(AstNode::syntax))
+        .collect::<Vec<_>>();
+    let mut nodes = Vec::new();
+    for &child in children.iter() {
+        if child.range().is_subrange(&range) {
+            nodes.push(child);
+        } else if child.range().start() < range.end() && range.start() < child.range().end() {
+            return None;
+        }
+    }
+    let first = *nodes.first()?;
+    let last = *nodes.last()?;
+    if first.range().start() != range.start() || last.range().end() != range.end() {
+        return None;
+    }
+    Some(Selection {
+        nodes,
+        tail: tail.filter(|it| it.syntax() == last),
+        block: Some(block),
+        is_block_end: children.last() == Some(&last),
+        range,
+    })
+}
+
+fn is_assignee(expr: &SyntaxNode) -> bool {
+    match expr.parent().and_then(ast::BinExpr::cast) {
+        Some(bin_expr) => {
+            is_assignment(bin_expr) && bin_expr.lhs().map(AstNode::syntax) == Some(expr)
+        }
+        None => false,
+    }
+}
+
+fn is_assignment(bin_expr: &ast::BinExpr) -> bool {
+    use ast::BinOp::*;
+    match bin_expr.op_kind() {
+        Some(Assignment) | Some(AddAssign) | Some(DivAssign) | Some(MulAssign)
+        | Some(RemAssign) | Some(ShrAssign) | Some(ShlAssign) | Some(SubAssign)
+        | Some(BitOrAssign) | Some(BitAndAssign) | Some(BitXorAssign) => true,
+        _ => false,
+    }
+}
+
+/// Whether the local or `self` used by `path_expr` is assigned to, borrowed
+/// mutably or receives a `&mut self` method call, directly or through one of
+/// its fields or elements.
+fn is_mutated(db: &impl HirDatabase, analyzer: &SourceAnalyzer, path_expr: &SyntaxNode) -> bool {
+    let mut expr = path_expr;
+    while let Some(parent) = expr.parent() {
+        let is_base = match parent.kind() {
+            FIELD_EXPR | INDEX_EXPR => parent.first_child() == Some(expr),
+            _ => false,
+        };
+        if !is_base {
+            break;
+        }
+        expr = parent;
+    }
+    let parent = match expr.parent() {
+        Some(it) => it,
+        None => return false,
+    };
+    if is_assignee(expr) {
+        return true;
+    }
+    if let Some(ref_expr) = ast::RefExpr::cast(parent) {
+        return ref_expr.is_mut();
+    }
+    if let Some(call) = ast::MethodCallExpr::cast(parent) {
+        if call.expr().map(AstNode::syntax) != Some(expr) {
+            return false;
+        }
+        let function = match analyzer.resolve_method_call(call) {
+            Some(it) => it,
+            None => return false,
+        };
+        let self_param = function.source(db).ast.param_list().and_then(|it| it.self_param());
+        return self_param.map_or(false, |it| it.kind() == ast::SelfParamKind::MutRef);
+    }
+    false
+}
+
+/// Field accesses, indexing and method calls dereference `&mut` parameters
+/// by themselves.
+fn is_auto_derefed(path_expr: &SyntaxNode) -> bool {
+    match path_expr.parent() {
+        Some(parent) => match parent.kind() {
+            FIELD_EXPR | INDEX_EXPR | METHOD_CALL_EXPR => parent.first_child() == Some(path_expr),
+            _ => false,
+        },
+        None => false,
+    }
+}
+
+fn is_fn_like(node: &SyntaxNode) -> bool {
+    node.kind() == FN_DEF || node.kind() == LAMBDA_EXPR
+}
+
+/// Whether one of the ancestors of `node` inside of `range` matches.
+fn is_nested(node: &SyntaxNode, range: TextRange, f: impl Fn(&SyntaxNode) -> bool) -> bool {
+    node.ancestors().skip(1).take_while(|it| it.range().is_subrange(&range)).any(f)
+}
+
+/// Whether the loop a `break` or `continue` jumps to is inside of `range`.
+fn jumps_inside(jump: &SyntaxNode, range: TextRange) -> bool {
+    let label = lifetime_text(jump);
+    is_nested(jump, range, |node| match node.kind() {
+        LOOP_EXPR | WHILE_EXPR | FOR_EXPR => {
+            label.is_none()
+                || node.children().find(|it| it.kind() == LABEL).and_then(lifetime_text) == label
+        }
+        _ => false,
+    })
+}
+
+fn lifetime_text(node: &SyntaxNode) -> Option<SmolStr> {
+    let lifetime = node.children_with_tokens().find(|it| it.kind() == LIFETIME)?;
+    Some(lifetime.as_token()?.text().clone())
+}
+
+/// Whether values of `ty` are copied rather than moved. Unknown types are
+/// assumed to be, so that their locals are passed as they are written.
+fn is_copy(db: &impl HirDatabase, ty: &Ty) -> bool {
+    let a_ty = match ty {
+        Ty::Apply(it) => it,
+        Ty::Unknown => return true,
+        _ => return false,
+    };
+    match a_ty.ctor {
+        TypeCtor::Bool
+        | TypeCtor::Char
+        | TypeCtor::Int(_)
+        | TypeCtor::Float(_)
+        | TypeCtor::RawPtr(_)
+        | TypeCtor::Ref(Mutability::Shared)
+        | TypeCtor::FnDef(_)
+        | TypeCtor::FnPtr { .. }
+        | TypeCtor::Never => true,
+        TypeCtor::Tuple { .. } | TypeCtor::Array { .. } => {
+            a_ty.parameters.iter().all(|it| is_copy(db, it))
+        }
+        TypeCtor::Adt(adt) => derives_copy(db, adt),
+        TypeCtor::Ref(Mutability::Mut) | TypeCtor::Str | TypeCtor::Slice => false,
+    }
+}
+
+fn derives_copy(db: &impl HirDatabase, adt: AdtDef) -> bool {
+    fn has_copy_derive(node: &impl AttrsOwner) -> bool {
+        node.attrs().filter_map(|it| it.as_call()).any(|(name, args)| {
+            name == "derive"
+                && args.syntax().children_with_tokens().any(|it| {
+                    it.as_token().map_or(false, |it| it.kind() == IDENT && it.text() == "Copy")
+                })
+        })
+    }
+
+    match adt {
+        AdtDef::Struct(it) => has_copy_derive(&*it.source(db).ast),
+        AdtDef::Union(it) => has_copy_derive(&*it.source(db).ast),
+        AdtDef::Enum(it) => has_copy_derive(&*it.source(db).ast),
+    }
+}
+
+fn type_text(db: &impl HirDatabase, ty: Option<Ty>) -> String {
+    match ty {
+        Some(ty) if !is_unknown(&ty) => ty.display(db).to_string(),
+        _ => "_".to_string(),
+    }
+}
+
+/// Moves the lines of `text` from the `old` indentation to the `new` one.
+pub(crate) fn reindent(text: &str, old: &str, new: &str) -> String {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let line = if i == 0 {
+                line
+            } else if line.starts_with(old) {
+                &line[old.len()..]
+            } else {
+                line.trim_start()
+            };
+            if line.is_empty() {
+                String::new()
+            } else {
+                format!("{}{}", new, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::helpers::{
+        check_assist_range, check_assist_range_not_applicable, check_assist_range_target,
+    };
+
+    use super::*;
+
+    #[test]
+    fn extract_function_with_params_and_output() {
+        check_assist_range(
+            extract_function,
+            "
+fn foo() {
+    let n = 1;
+    <|>let m = n + 1;
+    let k = m * 2;<|>
+    bar(k);
+}",
+            "
+fn foo() {
+    let n = 1;
+    let k = <|>fun_name(n);
+    bar(k);
+}
+
+fn fun_name(n: i32) -> i32 {
+    let m = n + 1;
+    let k = m * 2;
+    k
+}",
+        );
+    }
+
+    #[test]
+    fn extract_function_from_expression() {
+        check_assist_range(
+            extract_function,
+            "
+fn foo(n: i32) {
+    let m = <|>n * 2<|>;
+}",
+            "
+fn foo(n: i32) {
+    let m = <|>fun_name(n);
+}
+
+fn fun_name(n: i32) -> i32 {
+    n * 2
+}",
+        );
+    }
+
+    #[test]
+    fn extract_function_passes_mutated_locals_by_reference() {
+        check_assist_range(
+            extract_function,
+            "
+fn foo() {
+    let mut n = 1;
+    <|>n += 1;<|>
+    bar(n);
+}",
+            "
+fn foo() {
+    let mut n = 1;
+    <|>fun_name(&mut n);
+    bar(n);
+}
+
+fn fun_name(n: &mut i32) {
+    *n += 1;
+}",
+        );
+    }
+
+    #[test]
+    fn extract_function_borrows_locals_used_afterwards() {
+        check_assist_range(
+            extract_function,
+            "
+struct Data { n: i32 }
+
+fn foo() {
+    let d = Data { n: 1 };
+    <|>bar(&d, d.n);<|>
+    baz(d);
+}",
+            "
+struct Data { n: i32 }
+
+fn foo() {
+    let d = Data { n: 1 };
+    <|>fun_name(&d);
+    baz(d);
+}
+
+fn fun_name(d: &Data) {
+    bar(d, d.n);
+}",
+        );
+    }
+
+    #[test]
+    fn extract_function_passes_copy_locals_by_value() {
+        check_assist_range(
+            extract_function,
+            "
+#[derive(Clone, Copy)]
+struct Data { n: i32 }
+
+fn foo() {
+    let d = Data { n: 1 };
+    <|>bar(d);<|>
+    baz(d);
+}",
+            "
+#[derive(Clone, Copy)]
+struct Data { n: i32 }
+
+fn foo() {
+    let d = Data { n: 1 };
+    <|>fun_name(d);
+    baz(d);
+}
+
+fn fun_name(d: Data) {
+    bar(d);
+}",
+        );
+    }
+
+    #[test]
+    fn extract_function_using_self() {
+        check_assist_range(
+            extract_function,
+            "
+struct S { f: i32 }
+
+impl S {
+    fn foo(&self) -> i32 {
+        <|>self.f + 1<|>
+    }
+}",
+            "
+struct S { f: i32 }
+
+impl S {
+    fn foo(&self) -> i32 {
+        self.<|>fun_name()
+    }
+
+    fn fun_name(&self) -> i32 {
+        self.f + 1
+    }
+}",
+        );
+    }
+
+    #[test]
+    fn extract_function_with_try() {
+        check_assist_range(
+            extract_function,
+            "
+fn foo() -> Option<i32> {
+    let n = 1;
+    <|>let k: i32 = bar(n)?;<|>
+    Some(k)
+}
+fn bar(n: i32) -> Option<i32> { Some(n) }",
+            "
+fn foo() -> Option<i32> {
+    let n = 1;
+    let k = <|>fun_name(n)?;
+    Some(k)
+}
+
+fn fun_name(n: i32) -> Option<i32> {
+    let k: i32 = bar(n)?;
+    Some(k)
+}
+fn bar(n: i32) -> Option<i32> { Some(n) }",
+        );
+    }
+
+    #[test]
+    fn extract_function_with_return_at_the_end() {
+        check_assist_range(
+            extract_function,
+            "
+fn foo(n: i32) -> i32 {
+    <|>if n > 0 {
+        return 1;
+    }
+    n<|>
+}",
+            "
+fn foo(n: i32) -> i32 {
+    <|>fun_name(n)
+}
+
+fn fun_name(n: i32) -> i32 {
+    if n > 0 {
+        return 1;
+    }
+    n
+}",
+        );
+    }
+
+    #[test]
+    fn extract_function_not_applicable_to_early_return_or_outer_break() {
+        check_assist_range_not_applicable(
+            extract_function,
+            "
+fn foo(n: i32) -> i32 {
+    <|>if n > 0 {
+        return 1;
+    }<|>
+    n
+}",
+        );
+        check_assist_range_not_applicable(
+            extract_function,
+            "
+fn foo() {
+    loop {
+        <|>break;<|>
+    }
+}",
+        );
+    }
+
+    #[test]
+    fn extract_function_target() {
+        check_assist_range_target(
+            extract_function,
+            "
+fn foo() {
+    let n = <|>bar(1) <|>;
+}",
+            "bar(1)",
+        );
+    }
+}
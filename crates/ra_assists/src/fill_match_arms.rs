@@ -1,8 +1,14 @@
 use itertools::Itertools;
-use std::fmt::Write;
 
-use hir::{db::HirDatabase, AdtDef, FieldSource, HasSource};
-use ra_syntax::ast::{self, AstNode};
+use hir::db::HirDatabase;
+use ra_fmt::leading_indent;
+use ra_syntax::{
+    ast::{self, AstNode},
+    Direction, SyntaxElement,
+    SyntaxKind::{BLOCK_EXPR, WHITESPACE},
+    TextRange, T,
+};
+use ra_text_edit::{TextEdit, TextEditBuilder};
 
 use crate::{Assist, AssistCtx, AssistId};
 
@@ -12,89 +18,126 @@ fn is_trivial_arm(arm: &ast::MatchArm) -> bool {
         Some(pat.kind())
     }
     match single_pattern(arm) {
-        Some(ast::PatKind::PlaceholderPat(..)) => true,
+        Some(ast::PatKind::PlaceholderPat(..)) => arm.guard().is_none(),
         _ => false,
     }
 }
 
 pub(crate) fn fill_match_arms(mut ctx: AssistCtx<impl HirDatabase>) -> Option<Assist> {
     let match_expr = ctx.node_at_offset::<ast::MatchExpr>()?;
+    let expr = match_expr.expr()?;
+    let arm_list = match_expr.match_arm_list();
+
+    // A trivial match arm, like the one created by the match postfix
+    // completion, is replaced by the missing arms.
+    let arms = arm_list.iter().flat_map(|it| it.arms()).filter(|it| !is_trivial_arm(it));
+    let arms = arms.collect::<Vec<_>>();
+    let analyzer = hir::SourceAnalyzer::new(ctx.db, ctx.frange.file_id, match_expr.syntax(), None);
+    let patterns = analyzer.missing_match_arms(ctx.db, match_expr, &arms)?;
+    if patterns.is_empty() {
+        return None;
+    }
+    let indent = leading_indent(match_expr.syntax()).unwrap_or("");
 
-    // We already have some match arms, so we don't provide any assists.
-    // Unless if there is only one trivial match arm possibly created
-    // by match postfix complete. Trivial match arm is the catch all arm.
-    if let Some(arm_list) = match_expr.match_arm_list() {
-        let mut arm_iter = arm_list.arms();
-        let first = arm_iter.next();
-
-        match first {
-            // If there arm list is empty or there is only one trivial arm, then proceed.
-            Some(arm) if is_trivial_arm(arm) => {
-                if arm_iter.next() != None {
-                    return None;
+    ctx.add_action(AssistId("fill_match_arms"), "fill match arms", |edit| {
+        match arm_list {
+            Some(arm_list) => {
+                for (range, text) in arms_edits(indent, arm_list, &patterns) {
+                    edit.replace(range, text);
                 }
             }
-            None => {}
-
-            _ => {
-                return None;
+            None => {
+                let arms = patterns.iter().map(|it| format!("\n{}    {} => (),", indent, it));
+                let text = format!(" {{{}\n{}}}", arms.collect::<String>(), indent);
+                edit.insert(expr.syntax().range().end(), text);
             }
         }
-    };
-
-    let expr = match_expr.expr()?;
-    let analyzer = hir::SourceAnalyzer::new(ctx.db, ctx.frange.file_id, expr.syntax(), None);
-    let match_expr_ty = analyzer.type_of(ctx.db, expr)?;
-    let enum_def = analyzer.autoderef(ctx.db, match_expr_ty).find_map(|ty| match ty.as_adt() {
-        Some((AdtDef::Enum(e), _)) => Some(e),
-        _ => None,
-    })?;
-    let enum_name = enum_def.name(ctx.db)?;
-    let db = ctx.db;
-
-    ctx.add_action(AssistId("fill_match_arms"), "fill match arms", |edit| {
-        let mut buf = format!("match {} {{\n", expr.syntax().text().to_string());
-        let variants = enum_def.variants(db);
-        for variant in variants {
-            let name = match variant.name(db) {
-                Some(it) => it,
-                None => continue,
-            };
-            write!(&mut buf, "    {}::{}", enum_name, name.to_string()).unwrap();
-
-            let pat = variant
-                .fields(db)
-                .into_iter()
-                .map(|field| {
-                    let name = field.name(db).to_string();
-                    let src = field.source(db);
-                    match src.ast {
-                        FieldSource::Named(_) => name,
-                        FieldSource::Pos(_) => "_".to_string(),
-                    }
-                })
-                .collect::<Vec<_>>();
-
-            match pat.first().map(|s| s.as_str()) {
-                Some("_") => write!(&mut buf, "({})", pat.join(", ")).unwrap(),
-                Some(_) => write!(&mut buf, "{{{}}}", pat.join(", ")).unwrap(),
-                None => (),
-            };
-
-            buf.push_str(" => (),\n");
-        }
-        buf.push_str("}");
         edit.target(match_expr.syntax().range());
         edit.set_cursor(expr.syntax().range().start());
-        edit.replace_node_and_indent(match_expr.syntax(), buf);
     });
 
     ctx.build()
 }
 
+/// Adds arms for `patterns` to the match, in place of its trivial arm.
+pub fn fill_match_arms_text_edit(arm_list: &ast::MatchArmList, patterns: &[String]) -> TextEdit {
+    let indent = arm_list.syntax().parent().and_then(leading_indent).unwrap_or("");
+    let mut edit = TextEditBuilder::default();
+    for (range, text) in arms_edits(indent, arm_list, patterns) {
+        edit.replace(range, text);
+    }
+    edit.finish()
+}
+
+/// The edits adding the arms for `patterns` to `arm_list`. They go in place
+/// of the trivial arm if there is one, and before the closing brace
+/// otherwise, leaving the other arms alone.
+fn arms_edits(
+    indent: &str,
+    arm_list: &ast::MatchArmList,
+    patterns: &[String],
+) -> Vec<(TextRange, String)> {
+    let arms = patterns.iter().map(|it| format!("{} => (),", it)).collect::<Vec<_>>();
+    if let Some(arm) = arm_list.arms().find(|it| is_trivial_arm(it)) {
+        let next = arm.syntax().siblings_with_tokens(Direction::Next).skip(1).find(not_whitespace);
+        let range = match next {
+            Some(comma) if comma.kind() == T![,] => {
+                TextRange::from_to(arm.syntax().range().start(), comma.range().end())
+            }
+            _ => arm.syntax().range(),
+        };
+        return vec![(range, arms.join(&format!("\n{}    ", indent)))];
+    }
+
+    // The last arm needs a comma unless its expression is a block
+    let comma_offset = arm_list.arms().last().and_then(|arm| {
+        let mut next = arm.syntax().siblings_with_tokens(Direction::Next).skip(1);
+        let has_comma = next.find(|it| !it.kind().is_trivia()).map(|it| it.kind()) == Some(T![,]);
+        let is_block = arm.expr().map(|it| it.syntax().kind()) == Some(BLOCK_EXPR);
+        if has_comma || is_block {
+            None
+        } else {
+            Some(arm.syntax().range().end())
+        }
+    });
+
+    // The whitespace before the closing brace is replaced, to put the brace
+    // on its own line
+    let r_curly = arm_list.syntax().last_token().filter(|it| it.kind() == T!['}']);
+    let last = match r_curly {
+        Some(r_curly) => r_curly.siblings_with_tokens(Direction::Prev).skip(1).find(not_whitespace),
+        None => arm_list.syntax().children_with_tokens().filter(not_whitespace).last(),
+    };
+    let list_end = arm_list.syntax().range().end();
+    let start = last.map_or(list_end, |it| it.range().end());
+    let end = r_curly.map_or(list_end, |it| it.range().start());
+
+    let mut edits = Vec::new();
+    let mut text = String::new();
+    match comma_offset {
+        Some(offset) if offset == start => text.push(','),
+        Some(offset) => edits.push((TextRange::from_to(offset, offset), ",".to_string())),
+        None => (),
+    }
+    for arm in arms {
+        text.push_str(&format!("\n{}    {}", indent, arm));
+    }
+    text.push_str(&format!("\n{}", indent));
+    // An unclosed arm list gets its closing brace
+    if r_curly.is_none() {
+        text.push('}');
+    }
+    edits.push((TextRange::from_to(start, end), text));
+    edits
+}
+
+fn not_whitespace(element: &SyntaxElement) -> bool {
+    element.kind() != WHITESPACE
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::helpers::{check_assist, check_assist_target};
+    use crate::helpers::{check_assist, check_assist_not_applicable, check_assist_target};
 
     use super::fill_match_arms;
 
@@ -132,7 +175,7 @@ mod tests {
                     A::Bs => (),
                     A::Cs(_) => (),
                     A::Ds(_, _) => (),
-                    A::Es{x, y} => (),
+                    A::Es { .. } => (),
                 }
             }
             "#,
@@ -185,7 +228,7 @@ mod tests {
 
             fn foo(a: &mut A) {
                 match <|>a {
-                    A::Es{x, y} => (),
+                    A::Es { .. } => (),
                 }
             }
             "#,
@@ -277,4 +320,100 @@ mod tests {
             "#,
         );
     }
+
+    #[test]
+    fn fill_match_arms_partial() {
+        check_assist(
+            fill_match_arms,
+            r#"
+            enum Option<T> { Some(T), None }
+            use Option::*;
+            enum A { As, Bs(Option<bool>), Cs { x: u32 } }
+
+            fn foo(a: &A) {
+                match a<|> {
+                    A::As => (),
+                    A::Bs(Some(true)) => {}
+                }
+            }
+            "#,
+            r#"
+            enum Option<T> { Some(T), None }
+            use Option::*;
+            enum A { As, Bs(Option<bool>), Cs { x: u32 } }
+
+            fn foo(a: &A) {
+                match <|>a {
+                    A::As => (),
+                    A::Bs(Some(true)) => {}
+                    A::Cs { .. } => (),
+                }
+            }
+            "#,
+        );
+    }
+
+    #[test]
+    fn fill_match_arms_keeps_existing_arms() {
+        check_assist(
+            fill_match_arms,
+            r#"
+            fn foo(x: bool) {
+                match x<|> {
+                    true   =>   () // not reformatted
+                }
+            }
+            "#,
+            r#"
+            fn foo(x: bool) {
+                match <|>x {
+                    true   =>   (), // not reformatted
+                    false => (),
+                }
+            }
+            "#,
+        );
+    }
+
+    #[test]
+    fn fill_match_arms_tuple() {
+        check_assist(
+            fill_match_arms,
+            r#"
+            fn foo(x: (bool, bool)) {
+                match x<|> {
+                    (true, true) => (),
+                    (_, false) if false => (),
+                }
+            }
+            "#,
+            r#"
+            fn foo(x: (bool, bool)) {
+                match <|>x {
+                    (true, true) => (),
+                    (_, false) if false => (),
+                    (false, _) => (),
+                }
+            }
+            "#,
+        );
+    }
+
+    #[test]
+    fn fill_match_arms_not_applicable_when_exhaustive() {
+        check_assist_not_applicable(
+            fill_match_arms,
+            r#"
+            enum E { X, Y(u32, bool) }
+
+            fn foo(e: &E) {
+                match e<|> {
+                    E::X => (),
+                    &E::Y(.., true) => (),
+                    E::Y(_, false) => (),
+                }
+            }
+            "#,
+        );
+    }
 }
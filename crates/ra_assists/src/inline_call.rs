@@ -0,0 +1,619 @@
+use hir::{
+    db::HirDatabase, source_binder, HasSource, ImplItem, Module, ModuleDef, PathResolution,
+    Resolution, SourceAnalyzer,
+};
+use ra_db::FileId;
+use ra_fmt::leading_indent;
+use ra_syntax::{
+    ast::{self, ArgListOwner, AstNode, NameOwner, TypeAscriptionOwner},
+    SyntaxKind::{self, *},
+    SyntaxNode, TextRange, T,
+};
+
+use crate::{extract_function::reindent, Assist, AssistCtx, AssistId};
+
+/// Replaces a call of a function or method by the body of the callee, with
+/// the arguments substituted for the parameters. Paths in the body are
+/// qualified when they resolve differently at the call site, and the assist is
+/// not applicable when one of them is not visible there.
+pub(crate) fn inline_call(mut ctx: AssistCtx<impl HirDatabase>) -> Option<Assist> {
+    let offset = ctx.frange.range.start();
+    let call = match ctx.node_at_offset::<ast::CallExpr>() {
+        Some(call) if call.expr()?.syntax().range().contains_inclusive(offset) => Call::Fn(call),
+        _ => {
+            let call = ctx.node_at_offset::<ast::MethodCallExpr>()?;
+            if !call.name_ref()?.syntax().range().contains_inclusive(offset) {
+                return None;
+            }
+            Call::Method(call)
+        }
+    };
+    let db = ctx.db;
+    let analyzer = SourceAnalyzer::new(db, ctx.frange.file_id, call.syntax(), None);
+    let function = match call {
+        Call::Fn(call) => {
+            let path = ast::PathExpr::cast(call.expr()?.syntax())?.path()?;
+            match analyzer.resolve_path(db, path)? {
+                PathResolution::Def(ModuleDef::Function(it)) => it,
+                PathResolution::AssocItem(ImplItem::Method(it)) => it,
+                _ => return None,
+            }
+        }
+        Call::Method(call) => analyzer.resolve_method_call(call)?,
+    };
+    let module = source_binder::module_from_child_node(db, ctx.frange.file_id, call.syntax())?;
+
+    let src = function.source(db);
+    let callee_file = src.file_id.original_file(db);
+    // Functions generated by macros can't be inlined
+    if src.file_id != callee_file.into() {
+        return None;
+    }
+    let fn_def: &ast::FnDef = &src.ast;
+    let body = fn_def.body()?;
+    let callee_analyzer = SourceAnalyzer::new(db, callee_file, body.syntax(), None);
+
+    let mut args = call.arg_list()?.args().collect::<Vec<_>>();
+    let self_param = fn_def.param_list()?.self_param();
+    let self_arg = match (self_param, call) {
+        (Some(_), Call::Method(call)) => Some(call.expr()?),
+        // Calls like `S::method(s)` pass the receiver as the first argument
+        (Some(_), Call::Fn(_)) if !args.is_empty() => Some(args.remove(0)),
+        (None, _) => None,
+        _ => return None,
+    };
+    let params = fn_def.param_list()?.params().collect::<Vec<_>>();
+    if params.len() != args.len() {
+        return None;
+    }
+
+    // Edits of the body of the callee, in the coordinates of its file
+    let mut edits = Vec::new();
+    let mut lets = Vec::new();
+    let mut bound_names = Vec::new();
+
+    if let (Some(self_param), Some(self_arg)) = (self_param, self_arg) {
+        let prefix = match call {
+            Call::Method(_) => {
+                let ty = analyzer.type_of(db, self_arg);
+                let is_ref = ty.map_or(false, |it| it.as_reference().is_some());
+                match self_param.kind() {
+                    ast::SelfParamKind::Ref if !is_ref => "&",
+                    ast::SelfParamKind::MutRef if !is_ref => "&mut ",
+                    _ => "",
+                }
+            }
+            Call::Fn(_) => "",
+        };
+        let self_uses = body
+            .syntax()
+            .descendants()
+            .filter_map(ast::PathExpr::cast)
+            .filter(|it| is_self(it) && !is_nested(it.syntax(), body.syntax(), &[FN_DEF]))
+            .collect::<Vec<_>>();
+        if is_trivial(self_arg) && self_uses.len() <= 1 {
+            for self_use in self_uses {
+                let text = if is_receiver(self_use.syntax()) {
+                    self_arg.syntax().text().to_string()
+                } else {
+                    format!("{}{}", prefix, self_arg.syntax().text())
+                };
+                edits.push((self_use.syntax().range(), text));
+            }
+        } else {
+            lets.push(format!("let this = {}{};", prefix, self_arg.syntax().text()));
+            bound_names.push("this".to_string());
+            edits.extend(self_uses.iter().map(|it| (it.syntax().range(), "this".to_string())));
+        }
+    }
+
+    let body_bindings = body
+        .syntax()
+        .descendants()
+        .filter_map(ast::BindPat::cast)
+        .filter_map(|it| it.name().map(|it| it.text().to_string()))
+        .collect::<Vec<_>>();
+    let mut substitutions = Vec::new();
+    for (param, &arg) in params.iter().zip(args.iter()) {
+        let pat = match param.pat()?.kind() {
+            ast::PatKind::BindPat(it) => it,
+            _ => return None,
+        };
+        let uses = callee_analyzer.find_all_refs(pat);
+        // An argument substituted below a binding of the same name would refer
+        // to the binding
+        let is_shadowed = names_in(arg).iter().any(|it| body_bindings.contains(it));
+        if is_trivial(arg) && uses.len() <= 1 && !pat.is_mutable() && !is_shadowed {
+            substitutions.push(arg);
+            edits.extend(uses.iter().map(|it| (it.range, arg.syntax().text().to_string())));
+        } else {
+            if names_in(arg).iter().any(|it| bound_names.contains(it)) {
+                return None;
+            }
+            let ty = match param.ascribed_type() {
+                Some(ty) => {
+                    let edits = qualify_paths(db, &analyzer, module, callee_file, ty.syntax())?;
+                    format!(": {}", apply_edits(ty.syntax(), ty.syntax().range(), edits))
+                }
+                None => String::new(),
+            };
+            lets.push(format!("let {}{} = {};", pat.syntax().text(), ty, arg.syntax().text()));
+            bound_names.push(pat.name()?.text().to_string());
+        }
+    }
+    if substitutions.iter().any(|it| names_in(it).iter().any(|it| bound_names.contains(it))) {
+        return None;
+    }
+
+    let exits = body
+        .syntax()
+        .descendants()
+        .filter(|it| it.kind() == RETURN_EXPR || it.kind() == TRY_EXPR)
+        .filter(|it| !is_nested(it, body.syntax(), &[FN_DEF, LAMBDA_EXPR]))
+        .collect::<Vec<_>>();
+    let trailing_return = trailing_return(body);
+    let mut tail = body.expr();
+    let mut stmt_count = body.statements().count();
+    match (exits.as_slice(), trailing_return) {
+        ([], _) => (),
+        ([exit], Some((ret, stmt))) if *exit == ret.syntax() => {
+            match ret.expr() {
+                Some(value) => {
+                    let prefix = TextRange::from_to(
+                        ret.syntax().range().start(),
+                        value.syntax().range().start(),
+                    );
+                    edits.push((prefix, String::new()));
+                    if let Some(stmt) = stmt {
+                        let semi = TextRange::from_to(
+                            ret.syntax().range().end(),
+                            stmt.syntax().range().end(),
+                        );
+                        edits.push((semi, String::new()));
+                    }
+                }
+                None => {
+                    edits.push((stmt.map_or(ret.syntax(), AstNode::syntax).range(), String::new()))
+                }
+            }
+            if stmt.is_some() {
+                stmt_count -= 1;
+            }
+            tail = ret.expr();
+        }
+        // Exiting from the callee exits from the caller as well when the call
+        // is its tail
+        _ if is_tail_call(call.syntax()) => (),
+        _ => return None,
+    }
+
+    edits.extend(qualify_paths(db, &analyzer, module, callee_file, body.syntax())?);
+
+    let l_curly = body.syntax().first_child_or_token()?;
+    let r_curly = body.syntax().last_child_or_token()?;
+    if l_curly.kind() != T!['{'] || r_curly.kind() != T!['}'] {
+        return None;
+    }
+    let inner_range = TextRange::from_to(l_curly.range().end(), r_curly.range().start());
+    let inner = apply_edits(body.syntax(), inner_range, edits);
+    let inner = inner.trim();
+
+    let replacement = if lets.is_empty() && stmt_count == 0 {
+        let needs_parens = tail.map_or(false, |it| !is_atom(it.syntax()))
+            && call.syntax().parent().map_or(false, |it| is_operand(it.kind()));
+        if inner.is_empty() {
+            "()".to_string()
+        } else if needs_parens {
+            format!("({})", inner)
+        } else {
+            inner.to_string()
+        }
+    } else {
+        let indent = call.syntax().ancestors().find_map(leading_indent).unwrap_or("");
+        let inner_indent = format!("{}    ", indent);
+        let callee_indent = body
+            .statements()
+            .map(AstNode::syntax)
+            .chain(body.expr().map(AstNode::syntax))
+            .next()
+            .and_then(leading_indent)
+            .unwrap_or("");
+        let mut lines = lets.iter().map(|it| format!("{}{}", inner_indent, it)).collect::<Vec<_>>();
+        if !inner.is_empty() {
+            lines.push(reindent(inner, callee_indent, &inner_indent));
+        }
+        format!("{{\n{}\n{}}}", lines.join("\n"), indent)
+    };
+
+    ctx.add_action(AssistId("inline_call"), "inline call", |edit| {
+        edit.target(call.syntax().range());
+        edit.replace(call.syntax().range(), replacement);
+        edit.set_cursor(call.syntax().range().start());
+    });
+
+    ctx.build()
+}
+
+#[derive(Clone, Copy)]
+enum Call<'a> {
+    Fn(&'a ast::CallExpr),
+    Method(&'a ast::MethodCallExpr),
+}
+
+impl<'a> Call<'a> {
+    fn syntax(self) -> &'a SyntaxNode {
+        match self {
+            Call::Fn(it) => it.syntax(),
+            Call::Method(it) => it.syntax(),
+        }
+    }
+
+    fn arg_list(self) -> Option<&'a ast::ArgList> {
+        match self {
+            Call::Fn(it) => it.arg_list(),
+            Call::Method(it) => it.arg_list(),
+        }
+    }
+}
+
+/// Arguments which can be substituted for parameters without changing when
+/// or how many times they are evaluated.
+fn is_trivial(expr: &ast::Expr) -> bool {
+    match expr.syntax().kind() {
+        PATH_EXPR | LITERAL => true,
+        _ => false,
+    }
+}
+
+fn is_self(path_expr: &ast::PathExpr) -> bool {
+    match path_expr.path() {
+        Some(path) => {
+            path.qualifier().is_none()
+                && path.segment().and_then(|it| it.kind()) == Some(ast::PathSegmentKind::SelfKw)
+        }
+        None => false,
+    }
+}
+
+/// Field accesses and method calls take a reference to their receiver by
+/// themselves.
+fn is_receiver(expr: &SyntaxNode) -> bool {
+    match expr.parent() {
+        Some(parent) => match parent.kind() {
+            FIELD_EXPR | METHOD_CALL_EXPR => parent.first_child() == Some(expr),
+            _ => false,
+        },
+        None => false,
+    }
+}
+
+/// Whether `node` is inside of a node of one of the `kinds` in `body`.
+fn is_nested(node: &SyntaxNode, body: &SyntaxNode, kinds: &[SyntaxKind]) -> bool {
+    node.ancestors().take_while(|it| *it != body).any(|it| kinds.contains(&it.kind()))
+}
+
+fn names_in(expr: &ast::Expr) -> Vec<String> {
+    expr.syntax()
+        .descendants()
+        .filter_map(ast::NameRef::cast)
+        .map(|it| it.text().to_string())
+        .collect()
+}
+
+/// The `return` ending `block`, with its statement.
+fn trailing_return(block: &ast::Block) -> Option<(&ast::ReturnExpr, Option<&ast::ExprStmt>)> {
+    if let Some(tail) = block.expr() {
+        return Some((ast::ReturnExpr::cast(tail.syntax())?, None));
+    }
+    let stmt = ast::ExprStmt::cast(block.statements().last()?.syntax())?;
+    Some((ast::ReturnExpr::cast(stmt.expr()?.syntax())?, Some(stmt)))
+}
+
+/// Whether the value of `call` is returned by the function containing it.
+fn is_tail_call(call: &SyntaxNode) -> bool {
+    let parent = match call.parent() {
+        Some(it) => it,
+        None => return false,
+    };
+    if parent.kind() == RETURN_EXPR {
+        return true;
+    }
+    match ast::Block::cast(parent) {
+        Some(block) => {
+            block.expr().map(AstNode::syntax) == Some(call)
+                && block.syntax().parent().map_or(false, |it| it.kind() == FN_DEF)
+        }
+        None => false,
+    }
+}
+
+fn is_atom(expr: &SyntaxNode) -> bool {
+    match expr.kind() {
+        PATH_EXPR | LITERAL | CALL_EXPR | METHOD_CALL_EXPR | FIELD_EXPR | INDEX_EXPR
+        | PAREN_EXPR | TUPLE_EXPR | ARRAY_EXPR | STRUCT_LIT | MACRO_CALL | BLOCK_EXPR => true,
+        _ => false,
+    }
+}
+
+/// Whether an expression which is not an atom must be parenthesized to be a
+/// child of a `kind` node.
+fn is_operand(kind: SyntaxKind) -> bool {
+    match kind {
+        BIN_EXPR | PREFIX_EXPR | REF_EXPR | CAST_EXPR | RANGE_EXPR | TRY_EXPR | FIELD_EXPR
+        | METHOD_CALL_EXPR | INDEX_EXPR | CALL_EXPR => true,
+        _ => false,
+    }
+}
+
+/// Edits qualifying the paths in `node` of the callee which resolve
+/// differently at the call site, or `None` if one of them can't be reached
+/// from the call site.
+fn qualify_paths(
+    db: &impl HirDatabase,
+    analyzer: &SourceAnalyzer,
+    from: Module,
+    callee_file: FileId,
+    node: &SyntaxNode,
+) -> Option<Vec<(TextRange, String)>> {
+    let mut edits = Vec::new();
+    for path in node.descendants().filter_map(ast::Path::cast) {
+        if path.qualifier().is_some() {
+            continue;
+        }
+        let name_ref = match path.segment().and_then(|it| it.kind()) {
+            Some(ast::PathSegmentKind::Name(name_ref)) => name_ref,
+            _ => continue,
+        };
+        let hir_path = match hir::Path::from_ast(path) {
+            Some(it) => it,
+            None => continue,
+        };
+        let path_analyzer = SourceAnalyzer::new(db, callee_file, path.syntax(), None);
+        let resolution = path_analyzer.resolve_hir_path(db, &hir_path);
+        let def = match resolution.clone().take_types().or_else(|| resolution.clone().take_values())
+        {
+            Some(Resolution::Def(def)) => def,
+            _ => continue,
+        };
+        if analyzer.resolve_hir_path(db, &hir_path) == resolution {
+            continue;
+        }
+        let path = def.path_from(db, from)?;
+        edits.push((name_ref.syntax().range(), path.segment_texts().join("::")));
+    }
+    Some(edits)
+}
+
+/// The text of `range` of `node`, with the `edits` applied.
+fn apply_edits(node: &SyntaxNode, range: TextRange, mut edits: Vec<(TextRange, String)>) -> String {
+    edits.sort_by_key(|(range, _)| range.start());
+    let mut res = String::new();
+    let mut last = range.start();
+    for (range, text) in edits {
+        res.push_str(&node.text().slice(last..range.start()).to_string());
+        res.push_str(&text);
+        last = range.end();
+    }
+    res.push_str(&node.text().slice(last..range.end()).to_string());
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::helpers::{check_assist, check_assist_not_applicable, check_assist_target};
+
+    #[test]
+    fn inline_call_substitutes_trivial_arguments() {
+        check_assist(
+            inline_call,
+            "
+fn add_one(x: i32) -> i32 {
+    x + 1
+}
+
+fn main() {
+    let y = add_<|>one(2);
+}",
+            "
+fn add_one(x: i32) -> i32 {
+    x + 1
+}
+
+fn main() {
+    let y = <|>2 + 1;
+}",
+        );
+    }
+
+    #[test]
+    fn inline_call_binds_complex_arguments() {
+        check_assist(
+            inline_call,
+            "
+fn square(x: i32) -> i32 {
+    let y = x * x;
+    y
+}
+
+fn main() {
+    let z = squ<|>are(1 + 2);
+}",
+            "
+fn square(x: i32) -> i32 {
+    let y = x * x;
+    y
+}
+
+fn main() {
+    let z = <|>{
+        let x: i32 = 1 + 2;
+        let y = x * x;
+        y
+    };
+}",
+        );
+    }
+
+    #[test]
+    fn inline_method_call() {
+        check_assist(
+            inline_call,
+            "
+struct S { f: i32 }
+
+impl S {
+    fn get(&self) -> i32 {
+        self.f
+    }
+}
+
+fn main() {
+    let s = S { f: 1 };
+    let x = s.g<|>et();
+}",
+            "
+struct S { f: i32 }
+
+impl S {
+    fn get(&self) -> i32 {
+        self.f
+    }
+}
+
+fn main() {
+    let s = S { f: 1 };
+    let x = <|>s.f;
+}",
+        );
+    }
+
+    #[test]
+    fn inline_call_with_trailing_return() {
+        check_assist(
+            inline_call,
+            "
+fn double(x: i32) -> i32 {
+    return x * 2;
+}
+
+fn main() {
+    let y = dou<|>ble(3);
+}",
+            "
+fn double(x: i32) -> i32 {
+    return x * 2;
+}
+
+fn main() {
+    let y = <|>3 * 2;
+}",
+        );
+    }
+
+    #[test]
+    fn inline_call_keeps_parameter_types() {
+        check_assist(
+            inline_call,
+            "
+fn square(x: u64) -> u64 {
+    x * x
+}
+
+fn main() {
+    let z = squ<|>are(1 + 2);
+}",
+            "
+fn square(x: u64) -> u64 {
+    x * x
+}
+
+fn main() {
+    let z = <|>{
+        let x: u64 = 1 + 2;
+        x * x
+    };
+}",
+        );
+    }
+
+    #[test]
+    fn inline_call_qualifies_paths() {
+        check_assist(
+            inline_call,
+            "
+mod m {
+    pub fn helper() -> i32 { 1 }
+    pub fn foo() -> i32 {
+        helper()
+    }
+}
+
+fn main() {
+    let x = m::fo<|>o();
+}",
+            "
+mod m {
+    pub fn helper() -> i32 { 1 }
+    pub fn foo() -> i32 {
+        helper()
+    }
+}
+
+fn main() {
+    let x = <|>crate::m::helper();
+}",
+        );
+    }
+
+    #[test]
+    fn inline_call_not_applicable_with_private_paths() {
+        check_assist_not_applicable(
+            inline_call,
+            "
+mod m {
+    fn helper() -> i32 { 1 }
+    pub fn foo() -> i32 {
+        helper()
+    }
+}
+
+fn main() {
+    let x = m::fo<|>o();
+}",
+        );
+    }
+
+    #[test]
+    fn inline_call_not_applicable_to_early_return() {
+        check_assist_not_applicable(
+            inline_call,
+            "
+fn f(x: i32) -> i32 {
+    if x > 0 {
+        return 1;
+    }
+    2
+}
+
+fn main() {
+    let y = <|>f(1) + 1;
+}",
+        );
+    }
+
+    #[test]
+    fn inline_call_target() {
+        check_assist_target(
+            inline_call,
+            "
+fn f(x: i32) -> i32 { x }
+
+fn main() {
+    let y = <|>f(1) + 1;
+}",
+            "f(1)",
+        );
+    }
+}
@@ -17,6 +17,8 @@ use ra_syntax::{TextRange, TextUnit};
 use ra_text_edit::TextEdit;
 
 pub(crate) use crate::assist_ctx::{Assist, AssistCtx};
+pub use crate::fill_match_arms::fill_match_arms_text_edit;
+pub use crate::organize_imports::{organize_imports_text_edit, ImportGranularity};
 
 /// Unique identifier of the assist, should not be shown to the user
 /// directly.
@@ -89,14 +91,19 @@ where
 mod add_derive;
 mod add_explicit_type;
 mod add_impl;
+mod add_struct_methods;
+mod add_trait_impls;
 mod flip_comma;
 mod flip_binexpr;
 mod change_visibility;
 mod fill_match_arms;
 mod introduce_variable;
+mod extract_function;
 mod inline_local_variable;
+mod inline_call;
 mod replace_if_let_with_match;
 mod split_import;
+mod organize_imports;
 mod remove_dbg;
 pub mod auto_import;
 mod add_missing_impl_members;
@@ -107,11 +114,18 @@ fn all_assists<DB: HirDatabase>() -> &'static [fn(AssistCtx<DB>) -> Option<Assis
         add_derive::add_derive,
         add_explicit_type::add_explicit_type,
         add_impl::add_impl,
+        add_struct_methods::add_new,
+        add_struct_methods::add_getter,
+        add_struct_methods::add_setter,
+        add_trait_impls::add_from_impl,
+        add_trait_impls::add_default_impl,
+        add_trait_impls::add_display_impl,
         change_visibility::change_visibility,
         fill_match_arms::fill_match_arms,
         flip_comma::flip_comma,
         flip_binexpr::flip_binexpr,
         introduce_variable::introduce_variable,
+        extract_function::extract_function,
         replace_if_let_with_match::replace_if_let_with_match,
         split_import::split_import,
         remove_dbg::remove_dbg,
@@ -119,6 +133,7 @@ fn all_assists<DB: HirDatabase>() -> &'static [fn(AssistCtx<DB>) -> Option<Assis
         add_missing_impl_members::add_missing_impl_members,
         add_missing_impl_members::add_missing_default_members,
         inline_local_variable::inline_local_varialbe,
+        inline_call::inline_call,
         move_guard::move_guard_to_arm_body,
         move_guard::move_arm_cond_to_match_guard,
     ]
@@ -326,5 +341,4 @@ mod tests {
         assert_eq!(assists.next().expect("expected assist").0.label, "introduce variable");
         assert_eq!(assists.next().expect("expected assist").0.label, "replace with match");
     }
-
 }
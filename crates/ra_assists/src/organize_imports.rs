@@ -0,0 +1,442 @@
+use std::collections::BTreeMap;
+
+use hir::{db::HirDatabase, source_binder, Either, Module};
+use ra_db::{FileId, SourceDatabase};
+use ra_fmt::leading_indent;
+use ra_syntax::{
+    ast::{self, AttrsOwner, VisibilityOwner},
+    AstNode,
+    SyntaxKind::*,
+    SyntaxNode, TextRange,
+};
+use ra_text_edit::{TextEdit, TextEditBuilder};
+
+/// How organizing imports merges `use` items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportGranularity {
+    /// One `use` item per imported name: `use a::b; use a::c;`
+    Item,
+    /// One `use` item per module: `use a::{b, c}; use a::d::e;`
+    Module,
+    /// One `use` item per crate: `use a::{b, c, d::e};`
+    Crate,
+}
+
+impl Default for ImportGranularity {
+    fn default() -> ImportGranularity {
+        ImportGranularity::Crate
+    }
+}
+
+/// Organizes the imports of all the modules of the file.
+pub fn organize_imports_text_edit(
+    db: &impl HirDatabase,
+    file_id: FileId,
+    granularity: ImportGranularity,
+) -> Option<TextEdit> {
+    let file = db.parse(file_id).tree;
+    let inline_modules = file
+        .syntax()
+        .descendants()
+        .filter(|it| it.kind() == ITEM_LIST && it.parent().map(|it| it.kind()) == Some(MODULE));
+    let mut builder = TextEditBuilder::default();
+    let mut is_changed = false;
+    for container in std::iter::once(file.syntax()).chain(inline_modules) {
+        if let Some(edits) = organize(db, file_id, container, granularity) {
+            is_changed = true;
+            for (range, text) in edits {
+                builder.replace(range, text);
+            }
+        }
+    }
+    if is_changed {
+        Some(builder.finish())
+    } else {
+        None
+    }
+}
+
+/// An imported name, with the full path to it.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Import {
+    visibility: Option<String>,
+    path: Vec<String>,
+    alias: Option<String>,
+    is_glob: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum ImportGroup {
+    Std,
+    External,
+    Crate,
+}
+
+/// Computes the edits organizing the `use` items of `container`, a file or
+/// the item list of a module.
+fn organize(
+    db: &impl HirDatabase,
+    file_id: FileId,
+    container: &SyntaxNode,
+    granularity: ImportGranularity,
+) -> Option<Vec<(TextRange, String)>> {
+    // Attributes like `#[cfg(test)]` apply to single items, which are left alone
+    let use_items = container
+        .children()
+        .filter_map(ast::UseItem::cast)
+        .filter(|it| it.attrs().next().is_none())
+        .collect::<Vec<_>>();
+    let first = *use_items.first()?;
+    let last = *use_items.last()?;
+    let module = source_binder::module_from_child_node(db, file_id, first.syntax());
+    let dependencies = module
+        .and_then(|it| it.krate(db))
+        .map(|it| it.dependencies(db).into_iter().map(|dep| dep.name.to_string()).collect())
+        .unwrap_or_else(Vec::new);
+
+    let mut flattened = Vec::new();
+    for use_item in use_items.iter() {
+        let visibility = use_item.visibility().map(|it| it.syntax().text().to_string());
+        flatten_use_tree(&visibility, &[], use_item.use_tree()?, &mut flattened)?;
+    }
+    // Duplicates of an unused import are removed with it
+    let unused_trees = module.map(|it| unused_use_trees(db, it)).unwrap_or_default();
+    let unused = flattened
+        .iter()
+        .filter(|(_, tree)| unused_trees.contains(tree))
+        .map(|(import, _)| import.clone())
+        .collect::<Vec<_>>();
+    let mut imports = flattened
+        .into_iter()
+        .map(|(import, _)| import)
+        .filter(|it| !unused.contains(it))
+        .collect::<Vec<_>>();
+    imports.sort();
+    imports.dedup();
+
+    // Imports merged into a single `use` item share a key
+    let mut merged: BTreeMap<_, Vec<&Import>> = BTreeMap::new();
+    for import in imports.iter() {
+        let key_len = match granularity {
+            ImportGranularity::Item => import.path.len(),
+            ImportGranularity::Module if import.is_glob => import.path.len(),
+            ImportGranularity::Module => import.path.len().saturating_sub(1).max(1),
+            ImportGranularity::Crate => 1,
+        };
+        let group = import_group(&import.path[0], &dependencies);
+        let key = (import.visibility.clone(), group, import.path[..key_len].to_vec());
+        merged.entry(key).or_default().push(import);
+    }
+    let mut groups: BTreeMap<_, Vec<String>> = BTreeMap::new();
+    for ((visibility, group, _), imports) in merged {
+        let mut root = UseTreeNode::default();
+        for import in imports {
+            root.insert(&import.path, import);
+        }
+        let prefix = visibility.map(|it| format!("{} ", it)).unwrap_or_default();
+        let lines = groups.entry((prefix.clone(), group)).or_default();
+        for (name, node) in root.children.iter() {
+            lines.extend(node.render(name).into_iter().map(|it| format!("{}use {};", prefix, it)));
+        }
+    }
+
+    let indent = leading_indent(first.syntax()).unwrap_or("");
+    let text = groups
+        .into_iter()
+        .map(|(_, mut lines)| {
+            lines.sort();
+            lines.join(&format!("\n{}", indent))
+        })
+        .collect::<Vec<_>>()
+        .join(&format!("\n\n{}", indent));
+    let range = TextRange::from_to(first.syntax().range().start(), last.syntax().range().end());
+    if container.text().slice(range) == text.as_str() {
+        return None;
+    }
+
+    let mut edits = Vec::new();
+    if text.is_empty() {
+        for use_item in use_items.iter() {
+            edits.push((range_with_whitespace_after(use_item.syntax()), String::new()));
+        }
+    } else {
+        edits.push((first.syntax().range(), text));
+        for use_item in use_items[1..].iter() {
+            edits.push((range_with_whitespace_before(use_item.syntax()), String::new()));
+        }
+    }
+    Some(edits)
+}
+
+/// Collects the imports of `tree`, with the range of the use tree of each.
+fn flatten_use_tree(
+    visibility: &Option<String>,
+    prefix: &[String],
+    tree: &ast::UseTree,
+    acc: &mut Vec<(Import, TextRange)>,
+) -> Option<()> {
+    let mut path = prefix.to_vec();
+    if let Some(tree_path) = tree.path() {
+        let text = tree_path.syntax().text().to_string();
+        let text = text.split_whitespace().collect::<String>();
+        path.extend(text.split("::").map(String::from));
+    }
+    match tree.use_tree_list() {
+        Some(list) => {
+            for tree in list.use_trees() {
+                flatten_use_tree(visibility, &path, tree, acc)?;
+            }
+        }
+        None => {
+            // `a::{self}` imports `a`
+            if path.len() > 1 && path.last().map(String::as_str) == Some("self") {
+                path.pop();
+            }
+            if path.is_empty() {
+                return None;
+            }
+            let alias = tree.alias().map(|it| {
+                let text = it.syntax().text().to_string();
+                text.trim_start_matches("as").trim().to_string()
+            });
+            let import =
+                Import { visibility: visibility.clone(), path, alias, is_glob: tree.has_star() };
+            acc.push((import, tree.syntax().range()));
+        }
+    }
+    Some(())
+}
+
+fn import_group(first_segment: &str, dependencies: &[String]) -> ImportGroup {
+    match first_segment {
+        "std" | "core" | "alloc" => ImportGroup::Std,
+        // `::name` paths
+        "" => ImportGroup::External,
+        _ if dependencies.iter().any(|it| it == first_segment) => ImportGroup::External,
+        _ => ImportGroup::Crate,
+    }
+}
+
+/// The ranges of the use trees of the imports of `module` which are not used.
+fn unused_use_trees(db: &impl HirDatabase, module: Module) -> Vec<TextRange> {
+    module
+        .unused_imports(db)
+        .iter()
+        .filter_map(|&import| match module.import_source(db, import) {
+            Either::A(use_tree) => Some(use_tree.syntax().range()),
+            Either::B(_) => None,
+        })
+        .collect()
+}
+
+/// Imports merged into a `use` tree.
+#[derive(Default)]
+struct UseTreeNode {
+    /// The aliases this node is imported with, `None` when it is imported
+    /// without an alias.
+    imports: Vec<Option<String>>,
+    children: BTreeMap<String, UseTreeNode>,
+    is_glob: bool,
+}
+
+impl UseTreeNode {
+    fn insert(&mut self, path: &[String], import: &Import) {
+        match path.split_first() {
+            Some((first, rest)) => {
+                self.children.entry(first.clone()).or_default().insert(rest, import)
+            }
+            None if import.is_glob => self.is_glob = true,
+            None => {
+                if !self.imports.contains(&import.alias) {
+                    self.imports.push(import.alias.clone());
+                }
+            }
+        }
+    }
+
+    /// Renders the `use` trees of the imports of `name` and of its children.
+    fn render(&self, name: &str) -> Vec<String> {
+        let render_import = |alias: &Option<String>, name: &str| match alias {
+            Some(alias) => format!("{} as {}", name, alias),
+            None => name.to_string(),
+        };
+        // Only modules and enums can be imported as `self`
+        if self.children.is_empty() && !self.is_glob {
+            return self.imports.iter().map(|alias| render_import(alias, name)).collect();
+        }
+        let mut entries =
+            self.imports.iter().map(|alias| render_import(alias, "self")).collect::<Vec<_>>();
+        for (child_name, child) in self.children.iter() {
+            entries.extend(child.render(child_name));
+        }
+        if self.is_glob {
+            entries.push("*".to_string());
+        }
+        if entries.len() == 1 {
+            vec![format!("{}::{}", name, entries[0])]
+        } else {
+            vec![format!("{}::{{{}}}", name, entries.join(", "))]
+        }
+    }
+}
+
+fn range_with_whitespace_before(node: &SyntaxNode) -> TextRange {
+    match node.prev_sibling_or_token() {
+        Some(prev) if prev.kind() == WHITESPACE => {
+            TextRange::from_to(prev.range().start(), node.range().end())
+        }
+        _ => node.range(),
+    }
+}
+
+fn range_with_whitespace_after(node: &SyntaxNode) -> TextRange {
+    match node.next_sibling_or_token() {
+        Some(next) if next.kind() == WHITESPACE => {
+            TextRange::from_to(node.range().start(), next.range().end())
+        }
+        _ => node.range(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hir::mock::MockDatabase;
+    use test_utils::assert_eq_text;
+
+    use super::*;
+
+    fn check_organize_imports(before: &str, after: &str) {
+        check_granularity(ImportGranularity::default(), before, after);
+    }
+
+    fn check_granularity(granularity: ImportGranularity, before: &str, after: &str) {
+        let (db, _source_root, file_id) = MockDatabase::with_single_file(before);
+        let edit = organize_imports_text_edit(&db, file_id, granularity).unwrap();
+        assert_eq_text!(after, &edit.apply(before));
+    }
+
+    fn check_organized(text: &str) {
+        let (db, _source_root, file_id) = MockDatabase::with_single_file(text);
+        assert!(organize_imports_text_edit(&db, file_id, ImportGranularity::default()).is_none());
+    }
+
+    #[test]
+    fn organize_imports_merges_and_groups() {
+        check_organize_imports(
+            "
+use crate::foo::Baz;
+use std::fmt;
+use crate::foo::Bar;
+use std::collections::HashMap;
+
+mod foo {
+    pub struct Bar;
+    pub struct Baz;
+}
+
+fn main() {
+    let _: (Bar, Baz, HashMap<(), ()>) = unimplemented!();
+    fmt::Debug;
+}",
+            "
+use std::{collections::HashMap, fmt};
+
+use crate::foo::{Bar, Baz};
+
+mod foo {
+    pub struct Bar;
+    pub struct Baz;
+}
+
+fn main() {
+    let _: (Bar, Baz, HashMap<(), ()>) = unimplemented!();
+    fmt::Debug;
+}",
+        );
+    }
+
+    #[test]
+    fn organize_imports_removes_unused() {
+        check_organize_imports(
+            "
+use std::fmt;
+use crate::foo::Bar;
+use crate::foo::Baz;
+
+mod foo {
+    pub struct Bar;
+    pub struct Baz;
+}
+
+fn main() {
+    let _: Bar = unimplemented!();
+}",
+            "
+use std::fmt;
+
+use crate::foo::Bar;
+
+mod foo {
+    pub struct Bar;
+    pub struct Baz;
+}
+
+fn main() {
+    let _: Bar = unimplemented!();
+}",
+        );
+    }
+
+    #[test]
+    fn organize_imports_removes_imports_shadowed_by_locals() {
+        check_organize_imports(
+            "
+use crate::foo::bar;
+use crate::foo::Baz;
+
+mod foo {
+    pub fn bar() {}
+    pub struct Baz;
+}
+
+fn main(baz: Baz) {
+    let bar = 1;
+    bar;
+}",
+            "
+use crate::foo::Baz;
+
+mod foo {
+    pub fn bar() {}
+    pub struct Baz;
+}
+
+fn main(baz: Baz) {
+    let bar = 1;
+    bar;
+}",
+        );
+    }
+
+    #[test]
+    fn organize_imports_not_applicable_when_organized() {
+        check_organized(
+            "
+use std::fmt;
+
+fn main() {
+    fmt::Debug;
+}",
+        );
+    }
+
+    #[test]
+    fn organize_imports_by_module_and_item() {
+        check_granularity(
+            ImportGranularity::Module,
+            "use a::b::c;\nuse a::d;\nuse a::b::e;\n",
+            "use a::b::{c, e};\nuse a::d;\n",
+        );
+        check_granularity(ImportGranularity::Item, "use a::{b, c};\n", "use a::b;\nuse a::c;\n");
+    }
+}
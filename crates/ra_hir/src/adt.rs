@@ -145,6 +145,13 @@ impl VariantData {
             _ => None,
         }
     }
+
+    pub(crate) fn is_tuple(&self) -> bool {
+        match self.0 {
+            VariantDataInner::Tuple(_) => true,
+            _ => false,
+        }
+    }
 }
 
 impl VariantData {
@@ -5,29 +5,30 @@ use std::sync::Arc;
 
 use ra_db::{CrateId, Edition, FileId, SourceRootId};
 use ra_syntax::{
-    ast::{self, NameOwner, TypeAscriptionOwner},
-    TreeArc,
+    ast::{self, NameOwner, TypeAscriptionOwner, TypeBoundsOwner},
+    AstPtr, SyntaxNodePtr, TreeArc,
 };
 
 use crate::{
     adt::{EnumVariantId, StructFieldId, VariantDef},
-    diagnostics::DiagnosticSink,
-    expr::{validation::ExprValidator, Body, BodySourceMap},
-    generics::HasGenericParams,
+    diagnostics::{DiagnosticSink, UnusedImport},
+    expr::{validation::ExprValidator, Body, BodySourceMap, Expr},
+    generics::{trait_path_from_bound, HasGenericParams},
     ids::{
         AstItemDef, ConstId, EnumId, FunctionId, MacroDefId, StaticId, StructId, TraitId,
         TypeAliasId,
     },
     impl_block::ImplBlock,
     nameres::{CrateModuleId, ImportId, ModuleScope, Namespace},
-    resolve::Resolver,
+    resolve::{Resolution, Resolver},
     traits::{TraitData, TraitItem},
     ty::{
+        consteval::ConstValue,
         primitive::{FloatBitness, FloatTy, IntBitness, IntTy, Signedness},
-        InferenceResult, TraitRef,
+        CallableDef, InferenceResult, TraitRef,
     },
     type_ref::Mutability,
-    type_ref::TypeRef,
+    type_ref::{LifetimeRef, TypeRef},
     AsName, AstDatabase, AstId, DefDatabase, Either, HasSource, HirDatabase, KnownName, Name, Ty,
 };
 
@@ -201,6 +202,11 @@ impl Module {
         source_map.get(&src.ast, import)
     }
 
+    /// The imports of this module which are not used by it or by its children.
+    pub fn unused_imports(self, db: &impl HirDatabase) -> Arc<Vec<ImportId>> {
+        db.unused_imports(self)
+    }
+
     /// Returns the crate this module is part of.
     pub fn krate(self, _db: &impl DefDatabase) -> Option<Crate> {
         Some(self.krate)
@@ -256,6 +262,12 @@ impl Module {
 
     pub fn diagnostics(self, db: &impl HirDatabase, sink: &mut DiagnosticSink) {
         db.crate_def_map(self.krate).add_diagnostics(db, self.module_id, sink);
+        let file = self.definition_source(db).file_id;
+        for &import in self.unused_imports(db).iter() {
+            if let Either::A(use_tree) = self.import_source(db, import) {
+                sink.push(UnusedImport { file, use_tree: AstPtr::new(&*use_tree) });
+            }
+        }
         for decl in self.declarations(db) {
             match decl {
                 crate::ModuleDef::Function(f) => f.diagnostics(db, sink),
@@ -384,7 +396,7 @@ impl Struct {
         let r = self.module(db).resolver(db);
         // ...and add generic params, if present
         let p = self.generic_params(db);
-        let r = if !p.params.is_empty() { r.push_generic_params_scope(p) } else { r };
+        let r = if !p.params.is_empty() { r.push_generic_params_scope(self.into(), p) } else { r };
         r
     }
 }
@@ -414,7 +426,7 @@ impl Union {
         let r = self.module(db).resolver(db);
         // ...and add generic params, if present
         let p = self.generic_params(db);
-        let r = if !p.params.is_empty() { r.push_generic_params_scope(p) } else { r };
+        let r = if !p.params.is_empty() { r.push_generic_params_scope(self.into(), p) } else { r };
         r
     }
 }
@@ -456,7 +468,7 @@ impl Enum {
         let r = self.module(db).resolver(db);
         // ...and add generic params, if present
         let p = self.generic_params(db);
-        let r = if !p.params.is_empty() { r.push_generic_params_scope(p) } else { r };
+        let r = if !p.params.is_empty() { r.push_generic_params_scope(self.into(), p) } else { r };
         r
     }
 }
@@ -561,13 +573,14 @@ impl FnData {
                     TypeRef::from_ast(type_ref)
                 } else {
                     let self_type = TypeRef::Path(Name::self_type().into());
+                    let lifetime = LifetimeRef::from_token_opt(self_param.lifetime_token());
                     match self_param.kind() {
                         ast::SelfParamKind::Owned => self_type,
                         ast::SelfParamKind::Ref => {
-                            TypeRef::Reference(Box::new(self_type), Mutability::Shared)
+                            TypeRef::Reference(Box::new(self_type), Mutability::Shared, lifetime)
                         }
                         ast::SelfParamKind::MutRef => {
-                            TypeRef::Reference(Box::new(self_type), Mutability::Mut)
+                            TypeRef::Reference(Box::new(self_type), Mutability::Mut, lifetime)
                         }
                     }
                 };
@@ -664,10 +677,31 @@ impl Function {
         let r = self.container(db).map_or_else(|| self.module(db).resolver(db), |c| c.resolver(db));
         // ...and add generic params, if present
         let p = self.generic_params(db);
-        let r = if !p.params.is_empty() { r.push_generic_params_scope(p) } else { r };
+        let r = if !p.params.is_empty() { r.push_generic_params_scope(self.into(), p) } else { r };
         r
     }
 
+    /// Lists the functions called in the body of this function, with the call
+    /// expressions they are called from. Calls inside macros are skipped.
+    pub fn calls(self, db: &impl HirDatabase) -> Vec<(Function, SyntaxNodePtr)> {
+        let body = self.body(db);
+        let infer = self.infer(db);
+        let source_map = self.body_source_map(db);
+        body.exprs()
+            .filter_map(|(expr_id, expr)| {
+                let callee = match expr {
+                    Expr::Call { callee, .. } => match infer[*callee].as_callable()?.0 {
+                        CallableDef::Function(it) => it,
+                        _ => return None,
+                    },
+                    Expr::MethodCall { .. } => infer.method_resolution(expr_id)?,
+                    _ => return None,
+                };
+                Some((callee, source_map.expr_syntax(expr_id)?))
+            })
+            .collect()
+    }
+
     pub fn diagnostics(self, db: &impl HirDatabase, sink: &mut DiagnosticSink) {
         let infer = self.infer(db);
         infer.add_diagnostics(db, self, sink);
@@ -690,10 +724,19 @@ impl Const {
         db.const_data(self)
     }
 
+    pub fn ty(self, db: &impl HirDatabase) -> Ty {
+        db.type_for_def(self.into(), Namespace::Values)
+    }
+
     pub fn infer(self, db: &impl HirDatabase) -> Arc<InferenceResult> {
         db.infer(self.into())
     }
 
+    /// Evaluates the value of the constant, if it is simple enough.
+    pub fn value(self, db: &impl HirDatabase) -> Option<ConstValue> {
+        db.const_value(self)
+    }
+
     /// The containing impl block, if this is a method.
     pub fn impl_block(self, db: &impl DefDatabase) -> Option<ImplBlock> {
         let module_impls = db.impls_in_module(self.module(db));
@@ -765,6 +808,10 @@ impl Static {
         db.static_data(self)
     }
 
+    pub fn ty(self, db: &impl HirDatabase) -> Ty {
+        db.type_for_def(self.into(), Namespace::Values)
+    }
+
     /// Builds a resolver for code inside this item.
     pub(crate) fn resolver(self, db: &impl HirDatabase) -> Resolver {
         // take the outer scope...
@@ -818,11 +865,46 @@ impl Trait {
         self.trait_data(db).is_auto()
     }
 
+    /// The traits this trait builds upon, from its bounds (`trait Foo: Bar`)
+    /// and from the `Self: Bar` predicates of its where clause.
+    pub fn super_traits(self, db: &impl HirDatabase) -> Vec<Trait> {
+        let src = self.source(db);
+        let mut paths = src
+            .ast
+            .type_bound_list()
+            .iter()
+            .flat_map(|list| list.bounds())
+            .filter_map(trait_path_from_bound)
+            .collect::<Vec<_>>();
+        let generic_params = self.generic_params(db);
+        paths.extend(generic_params.where_predicates.iter().filter_map(
+            |pred| match &pred.type_ref {
+                TypeRef::Path(path) if path.as_ident() == Some(&Name::self_type()) => {
+                    Some(pred.trait_ref.clone())
+                }
+                _ => None,
+            },
+        ));
+
+        let resolver = self.resolver(db);
+        let mut res = Vec::new();
+        for path in paths {
+            if let Some(Resolution::Def(ModuleDef::Trait(it))) =
+                resolver.resolve_path_without_assoc_items(db, &path).take_types()
+            {
+                if !res.contains(&it) {
+                    res.push(it);
+                }
+            }
+        }
+        res
+    }
+
     pub(crate) fn resolver(self, db: &impl DefDatabase) -> Resolver {
         let r = self.module(db).resolver(db);
         // add generic params, if present
         let p = self.generic_params(db);
-        let r = if !p.params.is_empty() { r.push_generic_params_scope(p) } else { r };
+        let r = if !p.params.is_empty() { r.push_generic_params_scope(self.into(), p) } else { r };
         r
     }
 }
@@ -879,7 +961,7 @@ impl TypeAlias {
             .unwrap_or_else(|| self.module(db).resolver(db));
         // ...and add generic params, if present
         let p = self.generic_params(db);
-        let r = if !p.params.is_empty() { r.push_generic_params_scope(p) } else { r };
+        let r = if !p.params.is_empty() { r.push_generic_params_scope(self.into(), p) } else { r };
         r
     }
 }
@@ -62,7 +62,7 @@ pub trait Docs {
     fn docs(&self, db: &impl HirDatabase) -> Option<Documentation>;
 }
 
-pub(crate) fn docs_from_ast(node: &impl ast::DocCommentsOwner) -> Option<Documentation> {
+pub fn docs_from_ast(node: &impl ast::DocCommentsOwner) -> Option<Documentation> {
     node.doc_comment_text().map(|it| Documentation::new(&it))
 }
 
@@ -89,6 +89,39 @@ pub(crate) fn documentation_query(
     }
 }
 
+impl DocDef {
+    /// Whether the definition has a `#[deprecated]` attribute.
+    pub fn is_deprecated(self, db: &impl DefDatabase) -> bool {
+        db.is_deprecated(self)
+    }
+}
+
+pub(crate) fn is_deprecated_query(db: &(impl DefDatabase + AstDatabase), def: DocDef) -> bool {
+    fn has_deprecated_attr(node: &impl ast::AttrsOwner) -> bool {
+        node.attrs().filter_map(|it| it.as_named()).any(|it| it == "deprecated")
+    }
+
+    match def {
+        DocDef::Module(it) => {
+            it.declaration_source(db).map_or(false, |src| has_deprecated_attr(&*src.ast))
+        }
+        DocDef::StructField(it) => match it.source(db).ast {
+            FieldSource::Named(named) => has_deprecated_attr(&*named),
+            FieldSource::Pos(pos) => has_deprecated_attr(&*pos),
+        },
+        DocDef::Struct(it) => has_deprecated_attr(&*it.source(db).ast),
+        DocDef::Enum(it) => has_deprecated_attr(&*it.source(db).ast),
+        DocDef::EnumVariant(it) => has_deprecated_attr(&*it.source(db).ast),
+        DocDef::Static(it) => has_deprecated_attr(&*it.source(db).ast),
+        DocDef::Const(it) => has_deprecated_attr(&*it.source(db).ast),
+        DocDef::Function(it) => has_deprecated_attr(&*it.source(db).ast),
+        DocDef::Union(it) => has_deprecated_attr(&*it.source(db).ast),
+        DocDef::Trait(it) => has_deprecated_attr(&*it.source(db).ast),
+        DocDef::TypeAlias(it) => has_deprecated_attr(&*it.source(db).ast),
+        DocDef::MacroDef(it) => has_deprecated_attr(&*it.source(db).ast),
+    }
+}
+
 impl<T: Into<DocDef> + Copy> Docs for T {
     fn docs(&self, db: &impl HirDatabase) -> Option<Documentation> {
         db.documentation((*self).into())
@@ -10,7 +10,7 @@ use crate::{
     ids,
     impl_block::{ImplBlock, ImplSourceMap, ModuleImplBlocks},
     lang_item::{LangItemTarget, LangItems},
-    nameres::{CrateDefMap, ImportSourceMap, Namespace, RawItems},
+    nameres::{CrateDefMap, ImportId, ImportSourceMap, Namespace, RawItems},
     traits::TraitData,
     ty::{
         method_resolution::CrateImplBlocks, CallableDef, FnSig, GenericPredicate, InferenceResult,
@@ -144,6 +144,9 @@ pub trait DefDatabase: InternDatabase {
 
     #[salsa::invoke(crate::code_model::docs::documentation_query)]
     fn documentation(&self, def: crate::DocDef) -> Option<crate::Documentation>;
+
+    #[salsa::invoke(crate::code_model::docs::is_deprecated_query)]
+    fn is_deprecated(&self, def: crate::DocDef) -> bool;
 }
 
 #[salsa::query_group(HirDatabaseStorage)]
@@ -169,6 +172,9 @@ pub trait HirDatabase: DefDatabase + AstDatabase {
     #[salsa::invoke(crate::ty::generic_defaults_query)]
     fn generic_defaults(&self, def: GenericDef) -> Substs;
 
+    #[salsa::invoke(crate::ty::consteval::const_value_query)]
+    fn const_value(&self, konst: Const) -> Option<crate::ty::consteval::ConstValue>;
+
     #[salsa::invoke(crate::expr::body_with_source_map_query)]
     fn body_with_source_map(
         &self,
@@ -184,6 +190,9 @@ pub trait HirDatabase: DefDatabase + AstDatabase {
     #[salsa::invoke(crate::ty::traits::impls_for_trait_query)]
     fn impls_for_trait(&self, krate: Crate, trait_: Trait) -> Arc<[ImplBlock]>;
 
+    #[salsa::invoke(crate::nameres::unused_imports_query)]
+    fn unused_imports(&self, module: Module) -> Arc<Vec<ImportId>>;
+
     /// This provides the Chalk trait solver instance. Because Chalk always
     /// works from a specific crate, this query is keyed on the crate; and
     /// because Chalk does its own internal caching, the solver is wrapped in a
@@ -217,14 +226,14 @@ pub trait HirDatabase: DefDatabase + AstDatabase {
     fn implements(
         &self,
         krate: Crate,
-        goal: crate::ty::Canonical<crate::ty::TraitRef>,
+        goal: crate::ty::Canonical<crate::ty::InEnvironment<crate::ty::TraitRef>>,
     ) -> Option<crate::ty::traits::Solution>;
 
     #[salsa::invoke(crate::ty::traits::normalize_query)]
     fn normalize(
         &self,
         krate: Crate,
-        goal: crate::ty::Canonical<crate::ty::ProjectionPredicate>,
+        goal: crate::ty::Canonical<crate::ty::InEnvironment<crate::ty::ProjectionPredicate>>,
     ) -> Option<crate::ty::traits::Solution>;
 }
 
@@ -98,6 +98,29 @@ impl Diagnostic for NoSuchField {
     }
 }
 
+#[derive(Debug)]
+pub struct MismatchedArrayLen {
+    pub file: HirFileId,
+    pub expr: SyntaxNodePtr,
+    pub expected: u64,
+    pub found: u64,
+}
+
+impl Diagnostic for MismatchedArrayLen {
+    fn message(&self) -> String {
+        format!("expected an array with {} elements, found one with {}", self.expected, self.found)
+    }
+    fn file(&self) -> HirFileId {
+        self.file
+    }
+    fn syntax_node_ptr(&self) -> SyntaxNodePtr {
+        self.expr
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
 #[derive(Debug)]
 pub struct UnresolvedModule {
     pub file: HirFileId,
@@ -151,3 +174,100 @@ impl AstDiagnostic for MissingFields {
         ast::NamedFieldList::cast(&node).unwrap().to_owned()
     }
 }
+
+#[derive(Debug)]
+pub struct MissingMatchArms {
+    pub file: HirFileId,
+    pub expr: AstPtr<ast::Expr>,
+    pub arms: AstPtr<ast::MatchArmList>,
+    pub missed_patterns: Vec<String>,
+}
+
+impl Diagnostic for MissingMatchArms {
+    fn message(&self) -> String {
+        let patterns = self.missed_patterns.iter().map(|it| format!("`{}`", it));
+        format!("missing match arms: {}", patterns.collect::<Vec<_>>().join(", "))
+    }
+    fn file(&self) -> HirFileId {
+        self.file
+    }
+    fn syntax_node_ptr(&self) -> SyntaxNodePtr {
+        self.expr.into()
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
+impl AstDiagnostic for MissingMatchArms {
+    type AST = TreeArc<ast::MatchArmList>;
+
+    fn ast(&self, db: &impl HirDatabase) -> Self::AST {
+        let root = db.parse_or_expand(self.file()).unwrap();
+        let node = self.arms.to_node(&*root);
+        node.to_owned()
+    }
+}
+
+#[derive(Debug)]
+pub struct UnusedImport {
+    pub file: HirFileId,
+    pub use_tree: AstPtr<ast::UseTree>,
+}
+
+impl Diagnostic for UnusedImport {
+    fn message(&self) -> String {
+        "unused import".to_string()
+    }
+    fn file(&self) -> HirFileId {
+        self.file
+    }
+    fn syntax_node_ptr(&self) -> SyntaxNodePtr {
+        self.use_tree.into()
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
+impl AstDiagnostic for UnusedImport {
+    type AST = TreeArc<ast::UseTree>;
+
+    fn ast(&self, db: &impl HirDatabase) -> Self::AST {
+        let root = db.parse_or_expand(self.file()).unwrap();
+        let node = self.syntax_node_ptr().to_node(&*root);
+        ast::UseTree::cast(&node).unwrap().to_owned()
+    }
+}
+
+#[derive(Debug)]
+pub struct UnusedVariable {
+    pub file: HirFileId,
+    pub pat: AstPtr<ast::Pat>,
+    pub name: Name,
+}
+
+impl Diagnostic for UnusedVariable {
+    fn message(&self) -> String {
+        format!("unused variable: `{}`", self.name)
+    }
+    fn file(&self) -> HirFileId {
+        self.file
+    }
+    fn syntax_node_ptr(&self) -> SyntaxNodePtr {
+        self.pat.into()
+    }
+    fn as_any(&self) -> &(dyn Any + Send + 'static) {
+        self
+    }
+}
+
+impl AstDiagnostic for UnusedVariable {
+    type AST = TreeArc<ast::BindPat>;
+
+    fn ast(&self, db: &impl HirDatabase) -> Self::AST {
+        let root = db.parse_or_expand(self.file()).unwrap();
+        let node = self.syntax_node_ptr().to_node(&*root);
+        ast::BindPat::cast(&node).unwrap().to_owned()
+    }
+}
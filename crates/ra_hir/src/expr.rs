@@ -9,7 +9,7 @@ use ra_syntax::{
         self, ArgListOwner, ArrayExprKind, LiteralKind, LoopBodyOwner, NameOwner,
         TryBlockBodyOwner, TypeAscriptionOwner,
     },
-    AstNode, AstPtr, SyntaxNodePtr,
+    AstNode, AstPtr, SmolStr, SyntaxNode, SyntaxNodePtr, T,
 };
 
 use crate::{
@@ -25,6 +25,7 @@ use crate::{
 
 pub use self::scope::ExprScopes;
 
+pub(crate) mod match_check;
 pub(crate) mod scope;
 pub(crate) mod validation;
 
@@ -434,7 +435,11 @@ pub struct FieldPat {
 pub enum Pat {
     Missing,
     Wild,
-    Tuple(Vec<PatId>),
+    Tuple {
+        args: Vec<PatId>,
+        /// The number of patterns before the `..`, if there is one.
+        ellipsis: Option<usize>,
+    },
     Struct {
         path: Option<Path>,
         args: Vec<FieldPat>,
@@ -459,6 +464,7 @@ pub enum Pat {
     TupleStruct {
         path: Option<Path>,
         args: Vec<PatId>,
+        ellipsis: Option<usize>,
     },
     Ref {
         pat: PatId,
@@ -473,7 +479,7 @@ impl Pat {
             Pat::Bind { subpat, .. } => {
                 subpat.iter().copied().for_each(f);
             }
-            Pat::Tuple(args) | Pat::TupleStruct { args, .. } => {
+            Pat::Tuple { args, .. } | Pat::TupleStruct { args, .. } => {
                 args.iter().copied().for_each(f);
             }
             Pat::Ref { pat, .. } => f(*pat),
@@ -801,11 +807,12 @@ where
             ast::ExprKind::Literal(e) => {
                 let lit = match e.kind() {
                     LiteralKind::IntNumber { suffix } => {
+                        let value = parse_int_literal(e.token().text(), suffix.as_ref());
                         let known_name = suffix
                             .and_then(|it| IntTy::from_suffix(&it).map(UncertainIntTy::Known));
 
                         Literal::Int(
-                            Default::default(),
+                            value.unwrap_or_default(),
                             known_name.unwrap_or(UncertainIntTy::Unknown),
                         )
                     }
@@ -823,7 +830,7 @@ where
                     LiteralKind::Byte => {
                         Literal::Int(Default::default(), UncertainIntTy::Known(IntTy::u8()))
                     }
-                    LiteralKind::Bool => Literal::Bool(Default::default()),
+                    LiteralKind::Bool => Literal::Bool(e.token().kind() == T![true]),
                     LiteralKind::Char => Literal::Char(Default::default()),
                 };
                 self.alloc_expr(Expr::Literal(lit), syntax_ptr)
@@ -909,7 +916,8 @@ where
             ast::PatKind::TupleStructPat(p) => {
                 let path = p.path().and_then(Path::from_ast);
                 let args = p.args().map(|p| self.collect_pat(p)).collect();
-                Pat::TupleStruct { path, args }
+                let ellipsis = ellipsis_position(p.syntax());
+                Pat::TupleStruct { path, args, ellipsis }
             }
             ast::PatKind::RefPat(p) => {
                 let pat = self.collect_pat_opt(p.pat());
@@ -922,7 +930,8 @@ where
             }
             ast::PatKind::TuplePat(p) => {
                 let args = p.args().map(|p| self.collect_pat(p)).collect();
-                Pat::Tuple(args)
+                let ellipsis = ellipsis_position(p.syntax());
+                Pat::Tuple { args, ellipsis }
             }
             ast::PatKind::PlaceholderPat(_) => Pat::Wild,
             ast::PatKind::StructPat(p) => {
@@ -949,8 +958,14 @@ where
                 Pat::Struct { path, args: fields }
             }
 
+            ast::PatKind::LiteralPat(p) => match p.literal() {
+                Some(lit) => {
+                    let expr = ast::Expr::cast(lit.syntax()).expect("a literal is an expression");
+                    Pat::Lit(self.collect_expr(expr))
+                }
+                None => Pat::Missing,
+            },
             // FIXME: implement
-            ast::PatKind::LiteralPat(_) => Pat::Missing,
             ast::PatKind::SlicePat(_) | ast::PatKind::RangePat(_) => Pat::Missing,
         };
         let ptr = AstPtr::new(pat);
@@ -1017,6 +1032,37 @@ where
     }
 }
 
+/// The number of patterns before the `..` of a tuple or tuple struct pattern.
+fn ellipsis_position(node: &SyntaxNode) -> Option<usize> {
+    let mut pats = 0;
+    for child in node.children_with_tokens() {
+        if child.kind() == T![..] {
+            return Some(pats);
+        }
+        if child.as_node().and_then(ast::Pat::cast).is_some() {
+            pats += 1;
+        }
+    }
+    None
+}
+
+/// Parses the value of an integer literal like `0x_ff_u8`. Returns `None` if
+/// the value doesn't fit into a `u64`.
+pub(crate) fn parse_int_literal(text: &str, suffix: Option<&SmolStr>) -> Option<u64> {
+    let text = match suffix {
+        Some(suffix) => &text[..text.len() - suffix.len()],
+        None => text,
+    };
+    let (digits, radix) = match text.get(..2) {
+        Some("0x") => (&text[2..], 16),
+        Some("0o") => (&text[2..], 8),
+        Some("0b") => (&text[2..], 2),
+        _ => (text, 10),
+    };
+    let digits: String = digits.chars().filter(|&c| c != '_').collect();
+    u64::from_str_radix(&digits, radix).ok()
+}
+
 pub(crate) fn body_with_source_map_query(
     db: &impl HirDatabase,
     def: DefWithBody,
@@ -0,0 +1,380 @@
+//! Computes the values a `match` doesn't cover, as the patterns of the arms
+//! which are missing.
+//!
+//! This is the usefulness algorithm from "Warnings for pattern matching" by
+//! Luc Maranget: the arms are rows of a pattern matrix, and the matrix is
+//! split by the constructors of its first column until no column is left. A
+//! row of wildcards which is still useful at the end is a value the arms
+//! don't match, and the constructors it was split by make up its pattern.
+//! When some constructors of a column start no row, only the rows starting
+//! with a wildcard are looked at, and the missing rows start with the absent
+//! constructors.
+//!
+//! Enums, structs, tuples and bools have constructors which can be
+//! enumerated. Values of other types, like integers, are only covered by a
+//! wildcard. Patterns which are not lowered yet are assumed to match
+//! anything, so that the check reports too few arms rather than too many.
+
+use crate::{
+    adt::VariantDef,
+    expr::{Body, Expr, ExprId, Literal, Pat, PatId},
+    resolve::{Resolution, Resolver},
+    ty::{InferenceResult, Ty, TypeCtor},
+    AdtDef, HirDatabase, ModuleDef, Name, Path,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Constructor {
+    Bool(bool),
+    Tuple(usize),
+    /// A struct or an enum variant.
+    Variant(VariantDef),
+}
+
+#[derive(Debug, Clone)]
+enum Pattern {
+    Wild,
+    /// A pattern matching values which can't be enumerated, like an integer
+    /// literal or a constant.
+    Opaque,
+    Constructor(Constructor, Vec<Pattern>),
+}
+
+/// The patterns of the values a match of `scrutinee` against `pats` doesn't
+/// cover, rendered as source text. Arms with a guard cover no values and are
+/// to be left out of `pats`. `None` if there are no arms and the type of the
+/// scrutinee is unknown.
+pub(crate) fn missing_patterns(
+    db: &impl HirDatabase,
+    body: &Body,
+    infer: &InferenceResult,
+    resolver: &Resolver,
+    scrutinee: ExprId,
+    pats: &[PatId],
+) -> Option<Vec<String>> {
+    let cx = MatchCheck { db, body, infer, resolver };
+    let rows = pats.iter().map(|&pat| vec![cx.lower(pat)]).collect::<Vec<_>>();
+    // An empty match is split by the type, to list each of its constructors
+    let constructors = cx.constructors_of_ty(&infer[scrutinee]);
+    if rows.is_empty() && infer[scrutinee] == Ty::Unknown {
+        return None;
+    }
+    let missing = cx
+        .missing(&rows, 1, constructors)
+        .into_iter()
+        .flat_map(|row| row.into_iter())
+        .map(|pat| cx.render(&pat))
+        .collect();
+    Some(missing)
+}
+
+struct MatchCheck<'a, DB> {
+    db: &'a DB,
+    body: &'a Body,
+    infer: &'a InferenceResult,
+    resolver: &'a Resolver,
+}
+
+impl<'a, DB: HirDatabase> MatchCheck<'a, DB> {
+    fn lower(&self, pat: PatId) -> Pattern {
+        match &self.body[pat] {
+            Pat::Wild | Pat::Missing | Pat::Range { .. } | Pat::Slice { .. } => Pattern::Wild,
+            Pat::Bind { subpat: Some(subpat), .. } => self.lower(*subpat),
+            Pat::Bind { name, subpat: None, .. } => {
+                // `None` is a path to a unit variant, not a binding
+                match self.resolver.resolve_name(self.db, name).take_values() {
+                    Some(Resolution::Def(def)) => self.lower_def(def),
+                    _ => Pattern::Wild,
+                }
+            }
+            // References have a single constructor, and are left out
+            Pat::Ref { pat, .. } => self.lower(*pat),
+            Pat::Lit(expr) => match &self.body[*expr] {
+                Expr::Literal(Literal::Bool(value)) => {
+                    Pattern::Constructor(Constructor::Bool(*value), Vec::new())
+                }
+                _ => Pattern::Opaque,
+            },
+            Pat::Path(path) => {
+                match self.resolver.resolve_path_without_assoc_items(self.db, path).take_values() {
+                    Some(Resolution::Def(def)) => self.lower_def(def),
+                    _ => Pattern::Wild,
+                }
+            }
+            Pat::Tuple { args, ellipsis } => {
+                let arity = match ellipsis {
+                    None => args.len(),
+                    Some(_) => match self.infer[pat].as_tuple() {
+                        Some(tys) => tys.len(),
+                        None => return Pattern::Wild,
+                    },
+                };
+                match self.lower_args(args, *ellipsis, arity) {
+                    Some(fields) => Pattern::Constructor(Constructor::Tuple(arity), fields),
+                    None => Pattern::Wild,
+                }
+            }
+            Pat::TupleStruct { path, args, ellipsis } => {
+                let variant = match path.as_ref().and_then(|it| self.resolve_variant(it)) {
+                    Some(it) => it,
+                    None => return Pattern::Wild,
+                };
+                match self.lower_args(args, *ellipsis, self.arity(Constructor::Variant(variant))) {
+                    Some(fields) => Pattern::Constructor(Constructor::Variant(variant), fields),
+                    None => Pattern::Wild,
+                }
+            }
+            Pat::Struct { path, args } => {
+                let variant = match path.as_ref().and_then(|it| self.resolve_variant(it)) {
+                    Some(it) => it,
+                    None => return Pattern::Wild,
+                };
+                let data = variant.variant_data(self.db);
+                let field_names = data
+                    .fields()
+                    .into_iter()
+                    .flat_map(|it| it.iter())
+                    .map(|(_, field)| &field.name)
+                    .collect::<Vec<_>>();
+                // Fields which are left out are matched by `..`
+                let mut fields = vec![Pattern::Wild; field_names.len()];
+                for arg in args {
+                    if let Some(idx) = field_names.iter().position(|&it| *it == arg.name) {
+                        fields[idx] = self.lower(arg.pat);
+                    }
+                }
+                Pattern::Constructor(Constructor::Variant(variant), fields)
+            }
+        }
+    }
+
+    /// Lowers the patterns of a tuple or a tuple struct, where `..` matches
+    /// the fields between the patterns before and after it.
+    fn lower_args(
+        &self,
+        args: &[PatId],
+        ellipsis: Option<usize>,
+        arity: usize,
+    ) -> Option<Vec<Pattern>> {
+        let (before, after) = args.split_at(ellipsis.unwrap_or(args.len()));
+        if args.len() > arity || (ellipsis.is_none() && args.len() != arity) {
+            return None;
+        }
+        let mut fields = before.iter().map(|&it| self.lower(it)).collect::<Vec<_>>();
+        fields.resize(arity - after.len(), Pattern::Wild);
+        fields.extend(after.iter().map(|&it| self.lower(it)));
+        Some(fields)
+    }
+
+    fn lower_def(&self, def: ModuleDef) -> Pattern {
+        let variant: VariantDef = match def {
+            ModuleDef::EnumVariant(it) => it.into(),
+            ModuleDef::Struct(it) => it.into(),
+            ModuleDef::Const(_) | ModuleDef::Static(_) => return Pattern::Opaque,
+            _ => return Pattern::Wild,
+        };
+        let constructor = Constructor::Variant(variant);
+        Pattern::Constructor(constructor, vec![Pattern::Wild; self.arity(constructor)])
+    }
+
+    fn resolve_variant(&self, path: &Path) -> Option<VariantDef> {
+        match self.resolver.resolve_path_without_assoc_items(self.db, path).take_types()? {
+            Resolution::Def(ModuleDef::EnumVariant(it)) => Some(it.into()),
+            Resolution::Def(ModuleDef::Struct(it)) => Some(it.into()),
+            _ => None,
+        }
+    }
+
+    fn arity(&self, constructor: Constructor) -> usize {
+        match constructor {
+            Constructor::Bool(_) => 0,
+            Constructor::Tuple(arity) => arity,
+            Constructor::Variant(variant) => {
+                variant.variant_data(self.db).fields().map_or(0, |it| it.len())
+            }
+        }
+    }
+
+    /// All the constructors of the type `constructor` belongs to.
+    fn all_constructors(&self, constructor: Constructor) -> Vec<Constructor> {
+        match constructor {
+            Constructor::Bool(_) => vec![Constructor::Bool(true), Constructor::Bool(false)],
+            Constructor::Variant(VariantDef::EnumVariant(variant)) => self.variants(
+                variant.parent_enum(self.db).variants(self.db).into_iter().map(Into::into),
+            ),
+            Constructor::Tuple(_) | Constructor::Variant(VariantDef::Struct(_)) => {
+                vec![constructor]
+            }
+        }
+    }
+
+    fn constructors_of_ty(&self, ty: &Ty) -> Option<Vec<Constructor>> {
+        let mut ty = ty;
+        while let Some((inner, _)) = ty.as_reference() {
+            ty = inner;
+        }
+        let ctor = match ty {
+            Ty::Apply(it) => it.ctor,
+            _ => return None,
+        };
+        let res = match ctor {
+            TypeCtor::Bool => vec![Constructor::Bool(true), Constructor::Bool(false)],
+            TypeCtor::Tuple { cardinality } => vec![Constructor::Tuple(cardinality as usize)],
+            TypeCtor::Adt(AdtDef::Enum(it)) => {
+                self.variants(it.variants(self.db).into_iter().map(Into::into))
+            }
+            TypeCtor::Adt(AdtDef::Struct(it)) => vec![Constructor::Variant(it.into())],
+            TypeCtor::Never => Vec::new(),
+            _ => return None,
+        };
+        Some(res)
+    }
+
+    fn variants(&self, variants: impl Iterator<Item = VariantDef>) -> Vec<Constructor> {
+        variants.map(Constructor::Variant).collect()
+    }
+
+    /// The rows of `width` patterns matching the values none of `rows` does.
+    /// If the first column has no constructors, it is split by `split`.
+    fn missing(
+        &self,
+        rows: &[Vec<Pattern>],
+        width: usize,
+        split: Option<Vec<Constructor>>,
+    ) -> Vec<Vec<Pattern>> {
+        if width == 0 {
+            return if rows.is_empty() { vec![Vec::new()] } else { Vec::new() };
+        }
+        let used = rows.iter().find_map(|row| match &row[0] {
+            Pattern::Constructor(constructor, _) => Some(*constructor),
+            _ => None,
+        });
+        let constructors = match used.map(|it| self.all_constructors(it)).or(split) {
+            Some(it) => it,
+            None => {
+                // Only the wildcards match the values of the first column
+                return self
+                    .missing(&default_rows(rows), width - 1, None)
+                    .into_iter()
+                    .map(|row| std::iter::once(Pattern::Wild).chain(row).collect())
+                    .collect();
+            }
+        };
+
+        // The values of the constructors no row starts with are only matched
+        // by the wildcards, so the rows missing from those are missing for
+        // each of the constructors
+        let absent = constructors
+            .iter()
+            .copied()
+            .filter(|&constructor| {
+                !rows.iter().any(|row| match &row[0] {
+                    Pattern::Constructor(it, _) => *it == constructor,
+                    _ => false,
+                })
+            })
+            .collect::<Vec<_>>();
+        if !absent.is_empty() {
+            let mut res = Vec::new();
+            for row in self.missing(&default_rows(rows), width - 1, None) {
+                for &constructor in absent.iter() {
+                    let fields = vec![Pattern::Wild; self.arity(constructor)];
+                    let pat = Pattern::Constructor(constructor, fields);
+                    res.push(std::iter::once(pat).chain(row.iter().cloned()).collect());
+                }
+            }
+            return res;
+        }
+
+        let mut res = Vec::new();
+        for constructor in constructors {
+            let arity = self.arity(constructor);
+            let specialized = rows
+                .iter()
+                .filter_map(|row| {
+                    let mut fields = match &row[0] {
+                        Pattern::Wild => vec![Pattern::Wild; arity],
+                        Pattern::Constructor(it, fields) if *it == constructor => fields.clone(),
+                        _ => return None,
+                    };
+                    fields.extend_from_slice(&row[1..]);
+                    Some(fields)
+                })
+                .collect::<Vec<_>>();
+            for mut row in self.missing(&specialized, width - 1 + arity, None) {
+                let rest = row.split_off(arity);
+                let pat = Pattern::Constructor(constructor, row);
+                res.push(std::iter::once(pat).chain(rest).collect());
+            }
+        }
+        res
+    }
+
+    fn render(&self, pat: &Pattern) -> String {
+        let (constructor, fields) = match pat {
+            Pattern::Wild | Pattern::Opaque => return "_".to_string(),
+            Pattern::Constructor(constructor, fields) => (*constructor, fields),
+        };
+        let render_all = || fields.iter().map(|it| self.render(it)).collect::<Vec<_>>();
+        let variant = match constructor {
+            Constructor::Bool(value) => return value.to_string(),
+            Constructor::Tuple(1) => return format!("({},)", self.render(&fields[0])),
+            Constructor::Tuple(_) => return format!("({})", render_all().join(", ")),
+            Constructor::Variant(it) => it,
+        };
+        let path = self.variant_path(variant);
+        let data = variant.variant_data(self.db);
+        let field_data = match data.fields() {
+            Some(it) => it,
+            None => return path,
+        };
+        if data.is_tuple() {
+            return format!("{}({})", path, render_all().join(", "));
+        }
+        // Named fields matched by a wildcard are left to `..`
+        let mut items = field_data
+            .iter()
+            .zip(fields)
+            .filter(|(_, pat)| match pat {
+                Pattern::Wild => false,
+                _ => true,
+            })
+            .map(|((_, field), pat)| format!("{}: {}", field.name, self.render(pat)))
+            .collect::<Vec<_>>();
+        if items.len() < field_data.len() {
+            items.push("..".to_string());
+        }
+        if items.is_empty() {
+            return format!("{} {{}}", path);
+        }
+        format!("{} {{ {} }}", path, items.join(", "))
+    }
+
+    /// Variants in scope, like `Some`, are written without their enum.
+    fn variant_path(&self, variant: VariantDef) -> String {
+        let variant = match variant {
+            VariantDef::Struct(it) => {
+                return it.name(self.db).unwrap_or_else(Name::missing).to_string()
+            }
+            VariantDef::EnumVariant(it) => it,
+        };
+        let name = variant.name(self.db).unwrap_or_else(Name::missing);
+        let resolution = self.resolver.resolve_name(self.db, &name).take_values();
+        if resolution == Some(Resolution::Def(ModuleDef::EnumVariant(variant))) {
+            return name.to_string();
+        }
+        let enum_name = variant.parent_enum(self.db).name(self.db).unwrap_or_else(Name::missing);
+        format!("{}::{}", enum_name, name)
+    }
+}
+
+/// The rows starting with a wildcard, without it.
+fn default_rows(rows: &[Vec<Pattern>]) -> Vec<Vec<Pattern>> {
+    rows.iter()
+        .filter(|row| match row[0] {
+            Pattern::Wild => true,
+            _ => false,
+        })
+        .map(|row| row[1..].to_vec())
+        .collect()
+}
@@ -1,15 +1,21 @@
 use rustc_hash::FxHashSet;
 use std::sync::Arc;
 
-use ra_syntax::ast::{AstNode, StructLit};
+use ra_syntax::{
+    algo,
+    ast::{self, AstNode, StructLit},
+    SyntaxElement,
+    SyntaxKind::{IDENT, TOKEN_TREE},
+};
 
-use super::{Expr, ExprId, StructLitField};
+use super::{match_check, resolver_for_expr, Expr, ExprId, MatchArm, Pat, StructLitField};
 use crate::{
     adt::AdtDef,
-    diagnostics::{DiagnosticSink, MissingFields},
+    diagnostics::{DiagnosticSink, MissingFields, MissingMatchArms, UnusedVariable},
     expr::AstPtr,
+    resolve::Resolution,
     ty::InferenceResult,
-    Function, HasSource, HirDatabase, Name, Path,
+    Either, Function, HasSource, HirDatabase, Name, Path,
 };
 
 pub(crate) struct ExprValidator<'a, 'b: 'a> {
@@ -30,10 +36,15 @@ impl<'a, 'b> ExprValidator<'a, 'b> {
     pub(crate) fn validate_body(&mut self, db: &impl HirDatabase) {
         let body = self.func.body(db);
         for e in body.exprs() {
-            if let (id, Expr::StructLit { path, fields, spread }) = e {
-                self.validate_struct_literal(id, path, fields, *spread, db);
+            match e {
+                (id, Expr::StructLit { path, fields, spread }) => {
+                    self.validate_struct_literal(id, path, fields, *spread, db);
+                }
+                (id, Expr::Match { expr, arms }) => self.validate_match(id, *expr, arms, db),
+                _ => (),
             }
         }
+        self.validate_unused_variables(db);
     }
 
     fn validate_struct_literal(
@@ -86,4 +97,129 @@ impl<'a, 'b> ExprValidator<'a, 'b> {
             })
         }
     }
+
+    fn validate_match(
+        &mut self,
+        id: ExprId,
+        expr: ExprId,
+        arms: &[MatchArm],
+        db: &impl HirDatabase,
+    ) {
+        let file_id = self.func.source(db).file_id;
+        // The fix edits the original file, which doesn't contain the arms of
+        // functions defined by macros
+        if file_id != file_id.original_file(db).into() {
+            return;
+        }
+        let root = match db.parse_or_expand(file_id) {
+            Some(it) => it,
+            None => return,
+        };
+        let body = self.func.body(db);
+        let source_map = self.func.body_source_map(db);
+        // `if let` and `while let` are lowered to matches too. Matches expanded
+        // from macro calls point into the expansion, so they aren't found in
+        // the file.
+        let match_expr = match source_map.expr_syntax(id).and_then(|ptr| {
+            if !ptr.range().is_subrange(&root.range()) {
+                return None;
+            }
+            let node = match algo::find_covering_element(&root, ptr.range()) {
+                SyntaxElement::Node(it) => it,
+                SyntaxElement::Token(it) => it.parent(),
+            };
+            node.ancestors()
+                .take_while(|it| it.range() == ptr.range())
+                .find_map(ast::MatchExpr::cast)
+        }) {
+            Some(it) => it,
+            None => return,
+        };
+        let (scrutinee, arm_list) = match (match_expr.expr(), match_expr.match_arm_list()) {
+            (Some(scrutinee), Some(arm_list)) => (scrutinee, arm_list),
+            _ => return,
+        };
+
+        let pats = arms
+            .iter()
+            .filter(|arm| arm.guard.is_none())
+            .flat_map(|arm| arm.pats.iter().copied())
+            .collect::<Vec<_>>();
+        let resolver = resolver_for_expr(body.clone(), db, id);
+        let missed_patterns =
+            match match_check::missing_patterns(db, &body, &self.infer, &resolver, expr, &pats) {
+                Some(it) => it,
+                None => return,
+            };
+        if missed_patterns.is_empty() {
+            return;
+        }
+        self.sink.push(MissingMatchArms {
+            file: file_id,
+            expr: AstPtr::new(scrutinee),
+            arms: AstPtr::new(arm_list),
+            missed_patterns,
+        })
+    }
+
+    /// Reports the bindings which are never read. Names starting with `_` are
+    /// deliberately unused.
+    fn validate_unused_variables(&mut self, db: &impl HirDatabase) {
+        let source = self.func.source(db);
+        // Declarations without a body, like trait methods, have nothing to read
+        // their parameters
+        if source.ast.body().is_none() {
+            return;
+        }
+        // Bindings of functions defined by macros can't be renamed in the
+        // original file
+        if source.file_id != source.file_id.original_file(db).into() {
+            return;
+        }
+        let body = self.func.body(db);
+        let source_map = self.func.body_source_map(db);
+
+        let mut used = FxHashSet::default();
+        for (id, expr) in body.exprs() {
+            if let Expr::Path(path) = expr {
+                let resolver = resolver_for_expr(body.clone(), db, id);
+                let resolution = resolver.resolve_path_without_assoc_items(db, path);
+                if let Some(Resolution::LocalBinding(pat)) = resolution.take_values() {
+                    used.insert(pat);
+                }
+            }
+        }
+        // Unexpanded macro calls may read any binding
+        let macro_idents: FxHashSet<_> = source
+            .ast
+            .syntax()
+            .descendants_with_tokens()
+            .filter_map(|it| it.as_token())
+            .filter(|it| it.kind() == IDENT && it.parent().kind() == TOKEN_TREE)
+            .map(|it| it.text().clone())
+            .collect();
+
+        let resolver = self.func.resolver(db);
+        for (id, pat) in body.pats() {
+            let name = match pat {
+                Pat::Bind { name, .. } => name,
+                _ => continue,
+            };
+            if used.contains(&id)
+                || name.as_smolstr().starts_with('_')
+                || *name == Name::self_param()
+                || *name == Name::missing()
+                || macro_idents.contains(name.as_smolstr())
+            {
+                continue;
+            }
+            // `None` in `let None = x` is a path to an enum variant, not a binding
+            if resolver.resolve_binding_pattern(db, name).is_some() {
+                continue;
+            }
+            if let Some(Either::A(pat)) = source_map.pat_syntax(id) {
+                self.sink.push(UnusedVariable { file: source.file_id, pat, name: name.clone() });
+            }
+        }
+    }
 }
@@ -24,11 +24,20 @@ pub struct GenericParam {
     pub(crate) default: Option<Path>,
 }
 
+/// Data about a lifetime parameter, like `'a` in `struct Foo<'a>`. Lifetimes
+/// are numbered separately from the type parameters.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LifetimeParam {
+    pub(crate) idx: u32,
+    pub(crate) name: Name,
+}
+
 /// Data about the generic parameters of a function, struct, impl, etc.
 #[derive(Clone, PartialEq, Eq, Debug, Default)]
 pub struct GenericParams {
     pub(crate) parent_params: Option<Arc<GenericParams>>,
     pub(crate) params: Vec<GenericParam>,
+    pub(crate) lifetimes: Vec<LifetimeParam>,
     pub(crate) where_predicates: Vec<WherePredicate>,
 }
 
@@ -106,6 +115,14 @@ impl GenericParams {
     }
 
     fn fill_params(&mut self, params: &ast::TypeParamList, start: u32) {
+        let lifetime_start = self.count_parent_lifetimes() as u32;
+        for (idx, lifetime_param) in params.lifetime_params().enumerate() {
+            let name = match lifetime_param.lifetime_token() {
+                Some(lt) => Name::new_lifetime(lt),
+                None => Name::missing(),
+            };
+            self.lifetimes.push(LifetimeParam { idx: idx as u32 + lifetime_start, name });
+        }
         for (idx, type_param) in params.type_params().enumerate() {
             let name = type_param.name().map(AsName::as_name).unwrap_or_else(Name::missing);
             let default = type_param.default_type().and_then(|t| t.path()).and_then(Path::from_ast);
@@ -138,14 +155,7 @@ impl GenericParams {
     }
 
     fn add_where_predicate_from_bound(&mut self, bound: &ast::TypeBound, type_ref: TypeRef) {
-        let path = bound
-            .type_ref()
-            .and_then(|tr| match tr.kind() {
-                ast::TypeRefKind::PathType(path) => path.path(),
-                _ => None,
-            })
-            .and_then(Path::from_ast);
-        let path = match path {
+        let path = match trait_path_from_bound(bound) {
             Some(p) => p,
             None => return,
         };
@@ -165,6 +175,14 @@ impl GenericParams {
         parent_count + self.params.len()
     }
 
+    pub fn count_parent_lifetimes(&self) -> usize {
+        self.parent_params.as_ref().map(|p| p.count_lifetimes_including_parent()).unwrap_or(0)
+    }
+
+    pub fn count_lifetimes_including_parent(&self) -> usize {
+        self.count_parent_lifetimes() + self.lifetimes.len()
+    }
+
     fn for_each_param<'a>(&'a self, f: &mut impl FnMut(&'a GenericParam)) {
         if let Some(parent) = &self.parent_params {
             parent.for_each_param(f);
@@ -177,6 +195,29 @@ impl GenericParams {
         self.for_each_param(&mut |p| vec.push(p));
         vec
     }
+
+    pub(crate) fn find_lifetime_by_name(&self, name: &Name) -> Option<&LifetimeParam> {
+        self.lifetimes.iter().find(|p| &p.name == name)
+    }
+
+    pub fn lifetimes_including_parent(&self) -> Vec<&LifetimeParam> {
+        let mut vec = match &self.parent_params {
+            Some(parent) => parent.lifetimes_including_parent(),
+            None => Vec::with_capacity(self.lifetimes.len()),
+        };
+        vec.extend(self.lifetimes.iter());
+        vec
+    }
+}
+
+/// Extracts the trait path from a bound like `T: Trait`. Higher-ranked bounds
+/// like `F: for<'a> Fn(&'a u8)` are treated like the inner bound, since we
+/// don't model lifetimes.
+pub(crate) fn trait_path_from_bound(bound: &ast::TypeBound) -> Option<Path> {
+    match TypeRef::from_ast_opt(bound.type_ref()) {
+        TypeRef::Path(path) => Some(path),
+        _ => None,
+    }
 }
 
 impl GenericDef {
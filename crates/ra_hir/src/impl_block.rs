@@ -103,7 +103,8 @@ impl ImplBlock {
         let r = self.module().resolver(db);
         // add generic params, if present
         let p = self.generic_params(db);
-        let r = if !p.params.is_empty() { r.push_generic_params_scope(p) } else { r };
+        let r =
+            if !p.params.is_empty() { r.push_generic_params_scope((*self).into(), p) } else { r };
         let r = r.push_impl_block_scope(self.clone());
         r
     }
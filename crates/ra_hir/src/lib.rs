@@ -42,6 +42,7 @@ mod resolve;
 pub mod diagnostics;
 
 mod code_model;
+mod visibility;
 
 #[cfg(test)]
 mod marks;
@@ -55,10 +56,10 @@ use crate::{
 };
 
 pub use self::{
-    adt::AdtDef,
+    adt::{AdtDef, VariantDef},
     either::Either,
     expr::ExprScopes,
-    generics::{GenericParam, GenericParams, HasGenericParams},
+    generics::{GenericParam, GenericParams, HasGenericParams, LifetimeParam},
     ids::{HirFileId, MacroCallId, MacroCallLoc, MacroDefId, MacroFile},
     impl_block::{ImplBlock, ImplItem},
     name::Name,
@@ -67,11 +68,17 @@ pub use self::{
     resolve::Resolution,
     source_binder::{PathResolution, ScopeEntryWithSyntax, SourceAnalyzer},
     source_id::{AstIdMap, ErasedFileAstId},
-    ty::{display::HirDisplay, ApplicationTy, CallableDef, Substs, TraitRef, Ty, TypeCtor},
+    traits::TraitItem,
+    ty::{
+        consteval::ConstValue, display::HirDisplay, ApplicationTy, CallableDef, Lifetime, Substs,
+        TraitRef, Ty, TypeCtor,
+    },
+    type_ref::Mutability,
+    visibility::Visibility,
 };
 
 pub use self::code_model::{
-    docs::{DocDef, Docs, Documentation},
+    docs::{docs_from_ast, DocDef, Docs, Documentation},
     src::{HasSource, Source},
     BuiltinType, Const, ConstData, Container, Crate, CrateDependency, DefWithBody, Enum,
     EnumVariant, FieldSource, FnData, Function, MacroDef, Module, ModuleDef, ModuleSource, Static,
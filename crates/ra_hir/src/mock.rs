@@ -8,7 +8,10 @@ use relative_path::RelativePathBuf;
 use rustc_hash::FxHashMap;
 use test_utils::{extract_offset, parse_fixture, CURSOR_MARKER};
 
-use crate::{db, diagnostics::DiagnosticSink};
+use crate::{
+    db,
+    diagnostics::{Diagnostic, DiagnosticSink},
+};
 
 pub const WORKSPACE: SourceRootId = SourceRootId(0);
 
@@ -75,6 +78,12 @@ impl MockDatabase {
     }
 
     pub fn diagnostics(&self) -> String {
+        self.diagnostics_matching(|_| true)
+    }
+
+    /// The diagnostics for which `f` is true, leaving out the ones which are
+    /// not under test.
+    pub fn diagnostics_matching(&self, f: impl Fn(&dyn Diagnostic) -> bool) -> String {
         let mut buf = String::from("\n");
         let mut files: Vec<FileId> = self.files.values().copied().collect();
         files.sort();
@@ -83,7 +92,9 @@ impl MockDatabase {
             module.diagnostics(
                 self,
                 &mut DiagnosticSink::new(|d| {
-                    buf += &format!("{:?}: {}\n", d.syntax_node(self).text(), d.message());
+                    if f(d) {
+                        buf += &format!("{:?}: {}\n", d.syntax_node(self).text(), d.message());
+                    }
                 }),
             )
         }
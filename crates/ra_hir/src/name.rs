@@ -1,6 +1,6 @@
 use std::fmt;
 
-use ra_syntax::{ast, SmolStr};
+use ra_syntax::{ast, SmolStr, SyntaxToken};
 
 /// `Name` is a wrapper around string, which is used in hir for both references
 /// and declarations. In theory, names should also carry hygiene info, but we are
@@ -42,6 +42,16 @@ impl Name {
         Name::new("Self".into())
     }
 
+    /// The name of a lifetime, including the leading `'`.
+    pub(crate) fn new_lifetime(lt: SyntaxToken) -> Name {
+        Name::new(lt.text().clone())
+    }
+
+    /// The name of an identifier token, like the ones in macro arguments.
+    pub(crate) fn new_ident(ident: SyntaxToken) -> Name {
+        Name::new(resolve_name(ident.text()))
+    }
+
     pub(crate) fn tuple_field_name(idx: usize) -> Name {
         Name::new(idx.to_string().into())
     }
@@ -51,6 +61,12 @@ impl Name {
         Name::new("Target".into())
     }
 
+    // Needed to evaluate `size_of` in constants; `std` reexports it from `core`
+    pub(crate) fn size_of_paths() -> [[Name; 3]; 2] {
+        let name = |text: &str| Name::new(text.into());
+        [[name("core"), name("mem"), name("size_of")], [name("std"), name("mem"), name("size_of")]]
+    }
+
     // There's should be no way to extract a string out of `Name`: `Name` in the
     // future, `Name` will include hygiene information, and you can't encode
     // hygiene into a String.
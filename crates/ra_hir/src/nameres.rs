@@ -50,6 +50,7 @@
 mod per_ns;
 mod raw;
 mod collector;
+mod unused_imports;
 #[cfg(test)]
 mod tests;
 
@@ -69,7 +70,10 @@ use crate::{
     DefDatabase, HirFileId, MacroDef, Module, ModuleDef, Name, Path, PathKind, Trait,
 };
 
-pub(crate) use self::raw::{ImportSourceMap, RawItems};
+pub(crate) use self::{
+    raw::{ImportSourceMap, RawItems},
+    unused_imports::unused_imports_query,
+};
 
 pub use self::{
     per_ns::{Namespace, PerNs},
@@ -600,3 +600,42 @@ fn unresolved_module_diagnostics() {
 "###
     );
 }
+
+#[test]
+fn unused_import_diagnostics() {
+    let diagnostics = MockDatabase::with_files(
+        r"
+        //- /lib.rs
+        mod foo;
+        use foo::{Bar, Baz, Tr};
+        pub use foo::Qux;
+        use foo::Unresolved;
+        use foo::Child;
+        use foo::Glob;
+        mod bar {
+            use super::Bar;
+            fn f(_: Bar) {}
+        }
+        mod baz;
+        //- /baz.rs
+        use super::*;
+        fn g(_: Glob) {}
+        //- /foo.rs
+        pub struct Bar;
+        pub struct Baz;
+        pub struct Qux;
+        pub struct Child;
+        pub struct Glob;
+        pub trait Tr {}
+        mod child {
+            fn f() -> super::super::Child {}
+        }
+        ",
+    )
+    .diagnostics();
+
+    assert_snapshot_matches!(diagnostics, @r###"
+"Baz": unused import
+"###
+    );
+}
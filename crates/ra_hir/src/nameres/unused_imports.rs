@@ -0,0 +1,255 @@
+//! Finds the imports of a module which are not used.
+//!
+//! The candidates come from the module scope, which records the import each
+//! name was resolved through. Every path of the module's file is resolved,
+//! and the imports its first segment goes through are used. Child modules in
+//! other files can only use the imports through explicit `super::` paths, so
+//! only those paths of theirs are resolved. Names imported by a glob are
+//! followed to the import of the globbed module. Arguments of macro calls
+//! can't be resolved before expansion, so the imports of the names in them are
+//! considered used.
+
+use std::sync::Arc;
+
+use ra_db::{CheckCanceled, FileId};
+use ra_syntax::{
+    ast::{self, VisibilityOwner},
+    AstNode,
+    SyntaxKind::*,
+    SyntaxNode,
+};
+use rustc_hash::FxHashSet;
+
+use crate::{
+    name::{AsName, Name},
+    path::{Path, PathKind},
+    resolve::{Resolution, Resolver},
+    source_binder::{module_from_child_node, resolver_for_node},
+    Either, HirDatabase, HirFileId, ImportId, Module, ModuleDef, ModuleSource,
+};
+
+pub(crate) fn unused_imports_query(db: &impl HirDatabase, module: Module) -> Arc<Vec<ImportId>> {
+    Arc::new(unused_imports(db, module))
+}
+
+fn unused_imports(db: &impl HirDatabase, module: Module) -> Vec<ImportId> {
+    let src = module.definition_source(db);
+    let file_id = src.file_id.original_file(db);
+    // Imports in macro expansions are not reported
+    if src.file_id != HirFileId::from(file_id) {
+        return Vec::new();
+    }
+    let container = match &src.ast {
+        ModuleSource::SourceFile(file) => file.syntax(),
+        ModuleSource::Module(module) => match module.item_list() {
+            Some(it) => it.syntax(),
+            None => return Vec::new(),
+        },
+    };
+    let mut used = UsedImports::default();
+    collect_used_imports(db, file_id, container, false, &mut used);
+    // Inline child modules are part of the container, the others only use
+    // the imports through `super::`
+    for child in module.children(db) {
+        db.check_canceled();
+        let child_src = child.definition_source(db);
+        if let ModuleSource::SourceFile(file) = &child_src.ast {
+            let file_id = child_src.file_id.original_file(db);
+            let super_only = !has_super_glob(file);
+            collect_used_imports(db, file_id, file.syntax(), super_only, &mut used);
+        }
+    }
+
+    let mut res = Vec::new();
+    for (_, resolution) in module.scope(db).entries() {
+        let import = match resolution.import {
+            Some(it) => it,
+            None => continue,
+        };
+        // Imported traits are used by method calls without being named
+        if let Some(ModuleDef::Trait(_)) = resolution.def.take_types() {
+            continue;
+        }
+        if resolution.def.is_none() || used.imports.contains(&(module, import)) {
+            continue;
+        }
+        let use_tree = match module.import_source(db, import) {
+            Either::A(it) => it,
+            Either::B(_) => continue,
+        };
+        if use_tree.has_star() || is_reexport(&use_tree) || is_underscore(&use_tree) {
+            continue;
+        }
+        if !res.contains(&import) {
+            res.push(import);
+        }
+    }
+    res
+}
+
+#[derive(Default)]
+struct UsedImports {
+    imports: FxHashSet<(Module, ImportId)>,
+    /// The names each import was followed for, to stop at cycles of globs.
+    globs: FxHashSet<(Module, ImportId, Name)>,
+}
+
+/// Marks the imports used by the paths in `container`. With `super_only`,
+/// only the paths starting with `super::` are looked at.
+fn collect_used_imports(
+    db: &impl HirDatabase,
+    file_id: FileId,
+    container: &SyntaxNode,
+    super_only: bool,
+    used: &mut UsedImports,
+) {
+    for node in container.descendants() {
+        if let Some(use_item) = ast::UseItem::cast(node) {
+            let mut paths = Vec::new();
+            Path::expand_use_item(use_item, |path, _, _, _| {
+                // `use foo;` imports `foo` rather than using it
+                let is_use = path.segments.len() > 1 || path.kind != PathKind::Plain;
+                if is_use && (!super_only || path.kind == PathKind::Super) {
+                    paths.push(path);
+                }
+            });
+            if !paths.is_empty() {
+                let resolver = resolver_for_node(db, file_id, node);
+                for path in paths {
+                    mark_path(db, &resolver, &path, used);
+                }
+            }
+        } else if let Some(path) = ast::Path::cast(node) {
+            let is_qualifier = node.parent().and_then(ast::Path::cast).is_some();
+            let in_use_item = node.ancestors().any(|it| it.kind() == USE_ITEM);
+            if is_qualifier || in_use_item {
+                continue;
+            }
+            let super_count = super_count(path);
+            if super_only && super_count == 0 {
+                continue;
+            }
+            let hir_path = match Path::from_ast(path) {
+                Some(it) => it,
+                None => continue,
+            };
+            // `Path` keeps a single `super`, so `super::super::Foo` is resolved
+            // as `super::Foo` from the parent
+            let resolver = match super_count {
+                0 | 1 => resolver_for_node(db, file_id, node),
+                count => {
+                    let mut module = match module_from_child_node(db, file_id, node) {
+                        Some(it) => it,
+                        None => continue,
+                    };
+                    for _ in 1..count {
+                        module = match module.parent(db) {
+                            Some(it) => it,
+                            None => break,
+                        };
+                    }
+                    module.resolver(db)
+                }
+            };
+            mark_path(db, &resolver, &hir_path, used);
+        } else if super_only {
+            continue;
+        } else if let Some(pat) = ast::BindPat::cast(node) {
+            // `None` in `let None = x` refers to the variant, while other
+            // names are new bindings
+            let name = match pat.name() {
+                Some(it) => it.as_name(),
+                None => continue,
+            };
+            let resolver = resolver_for_node(db, file_id, node);
+            if resolver.resolve_binding_pattern(db, &name).is_some() {
+                mark_path(db, &resolver, &Path::from(name), used);
+            }
+        } else if node.kind() == TOKEN_TREE {
+            let resolver = resolver_for_node(db, file_id, node);
+            let idents = node
+                .children_with_tokens()
+                .filter_map(|it| it.as_token())
+                .filter(|it| it.kind() == IDENT);
+            for ident in idents {
+                let path = Path::from(Name::new_ident(ident));
+                mark_path(db, &resolver, &path, used);
+            }
+        }
+    }
+}
+
+/// Names imported by `use super::*;` are used without `super::`.
+fn has_super_glob(file: &ast::SourceFile) -> bool {
+    file.syntax().descendants().filter_map(ast::UseTree::cast).any(|tree| {
+        let path = tree.path().and_then(Path::from_ast);
+        tree.has_star() && path.map_or(false, |it| it.kind == PathKind::Super)
+    })
+}
+
+fn super_count(path: &ast::Path) -> usize {
+    let mut head = path;
+    while let Some(qualifier) = head.qualifier() {
+        head = qualifier;
+    }
+    let mut count = 0;
+    let mut segment = Some(head);
+    while let Some(path) = segment {
+        match path.segment().and_then(|it| it.kind()) {
+            Some(ast::PathSegmentKind::SuperKw) => count += 1,
+            _ => break,
+        }
+        segment = path.syntax().parent().and_then(ast::Path::cast);
+    }
+    count
+}
+
+fn mark_path(db: &impl HirDatabase, resolver: &Resolver, path: &Path, used: &mut UsedImports) {
+    if let Some((module, import)) = resolver.resolve_path_import(db, path) {
+        mark_import(db, module, import, &path.segments[0].name, used);
+    }
+}
+
+fn mark_import(
+    db: &impl HirDatabase,
+    module: Module,
+    import: ImportId,
+    name: &Name,
+    used: &mut UsedImports,
+) {
+    used.imports.insert((module, import));
+    if !used.globs.insert((module, import, name.clone())) {
+        return;
+    }
+    let use_tree = match module.import_source(db, import) {
+        Either::A(it) if it.has_star() => it,
+        _ => return,
+    };
+    // A name imported by a glob uses the import of the globbed module
+    let path = match use_tree.path().and_then(Path::from_ast) {
+        Some(it) => it,
+        None => return,
+    };
+    let target = match module.resolver(db).resolve_path_without_assoc_items(db, &path).take_types()
+    {
+        Some(Resolution::Def(ModuleDef::Module(it))) => it,
+        _ => return,
+    };
+    if let Some(import) = target.scope(db).get(name).and_then(|it| it.import) {
+        mark_import(db, target, import, name, used);
+    }
+}
+
+fn is_reexport(use_tree: &ast::UseTree) -> bool {
+    use_tree
+        .syntax()
+        .ancestors()
+        .find_map(ast::UseItem::cast)
+        .map_or(false, |use_item| use_item.visibility().is_some())
+}
+
+fn is_underscore(use_tree: &ast::UseTree) -> bool {
+    use_tree.alias().map_or(false, |alias| {
+        alias.syntax().children_with_tokens().any(|it| it.kind() == UNDERSCORE)
+    })
+}
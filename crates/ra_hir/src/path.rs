@@ -2,10 +2,13 @@ use std::sync::Arc;
 
 use ra_syntax::{
     ast::{self, NameOwner},
-    AstNode,
+    AstNode, SmolStr,
 };
 
-use crate::{type_ref::TypeRef, AsName, Name};
+use crate::{
+    type_ref::{LifetimeRef, TypeRef},
+    AsName, Name,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Path {
@@ -25,14 +28,20 @@ pub struct PathSegment {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct GenericArgs {
     pub args: Vec<GenericArg>,
+    /// This specifies whether the args contain a Self type as the first
+    /// element. This is the case for path segments like `<T as Trait>`, where
+    /// `T` is actually a type parameter for the path `Trait` specifying the
+    /// Self type. Otherwise, when we have a path `Trait<X, Y>`, the Self type
+    /// is left out.
+    pub has_self_type: bool,
     // someday also bindings
 }
 
 /// A single generic argument.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum GenericArg {
+    Lifetime(LifetimeRef),
     Type(TypeRef),
-    // or lifetime...
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -74,6 +83,33 @@ impl Path {
                     let segment = PathSegment { name: name.as_name(), args_and_bindings: args };
                     segments.push(segment);
                 }
+                ast::PathSegmentKind::Type { type_ref, trait_ref } => {
+                    // this can only occur at the first segment
+                    let self_type = TypeRef::from_ast(type_ref?);
+                    let prefix = match trait_ref {
+                        // <T>::foo
+                        None => match self_type {
+                            TypeRef::Path(path) => path,
+                            // FIXME: type-relative paths on non-path types like `<[T]>::len`
+                            _ => return None,
+                        },
+                        // <T as Trait<A>>::Foo desugars to Trait<Self=T, A>::Foo
+                        Some(trait_ref) => {
+                            let mut path = Path::from_ast(trait_ref.path()?)?;
+                            let last_segment = path.segments.last_mut()?;
+                            let args = last_segment.args_and_bindings.take();
+                            let mut args =
+                                args.map_or_else(GenericArgs::empty, |args| (*args).clone());
+                            args.has_self_type = true;
+                            args.args.insert(0, GenericArg::Type(self_type));
+                            last_segment.args_and_bindings = Some(Arc::new(args));
+                            path
+                        }
+                    };
+                    kind = prefix.kind;
+                    segments.extend(prefix.segments.into_iter().rev());
+                    break;
+                }
                 ast::PathSegmentKind::CrateKw => {
                     kind = PathKind::Crate;
                     break;
@@ -133,22 +169,47 @@ impl Path {
     pub fn expand_macro_expr(&self) -> Option<Name> {
         self.as_ident().and_then(|name| Some(name.clone()))
     }
+
+    /// The text of the segments of this path, starting with `crate`, `self` or
+    /// `super` for such paths. Generic arguments are left out.
+    pub fn segment_texts(&self) -> Vec<SmolStr> {
+        let prefix = match self.kind {
+            PathKind::Plain => None,
+            PathKind::Self_ => Some("self"),
+            PathKind::Super => Some("super"),
+            PathKind::Crate => Some("crate"),
+            PathKind::Abs => Some(""),
+        };
+        prefix
+            .map(SmolStr::new)
+            .into_iter()
+            .chain(self.segments.iter().map(|it| it.name.as_smolstr().clone()))
+            .collect()
+    }
 }
 
 impl GenericArgs {
     pub(crate) fn from_ast(node: &ast::TypeArgList) -> Option<GenericArgs> {
         let mut args = Vec::new();
+        for lifetime_arg in node.lifetime_args() {
+            let lifetime = LifetimeRef::from_token_opt(lifetime_arg.lifetime_token());
+            args.push(GenericArg::Lifetime(lifetime));
+        }
         for type_arg in node.type_args() {
             let type_ref = TypeRef::from_ast_opt(type_arg.type_ref());
             args.push(GenericArg::Type(type_ref));
         }
-        // lifetimes and assoc type args ignored for now
+        // assoc type args ignored for now
         if !args.is_empty() {
-            Some(GenericArgs { args })
+            Some(GenericArgs { args, has_self_type: false })
         } else {
             None
         }
     }
+
+    pub(crate) fn empty() -> GenericArgs {
+        GenericArgs { args: Vec::new(), has_self_type: false }
+    }
 }
 
 impl From<Name> for Path {
@@ -236,6 +297,10 @@ fn convert_path(prefix: Option<Path>, path: &ast::Path) -> Option<Path> {
             }
             Path { kind: PathKind::Super, segments: Vec::new() }
         }
+        ast::PathSegmentKind::Type { .. } => {
+            // not allowed in imports
+            return None;
+        }
     };
     Some(res)
 }
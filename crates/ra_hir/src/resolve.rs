@@ -11,12 +11,12 @@ use crate::{
         scope::{ExprScopes, ScopeId},
         PatId,
     },
-    generics::GenericParams,
+    generics::{GenericDef, GenericParams, LifetimeParam},
     impl_block::ImplBlock,
     name::{KnownName, Name},
-    nameres::{CrateDefMap, CrateModuleId, PerNs},
-    path::Path,
-    MacroDef, ModuleDef, Trait,
+    nameres::{CrateDefMap, CrateModuleId, ImportId, PerNs},
+    path::{Path, PathKind},
+    MacroDef, Module, ModuleDef, Trait,
 };
 
 #[derive(Debug, Clone, Default)]
@@ -103,7 +103,7 @@ pub(crate) enum Scope {
     /// All the items and imported names of a module
     ModuleScope(ModuleItemMap),
     /// Brings the generic parameters of an item into scope
-    GenericParams(Arc<GenericParams>),
+    GenericParams { def: GenericDef, params: Arc<GenericParams> },
     /// Brings `Self` into scope
     ImplBlockScope(ImplBlock),
     /// Local bindings
@@ -152,6 +152,10 @@ impl Resolver {
             PathResult::from_resolution(self.resolve_name(db, name))
         } else if path.is_self() {
             PathResult::from_resolution(self.resolve_name(db, &Name::self_param()))
+        } else if let Some(res) = self.resolve_type_relative_prefix(db, path) {
+            // e.g. `T::Item` or `Self::new`: the first segment is a generic
+            // param or `Self`, the rest needs to be resolved as associated items
+            PathResult::from_resolution_with_index(res, 1)
         } else {
             let (item_map, module) = match self.module() {
                 Some(it) => it,
@@ -169,6 +173,26 @@ impl Resolver {
         }
     }
 
+    /// If the first segment of a plain multi-segment path refers to a generic
+    /// parameter or to `Self`, returns that resolution.
+    fn resolve_type_relative_prefix(
+        &self,
+        db: &impl HirDatabase,
+        path: &Path,
+    ) -> Option<PerNs<Resolution>> {
+        if path.kind != PathKind::Plain || path.segments.len() < 2 {
+            return None;
+        }
+        let first = &path.segments[0].name;
+        let res = self.scopes.iter().rev().find_map(|scope| match scope {
+            Scope::GenericParams { .. } | Scope::ImplBlockScope(_) => {
+                scope.resolve_name(db, first).take_types()
+            }
+            _ => None,
+        })?;
+        Some(PerNs::types(res))
+    }
+
     /// Returns the fully resolved path if we were able to resolve it.
     /// otherwise returns `PerNs::none`
     pub(crate) fn resolve_path_without_assoc_items(
@@ -180,6 +204,54 @@ impl Resolver {
         self.resolve_path_segments(db, path).into_fully_resolved()
     }
 
+    /// The item a binding pattern named `name` refers to instead of binding
+    /// a local, like the variant in `let None = x`.
+    pub(crate) fn resolve_binding_pattern(
+        &self,
+        db: &impl HirDatabase,
+        name: &Name,
+    ) -> Option<ModuleDef> {
+        match self.resolve_name(db, name).take_values()? {
+            Resolution::Def(def) => match def {
+                ModuleDef::EnumVariant(_) | ModuleDef::Const(_) | ModuleDef::Struct(_) => Some(def),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The import the first segment of `path` resolves through, with the
+    /// module importing it. Locals, generic parameters and `Self` shadow
+    /// imports, and `super::` paths go through the imports of the parent.
+    pub(crate) fn resolve_path_import(
+        &self,
+        db: &impl HirDatabase,
+        path: &Path,
+    ) -> Option<(Module, ImportId)> {
+        let first = &path.segments.first()?.name;
+        let (item_map, module) = self.module()?;
+        let module = match path.kind {
+            PathKind::Self_ => module,
+            PathKind::Super => item_map[module].parent?,
+            PathKind::Plain => {
+                let is_local = |scope: &Scope| match scope {
+                    // Locals are only in the value namespace of single segment paths
+                    Scope::ExprScope(_) if path.segments.len() > 1 => false,
+                    Scope::ModuleScope(_) => false,
+                    _ => !scope.resolve_name(db, first).is_none(),
+                };
+                let scopes = self.scopes.iter().rev();
+                if scopes.take_while(|it| !is_module_scope(it)).any(is_local) {
+                    return None;
+                }
+                module
+            }
+            PathKind::Crate | PathKind::Abs => return None,
+        };
+        let import = item_map[module].scope.get(first)?.import?;
+        Some((item_map.mk_module(module), import))
+    }
+
     pub(crate) fn all_names(&self, db: &impl HirDatabase) -> FxHashMap<Name, PerNs<Resolution>> {
         let mut names = FxHashMap::default();
         for scope in self.scopes.iter().rev() {
@@ -228,11 +300,27 @@ impl Resolver {
         self.scopes
             .iter()
             .filter_map(|scope| match scope {
-                Scope::GenericParams(params) => Some(params),
+                Scope::GenericParams { params, .. } => Some(params),
                 _ => None,
             })
             .flat_map(|params| params.where_predicates.iter())
     }
+
+    /// The innermost item with generic parameters whose scope we're in.
+    pub(crate) fn generic_def(&self) -> Option<GenericDef> {
+        self.scopes.iter().rev().find_map(|scope| match scope {
+            Scope::GenericParams { def, .. } => Some(*def),
+            _ => None,
+        })
+    }
+
+    /// The lifetime parameter with the given name, like `'a`, in scope.
+    pub(crate) fn resolve_lifetime(&self, name: &Name) -> Option<&LifetimeParam> {
+        self.scopes.iter().rev().find_map(|scope| match scope {
+            Scope::GenericParams { params, .. } => params.find_lifetime_by_name(name),
+            _ => None,
+        })
+    }
 }
 
 impl Resolver {
@@ -241,8 +329,12 @@ impl Resolver {
         self
     }
 
-    pub(crate) fn push_generic_params_scope(self, params: Arc<GenericParams>) -> Resolver {
-        self.push_scope(Scope::GenericParams(params))
+    pub(crate) fn push_generic_params_scope(
+        self,
+        def: GenericDef,
+        params: Arc<GenericParams>,
+    ) -> Resolver {
+        self.push_scope(Scope::GenericParams { def, params })
     }
 
     pub(crate) fn push_impl_block_scope(self, impl_block: ImplBlock) -> Resolver {
@@ -266,6 +358,13 @@ impl Resolver {
     }
 }
 
+fn is_module_scope(scope: &Scope) -> bool {
+    match scope {
+        Scope::ModuleScope(_) => true,
+        _ => false,
+    }
+}
+
 impl Scope {
     fn resolve_name(&self, db: &impl HirDatabase, name: &Name) -> PerNs<Resolution> {
         match self {
@@ -278,7 +377,7 @@ impl Scope {
                         .map(Resolution::Def)
                 }
             }
-            Scope::GenericParams(gp) => match gp.find_by_name(name) {
+            Scope::GenericParams { params: gp, .. } => match gp.find_by_name(name) {
                 Some(gp) => PerNs::types(Resolution::GenericParam(gp.idx)),
                 None => PerNs::none(),
             },
@@ -323,7 +422,7 @@ impl Scope {
                     });
                 }
             }
-            Scope::GenericParams(gp) => {
+            Scope::GenericParams { params: gp, .. } => {
                 for param in &gp.params {
                     f(param.name.clone(), PerNs::types(Resolution::GenericParam(param.idx)))
                 }
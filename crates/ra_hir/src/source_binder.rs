@@ -20,12 +20,13 @@ use rustc_hash::{FxHashMap, FxHashSet};
 use crate::{
     expr,
     expr::{
+        match_check,
         scope::{ExprScopes, ScopeId},
-        BodySourceMap,
+        Body, BodySourceMap, PatId,
     },
-    ids::LocationCtx,
+    ids::{LocationCtx, MacroDefId},
     AsName, AstId, Const, Crate, DefWithBody, Either, Enum, Function, HirDatabase, HirFileId,
-    MacroDef, Module, Name, Path, PerNs, Resolver, Static, Struct, Trait, Ty,
+    KnownName, MacroDef, Module, Name, Path, PerNs, Resolver, Static, Struct, Trait, Ty, TypeAlias,
 };
 
 /// Locates the module by `FileId`. Picks topmost module in the file.
@@ -122,13 +123,96 @@ pub fn trait_from_module(
     Trait { id: ctx.to_def(trait_def) }
 }
 
+pub fn const_from_module(
+    db: &impl HirDatabase,
+    module: Module,
+    const_def: &ast::ConstDef,
+) -> Const {
+    let file_id = module.definition_source(db).file_id;
+    let ctx = LocationCtx::new(db, module, file_id);
+    Const { id: ctx.to_def(const_def) }
+}
+
+pub fn static_from_module(
+    db: &impl HirDatabase,
+    module: Module,
+    static_def: &ast::StaticDef,
+) -> Static {
+    let file_id = module.definition_source(db).file_id;
+    let ctx = LocationCtx::new(db, module, file_id);
+    Static { id: ctx.to_def(static_def) }
+}
+
+pub fn function_from_module(
+    db: &impl HirDatabase,
+    module: Module,
+    fn_def: &ast::FnDef,
+) -> Function {
+    let file_id = module.definition_source(db).file_id;
+    let ctx = LocationCtx::new(db, module, file_id);
+    Function { id: ctx.to_def(fn_def) }
+}
+
+pub fn type_alias_from_module(
+    db: &impl HirDatabase,
+    module: Module,
+    type_alias_def: &ast::TypeAliasDef,
+) -> TypeAlias {
+    let file_id = module.definition_source(db).file_id;
+    let ctx = LocationCtx::new(db, module, file_id);
+    TypeAlias { id: ctx.to_def(type_alias_def) }
+}
+
+/// Locates the macro defined by a `macro_rules!` invocation.
+pub fn macro_from_definition(
+    db: &impl HirDatabase,
+    file_id: FileId,
+    macro_call: &ast::MacroCall,
+) -> Option<MacroDef> {
+    let path = macro_call.path().and_then(Path::from_ast)?;
+    let is_macro_rules =
+        path.as_ident().and_then(Name::as_known_name) == Some(KnownName::MacroRules);
+    if !is_macro_rules || macro_call.name().is_none() {
+        return None;
+    }
+    let file_id = file_id.into();
+    let ast_id = db.ast_id_map(file_id).ast_id(macro_call).with_file_id(file_id);
+    Some(MacroDef { id: MacroDefId(ast_id) })
+}
+
+/// The resolver for the scope of `node`, down to the local bindings of a body.
+pub(crate) fn resolver_for_node(
+    db: &impl HirDatabase,
+    file_id: FileId,
+    node: &SyntaxNode,
+) -> Resolver {
+    match def_with_body_from_child_node(db, file_id, node) {
+        Some(def) => {
+            let source_map = def.body_source_map(db);
+            let scopes = db.expr_scopes(def);
+            let scope = scope_for(&scopes, &source_map, node);
+            expr::resolver_for_scope(def.body(db), db, scope)
+        }
+        None => node
+            .ancestors()
+            .find_map(|node| try_get_resolver_for_node(db, file_id, node))
+            .unwrap_or_default(),
+    }
+}
+
 fn try_get_resolver_for_node(
     db: &impl HirDatabase,
     file_id: FileId,
     node: &SyntaxNode,
 ) -> Option<Resolver> {
     if let Some(module) = ast::Module::cast(node) {
-        Some(module_from_declaration(db, file_id, module)?.resolver(db))
+        let module = if module.has_semi() {
+            module_from_declaration(db, file_id, module)?
+        } else {
+            // Nested inline modules are not children of the file's module
+            module_from_inline(db, file_id, module)?
+        };
+        Some(module.resolver(db))
     } else if let Some(_) = ast::SourceFile::cast(node) {
         Some(module_from_source(db, file_id.into(), None)?.resolver(db))
     } else if let Some(s) = ast::StructDef::cast(node) {
@@ -171,6 +255,7 @@ fn def_with_body_from_child_node(
 #[derive(Debug)]
 pub struct SourceAnalyzer {
     resolver: Resolver,
+    body: Option<Arc<Body>>,
     body_source_map: Option<Arc<BodySourceMap>>,
     infer: Option<Arc<crate::ty::InferenceResult>>,
     scopes: Option<Arc<crate::expr::ExprScopes>>,
@@ -226,9 +311,11 @@ impl SourceAnalyzer {
                 None => scope_for(&scopes, &source_map, &node),
                 Some(offset) => scope_for_offset(&scopes, &source_map, offset),
             };
-            let resolver = expr::resolver_for_scope(def.body(db), db, scope);
+            let body = def.body(db);
+            let resolver = expr::resolver_for_scope(body.clone(), db, scope);
             SourceAnalyzer {
                 resolver,
+                body: Some(body),
                 body_source_map: Some(source_map),
                 infer: Some(def.infer(db)),
                 scopes: Some(scopes),
@@ -239,6 +326,7 @@ impl SourceAnalyzer {
                     .ancestors()
                     .find_map(|node| try_get_resolver_for_node(db, file_id, node))
                     .unwrap_or_default(),
+                body: None,
                 body_source_map: None,
                 infer: None,
                 scopes: None,
@@ -256,16 +344,49 @@ impl SourceAnalyzer {
         Some(self.infer.as_ref()?[pat_id].clone())
     }
 
+    /// The type of a local binding found by name resolution, see
+    /// `Resolution::LocalBinding`.
+    pub fn type_of_binding(&self, pat: PatId) -> Option<crate::Ty> {
+        Some(self.infer.as_ref()?[pat].clone())
+    }
+
     pub fn resolve_method_call(&self, call: &ast::MethodCallExpr) -> Option<Function> {
         let expr_id = self.body_source_map.as_ref()?.node_expr(call.into())?;
         self.infer.as_ref()?.method_resolution(expr_id)
     }
 
+    /// The type of the method called by `call`, with the type arguments
+    /// inferred for the call, like those of the receiver's type.
+    pub fn type_of_method_call(&self, call: &ast::MethodCallExpr) -> Option<crate::Ty> {
+        let expr_id = self.body_source_map.as_ref()?.node_expr(call.into())?;
+        self.infer.as_ref()?.method_type(expr_id)
+    }
+
     pub fn resolve_field(&self, field: &ast::FieldExpr) -> Option<crate::StructField> {
         let expr_id = self.body_source_map.as_ref()?.node_expr(field.into())?;
         self.infer.as_ref()?.field_resolution(expr_id)
     }
 
+    /// The patterns of the values a match doesn't cover, as source text. Only
+    /// `arms` are taken into account, and the ones with a guard cover nothing.
+    pub fn missing_match_arms(
+        &self,
+        db: &impl HirDatabase,
+        match_expr: &ast::MatchExpr,
+        arms: &[&ast::MatchArm],
+    ) -> Option<Vec<String>> {
+        let source_map = self.body_source_map.as_ref()?;
+        let scrutinee = source_map.node_expr(match_expr.expr()?)?;
+        let pats = arms
+            .iter()
+            .filter(|arm| arm.guard().is_none())
+            .flat_map(|arm| arm.pats())
+            .map(|pat| source_map.node_pat(pat))
+            .collect::<Option<Vec<_>>>()?;
+        let (body, infer) = (self.body.as_ref()?, self.infer.as_ref()?);
+        match_check::missing_patterns(db, body, infer, &self.resolver, scrutinee, &pats)
+    }
+
     pub fn resolve_macro_call(
         &self,
         db: &impl HirDatabase,
@@ -11,22 +11,26 @@ mod op;
 mod lower;
 mod infer;
 pub(crate) mod display;
+pub(crate) mod consteval;
 
 use std::ops::Deref;
 use std::sync::Arc;
 use std::{fmt, mem};
 
-use crate::{db::HirDatabase, type_ref::Mutability, AdtDef, GenericParams, Name, Trait, TypeAlias};
+use crate::{
+    db::HirDatabase, type_ref::Mutability, AdtDef, GenericParams, HasGenericParams, Name, Trait,
+    TypeAlias,
+};
 use display::{HirDisplay, HirFormatter};
 
 pub(crate) use autoderef::autoderef;
 pub(crate) use infer::{infer_query, InferTy, InferenceResult};
 pub use lower::CallableDef;
 pub(crate) use lower::{
-    callable_item_sig, generic_defaults_query, generic_predicates_query, type_for_def,
+    callable_item_sig, generic_defaults_query, generic_predicates_query, trait_env, type_for_def,
     type_for_field, TypableDef,
 };
-pub(crate) use traits::ProjectionPredicate;
+pub(crate) use traits::{InEnvironment, ProjectionPredicate, TraitEnvironment};
 
 /// A type constructor or type name: this might be something like the primitive
 /// type `bool`, a struct like `Vec`, or things like function pointers or
@@ -55,8 +59,9 @@ pub enum TypeCtor {
     /// The pointee of an array slice.  Written as `[T]`.
     Slice,
 
-    /// An array with the given length. Written as `[T; n]`.
-    Array,
+    /// An array with the given length. Written as `[T; n]`. The length is
+    /// `None` if we couldn't evaluate it.
+    Array { len: Option<u64> },
 
     /// A raw pointer. Written as `*mut T` or `*const T`
     RawPtr(Mutability),
@@ -114,6 +119,22 @@ pub struct ProjectionTy {
     pub parameters: Substs,
 }
 
+impl ProjectionTy {
+    /// The trait reference this projection goes through, i.e. `P0: Trait<P1..Pn>`.
+    pub fn trait_ref(&self, db: &impl HirDatabase) -> TraitRef {
+        let trait_ = self.associated_ty.parent_trait(db).expect("associated type not in trait");
+        TraitRef { trait_, substs: self.parameters.clone() }
+    }
+
+    pub fn walk(&self, f: &mut impl FnMut(&Ty)) {
+        self.parameters.walk(f);
+    }
+
+    pub fn walk_mut(&mut self, f: &mut impl FnMut(&mut Ty)) {
+        self.parameters.walk_mut(f);
+    }
+}
+
 /// A type.
 ///
 /// See also the `TyKind` enum in rustc (librustc/ty/sty.rs), which represents
@@ -127,6 +148,16 @@ pub enum Ty {
     /// several other things.
     Apply(ApplicationTy),
 
+    /// A "projection" type corresponds to an (unnormalized)
+    /// projection like `<P0 as Trait<P1..Pn>>::Foo`. Note that the
+    /// trait and all its parameters are fully known.
+    ///
+    /// During inference, projections are normalized by replacing them with a
+    /// type variable and registering an obligation; if normalization isn't
+    /// possible (e.g. because the self type is a type parameter), the
+    /// projection itself stands in for the normalized type.
+    Projection(ProjectionTy),
+
     /// A type parameter; for example, `T` in `fn f<T>(x: T) {}
     Param {
         /// The index of the parameter (starting with parameters from the
@@ -153,21 +184,71 @@ pub enum Ty {
     Unknown,
 }
 
-/// A list of substitutions for generic parameters.
+/// A lifetime argument, like the `'a` in `&'a T` or `Foo<'a>`. Lifetimes are
+/// erased for type inference and trait solving, so they are only kept to show
+/// them to the user.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub enum Lifetime {
+    /// A lifetime parameter; for example, `'a` in `fn f<'a>(x: &'a u32) {}`.
+    Param {
+        /// The index of the parameter among the lifetime parameters (starting
+        /// with the ones from the surrounding impl, then the current function).
+        idx: u32,
+        /// The name of the parameter, including the `'`.
+        name: Name,
+    },
+    Static,
+    /// An elided lifetime, `'_`, or one we don't know, e.g. because it was
+    /// inferred.
+    Unknown,
+}
+
+/// A list of substitutions for generic parameters. The lifetime arguments are
+/// kept separately from the types, and may be left out, in which case they are
+/// all `Lifetime::Unknown`.
 #[derive(Clone, PartialEq, Eq, Debug, Hash)]
-pub struct Substs(Arc<[Ty]>);
+pub struct Substs(Arc<[Ty]>, Arc<[Lifetime]>);
 
 impl Substs {
     pub fn empty() -> Substs {
-        Substs(Arc::new([]))
+        Substs(Arc::new([]), Arc::new([]))
     }
 
     pub fn single(ty: Ty) -> Substs {
-        Substs(Arc::new([ty]))
+        Substs(Arc::new([ty]), Arc::new([]))
     }
 
+    /// The substitutions for the first `n` type parameters. The lifetimes are
+    /// left out, since we don't know how many of them belong to these.
     pub fn prefix(&self, n: usize) -> Substs {
-        Substs(self.0.iter().cloned().take(n).collect::<Vec<_>>().into())
+        self.0.iter().cloned().take(n).collect::<Vec<_>>().into()
+    }
+
+    pub fn with_lifetimes(self, lifetimes: Vec<Lifetime>) -> Substs {
+        Substs(self.0, lifetimes.into())
+    }
+
+    pub fn lifetimes(&self) -> &[Lifetime] {
+        &self.1
+    }
+
+    /// Replaces lifetime parameters in the lifetime arguments using the given
+    /// `Substs`.
+    fn subst_lifetimes(&mut self, substs: &Substs) {
+        if self.1.iter().all(|lt| lt.param_idx().is_none()) {
+            return;
+        }
+        let lifetimes = self.1.iter().map(|lt| match lt.param_idx() {
+            Some(idx) => substs.1.get(idx as usize).cloned().unwrap_or(Lifetime::Unknown),
+            None => lt.clone(),
+        });
+        self.1 = lifetimes.collect::<Vec<_>>().into();
+    }
+
+    pub fn walk(&self, f: &mut impl FnMut(&Ty)) {
+        for t in self.0.iter() {
+            t.walk(f);
+        }
     }
 
     pub fn walk_mut(&mut self, f: &mut impl FnMut(&mut Ty)) {
@@ -186,7 +267,8 @@ impl Substs {
         &self.0[0]
     }
 
-    /// Return Substs that replace each parameter by itself (i.e. `Ty::Param`).
+    /// Return Substs that replace each parameter by itself (i.e. `Ty::Param`
+    /// and `Lifetime::Param`).
     pub fn identity(generic_params: &GenericParams) -> Substs {
         Substs(
             generic_params
@@ -195,25 +277,38 @@ impl Substs {
                 .map(|p| Ty::Param { idx: p.idx, name: p.name.clone() })
                 .collect::<Vec<_>>()
                 .into(),
-        )
-    }
-
-    /// Return Substs that replace each parameter by a bound variable.
-    pub fn bound_vars(generic_params: &GenericParams) -> Substs {
-        Substs(
             generic_params
-                .params_including_parent()
+                .lifetimes_including_parent()
                 .into_iter()
-                .map(|p| Ty::Bound(p.idx))
+                .map(|p| Lifetime::Param { idx: p.idx, name: p.name.clone() })
                 .collect::<Vec<_>>()
                 .into(),
         )
     }
+
+    /// Return Substs that replace each parameter by a bound variable.
+    pub fn bound_vars(generic_params: &GenericParams) -> Substs {
+        generic_params
+            .params_including_parent()
+            .into_iter()
+            .map(|p| Ty::Bound(p.idx))
+            .collect::<Vec<_>>()
+            .into()
+    }
 }
 
 impl From<Vec<Ty>> for Substs {
     fn from(v: Vec<Ty>) -> Self {
-        Substs(v.into())
+        Substs(v.into(), Arc::new([]))
+    }
+}
+
+impl Lifetime {
+    fn param_idx(&self) -> Option<u32> {
+        match self {
+            Lifetime::Param { idx, .. } => Some(*idx),
+            Lifetime::Static | Lifetime::Unknown => None,
+        }
     }
 }
 
@@ -288,6 +383,12 @@ pub struct Canonical<T> {
     pub num_vars: usize,
 }
 
+impl<T> Canonical<T> {
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Canonical<U> {
+        Canonical { value: f(self.value), num_vars: self.num_vars }
+    }
+}
+
 /// A function signature as seen by type inference: Several parameter types and
 /// one return type.
 #[derive(Clone, PartialEq, Eq, Debug)]
@@ -352,6 +453,11 @@ impl Ty {
                     t.walk(f);
                 }
             }
+            Ty::Projection(p_ty) => {
+                for t in p_ty.parameters.iter() {
+                    t.walk(f);
+                }
+            }
             Ty::Param { .. } | Ty::Bound(_) | Ty::Infer(_) | Ty::Unknown => {}
         }
         f(self);
@@ -362,6 +468,9 @@ impl Ty {
             Ty::Apply(a_ty) => {
                 a_ty.parameters.walk_mut(f);
             }
+            Ty::Projection(p_ty) => {
+                p_ty.parameters.walk_mut(f);
+            }
             Ty::Param { .. } | Ty::Bound(_) | Ty::Infer(_) | Ty::Unknown => {}
         }
         f(self);
@@ -453,11 +562,20 @@ impl Ty {
     /// Replaces type parameters in this type using the given `Substs`. (So e.g.
     /// if `self` is `&[T]`, where type parameter T has index 0, and the
     /// `Substs` contain `u32` at index 0, we'll have `&[u32]` afterwards.)
+    /// Lifetime parameters are replaced as well.
     pub fn subst(self, substs: &Substs) -> Ty {
         self.fold(&mut |ty| match ty {
             Ty::Param { idx, name } => {
                 substs.get(idx as usize).cloned().unwrap_or(Ty::Param { idx, name })
             }
+            Ty::Apply(mut a_ty) => {
+                a_ty.parameters.subst_lifetimes(substs);
+                Ty::Apply(a_ty)
+            }
+            Ty::Projection(mut p_ty) => {
+                p_ty.parameters.subst_lifetimes(substs);
+                Ty::Projection(p_ty)
+            }
             ty => ty,
         })
     }
@@ -507,25 +625,44 @@ impl HirDisplay for ApplicationTy {
             TypeCtor::Str => write!(f, "str")?,
             TypeCtor::Slice => {
                 let t = self.parameters.as_single();
-                write!(f, "[{}]", t.display(f.db))?;
+                write!(f, "[")?;
+                t.hir_fmt(f)?;
+                write!(f, "]")?;
             }
-            TypeCtor::Array => {
+            TypeCtor::Array { len } => {
                 let t = self.parameters.as_single();
-                write!(f, "[{};_]", t.display(f.db))?;
+                write!(f, "[")?;
+                t.hir_fmt(f)?;
+                match len {
+                    Some(len) => write!(f, "; {}]", len)?,
+                    None => write!(f, "; _]")?,
+                }
             }
             TypeCtor::RawPtr(m) => {
                 let t = self.parameters.as_single();
-                write!(f, "*{}{}", m.as_keyword_for_ptr(), t.display(f.db))?;
+                write!(f, "*{}", m.as_keyword_for_ptr())?;
+                t.hir_fmt(f)?;
             }
             TypeCtor::Ref(m) => {
                 let t = self.parameters.as_single();
-                write!(f, "&{}{}", m.as_keyword_for_ref(), t.display(f.db))?;
+                write!(f, "&")?;
+                match self.parameters.lifetimes().first() {
+                    Some(Lifetime::Unknown) | None => {}
+                    Some(lifetime) => {
+                        lifetime.hir_fmt(f)?;
+                        write!(f, " ")?;
+                    }
+                }
+                write!(f, "{}", m.as_keyword_for_ref())?;
+                t.hir_fmt(f)?;
             }
             TypeCtor::Never => write!(f, "!")?,
             TypeCtor::Tuple { .. } => {
                 let ts = &self.parameters;
                 if ts.len() == 1 {
-                    write!(f, "({},)", ts[0].display(f.db))?;
+                    write!(f, "(")?;
+                    ts[0].hir_fmt(f)?;
+                    write!(f, ",)")?;
                 } else {
                     write!(f, "(")?;
                     f.write_joined(&*ts.0, ", ")?;
@@ -536,7 +673,8 @@ impl HirDisplay for ApplicationTy {
                 let sig = FnSig::from_fn_ptr_substs(&self.parameters);
                 write!(f, "fn(")?;
                 f.write_joined(sig.params(), ", ")?;
-                write!(f, ") -> {}", sig.ret().display(f.db))?;
+                write!(f, ") -> ")?;
+                sig.ret().hir_fmt(f)?;
             }
             TypeCtor::FnDef(def) => {
                 let sig = f.db.callable_item_signature(def);
@@ -549,14 +687,12 @@ impl HirDisplay for ApplicationTy {
                     CallableDef::Function(_) => write!(f, "fn {}", name)?,
                     CallableDef::Struct(_) | CallableDef::EnumVariant(_) => write!(f, "{}", name)?,
                 }
-                if self.parameters.len() > 0 {
-                    write!(f, "<")?;
-                    f.write_joined(&*self.parameters.0, ", ")?;
-                    write!(f, ">")?;
-                }
+                let num_lifetimes = def.generic_params(f.db).count_lifetimes_including_parent();
+                write_generic_args(f, num_lifetimes, &self.parameters)?;
                 write!(f, "(")?;
                 f.write_joined(sig.params(), ", ")?;
-                write!(f, ") -> {}", sig.ret().display(f.db))?;
+                write!(f, ") -> ")?;
+                sig.ret().hir_fmt(f)?;
             }
             TypeCtor::Adt(def_id) => {
                 let name = match def_id {
@@ -566,21 +702,76 @@ impl HirDisplay for ApplicationTy {
                 }
                 .unwrap_or_else(Name::missing);
                 write!(f, "{}", name)?;
-                if self.parameters.len() > 0 {
-                    write!(f, "<")?;
-                    f.write_joined(&*self.parameters.0, ", ")?;
-                    write!(f, ">")?;
-                }
+                let num_lifetimes = def_id.generic_params(f.db).count_lifetimes_including_parent();
+                write_generic_args(f, num_lifetimes, &self.parameters)?;
             }
         }
         Ok(())
     }
 }
 
+/// Writes the generic arguments of a type like `Foo<'a, u32>`. Lifetimes which
+/// are left out of the `Substs` are shown as `'_`.
+fn write_generic_args(
+    f: &mut HirFormatter<impl HirDatabase>,
+    num_lifetimes: usize,
+    parameters: &Substs,
+) -> fmt::Result {
+    if num_lifetimes == 0 && parameters.len() == 0 {
+        return Ok(());
+    }
+    write!(f, "<")?;
+    for i in 0..num_lifetimes {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        parameters.lifetimes().get(i).unwrap_or(&Lifetime::Unknown).hir_fmt(f)?;
+    }
+    if num_lifetimes > 0 && parameters.len() > 0 {
+        write!(f, ", ")?;
+    }
+    f.write_joined(&*parameters.0, ", ")?;
+    write!(f, ">")
+}
+
+impl HirDisplay for Lifetime {
+    fn hir_fmt(&self, f: &mut HirFormatter<impl HirDatabase>) -> fmt::Result {
+        match self {
+            Lifetime::Param { name, .. } => write!(f, "{}", name),
+            Lifetime::Static => write!(f, "'static"),
+            Lifetime::Unknown => write!(f, "'_"),
+        }
+    }
+}
+
+impl HirDisplay for ProjectionTy {
+    fn hir_fmt(&self, f: &mut HirFormatter<impl HirDatabase>) -> fmt::Result {
+        let trait_name = self
+            .associated_ty
+            .parent_trait(f.db)
+            .and_then(|t| t.name(f.db))
+            .unwrap_or_else(Name::missing);
+        write!(f, "<")?;
+        self.parameters[0].hir_fmt(f)?;
+        write!(f, " as {}", trait_name)?;
+        if self.parameters.len() > 1 {
+            write!(f, "<")?;
+            f.write_joined(&self.parameters[1..], ", ")?;
+            write!(f, ">")?;
+        }
+        write!(f, ">::{}", self.associated_ty.name(f.db))?;
+        Ok(())
+    }
+}
+
 impl HirDisplay for Ty {
     fn hir_fmt(&self, f: &mut HirFormatter<impl HirDatabase>) -> fmt::Result {
+        if f.should_truncate() {
+            return write!(f, "…");
+        }
         match self {
             Ty::Apply(a_ty) => a_ty.hir_fmt(f)?,
+            Ty::Projection(p_ty) => p_ty.hir_fmt(f)?,
             Ty::Param { name, .. } => write!(f, "{}", name)?,
             Ty::Bound(idx) => write!(f, "?{}", idx)?,
             Ty::Unknown => write!(f, "{{unknown}}")?,
@@ -7,7 +7,7 @@ use std::iter::successors;
 
 use log::{info, warn};
 
-use super::{traits::Solution, Canonical, Ty};
+use super::{lower, traits::Solution, Canonical, InEnvironment, Ty};
 use crate::{HasGenericParams, HirDatabase, Name, Resolver};
 
 const AUTODEREF_RECURSION_LIMIT: usize = 10;
@@ -60,7 +60,9 @@ fn deref_by_trait(
         },
     };
 
-    let canonical = super::Canonical { num_vars: 1 + ty.num_vars, value: projection };
+    let env = lower::trait_env(db, resolver);
+    let canonical =
+        super::Canonical { num_vars: 1 + ty.num_vars, value: InEnvironment::new(env, projection) };
 
     let solution = db.normalize(krate, canonical)?;
 
@@ -0,0 +1,295 @@
+//! A small evaluator for constant expressions. It understands integer and
+//! boolean literals, arithmetic, references to other `const` items and
+//! `core::mem::size_of` of primitive types, which is enough to compute array
+//! lengths and the values of simple constants. Integers are wrapped around
+//! to the type they are cast to, or declared with. Everything else evaluates
+//! to `None`.
+
+use std::{fmt, sync::Arc};
+
+use crate::{
+    expr::{self, BinaryOp, Body, Expr, ExprId, Literal, UnaryOp},
+    path::{GenericArg, PathKind, PathSegment},
+    resolve::{Resolution, Resolver},
+    ty::primitive::{FloatBitness, IntBitness, IntTy, Signedness},
+    type_ref::{ArrayLen, TypeRef},
+    BuiltinType, Const, Function, HirDatabase, ModuleDef, Name, Path,
+};
+
+/// The value of a constant expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConstValue {
+    Int(i128),
+    Bool(bool),
+}
+
+impl ConstValue {
+    /// Returns the value if it is an integer which can be used as an array
+    /// length.
+    pub fn as_usize(self) -> Option<u64> {
+        match self {
+            ConstValue::Int(v) if v >= 0 && v <= i128::from(u64::max_value()) => Some(v as u64),
+            _ => None,
+        }
+    }
+
+    fn as_int(self) -> Option<i128> {
+        match self {
+            ConstValue::Int(v) => Some(v),
+            ConstValue::Bool(v) => Some(i128::from(v)),
+        }
+    }
+}
+
+impl fmt::Display for ConstValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConstValue::Int(v) => write!(f, "{}", v),
+            ConstValue::Bool(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+pub(crate) fn const_value_query(db: &impl HirDatabase, konst: Const) -> Option<ConstValue> {
+    eval_const(db, konst, &[])
+}
+
+/// Evaluates the body of a constant. The constants it refers to are
+/// evaluated inline rather than through `const_value`, as a cycle of queries
+/// would panic: `stack` holds the constants being evaluated, so that
+/// `const A: usize = B; const B: usize = A;` have no value.
+fn eval_const(db: &impl HirDatabase, konst: Const, stack: &[Const]) -> Option<ConstValue> {
+    if stack.contains(&konst) {
+        return None;
+    }
+    let mut stack = stack.to_vec();
+    stack.push(konst);
+    let body = db.body_hir(konst.into());
+    let body_expr = body.body_expr();
+    let value = ConstEvaluator { db, body, stack: &stack }.eval(body_expr)?;
+    match builtin_type(db, &konst.resolver(db), &konst.data(db).type_ref) {
+        Some(BuiltinType::Int(int_ty)) => Some(ConstValue::Int(wrap_int(value.as_int()?, int_ty)?)),
+        _ => Some(value),
+    }
+}
+
+/// Evaluates an expression in the given body, e.g. the length in `[0; N + 1]`.
+pub(crate) fn eval_expr(
+    db: &impl HirDatabase,
+    body: Arc<Body>,
+    expr: ExprId,
+) -> Option<ConstValue> {
+    ConstEvaluator { db, body, stack: &[] }.eval(expr)
+}
+
+pub(crate) fn eval_array_len(
+    db: &impl HirDatabase,
+    resolver: &Resolver,
+    len: &ArrayLen,
+) -> Option<u64> {
+    match len {
+        ArrayLen::Literal(len) => Some(*len),
+        ArrayLen::Path(path) => eval_path(db, resolver, path)?.as_usize(),
+        ArrayLen::Unknown => None,
+    }
+}
+
+fn eval_path(db: &impl HirDatabase, resolver: &Resolver, path: &Path) -> Option<ConstValue> {
+    match resolver.resolve_path_without_assoc_items(db, path).take_values()? {
+        Resolution::Def(ModuleDef::Const(konst)) => db.const_value(konst),
+        _ => None,
+    }
+}
+
+/// Whether `function` is `core::mem::size_of`, which `std` reexports.
+fn is_size_of(db: &impl HirDatabase, resolver: &Resolver, function: Function) -> bool {
+    Name::size_of_paths().iter().any(|names| {
+        let segments = names
+            .iter()
+            .map(|name| PathSegment { name: name.clone(), args_and_bindings: None })
+            .collect();
+        let path = Path { kind: PathKind::Abs, segments };
+        resolver.resolve_path_without_assoc_items(db, &path).take_values()
+            == Some(Resolution::Def(ModuleDef::Function(function)))
+    })
+}
+
+struct ConstEvaluator<'a, DB> {
+    db: &'a DB,
+    body: Arc<Body>,
+    /// The constants being evaluated, empty outside of `const_value`.
+    stack: &'a [Const],
+}
+
+impl<'a, DB: HirDatabase> ConstEvaluator<'a, DB> {
+    fn eval(&self, expr: ExprId) -> Option<ConstValue> {
+        match &self.body[expr] {
+            Expr::Literal(Literal::Int(v, _)) => Some(ConstValue::Int(i128::from(*v))),
+            Expr::Literal(Literal::Bool(v)) => Some(ConstValue::Bool(*v)),
+            Expr::Path(path) => {
+                let resolver = expr::resolver_for_expr(self.body.clone(), self.db, expr);
+                match resolver.resolve_path_without_assoc_items(self.db, path).take_values()? {
+                    Resolution::Def(ModuleDef::Const(konst)) if self.stack.is_empty() => {
+                        self.db.const_value(konst)
+                    }
+                    Resolution::Def(ModuleDef::Const(konst)) => {
+                        eval_const(self.db, konst, self.stack)
+                    }
+                    _ => None,
+                }
+            }
+            Expr::UnaryOp { expr, op } => match (op, self.eval(*expr)?) {
+                (UnaryOp::Neg, ConstValue::Int(v)) => v.checked_neg().map(ConstValue::Int),
+                (UnaryOp::Not, ConstValue::Bool(v)) => Some(ConstValue::Bool(!v)),
+                // `!` on integers depends on the width of the type, which we don't know here
+                _ => None,
+            },
+            Expr::BinaryOp { lhs, rhs, op: Some(op) } => {
+                eval_bin_op(*op, self.eval(*lhs)?, self.eval(*rhs)?)
+            }
+            Expr::Cast { expr: inner, type_ref } => {
+                let value = self.eval(*inner)?;
+                let resolver = expr::resolver_for_expr(self.body.clone(), self.db, expr);
+                match builtin_type(self.db, &resolver, type_ref)? {
+                    BuiltinType::Int(int_ty) => {
+                        Some(ConstValue::Int(wrap_int(value.as_int()?, int_ty)?))
+                    }
+                    BuiltinType::Bool => match value {
+                        ConstValue::Bool(_) => Some(value),
+                        ConstValue::Int(_) => None,
+                    },
+                    _ => None,
+                }
+            }
+            Expr::Block { statements, tail: Some(tail) } if statements.is_empty() => {
+                self.eval(*tail)
+            }
+            Expr::Call { callee, args } if args.is_empty() => self.eval_size_of(expr, *callee),
+            _ => None,
+        }
+    }
+
+    /// Evaluates `core::mem::size_of::<T>()` for a primitive type `T`.
+    fn eval_size_of(&self, call: ExprId, callee: ExprId) -> Option<ConstValue> {
+        let path = match &self.body[callee] {
+            Expr::Path(path) => path,
+            _ => return None,
+        };
+        let resolver = expr::resolver_for_expr(self.body.clone(), self.db, call);
+        match resolver.resolve_path_without_assoc_items(self.db, path).take_values()? {
+            Resolution::Def(ModuleDef::Function(f)) if is_size_of(self.db, &resolver, f) => (),
+            _ => return None,
+        }
+        let args = path.segments.last()?.args_and_bindings.as_ref()?;
+        // The type is resolved rather than lowered, as lowering an array type
+        // could evaluate the constant being evaluated
+        let size = match args.args.as_slice() {
+            [GenericArg::Type(TypeRef::Tuple(fields))] if fields.is_empty() => 0,
+            [GenericArg::Type(type_ref)] => {
+                size_of_builtin(builtin_type(self.db, &resolver, type_ref)?)?
+            }
+            _ => return None,
+        };
+        Some(ConstValue::Int(i128::from(size)))
+    }
+}
+
+/// The builtin type a path type refers to, like `u8`.
+fn builtin_type(
+    db: &impl HirDatabase,
+    resolver: &Resolver,
+    type_ref: &TypeRef,
+) -> Option<BuiltinType> {
+    let path = match type_ref {
+        TypeRef::Path(it) => it,
+        _ => return None,
+    };
+    match resolver.resolve_path_without_assoc_items(db, path).take_types()? {
+        Resolution::Def(ModuleDef::BuiltinType(it)) => Some(it),
+        _ => None,
+    }
+}
+
+fn eval_bin_op(op: BinaryOp, lhs: ConstValue, rhs: ConstValue) -> Option<ConstValue> {
+    use ConstValue::{Bool, Int};
+    let res = match (lhs, rhs) {
+        (Int(l), Int(r)) => match op {
+            BinaryOp::Addition => Int(l.checked_add(r)?),
+            BinaryOp::Subtraction => Int(l.checked_sub(r)?),
+            BinaryOp::Multiplication => Int(l.checked_mul(r)?),
+            BinaryOp::Division => Int(l.checked_div(r)?),
+            BinaryOp::Remainder => Int(l.checked_rem(r)?),
+            BinaryOp::LeftShift => Int(l.checked_shl(shift_amount(r)?)?),
+            BinaryOp::RightShift => Int(l.checked_shr(shift_amount(r)?)?),
+            BinaryOp::BitwiseAnd => Int(l & r),
+            BinaryOp::BitwiseOr => Int(l | r),
+            BinaryOp::BitwiseXor => Int(l ^ r),
+            BinaryOp::EqualityTest => Bool(l == r),
+            BinaryOp::NegatedEqualityTest => Bool(l != r),
+            BinaryOp::LesserTest => Bool(l < r),
+            BinaryOp::LesserEqualTest => Bool(l <= r),
+            BinaryOp::GreaterTest => Bool(l > r),
+            BinaryOp::GreaterEqualTest => Bool(l >= r),
+            _ => return None,
+        },
+        (Bool(l), Bool(r)) => match op {
+            BinaryOp::BooleanAnd => Bool(l && r),
+            BinaryOp::BooleanOr => Bool(l || r),
+            BinaryOp::EqualityTest => Bool(l == r),
+            BinaryOp::NegatedEqualityTest => Bool(l != r),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(res)
+}
+
+fn shift_amount(v: i128) -> Option<u32> {
+    if v >= 0 && v < 128 {
+        Some(v as u32)
+    } else {
+        None
+    }
+}
+
+// FIXME: this assumes a 64-bit target
+fn int_bits(bitness: IntBitness) -> u32 {
+    match bitness {
+        IntBitness::X8 => 8,
+        IntBitness::X16 => 16,
+        IntBitness::X32 => 32,
+        IntBitness::X64 | IntBitness::Xsize => 64,
+        IntBitness::X128 => 128,
+    }
+}
+
+fn size_of_builtin(ty: BuiltinType) -> Option<u64> {
+    let size = match ty {
+        BuiltinType::Bool => 1,
+        BuiltinType::Char => 4,
+        BuiltinType::Int(int_ty) => u64::from(int_bits(int_ty.bitness) / 8),
+        BuiltinType::Float(float_ty) => match float_ty.bitness {
+            FloatBitness::X32 => 4,
+            FloatBitness::X64 => 8,
+        },
+        BuiltinType::Str => return None,
+    };
+    Some(size)
+}
+
+/// Wraps the value around to the range of the type, as an `as` cast does.
+/// `None` for the `u128` values which don't fit an `i128`.
+fn wrap_int(value: i128, int_ty: IntTy) -> Option<i128> {
+    let bits = int_bits(int_ty.bitness);
+    if bits == 128 {
+        return match int_ty.signedness {
+            Signedness::Unsigned if value < 0 => None,
+            _ => Some(value),
+        };
+    }
+    let unsigned = value & ((1 << bits) - 1);
+    match int_ty.signedness {
+        Signedness::Signed if unsigned >= 1 << (bits - 1) => Some(unsigned - (1 << bits)),
+        _ => Some(unsigned),
+    }
+}
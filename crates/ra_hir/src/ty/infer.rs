@@ -27,9 +27,10 @@ use ra_prof::profile;
 use test_utils::tested_by;
 
 use super::{
-    autoderef, method_resolution, op, primitive,
-    traits::{Guidance, Obligation, Solution},
-    ApplicationTy, CallableDef, Substs, TraitRef, Ty, TypableDef, TypeCtor,
+    autoderef, consteval, lower, method_resolution, op, primitive,
+    traits::{Guidance, Obligation, ProjectionPredicate, Solution, TraitEnvironment},
+    ApplicationTy, CallableDef, InEnvironment, ProjectionTy, Substs, TraitRef, Ty, TypableDef,
+    TypeCtor,
 };
 use crate::{
     adt::VariantDef,
@@ -42,6 +43,7 @@ use crate::{
     nameres::Namespace,
     path::{GenericArg, GenericArgs},
     resolve::{Resolution, Resolver},
+    traits::TraitItem,
     ty::infer::diagnostics::InferenceDiagnostic,
     type_ref::{Mutability, TypeRef},
     AdtDef, ConstData, DefWithBody, FnData, Function, HirDatabase, ImplItem, ModuleDef, Name, Path,
@@ -105,6 +107,9 @@ impl Default for BindingMode {
 pub struct InferenceResult {
     /// For each method call expr, records the function it resolves to.
     method_resolutions: FxHashMap<ExprId, Function>,
+    /// For each method call expr, records the type of the called function, with
+    /// the substitutions inferred for the call.
+    method_types: FxHashMap<ExprId, Ty>,
     /// For each field access expr, records the field it resolves to.
     field_resolutions: FxHashMap<ExprId, StructField>,
     /// For each associated item record what it resolves to
@@ -118,6 +123,9 @@ impl InferenceResult {
     pub fn method_resolution(&self, expr: ExprId) -> Option<Function> {
         self.method_resolutions.get(&expr).copied()
     }
+    pub fn method_type(&self, expr: ExprId) -> Option<Ty> {
+        self.method_types.get(&expr).cloned()
+    }
     pub fn field_resolution(&self, expr: ExprId) -> Option<StructField> {
         self.field_resolutions.get(&expr).copied()
     }
@@ -161,7 +169,10 @@ struct InferenceContext<'a, D: HirDatabase> {
     resolver: Resolver,
     var_unification_table: InPlaceUnificationTable<TypeVarId>,
     obligations: Vec<Obligation>,
+    /// The where clauses that hold in the body we're inferring.
+    trait_env: Arc<TraitEnvironment>,
     method_resolutions: FxHashMap<ExprId, Function>,
+    method_types: FxHashMap<ExprId, Ty>,
     field_resolutions: FxHashMap<ExprId, StructField>,
     assoc_resolutions: FxHashMap<ExprOrPatId, ImplItem>,
     type_of_expr: ArenaMap<ExprId, Ty>,
@@ -175,6 +186,7 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
     fn new(db: &'a D, body: Arc<Body>, resolver: Resolver) -> Self {
         InferenceContext {
             method_resolutions: FxHashMap::default(),
+            method_types: FxHashMap::default(),
             field_resolutions: FxHashMap::default(),
             assoc_resolutions: FxHashMap::default(),
             type_of_expr: ArenaMap::default(),
@@ -182,6 +194,7 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
             diagnostics: Vec::default(),
             var_unification_table: InPlaceUnificationTable::new(),
             obligations: Vec::default(),
+            trait_env: lower::trait_env(db, &resolver),
             return_ty: Ty::Unknown, // set in collect_fn_signature
             db,
             body,
@@ -202,8 +215,14 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
             let resolved = self.resolve_ty_completely(&mut tv_stack, mem::replace(ty, Ty::Unknown));
             *ty = resolved;
         }
+        let mut method_types = mem::replace(&mut self.method_types, FxHashMap::default());
+        for ty in method_types.values_mut() {
+            let resolved = self.resolve_ty_completely(&mut tv_stack, mem::replace(ty, Ty::Unknown));
+            *ty = resolved;
+        }
         InferenceResult {
             method_resolutions: self.method_resolutions,
+            method_types,
             field_resolutions: self.field_resolutions,
             assoc_resolutions: self.assoc_resolutions,
             type_of_expr: expr_types,
@@ -239,7 +258,8 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
             &self.resolver,
             type_ref,
         );
-        self.insert_type_vars(ty)
+        let ty = self.insert_type_vars(ty);
+        self.normalize_associated_types_in(ty)
     }
 
     fn unify_substs(&mut self, substs1: &Substs, substs2: &Substs, depth: usize) -> bool {
@@ -264,9 +284,14 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
         match (&*ty1, &*ty2) {
             (Ty::Unknown, ..) => true,
             (.., Ty::Unknown) => true,
-            (Ty::Apply(a_ty1), Ty::Apply(a_ty2)) if a_ty1.ctor == a_ty2.ctor => {
+            (Ty::Apply(a_ty1), Ty::Apply(a_ty2)) if ctors_unify(a_ty1.ctor, a_ty2.ctor) => {
                 self.unify_substs(&a_ty1.parameters, &a_ty2.parameters, depth + 1)
             }
+            (Ty::Projection(p_ty1), Ty::Projection(p_ty2))
+                if p_ty1.associated_ty == p_ty2.associated_ty =>
+            {
+                self.unify_substs(&p_ty1.parameters, &p_ty2.parameters, depth + 1)
+            }
             (Ty::Infer(InferTy::TypeVar(tv1)), Ty::Infer(InferTy::TypeVar(tv2)))
             | (Ty::Infer(InferTy::IntVar(tv1)), Ty::Infer(InferTy::IntVar(tv2)))
             | (Ty::Infer(InferTy::FloatVar(tv1)), Ty::Infer(InferTy::FloatVar(tv2))) => {
@@ -320,19 +345,40 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
         ty.fold(&mut |ty| self.insert_type_vars_shallow(ty))
     }
 
+    /// Replaces all projections in the type by type variables, registering
+    /// obligations that the projections normalize to these variables.
+    fn normalize_associated_types_in(&mut self, ty: Ty) -> Ty {
+        ty.fold(&mut |ty| match ty {
+            Ty::Projection(proj_ty) => self.normalize_projection_ty(proj_ty),
+            _ => ty,
+        })
+    }
+
+    fn normalize_projection_ty(&mut self, proj_ty: ProjectionTy) -> Ty {
+        let var = self.new_type_var();
+        let predicate = ProjectionPredicate { projection_ty: proj_ty, ty: var.clone() };
+        self.obligations.push(Obligation::Projection(predicate));
+        var
+    }
+
     fn resolve_obligations_as_possible(&mut self) {
         let obligations = mem::replace(&mut self.obligations, Vec::new());
+        let krate = match self.resolver.krate() {
+            Some(krate) => krate,
+            None => return,
+        };
         for obligation in obligations {
+            let env = self.trait_env.clone();
             let (solution, canonicalized) = match &obligation {
                 Obligation::Trait(tr) => {
                     let canonicalized = self.canonicalizer().canonicalize_trait_ref(tr.clone());
-                    (
-                        self.db.implements(
-                            self.resolver.krate().unwrap(),
-                            canonicalized.value.clone(),
-                        ),
-                        canonicalized,
-                    )
+                    let goal = canonicalized.value.clone().map(|tr| InEnvironment::new(env, tr));
+                    (self.db.implements(krate, goal), canonicalized)
+                }
+                Obligation::Projection(pr) => {
+                    let canonicalized = self.canonicalizer().canonicalize_projection(pr.clone());
+                    let goal = canonicalized.value.clone().map(|pr| InEnvironment::new(env, pr));
+                    (self.db.normalize(krate, goal), canonicalized)
                 }
             };
             match solution {
@@ -461,24 +507,47 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
 
         let remaining_index = remaining_index.unwrap_or_else(|| path.segments.len());
         let mut actual_def_ty: Option<Ty> = None;
+        // set if the item was found through a trait, e.g. `T::default` or
+        // `<T as Trait>::item`; the Self type is the first parameter
+        let mut trait_ref: Option<TraitRef> = None;
 
         let krate = resolver.krate()?;
         // resolve intermediate segments
         for (i, segment) in path.segments[remaining_index..].iter().enumerate() {
+            // `remaining_index` is never 0 here, there's always a resolved prefix
+            assert!(remaining_index > 0);
+            let prev_segment = &path.segments[remaining_index + i - 1];
             let ty = match resolved {
+                Resolution::Def(ModuleDef::Trait(trait_)) => {
+                    // `Trait::item` or `<T as Trait>::item`
+                    let item = method_resolution::trait_assoc_item_by_name(
+                        self.db,
+                        trait_,
+                        &segment.name,
+                    )?;
+                    let item = trait_item_to_impl_item(item);
+                    self.write_assoc_resolution(id, item);
+                    trait_ref = Some(TraitRef::from_resolved_path(
+                        self.db,
+                        &self.resolver,
+                        trait_,
+                        prev_segment,
+                        None,
+                    ));
+                    resolved = Resolution::Def(impl_item_to_def(item));
+                    continue;
+                }
                 Resolution::Def(def) => {
-                    // FIXME resolve associated items from traits as well
                     let typable: Option<TypableDef> = def.into();
                     let typable = typable?;
 
                     let ty = self.db.type_for_def(typable, Namespace::Types);
 
                     // For example, this substs will take `Gen::*<u32>*::make`
-                    assert!(remaining_index > 0);
                     let substs = Ty::substs_from_path_segment(
                         self.db,
                         &self.resolver,
-                        &path.segments[remaining_index + i - 1],
+                        prev_segment,
                         typable,
                     );
 
@@ -488,14 +557,8 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                     // can't have a local binding in an associated item path
                     return None;
                 }
-                Resolution::GenericParam(..) => {
-                    // FIXME associated item of generic param
-                    return None;
-                }
-                Resolution::SelfType(_) => {
-                    // FIXME associated item of self type
-                    return None;
-                }
+                Resolution::GenericParam(idx) => Ty::Param { idx, name: prev_segment.name.clone() },
+                Resolution::SelfType(impl_block) => impl_block.target_ty(self.db),
             };
 
             // Attempt to find an impl_item for the type which has a name matching
@@ -504,38 +567,42 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
 
             actual_def_ty = Some(ty.clone());
 
-            let item: crate::ModuleDef = ty.iterate_impl_items(self.db, krate, |item| {
-                let matching_def: Option<crate::ModuleDef> = match item {
-                    crate::ImplItem::Method(func) => {
-                        if segment.name == func.name(self.db) {
-                            Some(func.into())
-                        } else {
-                            None
-                        }
-                    }
-
-                    crate::ImplItem::Const(konst) => {
-                        let data = konst.data(self.db);
-                        if segment.name == *data.name() {
-                            Some(konst.into())
-                        } else {
-                            None
-                        }
-                    }
-
-                    // FIXME: Resolve associated types
-                    crate::ImplItem::TypeAlias(_) => None,
-                };
-                match matching_def {
-                    Some(_) => {
-                        self.write_assoc_resolution(id, item);
-                        matching_def
+            let inherent_item: Option<ImplItem> =
+                ty.clone().iterate_impl_items(self.db, krate, |item| {
+                    let matches = match item {
+                        ImplItem::Method(func) => segment.name == func.name(self.db),
+                        ImplItem::Const(konst) => segment.name == *konst.data(self.db).name(),
+                        // FIXME: Resolve associated types
+                        ImplItem::TypeAlias(_) => false,
+                    };
+                    if matches {
+                        Some(item)
+                    } else {
+                        None
                     }
-                    None => None,
+                });
+            let item = match inherent_item {
+                Some(item) => item,
+                None => {
+                    // the item might come from a trait the type implements
+                    let (trait_, item) = method_resolution::lookup_trait_assoc_item(
+                        &ty,
+                        self.db,
+                        resolver,
+                        &segment.name,
+                    )?;
+                    // other trait parameters are inferred
+                    let param_count =
+                        trait_.generic_params(self.db).count_params_including_parent();
+                    let substs: Vec<_> =
+                        std::iter::once(ty).chain(repeat(Ty::Unknown)).take(param_count).collect();
+                    trait_ref = Some(TraitRef { trait_, substs: substs.into() });
+                    trait_item_to_impl_item(item)
                 }
-            })?;
+            };
+            self.write_assoc_resolution(id, item);
 
-            resolved = Resolution::Def(item);
+            resolved = Resolution::Def(impl_item_to_def(item));
         }
 
         match resolved {
@@ -543,13 +610,26 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                 let typable: Option<TypableDef> = def.into();
                 let typable = typable?;
                 let mut ty = self.db.type_for_def(typable, Namespace::Values);
-                if let Some(sts) = self.find_self_types(&def, actual_def_ty) {
+                if let Some(trait_ref) = trait_ref {
+                    // the item's parent parameters are the trait's, with
+                    // Self first; the type needs to implement the trait
+                    let substs: Substs = trait_ref
+                        .substs
+                        .iter()
+                        .map(|ty| self.insert_type_vars(ty.clone()))
+                        .collect::<Vec<_>>()
+                        .into();
+                    ty = ty.subst(&substs);
+                    self.obligations
+                        .push(Obligation::Trait(TraitRef { trait_: trait_ref.trait_, substs }));
+                } else if let Some(sts) = self.find_self_types(&def, actual_def_ty) {
                     ty = ty.subst(&sts);
                 }
 
                 let substs = Ty::substs_from_path(self.db, &self.resolver, path, typable);
                 let ty = ty.subst(&substs);
                 let ty = self.insert_type_vars(ty);
+                let ty = self.normalize_associated_types_in(ty);
                 Some(ty)
             }
             Resolution::LocalBinding(pat) => {
@@ -594,7 +674,7 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                 }
             });
 
-            Some(Substs(new_substs.into()))
+            Some(new_substs.into())
         } else {
             None
         }
@@ -657,6 +737,7 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
         &mut self,
         path: Option<&Path>,
         subpats: &[PatId],
+        ellipsis: Option<usize>,
         expected: &Ty,
         default_bm: BindingMode,
     ) -> Ty {
@@ -665,8 +746,19 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
         self.unify(&ty, expected);
 
         let substs = ty.substs().unwrap_or_else(Substs::empty);
+        // The patterns after the `..` match the last fields
+        let field_count =
+            def.and_then(|d| d.variant_data(self.db).fields().map(|it| it.len())).unwrap_or(0);
+        let skipped = match ellipsis {
+            Some(_) => field_count.saturating_sub(subpats.len()),
+            None => 0,
+        };
 
         for (i, &subpat) in subpats.iter().enumerate() {
+            let i = match ellipsis {
+                Some(ellipsis) if i >= ellipsis => i + skipped,
+                _ => i,
+            };
             let expected_ty = def
                 .and_then(|d| d.field(self.db, &Name::tuple_field_name(i)))
                 .map_or(Ty::Unknown, |field| field.ty(self.db))
@@ -704,7 +796,7 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
         let body = Arc::clone(&self.body); // avoid borrow checker problem
 
         let is_non_ref_pat = match &body[pat] {
-            Pat::Tuple(..)
+            Pat::Tuple { .. }
             | Pat::TupleStruct { .. }
             | Pat::Struct { .. }
             | Pat::Range { .. }
@@ -734,19 +826,30 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
         let expected = expected;
 
         let ty = match &body[pat] {
-            Pat::Tuple(ref args) => {
+            Pat::Tuple { ref args, ellipsis } => {
                 let expectations = match expected.as_tuple() {
                     Some(parameters) => &*parameters.0,
                     _ => &[],
                 };
-                let expectations_iter = expectations.iter().chain(repeat(&Ty::Unknown));
+                // The `..` stands for the elements not matched by the patterns
+                let (before, after) = args.split_at(ellipsis.unwrap_or(args.len()));
+                let skipped = match ellipsis {
+                    Some(_) => expectations.len().saturating_sub(args.len()),
+                    None => 0,
+                };
+                let mut expectations_iter = expectations.iter().chain(repeat(&Ty::Unknown));
 
-                let inner_tys: Substs = args
-                    .iter()
-                    .zip(expectations_iter)
-                    .map(|(&pat, ty)| self.infer_pat(pat, ty, default_bm))
-                    .collect::<Vec<_>>()
-                    .into();
+                let mut inner_tys = Vec::with_capacity(args.len() + skipped);
+                for &pat in before {
+                    let ty = expectations_iter.next().unwrap();
+                    inner_tys.push(self.infer_pat(pat, ty, default_bm));
+                }
+                inner_tys.extend(expectations_iter.by_ref().take(skipped).cloned());
+                for &pat in after {
+                    let ty = expectations_iter.next().unwrap();
+                    inner_tys.push(self.infer_pat(pat, ty, default_bm));
+                }
+                let inner_tys: Substs = inner_tys.into();
 
                 Ty::apply(TypeCtor::Tuple { cardinality: inner_tys.len() as u16 }, inner_tys)
             }
@@ -763,12 +866,13 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                 let subty = self.infer_pat(*pat, expectation, default_bm);
                 Ty::apply_one(TypeCtor::Ref(*mutability), subty)
             }
-            Pat::TupleStruct { path: ref p, args: ref subpats } => {
-                self.infer_tuple_struct_pat(p.as_ref(), subpats, expected, default_bm)
+            Pat::TupleStruct { path: ref p, args: ref subpats, ellipsis } => {
+                self.infer_tuple_struct_pat(p.as_ref(), subpats, *ellipsis, expected, default_bm)
             }
             Pat::Struct { path: ref p, args: ref fields } => {
                 self.infer_struct_pat(p.as_ref(), fields, expected, default_bm)
             }
+            Pat::Lit(expr) => self.infer_expr(*expr, &Expectation::none()),
             Pat::Path(path) => {
                 // FIXME use correct resolver for the surrounding expression
                 let resolver = self.resolver.clone();
@@ -829,13 +933,13 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
         // handle provided type arguments
         if let Some(generic_args) = generic_args {
             // if args are provided, it should be all of them, but we can't rely on that
-            for arg in generic_args.args.iter().take(param_count) {
-                match arg {
-                    GenericArg::Type(type_ref) => {
-                        let ty = self.make_ty(type_ref);
-                        substs.push(ty);
-                    }
-                }
+            let type_args = generic_args.args.iter().filter_map(|arg| match arg {
+                GenericArg::Type(type_ref) => Some(type_ref),
+                GenericArg::Lifetime(_) => None,
+            });
+            for type_ref in type_args.take(param_count) {
+                let ty = self.make_ty(type_ref);
+                substs.push(ty);
             }
         };
         let supplied_params = substs.len();
@@ -843,7 +947,7 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
             substs.push(Ty::Unknown);
         }
         assert_eq!(substs.len(), parent_param_count + param_count);
-        Substs(substs.into())
+        substs.into()
     }
 
     fn register_obligations_for_call(&mut self, callable_ty: &Ty) {
@@ -889,6 +993,7 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
             method_name,
             &self.resolver,
         );
+        let is_resolved = resolved.is_some();
         let (derefed_receiver_ty, method_ty, def_generics) = match resolved {
             Some((ty, func)) => {
                 let ty = canonicalized_receiver.decanonicalize_ty(ty);
@@ -905,6 +1010,9 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
         let method_ty = method_ty.apply_substs(substs);
         let method_ty = self.insert_type_vars(method_ty);
         self.register_obligations_for_call(&method_ty);
+        if is_resolved {
+            self.method_types.insert(tgt_expr, method_ty.clone());
+        }
         let (expected_receiver_ty, param_tys, ret_ty) = match method_ty.callable_sig(self.db) {
             Some(sig) => {
                 if !sig.params().is_empty() {
@@ -915,6 +1023,12 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
             }
             None => (Ty::Unknown, Vec::new(), Ty::Unknown),
         };
+        let expected_receiver_ty = self.normalize_associated_types_in(expected_receiver_ty);
+        let param_tys = param_tys
+            .into_iter()
+            .map(|ty| self.normalize_associated_types_in(ty))
+            .collect::<Vec<_>>();
+        let ret_ty = self.normalize_associated_types_in(ret_ty);
         // Apply autoref so the below unification works correctly
         // FIXME: return correct autorefs from lookup_method
         let actual_receiver_ty = match expected_receiver_ty.as_reference() {
@@ -998,6 +1112,11 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                         (Vec::new(), Ty::Unknown)
                     }
                 };
+                let param_tys = param_tys
+                    .into_iter()
+                    .map(|ty| self.normalize_associated_types_in(ty))
+                    .collect::<Vec<_>>();
+                let ret_ty = self.normalize_associated_types_in(ret_ty);
                 self.register_obligations_for_call(&callee_ty);
                 let param_iter = param_tys.into_iter().chain(repeat(Ty::Unknown));
                 for (arg, param) in args.iter().zip(param_iter) {
@@ -1096,7 +1215,8 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                     _ => None,
                 })
                 .unwrap_or(Ty::Unknown);
-                self.insert_type_vars(ty)
+                let ty = self.insert_type_vars(ty);
+                self.normalize_associated_types_in(ty)
             }
             Expr::Try { expr } => {
                 let _inner_ty = self.infer_expr(*expr, &Expectation::none());
@@ -1194,27 +1314,24 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                     ty_vec.push(self.infer_expr(*arg, &Expectation::none()));
                 }
 
-                Ty::apply(
-                    TypeCtor::Tuple { cardinality: ty_vec.len() as u16 },
-                    Substs(ty_vec.into()),
-                )
+                Ty::apply(TypeCtor::Tuple { cardinality: ty_vec.len() as u16 }, ty_vec.into())
             }
             Expr::Array(array) => {
-                let elem_ty = match &expected.ty {
+                let (elem_ty, expected_len) = match &expected.ty {
                     Ty::Apply(a_ty) => match a_ty.ctor {
-                        TypeCtor::Slice | TypeCtor::Array => {
-                            Ty::clone(&a_ty.parameters.as_single())
-                        }
-                        _ => self.new_type_var(),
+                        TypeCtor::Slice => (Ty::clone(&a_ty.parameters.as_single()), None),
+                        TypeCtor::Array { len } => (Ty::clone(&a_ty.parameters.as_single()), len),
+                        _ => (self.new_type_var(), None),
                     },
-                    _ => self.new_type_var(),
+                    _ => (self.new_type_var(), None),
                 };
 
-                match array {
+                let len = match array {
                     Array::ElementList(items) => {
                         for expr in items.iter() {
                             self.infer_expr(*expr, &Expectation::has_type(elem_ty.clone()));
                         }
+                        Some(items.len() as u64)
                     }
                     Array::Repeat { initializer, repeat } => {
                         self.infer_expr(*initializer, &Expectation::has_type(elem_ty.clone()));
@@ -1224,10 +1341,22 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
                                 primitive::UncertainIntTy::Known(primitive::IntTy::usize()),
                             ))),
                         );
+                        consteval::eval_expr(self.db, body.clone(), *repeat)
+                            .and_then(|len| len.as_usize())
+                    }
+                };
+
+                if let (Some(expected), Some(found)) = (expected_len, len) {
+                    if expected != found {
+                        self.diagnostics.push(InferenceDiagnostic::MismatchedArrayLen {
+                            expr: tgt_expr,
+                            expected,
+                            found,
+                        });
                     }
                 }
 
-                Ty::apply_one(TypeCtor::Array, elem_ty)
+                Ty::apply_one(TypeCtor::Array { len }, elem_ty)
             }
             Expr::Literal(lit) => match lit {
                 Literal::Bool(..) => Ty::simple(TypeCtor::Bool),
@@ -1303,6 +1432,34 @@ impl<'a, D: HirDatabase> InferenceContext<'a, D> {
     }
 }
 
+/// Whether two type constructors can be unified. This is equality, except that
+/// arrays whose length we couldn't evaluate are compatible with any length.
+fn ctors_unify(ctor1: TypeCtor, ctor2: TypeCtor) -> bool {
+    match (ctor1, ctor2) {
+        (TypeCtor::Array { len: Some(len1) }, TypeCtor::Array { len: Some(len2) }) => len1 == len2,
+        (TypeCtor::Array { .. }, TypeCtor::Array { .. }) => true,
+        _ => ctor1 == ctor2,
+    }
+}
+
+// Items found through traits are recorded as `ImplItem`s as well, so that
+// e.g. goto definition for `T::default` leads to the trait's declaration.
+fn trait_item_to_impl_item(item: TraitItem) -> ImplItem {
+    match item {
+        TraitItem::Function(f) => ImplItem::Method(f),
+        TraitItem::Const(c) => ImplItem::Const(c),
+        TraitItem::TypeAlias(t) => ImplItem::TypeAlias(t),
+    }
+}
+
+fn impl_item_to_def(item: ImplItem) -> ModuleDef {
+    match item {
+        ImplItem::Method(f) => f.into(),
+        ImplItem::Const(c) => c.into(),
+        ImplItem::TypeAlias(t) => t.into(),
+    }
+}
+
 /// The ID of a type variable.
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct TypeVarId(pub(super) u32);
@@ -1417,7 +1574,7 @@ impl Expectation {
 
 mod diagnostics {
     use crate::{
-        diagnostics::{DiagnosticSink, NoSuchField},
+        diagnostics::{DiagnosticSink, MismatchedArrayLen, NoSuchField},
         expr::ExprId,
         Function, HasSource, HirDatabase,
     };
@@ -1425,6 +1582,7 @@ mod diagnostics {
     #[derive(Debug, PartialEq, Eq, Clone)]
     pub(super) enum InferenceDiagnostic {
         NoSuchField { expr: ExprId, field: usize },
+        MismatchedArrayLen { expr: ExprId, expected: u64, found: u64 },
     }
 
     impl InferenceDiagnostic {
@@ -1440,6 +1598,17 @@ mod diagnostics {
                     let field = owner.body_source_map(db).field_syntax(*expr, *field);
                     sink.push(NoSuchField { file, field })
                 }
+                InferenceDiagnostic::MismatchedArrayLen { expr, expected, found } => {
+                    let file = owner.source(db).file_id;
+                    if let Some(expr) = owner.body_source_map(db).expr_syntax(*expr) {
+                        sink.push(MismatchedArrayLen {
+                            file,
+                            expr,
+                            expected: *expected,
+                            found: *found,
+                        })
+                    }
+                }
             }
         }
     }
@@ -2,7 +2,7 @@
 
 use super::InferenceContext;
 use crate::db::HirDatabase;
-use crate::ty::{Canonical, InferTy, TraitRef, Ty};
+use crate::ty::{Canonical, InferTy, ProjectionPredicate, ProjectionTy, TraitRef, Ty};
 
 impl<'a, D: HirDatabase> InferenceContext<'a, D> {
     pub(super) fn canonicalizer<'b>(&'b mut self) -> Canonicalizer<'a, 'b, D>
@@ -79,6 +79,25 @@ where
         TraitRef { trait_: trait_ref.trait_, substs: substs.into() }
     }
 
+    fn do_canonicalize_projection_ty(&mut self, projection_ty: ProjectionTy) -> ProjectionTy {
+        let params = projection_ty
+            .parameters
+            .iter()
+            .map(|ty| self.do_canonicalize_ty(ty.clone()))
+            .collect::<Vec<_>>();
+        ProjectionTy { associated_ty: projection_ty.associated_ty, parameters: params.into() }
+    }
+
+    fn do_canonicalize_projection_predicate(
+        &mut self,
+        projection: ProjectionPredicate,
+    ) -> ProjectionPredicate {
+        let ty = self.do_canonicalize_ty(projection.ty);
+        let projection_ty = self.do_canonicalize_projection_ty(projection.projection_ty);
+
+        ProjectionPredicate { ty, projection_ty }
+    }
+
     fn into_canonicalized<T>(self, result: T) -> Canonicalized<T> {
         Canonicalized {
             value: Canonical { value: result, num_vars: self.free_vars.len() },
@@ -95,6 +114,14 @@ where
         let result = self.do_canonicalize_trait_ref(trait_ref);
         self.into_canonicalized(result)
     }
+
+    pub fn canonicalize_projection(
+        mut self,
+        projection: ProjectionPredicate,
+    ) -> Canonicalized<ProjectionPredicate> {
+        let result = self.do_canonicalize_projection_predicate(projection);
+        self.into_canonicalized(result)
+    }
 }
 
 impl<T> Canonicalized<T> {
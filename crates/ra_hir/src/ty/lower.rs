@@ -8,7 +8,10 @@
 use std::iter;
 use std::sync::Arc;
 
-use super::{FnSig, GenericPredicate, Substs, TraitRef, Ty, TypeCtor};
+use super::{
+    consteval, FnSig, GenericPredicate, Lifetime, ProjectionTy, Substs, TraitEnvironment, TraitRef,
+    Ty, TypeCtor,
+};
 use crate::{
     adt::VariantDef,
     generics::HasGenericParams,
@@ -17,9 +20,9 @@ use crate::{
     path::{GenericArg, PathSegment},
     resolve::{Resolution, Resolver},
     ty::AdtDef,
-    type_ref::TypeRef,
-    BuiltinType, Const, Enum, EnumVariant, Function, HirDatabase, ModuleDef, Path, Static, Struct,
-    StructField, Trait, TypeAlias, Union,
+    type_ref::{LifetimeRef, TypeRef},
+    BuiltinType, Const, Enum, EnumVariant, Function, HirDatabase, ImplItem, ModuleDef, Name, Path,
+    Static, Struct, StructField, Trait, TypeAlias, Union,
 };
 
 impl Ty {
@@ -29,33 +32,33 @@ impl Ty {
             TypeRef::Tuple(inner) => {
                 let inner_tys =
                     inner.iter().map(|tr| Ty::from_hir(db, resolver, tr)).collect::<Vec<_>>();
-                Ty::apply(
-                    TypeCtor::Tuple { cardinality: inner_tys.len() as u16 },
-                    Substs(inner_tys.into()),
-                )
+                Ty::apply(TypeCtor::Tuple { cardinality: inner_tys.len() as u16 }, inner_tys.into())
             }
             TypeRef::Path(path) => Ty::from_hir_path(db, resolver, path),
             TypeRef::RawPtr(inner, mutability) => {
                 let inner_ty = Ty::from_hir(db, resolver, inner);
                 Ty::apply_one(TypeCtor::RawPtr(*mutability), inner_ty)
             }
-            TypeRef::Array(inner) => {
+            TypeRef::Array(inner, len) => {
                 let inner_ty = Ty::from_hir(db, resolver, inner);
-                Ty::apply_one(TypeCtor::Array, inner_ty)
+                let len = consteval::eval_array_len(db, resolver, len);
+                Ty::apply_one(TypeCtor::Array { len }, inner_ty)
             }
             TypeRef::Slice(inner) => {
                 let inner_ty = Ty::from_hir(db, resolver, inner);
                 Ty::apply_one(TypeCtor::Slice, inner_ty)
             }
-            TypeRef::Reference(inner, mutability) => {
+            TypeRef::Reference(inner, mutability, lifetime) => {
                 let inner_ty = Ty::from_hir(db, resolver, inner);
-                Ty::apply_one(TypeCtor::Ref(*mutability), inner_ty)
+                let lifetime = Lifetime::from_hir(resolver, lifetime);
+                let substs = Substs::single(inner_ty).with_lifetimes(vec![lifetime]);
+                Ty::apply(TypeCtor::Ref(*mutability), substs)
             }
             TypeRef::Placeholder => Ty::Unknown,
             TypeRef::Fn(params) => {
                 let inner_tys =
                     params.iter().map(|tr| Ty::from_hir(db, resolver, tr)).collect::<Vec<_>>();
-                let sig = Substs(inner_tys.into());
+                let sig = Substs::from(inner_tys);
                 Ty::apply(TypeCtor::FnPtr { num_args: sig.len() as u16 - 1 }, sig)
             }
             TypeRef::Error => Ty::Unknown,
@@ -64,15 +67,28 @@ impl Ty {
 
     pub(crate) fn from_hir_path(db: &impl HirDatabase, resolver: &Resolver, path: &Path) -> Self {
         // Resolve the path (in type namespace)
-        let resolution = resolver.resolve_path_without_assoc_items(db, path).take_types();
+        let (resolution, remaining_index) = resolver.resolve_path_segments(db, path).into_inner();
+        let resolution = match resolution.take_types() {
+            Some(it) => it,
+            None => return Ty::Unknown,
+        };
+
+        if let Some(remaining_index) = remaining_index {
+            // a type-relative path like `T::Item`; only associated types can
+            // be referred to like this
+            if remaining_index + 1 != path.segments.len() {
+                return Ty::Unknown;
+            }
+            return Ty::from_type_relative_path(db, resolver, path, resolution);
+        }
 
         let def = match resolution {
-            Some(Resolution::Def(def)) => def,
-            Some(Resolution::LocalBinding(..)) => {
+            Resolution::Def(def) => def,
+            Resolution::LocalBinding(..) => {
                 // this should never happen
                 panic!("path resolved to local binding in type ns");
             }
-            Some(Resolution::GenericParam(idx)) => {
+            Resolution::GenericParam(idx) => {
                 return Ty::Param {
                     idx,
                     // FIXME: maybe return name in resolution?
@@ -82,10 +98,9 @@ impl Ty {
                         .clone(),
                 };
             }
-            Some(Resolution::SelfType(impl_block)) => {
+            Resolution::SelfType(impl_block) => {
                 return impl_block.target_ty(db);
             }
-            None => return Ty::Unknown,
         };
 
         let typable: TypableDef = match def.into() {
@@ -97,6 +112,49 @@ impl Ty {
         ty.subst(&substs)
     }
 
+    /// Lowers a path like `T::Item`, `Self::Item` or `<T as Trait>::Item`,
+    /// where the prefix resolved to a type parameter, `Self` or a trait, to
+    /// the associated type of that name.
+    fn from_type_relative_path(
+        db: &impl HirDatabase,
+        resolver: &Resolver,
+        path: &Path,
+        resolution: Resolution,
+    ) -> Ty {
+        let assoc_name = &path.segments[path.segments.len() - 1].name;
+        let projection = match resolution {
+            Resolution::GenericParam(idx) => {
+                let self_ty = Ty::Param { idx, name: path.segments[0].name.clone() };
+                select_associated_type(db, resolver, self_ty, assoc_name)
+            }
+            Resolution::SelfType(impl_block) => {
+                // in a trait impl, `Self::Item` refers to the impl's definition
+                let item = impl_block.items(db).into_iter().find_map(|item| match item {
+                    ImplItem::TypeAlias(t) if t.name(db) == *assoc_name => Some(t),
+                    _ => None,
+                });
+                if let Some(type_alias) = item {
+                    return db.type_for_def(type_alias.into(), Namespace::Types);
+                }
+                impl_block.target_trait_ref(db).and_then(|trait_ref| {
+                    let associated_ty =
+                        trait_ref.trait_.associated_type_by_name(db, assoc_name.clone())?;
+                    Some(ProjectionTy { associated_ty, parameters: trait_ref.substs })
+                })
+            }
+            Resolution::Def(ModuleDef::Trait(trait_)) => {
+                // `<T as Trait>::Item` or `Trait::Item`
+                let segment = &path.segments[path.segments.len() - 2];
+                let trait_ref = TraitRef::from_resolved_path(db, resolver, trait_, segment, None);
+                trait_.associated_type_by_name(db, assoc_name.clone()).map(|associated_ty| {
+                    ProjectionTy { associated_ty, parameters: trait_ref.substs }
+                })
+            }
+            Resolution::Def(_) | Resolution::LocalBinding(_) => None,
+        };
+        projection.map_or(Ty::Unknown, Ty::Projection)
+    }
+
     pub(super) fn substs_from_path_segment(
         db: &impl HirDatabase,
         resolver: &Resolver,
@@ -163,29 +221,48 @@ pub(super) fn substs_from_path_segment(
 ) -> Substs {
     let mut substs = Vec::new();
     let def_generics = def_generic.map(|def| def.generic_params(db)).unwrap_or_default();
+    let mut lifetimes = vec![Lifetime::Unknown; def_generics.count_parent_lifetimes()];
 
     let parent_param_count = def_generics.count_parent_params();
     substs.extend(iter::repeat(Ty::Unknown).take(parent_param_count));
+    let explicit_self_ty = segment.args_and_bindings.as_ref().and_then(|args| {
+        if args.has_self_type {
+            args.args.first()
+        } else {
+            None
+        }
+    });
     if add_self_param {
         // FIXME this add_self_param argument is kind of a hack: Traits have the
         // Self type as an implicit first type parameter, but it can't be
-        // actually provided in the type arguments
-        // (well, actually sometimes it can, in the form of type-relative paths: `<Foo as Default>::default()`)
-        substs.push(Ty::Unknown);
+        // actually provided in the type arguments, except in the form of
+        // qualified paths like `<Foo as Default>::default()`
+        let self_ty = match explicit_self_ty {
+            Some(GenericArg::Type(type_ref)) => Ty::from_hir(db, resolver, type_ref),
+            Some(GenericArg::Lifetime(_)) | None => Ty::Unknown,
+        };
+        substs.push(self_ty);
     }
     if let Some(generic_args) = &segment.args_and_bindings {
         // if args are provided, it should be all of them, but we can't rely on that
         let self_param_correction = if add_self_param { 1 } else { 0 };
         let param_count = def_generics.params.len() - self_param_correction;
-        for arg in generic_args.args.iter().take(param_count) {
-            match arg {
-                GenericArg::Type(type_ref) => {
-                    let ty = Ty::from_hir(db, resolver, type_ref);
-                    substs.push(ty);
-                }
-            }
+        let skip = if generic_args.has_self_type { 1 } else { 0 };
+        let type_args = generic_args.args.iter().filter_map(|arg| match arg {
+            GenericArg::Type(type_ref) => Some(type_ref),
+            GenericArg::Lifetime(_) => None,
+        });
+        for type_ref in type_args.skip(skip).take(param_count) {
+            let ty = Ty::from_hir(db, resolver, type_ref);
+            substs.push(ty);
         }
+        let lifetime_args = generic_args.args.iter().filter_map(|arg| match arg {
+            GenericArg::Lifetime(lifetime) => Some(Lifetime::from_hir(resolver, lifetime)),
+            GenericArg::Type(_) => None,
+        });
+        lifetimes.extend(lifetime_args.take(def_generics.lifetimes.len()));
     }
+    lifetimes.resize(def_generics.count_lifetimes_including_parent(), Lifetime::Unknown);
     // add placeholders for args that were not provided
     let supplied_params = substs.len();
     for _ in supplied_params..def_generics.count_params_including_parent() {
@@ -205,7 +282,20 @@ pub(super) fn substs_from_path_segment(
         }
     }
 
-    Substs(substs.into())
+    Substs::from(substs).with_lifetimes(lifetimes)
+}
+
+impl Lifetime {
+    pub(crate) fn from_hir(resolver: &Resolver, lifetime_ref: &LifetimeRef) -> Self {
+        match lifetime_ref {
+            LifetimeRef::Named(name) => match resolver.resolve_lifetime(name) {
+                Some(param) => Lifetime::Param { idx: param.idx, name: param.name.clone() },
+                None => Lifetime::Unknown,
+            },
+            LifetimeRef::Static => Lifetime::Static,
+            LifetimeRef::Elided => Lifetime::Unknown,
+        }
+    }
 }
 
 impl TraitRef {
@@ -219,14 +309,29 @@ impl TraitRef {
             Resolution::Def(ModuleDef::Trait(tr)) => tr,
             _ => return None,
         };
-        let mut substs = Self::substs_from_path(db, resolver, path, resolved);
+        let segment = path.segments.last().expect("path should have at least one segment");
+        Some(TraitRef::from_resolved_path(db, resolver, resolved, segment, explicit_self_ty))
+    }
+
+    /// Builds the trait ref for an already resolved trait path, taking the
+    /// generic arguments (and possibly the Self type, for qualified paths
+    /// like `<T as Trait>`) from the given segment.
+    pub(crate) fn from_resolved_path(
+        db: &impl HirDatabase,
+        resolver: &Resolver,
+        resolved: Trait,
+        segment: &PathSegment,
+        explicit_self_ty: Option<Ty>,
+    ) -> Self {
+        let mut substs =
+            substs_from_path_segment(db, resolver, segment, Some(resolved.into()), true);
         if let Some(self_ty) = explicit_self_ty {
             // FIXME this could be nicer
             let mut substs_vec = substs.0.to_vec();
             substs_vec[0] = self_ty;
             substs.0 = substs_vec.into();
         }
-        Some(TraitRef { trait_: resolved, substs })
+        TraitRef { trait_: resolved, substs }
     }
 
     pub(crate) fn from_hir(
@@ -242,16 +347,6 @@ impl TraitRef {
         TraitRef::from_path(db, resolver, path, explicit_self_ty)
     }
 
-    fn substs_from_path(
-        db: &impl HirDatabase,
-        resolver: &Resolver,
-        path: &Path,
-        resolved: Trait,
-    ) -> Substs {
-        let segment = path.segments.last().expect("path should have at least one segment");
-        substs_from_path_segment(db, resolver, segment, Some(resolved.into()), true)
-    }
-
     pub(crate) fn for_trait(db: &impl HirDatabase, trait_: Trait) -> TraitRef {
         let substs = Substs::identity(&trait_.generic_params(db));
         TraitRef { trait_, substs }
@@ -333,6 +428,86 @@ pub(crate) fn generic_predicates_query(
     predicates.into()
 }
 
+/// Finds the associated type with the given name in the traits the type
+/// parameter `self_ty` is known to implement, i.e. from the where clauses in
+/// scope and, inside a trait, the implicit `Self: Trait`.
+fn select_associated_type(
+    db: &impl HirDatabase,
+    resolver: &Resolver,
+    self_ty: Ty,
+    assoc_name: &Name,
+) -> Option<ProjectionTy> {
+    let projection = |trait_ref: TraitRef| {
+        let associated_ty = trait_ref.trait_.associated_type_by_name(db, assoc_name.clone())?;
+        Some(ProjectionTy { associated_ty, parameters: trait_ref.substs })
+    };
+    if let Ty::Param { name, .. } = &self_ty {
+        if *name == Name::self_type() {
+            if let Some(trait_) = enclosing_trait(db, resolver) {
+                if let Some(proj_ty) = projection(TraitRef::for_trait(db, trait_)) {
+                    return Some(proj_ty);
+                }
+            }
+        }
+    }
+    for pred in resolver.where_predicates_in_scope() {
+        // Only look at predicates directly on the parameter here; lowering
+        // something like `T::Item: Trait` would recurse back into us.
+        let is_on_param = match &pred.type_ref {
+            TypeRef::Path(path) => {
+                path.as_ident().is_some() && Ty::from_hir_path(db, resolver, path) == self_ty
+            }
+            _ => false,
+        };
+        if !is_on_param {
+            continue;
+        }
+        // check the trait before lowering its arguments, which may themselves
+        // contain associated type shorthands
+        let trait_ =
+            match resolver.resolve_path_without_assoc_items(db, &pred.trait_ref).take_types() {
+                Some(Resolution::Def(ModuleDef::Trait(t))) => t,
+                _ => continue,
+            };
+        if trait_.associated_type_by_name(db, assoc_name.clone()).is_none() {
+            continue;
+        }
+        if let Some(trait_ref) =
+            TraitRef::from_path(db, resolver, &pred.trait_ref, Some(self_ty.clone()))
+        {
+            return projection(trait_ref);
+        }
+    }
+    None
+}
+
+/// The trait we're in, if the resolver belongs to a trait or one of its items.
+fn enclosing_trait(db: &impl HirDatabase, resolver: &Resolver) -> Option<Trait> {
+    match resolver.generic_def()? {
+        GenericDef::Trait(t) => Some(t),
+        GenericDef::Function(f) => f.parent_trait(db),
+        GenericDef::TypeAlias(t) => t.parent_trait(db),
+        _ => None,
+    }
+}
+
+/// Collects the predicates that can be assumed to hold in the scope of the
+/// given resolver, for use as the environment of trait goals.
+pub(crate) fn trait_env(db: &impl HirDatabase, resolver: &Resolver) -> Arc<TraitEnvironment> {
+    let mut predicates = resolver
+        .where_predicates_in_scope()
+        .map(|pred| {
+            TraitRef::for_where_predicate(db, &resolver, pred)
+                .map_or(GenericPredicate::Error, GenericPredicate::Implemented)
+        })
+        .collect::<Vec<_>>();
+    if let Some(trait_) = enclosing_trait(db, resolver) {
+        // inside a trait, `Self` implements the trait
+        predicates.push(GenericPredicate::Implemented(TraitRef::for_trait(db, trait_)));
+    }
+    Arc::new(TraitEnvironment { predicates })
+}
+
 /// Resolve the default type params from generics
 pub(crate) fn generic_defaults_query(db: &impl HirDatabase, def: GenericDef) -> Substs {
     let resolver = def.resolver(db);
@@ -346,7 +521,7 @@ pub(crate) fn generic_defaults_query(db: &impl HirDatabase, def: GenericDef) ->
         })
         .collect::<Vec<_>>();
 
-    Substs(defaults.into())
+    defaults.into()
 }
 
 fn fn_sig_for_fn(db: &impl HirDatabase, def: Function) -> FnSig {
@@ -5,9 +5,11 @@
 use std::sync::Arc;
 
 use arrayvec::ArrayVec;
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 
-use super::{autoderef, Canonical, TraitRef};
+use super::{
+    autoderef, lower, Canonical, GenericPredicate, InEnvironment, TraitEnvironment, TraitRef,
+};
 use crate::{
     generics::HasGenericParams,
     impl_block::{ImplBlock, ImplId, ImplItem},
@@ -16,6 +18,7 @@ use crate::{
     traits::TraitItem,
     ty::primitive::{UncertainFloatTy, UncertainIntTy},
     ty::{Ty, TypeCtor},
+    type_ref::Mutability,
     Crate, Function, HirDatabase, Module, Name, Trait,
 };
 
@@ -68,6 +71,11 @@ impl CrateImplBlocks {
         )
     }
 
+    /// The traits which have impls in this crate.
+    pub fn traits<'a>(&'a self) -> impl Iterator<Item = Trait> + 'a {
+        self.impls_by_trait.keys().cloned()
+    }
+
     fn collect_recursive(&mut self, db: &impl HirDatabase, module: Module) {
         let module_impl_blocks = db.impls_in_module(module);
 
@@ -117,13 +125,20 @@ impl CrateImplBlocks {
 fn def_crates(db: &impl HirDatabase, cur_crate: Crate, ty: &Ty) -> Option<ArrayVec<[Crate; 2]>> {
     // Types like slice can have inherent impls in several crates, (core and alloc).
     // The correspoinding impls are marked with lang items, so we can use them to find the required crates.
+    // Not all of these crates need to be present (e.g. in `no_std` crates), so
+    // missing lang items are skipped.
     macro_rules! lang_item_crate {
         ($db:expr, $cur_crate:expr, $($name:expr),+ $(,)?) => {{
             let mut v = ArrayVec::<[Crate; 2]>::new();
             $(
-                v.push($db.lang_item($cur_crate, $name.into())?.krate($db)?);
+                let krate = $db.lang_item($cur_crate, $name.into()).and_then(|it| it.krate($db));
+                if let Some(krate) = krate {
+                    if !v.contains(&krate) {
+                        v.push(krate);
+                    }
+                }
             )+
-            Some(v)
+            if v.is_empty() { None } else { Some(v) }
         }};
     }
 
@@ -133,13 +148,17 @@ fn def_crates(db: &impl HirDatabase, cur_crate: Crate, ty: &Ty) -> Option<ArrayV
             TypeCtor::Bool => lang_item_crate!(db, cur_crate, "bool"),
             TypeCtor::Char => lang_item_crate!(db, cur_crate, "char"),
             TypeCtor::Float(UncertainFloatTy::Known(f)) => {
-                lang_item_crate!(db, cur_crate, f.ty_to_string())
+                // methods like `abs` need the runtime and are implemented in std
+                let runtime = format!("{}_runtime", f.ty_to_string());
+                lang_item_crate!(db, cur_crate, f.ty_to_string(), runtime)
             }
             TypeCtor::Int(UncertainIntTy::Known(i)) => {
                 lang_item_crate!(db, cur_crate, i.ty_to_string())
             }
-            TypeCtor::Str => lang_item_crate!(db, cur_crate, "str"),
+            TypeCtor::Str => lang_item_crate!(db, cur_crate, "str_alloc", "str"),
             TypeCtor::Slice => lang_item_crate!(db, cur_crate, "slice_alloc", "slice"),
+            TypeCtor::RawPtr(Mutability::Shared) => lang_item_crate!(db, cur_crate, "const_ptr"),
+            TypeCtor::RawPtr(Mutability::Mut) => lang_item_crate!(db, cur_crate, "mut_ptr"),
             _ => None,
         },
         _ => None,
@@ -176,7 +195,13 @@ pub(crate) fn iterate_method_candidates<T>(
     // rustc does an autoderef and then autoref again).
 
     let krate = resolver.krate()?;
-    for derefed_ty in autoderef::autoderef(db, resolver, ty.clone()) {
+    let mut deref_chain: Vec<_> = autoderef::autoderef(db, resolver, ty.clone()).collect();
+    // As a last step, arrays are unsized to slices, so that e.g. `[1, 2].iter()`
+    // finds the inherent methods of `[T]`.
+    if let Some(unsized_ty) = deref_chain.last().and_then(unsize_array) {
+        deref_chain.push(unsized_ty);
+    }
+    for derefed_ty in deref_chain {
         if let Some(result) = iterate_inherent_methods(&derefed_ty, db, name, krate, &mut callback)
         {
             return Some(result);
@@ -190,6 +215,19 @@ pub(crate) fn iterate_method_candidates<T>(
     None
 }
 
+fn unsize_array(ty: &Canonical<Ty>) -> Option<Canonical<Ty>> {
+    match &ty.value {
+        Ty::Apply(a_ty) => match a_ty.ctor {
+            TypeCtor::Array { .. } => Some(Canonical {
+                num_vars: ty.num_vars,
+                value: Ty::apply(TypeCtor::Slice, a_ty.parameters.clone()),
+            }),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 fn iterate_trait_method_candidates<T>(
     ty: &Canonical<Ty>,
     db: &impl HirDatabase,
@@ -198,6 +236,7 @@ fn iterate_trait_method_candidates<T>(
     mut callback: impl FnMut(&Ty, Function) -> Option<T>,
 ) -> Option<T> {
     let krate = resolver.krate()?;
+    let env = lower::trait_env(db, resolver);
     'traits: for t in resolver.traits_in_scope(db) {
         let data = t.trait_data(db);
         // we'll be lazy about checking whether the type implements the
@@ -209,8 +248,8 @@ fn iterate_trait_method_candidates<T>(
                 let data = m.data(db);
                 if name.map_or(true, |name| data.name() == name) && data.has_self_param() {
                     if !known_implemented {
-                        let trait_ref = canonical_trait_ref(db, t, ty.clone());
-                        if db.implements(krate, trait_ref).is_none() {
+                        let goal = generic_implements_goal(db, env.clone(), t, ty.clone());
+                        if db.implements(krate, goal).is_none() {
                             continue 'traits;
                         }
                     }
@@ -225,6 +264,53 @@ fn iterate_trait_method_candidates<T>(
     None
 }
 
+/// Looks up an associated item (function or const) of the given name that
+/// `ty` has through a trait, for paths like `T::default` or `S::new`. Traits
+/// bounding `ty` in the current environment are considered first, even if
+/// they aren't in scope; then any trait in scope that `ty` implements.
+pub(crate) fn lookup_trait_assoc_item(
+    ty: &Ty,
+    db: &impl HirDatabase,
+    resolver: &Resolver,
+    name: &Name,
+) -> Option<(Trait, TraitItem)> {
+    let krate = resolver.krate()?;
+    let env = lower::trait_env(db, resolver);
+    let bound_traits = env.predicates.iter().filter_map(|pred| match pred {
+        GenericPredicate::Implemented(tr) if tr.self_ty() == ty => Some(tr.trait_),
+        _ => None,
+    });
+    for t in bound_traits {
+        if let Some(item) = trait_assoc_item_by_name(db, t, name) {
+            return Some((t, item));
+        }
+    }
+    // the type comes from lowering a path, so it doesn't contain any inference
+    // variables that would need to be canonicalized
+    let canonical_ty = Canonical { num_vars: 0, value: ty.clone() };
+    for t in resolver.traits_in_scope(db) {
+        if let Some(item) = trait_assoc_item_by_name(db, t, name) {
+            let goal = generic_implements_goal(db, env.clone(), t, canonical_ty.clone());
+            if db.implements(krate, goal).is_some() {
+                return Some((t, item));
+            }
+        }
+    }
+    None
+}
+
+pub(crate) fn trait_assoc_item_by_name(
+    db: &impl HirDatabase,
+    trait_: Trait,
+    name: &Name,
+) -> Option<TraitItem> {
+    trait_.trait_data(db).items().iter().cloned().find(|item| match item {
+        TraitItem::Function(f) => f.name(db) == *name,
+        TraitItem::Const(c) => c.data(db).name() == name,
+        TraitItem::TypeAlias(_) => false,
+    })
+}
+
 fn iterate_inherent_methods<T>(
     ty: &Canonical<Ty>,
     db: &impl HirDatabase,
@@ -273,15 +359,69 @@ impl Ty {
         }
         None
     }
+
+    /// Lists the traits this type implements, out of the traits which have
+    /// impls in `krate`, in the crate of the type or in their dependencies:
+    /// the crates which can hold impls for the type visible from `krate`.
+    /// Blanket impls are taken into account, as the trait solver decides for
+    /// each trait.
+    pub fn implemented_traits(&self, db: &impl HirDatabase, krate: Crate) -> Vec<Trait> {
+        let canonical_ty = Canonical { num_vars: 0, value: self.clone() };
+        // Impls are checked from a crate which has them in its dependencies
+        let mut roots = vec![(krate, with_dependencies(db, krate))];
+        if let Some(def_crate) = self.as_adt().and_then(|(adt, _)| adt.krate(db)) {
+            if !roots[0].1.contains(&def_crate) {
+                roots.push((def_crate, with_dependencies(db, def_crate)));
+            }
+        }
+        let mut res = Vec::new();
+        for (root, crates) in roots {
+            let mut checked = FxHashSet::default();
+            for &dep in crates.iter() {
+                for t in db.impls_in_crate(dep).traits() {
+                    if res.contains(&t) || !checked.insert(t) {
+                        continue;
+                    }
+                    db.check_canceled();
+                    let goal = generic_implements_goal(
+                        db,
+                        TraitEnvironment::empty(),
+                        t,
+                        canonical_ty.clone(),
+                    );
+                    if db.implements(root, goal).is_some() {
+                        res.push(t);
+                    }
+                }
+            }
+        }
+        res
+    }
+}
+
+/// The crate and all the crates it depends on, directly or not.
+fn with_dependencies(db: &impl HirDatabase, krate: Crate) -> Vec<Crate> {
+    let mut res = vec![krate];
+    let mut idx = 0;
+    while idx < res.len() {
+        for dep in res[idx].dependencies(db) {
+            if !res.contains(&dep.krate) {
+                res.push(dep.krate);
+            }
+        }
+        idx += 1;
+    }
+    res
 }
 
 /// This creates Substs for a trait with the given Self type and type variables
 /// for all other parameters, to query Chalk with it.
-fn canonical_trait_ref(
+fn generic_implements_goal(
     db: &impl HirDatabase,
+    env: Arc<TraitEnvironment>,
     trait_: Trait,
     self_ty: Canonical<Ty>,
-) -> Canonical<TraitRef> {
+) -> Canonical<InEnvironment<TraitRef>> {
     let mut substs = Vec::new();
     let generics = trait_.generic_params(db);
     let num_vars = self_ty.num_vars;
@@ -294,8 +434,7 @@ fn canonical_trait_ref(
             .enumerate()
             .map(|(i, _p)| Ty::Bound((i + num_vars) as u32)),
     );
-    Canonical {
-        num_vars: substs.len() - 1 + self_ty.num_vars,
-        value: TraitRef { trait_, substs: substs.into() },
-    }
+    let num_vars = substs.len() - 1 + self_ty.num_vars;
+    let trait_ref = TraitRef { trait_, substs: substs.into() };
+    Canonical { num_vars, value: InEnvironment::new(env, trait_ref) }
 }
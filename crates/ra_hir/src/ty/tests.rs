@@ -12,8 +12,8 @@ use ra_syntax::{
 use test_utils::covers;
 
 use crate::{
-    expr::BodySourceMap, mock::MockDatabase, ty::display::HirDisplay, ty::InferenceResult,
-    SourceAnalyzer,
+    diagnostics::MismatchedArrayLen, expr::BodySourceMap, mock::MockDatabase,
+    ty::display::HirDisplay, ty::InferenceResult, SourceAnalyzer,
 };
 
 // These tests compare the inference results for all expressions in a file
@@ -649,6 +649,114 @@ fn test() {
     );
 }
 
+#[test]
+fn inherent_methods_of_primitives_across_sysroot_crates() {
+    let (mut db, pos) = MockDatabase::with_position(
+        r#"
+//- /main.rs
+fn test(x: f64, p: *const u8, q: *mut u8) {
+    (
+        "a".len(),
+        "a".to_uppercase(),
+        [1, 2].iter(),
+        [1u8].to_vec(),
+        x.abs(),
+        x.to_bits(),
+        p.add(1),
+        q.as_ptr(),
+        'c'.is_alphabetic(),
+    )<|>;
+}
+
+//- /std.rs
+#[lang = "f64_runtime"]
+impl f64 {
+    pub fn abs(self) -> f64 {}
+}
+
+//- /alloc.rs
+pub struct String;
+pub struct Vec<T>;
+
+#[lang = "str_alloc"]
+impl str {
+    pub fn to_uppercase(&self) -> String {}
+}
+
+#[lang = "slice_alloc"]
+impl<T> [T] {
+    pub fn to_vec(&self) -> Vec<T> {}
+}
+
+//- /core.rs
+pub struct Iter<T>;
+
+#[lang = "str"]
+impl str {
+    pub fn len(&self) -> usize {}
+}
+
+#[lang = "slice"]
+impl<T> [T] {
+    pub fn iter(&self) -> Iter<T> {}
+}
+
+#[lang = "f64"]
+impl f64 {
+    pub fn to_bits(self) -> u64 {}
+}
+
+#[lang = "const_ptr"]
+impl<T> *const T {
+    pub fn add(self, count: usize) -> *const T {}
+}
+
+#[lang = "mut_ptr"]
+impl<T> *mut T {
+    pub fn as_ptr(self) -> *const T {}
+}
+
+#[lang = "char"]
+impl char {
+    pub fn is_alphabetic(self) -> bool {}
+}
+"#,
+    );
+    db.set_crate_graph_from_fixture(crate_graph! {
+        "main": ("/main.rs", ["std"]),
+        "std": ("/std.rs", ["alloc", "core"]),
+        "alloc": ("/alloc.rs", ["core"]),
+        "core": ("/core.rs", []),
+    });
+    assert_eq!(
+        "(usize, String, Iter<i32>, Vec<u8>, f64, u64, *const u8, *const u8, bool)",
+        type_at_pos(&db, pos)
+    );
+}
+
+#[test]
+fn inherent_methods_of_slices_without_alloc() {
+    let (mut db, pos) = MockDatabase::with_position(
+        r#"
+//- /main.rs
+fn test() {
+    [1, 2].len()<|>;
+}
+
+//- /core.rs
+#[lang = "slice"]
+impl<T> [T] {
+    pub fn len(&self) -> usize {}
+}
+"#,
+    );
+    db.set_crate_graph_from_fixture(crate_graph! {
+        "main": ("/main.rs", ["core"]),
+        "core": ("/core.rs", []),
+    });
+    assert_eq!("usize", type_at_pos(&db, pos));
+}
+
 #[test]
 fn infer_tuple() {
     assert_snapshot_matches!(
@@ -720,58 +828,58 @@ fn test(x: &str, y: isize) {
 [9; 10) 'x': &str
 [18; 19) 'y': isize
 [28; 324) '{     ... 3]; }': ()
-[38; 39) 'a': [&str;_]
-[42; 45) '[x]': [&str;_]
+[38; 39) 'a': [&str; 1]
+[42; 45) '[x]': [&str; 1]
 [43; 44) 'x': &str
-[55; 56) 'b': [[&str;_];_]
-[59; 65) '[a, a]': [[&str;_];_]
-[60; 61) 'a': [&str;_]
-[63; 64) 'a': [&str;_]
-[75; 76) 'c': [[[&str;_];_];_]
-[79; 85) '[b, b]': [[[&str;_];_];_]
-[80; 81) 'b': [[&str;_];_]
-[83; 84) 'b': [[&str;_];_]
-[96; 97) 'd': [isize;_]
-[100; 112) '[y, 1, 2, 3]': [isize;_]
+[55; 56) 'b': [[&str; 1]; 2]
+[59; 65) '[a, a]': [[&str; 1]; 2]
+[60; 61) 'a': [&str; 1]
+[63; 64) 'a': [&str; 1]
+[75; 76) 'c': [[[&str; 1]; 2]; 2]
+[79; 85) '[b, b]': [[[&str; 1]; 2]; 2]
+[80; 81) 'b': [[&str; 1]; 2]
+[83; 84) 'b': [[&str; 1]; 2]
+[96; 97) 'd': [isize; 4]
+[100; 112) '[y, 1, 2, 3]': [isize; 4]
 [101; 102) 'y': isize
 [104; 105) '1': isize
 [107; 108) '2': isize
 [110; 111) '3': isize
-[122; 123) 'd': [isize;_]
-[126; 138) '[1, y, 2, 3]': [isize;_]
+[122; 123) 'd': [isize; 4]
+[126; 138) '[1, y, 2, 3]': [isize; 4]
 [127; 128) '1': isize
 [130; 131) 'y': isize
 [133; 134) '2': isize
 [136; 137) '3': isize
-[148; 149) 'e': [isize;_]
-[152; 155) '[y]': [isize;_]
+[148; 149) 'e': [isize; 1]
+[152; 155) '[y]': [isize; 1]
 [153; 154) 'y': isize
-[165; 166) 'f': [[isize;_];_]
-[169; 175) '[d, d]': [[isize;_];_]
-[170; 171) 'd': [isize;_]
-[173; 174) 'd': [isize;_]
-[185; 186) 'g': [[isize;_];_]
-[189; 195) '[e, e]': [[isize;_];_]
-[190; 191) 'e': [isize;_]
-[193; 194) 'e': [isize;_]
-[206; 207) 'h': [i32;_]
-[210; 216) '[1, 2]': [i32;_]
+[165; 166) 'f': [[isize; 4]; 2]
+[169; 175) '[d, d]': [[isize; 4]; 2]
+[170; 171) 'd': [isize; 4]
+[173; 174) 'd': [isize; 4]
+[185; 186) 'g': [[isize; 1]; 2]
+[189; 195) '[e, e]': [[isize; 1]; 2]
+[190; 191) 'e': [isize; 1]
+[193; 194) 'e': [isize; 1]
+[206; 207) 'h': [i32; 2]
+[210; 216) '[1, 2]': [i32; 2]
 [211; 212) '1': i32
 [214; 215) '2': i32
-[226; 227) 'i': [&str;_]
-[230; 240) '["a", "b"]': [&str;_]
+[226; 227) 'i': [&str; 2]
+[230; 240) '["a", "b"]': [&str; 2]
 [231; 234) '"a"': &str
 [236; 239) '"b"': &str
-[251; 252) 'b': [[&str;_];_]
-[255; 265) '[a, ["b"]]': [[&str;_];_]
-[256; 257) 'a': [&str;_]
-[259; 264) '["b"]': [&str;_]
+[251; 252) 'b': [[&str; 1]; 2]
+[255; 265) '[a, ["b"]]': [[&str; 1]; 2]
+[256; 257) 'a': [&str; 1]
+[259; 264) '["b"]': [&str; 1]
 [260; 263) '"b"': &str
-[275; 276) 'x': [u8;_]
-[288; 290) '[]': [u8;_]
-[300; 301) 'z': &[u8;_]
-[311; 321) '&[1, 2, 3]': &[u8;_]
-[312; 321) '[1, 2, 3]': [u8;_]
+[275; 276) 'x': [u8; 0]
+[288; 290) '[]': [u8; 0]
+[300; 301) 'z': &[u8; 3]
+[311; 321) '&[1, 2, 3]': &[u8; 3]
+[312; 321) '[1, 2, 3]': [u8; 3]
 [313; 314) '1': u8
 [316; 317) '2': u8
 [319; 320) '3': u8"###
@@ -1674,7 +1782,7 @@ fn test() {
 [11; 48) '{     ...&y]; }': ()
 [21; 22) 'y': &{unknown}
 [25; 32) 'unknown': &{unknown}
-[38; 45) '[y, &y]': [&&{unknown};_]
+[38; 45) '[y, &y]': [&&{unknown}; 2]
 [39; 40) 'y': &{unknown}
 [42; 44) '&y': &&{unknown}
 [43; 44) 'y': &{unknown}"###
@@ -1699,7 +1807,7 @@ fn test() {
 [25; 32) 'unknown': &&{unknown}
 [42; 43) 'y': &&{unknown}
 [46; 53) 'unknown': &&{unknown}
-[59; 77) '[(x, y..., &x)]': [(&&{unknown}, &&{unknown});_]
+[59; 77) '[(x, y..., &x)]': [(&&{unknown}, &&{unknown}); 2]
 [60; 66) '(x, y)': (&&{unknown}, &&{unknown})
 [61; 62) 'x': &&{unknown}
 [64; 65) 'y': &&{unknown}
@@ -1791,8 +1899,8 @@ fn test_line_buffer() {
 "#),
         @r###"
 [23; 53) '{     ...n']; }': ()
-[29; 50) '&[0, b...b'\n']': &[u8;_]
-[30; 50) '[0, b'...b'\n']': [u8;_]
+[29; 50) '&[0, b...b'\n']': &[u8; 4]
+[30; 50) '[0, b'...b'\n']': [u8; 4]
 [31; 32) '0': u8
 [34; 39) 'b'\n'': u8
 [41; 42) '1': u8
@@ -2207,14 +2315,14 @@ fn test() {
         @r###"
 [87; 193) '{     ...t(); }': ()
 [97; 99) 's1': S
-[105; 121) 'Defaul...efault': {unknown}
+[105; 121) 'Defaul...efault': fn default<S>() -> Self
 [105; 123) 'Defaul...ault()': S
-[133; 135) 's2': {unknown}
-[138; 148) 'S::default': {unknown}
-[138; 150) 'S::default()': {unknown}
-[160; 162) 's3': {unknown}
-[165; 188) '<S as ...efault': {unknown}
-[165; 190) '<S as ...ault()': {unknown}"###
+[133; 135) 's2': S
+[138; 148) 'S::default': fn default<S>() -> Self
+[138; 150) 'S::default()': S
+[160; 162) 's3': S
+[165; 188) '<S as ...efault': fn default<S>() -> Self
+[165; 190) '<S as ...ault()': S"###
     );
 }
 
@@ -2367,8 +2475,8 @@ fn test<T: Iterable>() {
 [108; 181) '{     ...ter; }': ()
 [118; 119) 'x': i32
 [145; 146) '1': i32
-[156; 157) 'y': {unknown}
-[169; 178) 'no_matter': {unknown}"###
+[156; 157) 'y': <T as Iterable>::Item
+[169; 178) 'no_matter': <T as Iterable>::Item"###
     );
 }
 
@@ -2385,8 +2493,8 @@ fn test<T: Iterable<Item=u32>>() {
 "#),
         @r###"
 [67; 100) '{     ...own; }': ()
-[77; 78) 'y': {unknown}
-[90; 97) 'unknown': {unknown}"###
+[77; 78) 'y': <T as Iterable>::Item
+[90; 97) 'unknown': <T as Iterable>::Item"###
     );
 }
 
@@ -2914,6 +3022,395 @@ fn test(o: O<S>) {
     assert_eq!(t, "&str");
 }
 
+#[test]
+fn projection_normalized_through_impl() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Iterable {
+    type Item;
+    fn first(&self) -> Self::Item;
+}
+struct S;
+impl Iterable for S {
+    type Item = u32;
+    fn first(&self) -> Self::Item {}
+}
+
+fn test(s: S) {
+    s.first()<|>;
+}
+"#,
+    );
+    assert_eq!(t, "u32");
+}
+
+#[test]
+fn where_clause_on_projection() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Trait {
+    fn foo(&self) -> u32;
+}
+trait Iterable {
+    type Item;
+    fn first(&self) -> Self::Item;
+}
+
+fn test<T: Iterable>(t: T) where T::Item: Trait {
+    t.first().foo()<|>;
+}
+"#,
+    );
+    assert_eq!(t, "u32");
+}
+
+#[test]
+fn associated_type_bound_in_trait() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Trait {
+    fn foo(&self) -> u32;
+}
+trait Iterable {
+    type Item: Trait;
+    fn first(&self) -> Self::Item;
+}
+
+fn test<T: Iterable>(t: T) {
+    t.first().foo()<|>;
+}
+"#,
+    );
+    assert_eq!(t, "u32");
+}
+
+#[test]
+fn higher_ranked_where_clause() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Trait<T> {
+    fn foo(&self) -> T;
+}
+
+fn test<F>(f: F) where F: for<'a> Trait<u64> {
+    f.foo()<|>;
+}
+"#,
+    );
+    assert_eq!(t, "u64");
+}
+
+#[test]
+fn self_projection_in_trait_default_method() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Iterable {
+    type Item;
+    fn first(&self) -> Self::Item;
+    fn second(&self) -> Self::Item {
+        self.first()<|>
+    }
+}
+"#,
+    );
+    assert_eq!(t, "<Self as Iterable>::Item");
+}
+
+#[test]
+fn assoc_fn_of_type_param_through_bound() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Default {
+    fn default() -> Self;
+}
+fn test<T: Default>() {
+    let t = T::default();
+    t<|>;
+}
+"#,
+    );
+    assert_eq!(t, "T");
+}
+
+#[test]
+fn assoc_const_of_type_param_through_bound() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Trait {
+    const ID: u32;
+}
+fn test<T: Trait>() {
+    T::ID<|>;
+}
+"#,
+    );
+    assert_eq!(t, "u32");
+}
+
+#[test]
+fn assoc_fn_of_self_type() {
+    let t = type_at(
+        r#"
+//- /main.rs
+struct S<T>(T);
+impl<T> S<T> {
+    fn new() -> Self {}
+    fn test() {
+        Self::new()<|>;
+    }
+}
+"#,
+    );
+    assert_eq!(t, "S<T>");
+}
+
+#[test]
+fn assoc_fn_of_self_in_trait_default_method() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Trait {
+    fn make() -> Self;
+    fn remake(&self) -> Self {
+        Self::make()<|>
+    }
+}
+"#,
+    );
+    assert_eq!(t, "Self");
+}
+
+#[test]
+fn qualified_path_to_trait_fn() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait From<T> {
+    fn from(t: T) -> Self;
+}
+struct U;
+fn test<T: From<U>>(u: U) {
+    <T as From<U>>::from(u)<|>;
+}
+"#,
+    );
+    assert_eq!(t, "T");
+}
+
+#[test]
+fn qualified_path_to_assoc_type() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Iterable {
+    type Item;
+}
+fn test<T: Iterable>(t: <T as Iterable>::Item) {
+    t<|>;
+}
+"#,
+    );
+    assert_eq!(t, "<T as Iterable>::Item");
+}
+
+#[test]
+fn assoc_fn_of_concrete_type_through_trait_impl() {
+    let t = type_at(
+        r#"
+//- /main.rs
+struct Vec<T>;
+trait WithCapacity {
+    fn with_capacity(capacity: usize) -> Self;
+}
+impl<T> WithCapacity for Vec<T> {}
+fn test() {
+    Vec::<u8>::with_capacity(1)<|>;
+}
+"#,
+    );
+    assert_eq!(t, "Vec<u8>");
+}
+
+#[test]
+fn array_len_from_type_annotation() {
+    let t = type_at(
+        r#"
+//- /main.rs
+fn test(a: [u8; 32]) {
+    a<|>;
+}
+"#,
+    );
+    assert_eq!(t, "[u8; 32]");
+}
+
+#[test]
+fn array_len_from_const() {
+    let t = type_at(
+        r#"
+//- /main.rs
+const LEN: usize = 4 * 2 + 1;
+fn test(a: [u8; LEN]) {
+    a<|>;
+}
+"#,
+    );
+    assert_eq!(t, "[u8; 9]");
+}
+
+#[test]
+fn array_len_of_repeat_expr() {
+    let t = type_at(
+        r#"
+//- /main.rs
+const N: usize = 3;
+fn test() {
+    [0u8; N - 1]<|>;
+}
+"#,
+    );
+    assert_eq!(t, "[u8; 2]");
+}
+
+#[test]
+fn array_len_from_size_of() {
+    let (mut db, pos) = MockDatabase::with_position(
+        r#"
+//- /main.rs
+use std::mem::size_of;
+const LEN: usize = size_of::<u32>() + core::mem::size_of::<i64>();
+fn test(a: [bool; LEN]) {
+    a<|>;
+}
+
+//- /std.rs
+pub use core::mem;
+
+//- /core.rs
+pub mod mem {
+    pub const fn size_of<T>() -> usize {}
+}
+"#,
+    );
+    db.set_crate_graph_from_fixture(crate_graph! {
+        "main": ("/main.rs", ["std", "core"]),
+        "std": ("/std.rs", ["core"]),
+        "core": ("/core.rs", []),
+    });
+    assert_eq!("[bool; 12]", type_at_pos(&db, pos));
+}
+
+#[test]
+fn array_len_from_other_size_of() {
+    let t = type_at(
+        r#"
+//- /main.rs
+fn size_of<T>() -> usize { 0 }
+const LEN: usize = size_of::<u32>();
+fn test(a: [bool; LEN]) {
+    a<|>;
+}
+"#,
+    );
+    assert_eq!(t, "[bool; _]");
+}
+
+#[test]
+fn array_len_wraps_to_the_cast_type() {
+    let t = type_at(
+        r#"
+//- /main.rs
+const BYTE: u8 = 258u16 as u8;
+const LEN: usize = BYTE as usize + (-1i8 as u8 - 250) as usize;
+fn test(a: [bool; LEN]) {
+    a<|>;
+}
+"#,
+    );
+    assert_eq!(t, "[bool; 7]");
+}
+
+#[test]
+fn array_len_from_cyclic_consts() {
+    let t = type_at(
+        r#"
+//- /main.rs
+const A: usize = B + 1;
+const B: usize = A + 1;
+fn test(a: [bool; A]) {
+    a<|>;
+}
+"#,
+    );
+    assert_eq!(t, "[bool; _]");
+}
+
+#[test]
+fn array_len_unknown() {
+    let t = type_at(
+        r#"
+//- /main.rs
+fn test(a: [u8; unknown()]) {
+    a<|>;
+}
+"#,
+    );
+    assert_eq!(t, "[u8; _]");
+}
+
+#[test]
+fn infer_lifetime_params() {
+    assert_snapshot_matches!(
+        infer(r#"
+struct Ref<'a, T> { t: &'a T }
+fn get<'a, T>(r: Ref<'a, T>) -> &'a T { r.t }
+fn test(x: &'static u32) {
+    let r = Ref { t: x };
+    let y: Ref<'_, u32> = r;
+    get(y);
+}
+"#),
+        @r###"
+[46; 47) 'r': Ref<'a, T>
+[70; 77) '{ r.t }': &'a T
+[72; 73) 'r': Ref<'a, T>
+[72; 75) 'r.t': &'a T
+[86; 87) 'x': &'static u32
+[103; 173) '{     ...(y); }': ()
+[113; 114) 'r': Ref<'_, u32>
+[117; 129) 'Ref { t: x }': Ref<'_, u32>
+[126; 127) 'x': &'static u32
+[139; 140) 'y': Ref<'_, u32>
+[157; 158) 'r': Ref<'_, u32>
+[164; 167) 'get': fn get<'_, u32>(Ref<'a, T>) -> &'a T
+[164; 170) 'get(y)': &u32
+[168; 169) 'y': Ref<'_, u32>"###
+    );
+}
+
+#[test]
+fn trait_impl_for_type_with_lifetime_params() {
+    let t = type_at(
+        r#"
+//- /main.rs
+trait Trait { fn foo(&self) -> u8; }
+struct S<'a>(&'a u32);
+impl<'a> Trait for S<'a> {}
+fn test<'a>(s: S<'a>) {
+    s.foo()<|>;
+}
+"#,
+    );
+    assert_eq!(t, "u8");
+}
+
 fn type_at_pos(db: &MockDatabase, pos: FilePosition) -> String {
     let file = db.parse(pos.file_id).ok().unwrap();
     let expr = algo::find_node_at_offset::<ast::Expr>(file.syntax(), pos.offset).unwrap();
@@ -3062,3 +3559,106 @@ fn no_such_field_diagnostics() {
 "###
     );
 }
+
+#[test]
+fn unused_variable_diagnostics() {
+    let diagnostics = MockDatabase::with_files(
+        r"
+        //- /lib.rs
+        enum Option<T> { Some(T), None }
+        use Option::None;
+        struct S { foo: i32 }
+        trait Tr { fn method(&self, x: i32); }
+        fn bar() {}
+        fn test(x: i32, y: i32, _z: i32) -> i32 {
+            let bar = 1;
+            let S { foo } = S { foo: x };
+            let None = Option::Some(1);
+            let closure = |a: i32| y;
+            closure(1)
+        }
+        macro_rules! unused_fn {
+            () => { fn from_macro(unused: i32) {} }
+        }
+        unused_fn!();
+        ",
+    )
+    .diagnostics();
+
+    assert_snapshot_matches!(diagnostics, @r###"
+"bar": unused variable: `bar`
+"foo": unused variable: `foo`
+"a": unused variable: `a`
+"###
+    );
+}
+
+#[test]
+fn missing_match_arms_diagnostics() {
+    let diagnostics = MockDatabase::with_files(
+        r"
+        //- /lib.rs
+        enum Option<T> { Some(T), None }
+        use Option::*;
+        struct S { a: bool, b: Option<u8> }
+        fn test(s: &S, n: u8, never: Never) {
+            match s {
+                S { a: true, b: Some(_) } => (),
+                &S { b: None, .. } => (),
+            }
+            match (n, s.a) {
+                (0, false) => (),
+            }
+            match n {}
+            match never {}
+            match s {
+                S { .. } => (),
+            }
+        }
+        enum Never {}
+        fn diverge(x: !) {
+            match x {}
+        }
+        macro_rules! match_fn {
+            () => { fn from_macro(x: u8) { match x {} } }
+        }
+        match_fn!();
+        macro_rules! empty_match {
+            ($e:expr) => { match $e {} }
+        }
+        fn expanded(n: u8) {
+            empty_match!(n)
+        }
+        ",
+    )
+    .diagnostics();
+
+    assert_snapshot_matches!(diagnostics, @r###"
+"s": missing match arms: `S { a: false, b: Some(_) }`
+"(n, s.a)": missing match arms: `(_, _)`
+"n": missing match arms: `_`
+"###
+    );
+}
+
+#[test]
+fn mismatched_array_len_diagnostics() {
+    let diagnostics = MockDatabase::with_files(
+        r"
+        //- /lib.rs
+        const N: usize = 2;
+        fn test() {
+            let a: [u8; 3] = [1, 2];
+            let b: [u8; N] = [0; 2];
+            let c: [u8; N] = [0; 3];
+        }
+        ",
+    )
+    .diagnostics_matching(|d| d.downcast_ref::<MismatchedArrayLen>().is_some());
+
+    assert_snapshot_matches!(diagnostics, @r###"
+"[1, 2]": expected an array with 3 elements, found one with 2
+"[0; 3]": expected an array with 2 elements, found one with 3
+"###
+    );
+}
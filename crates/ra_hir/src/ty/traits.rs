@@ -10,7 +10,7 @@ use rustc_hash::FxHashSet;
 use super::{Canonical, GenericPredicate, ProjectionTy, TraitRef, Ty};
 use crate::{db::HirDatabase, Crate, ImplBlock, Trait};
 
-use self::chalk::{from_chalk, ToChalk};
+use self::chalk::{environment_to_chalk, from_chalk, ToChalk};
 
 pub(crate) mod chalk;
 
@@ -67,6 +67,35 @@ fn solve(
     solution
 }
 
+/// A set of clauses that we assume to be true. E.g. if we are inside this function:
+/// ```rust
+/// fn foo<T: Default>(t: T) {}
+/// ```
+/// we assume that `T: Default`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TraitEnvironment {
+    pub predicates: Vec<GenericPredicate>,
+}
+
+impl TraitEnvironment {
+    pub fn empty() -> Arc<TraitEnvironment> {
+        Arc::new(TraitEnvironment { predicates: Vec::new() })
+    }
+}
+
+/// Something (usually a goal), along with an environment.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct InEnvironment<T> {
+    pub environment: Arc<TraitEnvironment>,
+    pub value: T,
+}
+
+impl<T> InEnvironment<T> {
+    pub fn new(environment: Arc<TraitEnvironment>, value: T) -> InEnvironment<T> {
+        InEnvironment { environment, value }
+    }
+}
+
 /// Something that needs to be proven (by Chalk) during type checking, e.g. that
 /// a certain type implements a certain trait. Proving the Obligation might
 /// result in additional information about inference variables.
@@ -75,7 +104,8 @@ pub enum Obligation {
     /// Prove that a certain type implements a trait (the type is the `Self` type
     /// parameter to the `TraitRef`).
     Trait(TraitRef),
-    // Projection(ProjectionPredicate),
+    /// Prove that a certain projection normalizes to a certain type.
+    Projection(ProjectionPredicate),
 }
 
 impl Obligation {
@@ -97,40 +127,41 @@ pub struct ProjectionPredicate {
 pub(crate) fn implements_query(
     db: &impl HirDatabase,
     krate: Crate,
-    trait_ref: Canonical<TraitRef>,
+    trait_ref: Canonical<InEnvironment<TraitRef>>,
 ) -> Option<Solution> {
     let _p = profile("implements_query");
-    let goal: chalk_ir::Goal = trait_ref.value.to_chalk(db).cast();
+    let goal: chalk_ir::Goal = trait_ref.value.value.to_chalk(db).cast();
     debug!("goal: {:?}", goal);
-    let env = chalk_ir::Environment::new();
-    let in_env = chalk_ir::InEnvironment::new(&env, goal);
-    let parameter = chalk_ir::ParameterKind::Ty(chalk_ir::UniverseIndex::ROOT);
-    let canonical =
-        chalk_ir::Canonical { value: in_env, binders: vec![parameter; trait_ref.num_vars] };
-    // We currently don't deal with universes (I think / hope they're not yet
-    // relevant for our use cases?)
-    let u_canonical = chalk_ir::UCanonical { canonical, universes: 1 };
-    let solution = solve(db, krate, &u_canonical);
-    solution.map(|solution| solution_from_chalk(db, solution))
+    let env = environment_to_chalk(db, &trait_ref.value.environment);
+    solve_in_env(db, krate, &env, goal, trait_ref.num_vars)
 }
 
 pub(crate) fn normalize_query(
     db: &impl HirDatabase,
     krate: Crate,
-    projection: Canonical<ProjectionPredicate>,
+    projection: Canonical<InEnvironment<ProjectionPredicate>>,
 ) -> Option<Solution> {
+    let _p = profile("normalize_query");
     let goal: chalk_ir::Goal = chalk_ir::Normalize {
-        projection: projection.value.projection_ty.to_chalk(db),
-        ty: projection.value.ty.to_chalk(db),
+        projection: projection.value.value.projection_ty.to_chalk(db),
+        ty: projection.value.value.ty.to_chalk(db),
     }
     .cast();
     debug!("goal: {:?}", goal);
-    // FIXME unify with `implements`
-    let env = chalk_ir::Environment::new();
-    let in_env = chalk_ir::InEnvironment::new(&env, goal);
+    let env = environment_to_chalk(db, &projection.value.environment);
+    solve_in_env(db, krate, &env, goal, projection.num_vars)
+}
+
+fn solve_in_env(
+    db: &impl HirDatabase,
+    krate: Crate,
+    env: &Arc<chalk_ir::Environment>,
+    goal: chalk_ir::Goal,
+    num_vars: usize,
+) -> Option<Solution> {
+    let in_env = chalk_ir::InEnvironment::new(env, goal);
     let parameter = chalk_ir::ParameterKind::Ty(chalk_ir::UniverseIndex::ROOT);
-    let canonical =
-        chalk_ir::Canonical { value: in_env, binders: vec![parameter; projection.num_vars] };
+    let canonical = chalk_ir::Canonical { value: in_env, binders: vec![parameter; num_vars] };
     // We currently don't deal with universes (I think / hope they're not yet
     // relevant for our use cases?)
     let u_canonical = chalk_ir::UCanonical { canonical, universes: 1 };
@@ -144,12 +175,10 @@ fn solution_from_chalk(db: &impl HirDatabase, solution: chalk_solve::Solution) -
             .value
             .parameters
             .into_iter()
-            .map(|p| {
-                let ty = match p {
-                    chalk_ir::Parameter(chalk_ir::ParameterKind::Ty(ty)) => from_chalk(db, ty),
-                    chalk_ir::Parameter(chalk_ir::ParameterKind::Lifetime(_)) => unimplemented!(),
-                };
-                ty
+            .filter_map(|p| match p {
+                chalk_ir::Parameter(chalk_ir::ParameterKind::Ty(ty)) => Some(from_chalk(db, ty)),
+                // lifetimes are erased, we never have variables for them
+                chalk_ir::Parameter(chalk_ir::ParameterKind::Lifetime(_)) => None,
             })
             .collect();
         let result = Canonical { value, num_vars: subst.binders.len() };
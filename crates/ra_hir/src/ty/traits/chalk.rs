@@ -12,7 +12,7 @@ use chalk_rust_ir::{AssociatedTyDatum, ImplDatum, StructDatum, TraitDatum};
 use ra_db::salsa::{InternId, InternKey};
 use test_utils::tested_by;
 
-use super::ChalkContext;
+use super::{ChalkContext, TraitEnvironment};
 use crate::{
     db::HirDatabase,
     generics::GenericDef,
@@ -45,11 +45,14 @@ impl ToChalk for Ty {
     fn to_chalk(self, db: &impl HirDatabase) -> chalk_ir::Ty {
         match self {
             Ty::Apply(apply_ty) => {
+                let num_lifetimes = lifetime_param_count(db, apply_ty.ctor);
                 let struct_id = apply_ty.ctor.to_chalk(db);
                 let name = TypeName::TypeKindId(struct_id.into());
-                let parameters = apply_ty.parameters.to_chalk(db);
+                let mut parameters = apply_ty.parameters.to_chalk(db);
+                parameters.extend(erased_lifetimes(num_lifetimes));
                 chalk_ir::ApplicationTy { name, parameters }.cast()
             }
+            Ty::Projection(proj_ty) => proj_ty.to_chalk(db).cast(),
             Ty::Param { idx, .. } => {
                 PlaceholderIndex { ui: UniverseIndex::ROOT, idx: idx as usize }.to_ty()
             }
@@ -74,14 +77,21 @@ impl ToChalk for Ty {
                     }
                     // FIXME handle TypeKindId::Trait/Type here
                     TypeName::TypeKindId(_) => unimplemented!(),
-                    TypeName::AssociatedType(_) => unimplemented!(),
+                    TypeName::AssociatedType(type_id) => {
+                        // This is a projection that couldn't be normalized
+                        // further, e.g. `<T as Iterator>::Item` for a type
+                        // parameter `T`; we represent it as the projection.
+                        let associated_ty = from_chalk(db, type_id);
+                        let parameters = from_chalk(db, apply_ty.parameters);
+                        Ty::Projection(ProjectionTy { associated_ty, parameters })
+                    }
                     TypeName::Placeholder(idx) => {
                         assert_eq!(idx.ui, UniverseIndex::ROOT);
                         Ty::Param { idx: idx.idx as u32, name: crate::Name::missing() }
                     }
                 }
             }
-            chalk_ir::Ty::Projection(_) => unimplemented!(),
+            chalk_ir::Ty::Projection(proj_ty) => Ty::Projection(from_chalk(db, proj_ty)),
             chalk_ir::Ty::UnselectedProjection(_) => unimplemented!(),
             chalk_ir::Ty::ForAll(_) => unimplemented!(),
             chalk_ir::Ty::BoundVar(idx) => Ty::Bound(idx as u32),
@@ -100,9 +110,10 @@ impl ToChalk for Substs {
     fn from_chalk(db: &impl HirDatabase, parameters: Vec<chalk_ir::Parameter>) -> Substs {
         parameters
             .into_iter()
-            .map(|p| match p {
-                chalk_ir::Parameter(chalk_ir::ParameterKind::Ty(ty)) => from_chalk(db, ty),
-                chalk_ir::Parameter(chalk_ir::ParameterKind::Lifetime(_)) => unimplemented!(),
+            .filter_map(|p| match p {
+                chalk_ir::Parameter(chalk_ir::ParameterKind::Ty(ty)) => Some(from_chalk(db, ty)),
+                // lifetimes are erased, so they come back as `Lifetime::Unknown`
+                chalk_ir::Parameter(chalk_ir::ParameterKind::Lifetime(_)) => None,
             })
             .collect::<Vec<_>>()
             .into()
@@ -218,6 +229,28 @@ impl ToChalk for ProjectionTy {
     }
 }
 
+/// The environment is only ever passed to Chalk and never comes back, so
+/// unlike the other conversions this one only goes in one direction.
+pub(super) fn environment_to_chalk(
+    db: &impl HirDatabase,
+    env: &TraitEnvironment,
+) -> Arc<chalk_ir::Environment> {
+    let mut clauses: Vec<chalk_ir::ProgramClause> = Vec::new();
+    for pred in &env.predicates {
+        if pred.is_error() {
+            // for env, we just ignore errors
+            continue;
+        }
+        if let GenericPredicate::Implemented(trait_ref) = pred {
+            if blacklisted_trait(db, trait_ref.trait_) {
+                continue;
+            }
+        }
+        clauses.push(pred.clone().to_chalk(db).cast());
+    }
+    chalk_ir::Environment::new().add_clauses(clauses)
+}
+
 fn make_binders<T>(value: T, num_vars: usize) -> chalk_ir::Binders<T> {
     chalk_ir::Binders {
         value,
@@ -225,6 +258,25 @@ fn make_binders<T>(value: T, num_vars: usize) -> chalk_ir::Binders<T> {
     }
 }
 
+/// We don't reason about lifetimes, but still pass the right number of
+/// lifetime arguments to Chalk for types like `&'a T` or `Foo<'a>`. All of
+/// them are the same placeholder, so they never keep two types from unifying.
+fn erased_lifetimes(count: usize) -> impl Iterator<Item = Parameter> {
+    let erased = PlaceholderIndex { ui: UniverseIndex::ROOT, idx: usize::max_value() };
+    std::iter::repeat(erased.to_lifetime().cast()).take(count)
+}
+
+fn lifetime_param_count(db: &impl HirDatabase, type_ctor: TypeCtor) -> usize {
+    match type_ctor {
+        TypeCtor::Ref(_) => 1,
+        TypeCtor::FnDef(callable) => {
+            GenericDef::from(callable).generic_params(db).count_lifetimes_including_parent()
+        }
+        TypeCtor::Adt(adt) => adt.generic_params(db).count_lifetimes_including_parent(),
+        _ => 0,
+    }
+}
+
 fn blacklisted_trait(db: &impl HirDatabase, trait_: Trait) -> bool {
     let name = trait_.name(db).unwrap_or_else(crate::Name::missing).to_string();
     match &*name {
@@ -331,19 +383,40 @@ pub(crate) fn associated_ty_data_query(
         _ => panic!("associated type not in trait"),
     };
     let generic_params = type_alias.generic_params(db);
+    let bound_vars = Substs::bound_vars(&generic_params);
     let parameter_kinds = generic_params
         .params_including_parent()
         .into_iter()
         .map(|p| chalk_ir::ParameterKind::Ty(lalrpop_intern::intern(&p.name.to_string())))
         .collect();
+    let resolver = trait_.resolver(db);
+    // The bounds are on the associated type itself, so the `Self` of each
+    // bound is implicit; lower them with `Unknown` and leave that out again.
+    let bounds = db
+        .type_alias_data(type_alias)
+        .bounds
+        .iter()
+        .filter_map(|path| TraitRef::from_path(db, &resolver, path, Some(Ty::Unknown)))
+        .filter(|trait_ref| !blacklisted_trait(db, trait_ref.trait_))
+        .map(|trait_ref| {
+            let trait_ref = trait_ref.subst(&bound_vars);
+            chalk_rust_ir::InlineBound::TraitBound(chalk_rust_ir::TraitBound {
+                trait_id: trait_ref.trait_.to_chalk(db),
+                args_no_self: trait_ref.substs[1..]
+                    .iter()
+                    .map(|ty| ty.clone().to_chalk(db).cast())
+                    .collect(),
+            })
+        })
+        .collect();
+    let where_clauses = convert_where_clauses(db, type_alias.into(), &bound_vars);
     let datum = AssociatedTyDatum {
         trait_id: trait_.to_chalk(db),
         id,
         name: lalrpop_intern::intern(&type_alias.name(db).to_string()),
         parameter_kinds,
-        // FIXME add bounds and where clauses
-        bounds: vec![],
-        where_clauses: vec![],
+        bounds,
+        where_clauses,
     };
     Arc::new(datum)
 }
@@ -416,7 +489,7 @@ pub(crate) fn struct_datum_query(
         | TypeCtor::Float(_)
         | TypeCtor::Never
         | TypeCtor::Str => (0, vec![], true),
-        TypeCtor::Slice | TypeCtor::Array | TypeCtor::RawPtr(_) | TypeCtor::Ref(_) => {
+        TypeCtor::Slice | TypeCtor::Array { .. } | TypeCtor::RawPtr(_) | TypeCtor::Ref(_) => {
             (1, vec![], true)
         }
         TypeCtor::FnPtr { num_args } => (num_args as usize + 1, vec![], true),
@@ -452,7 +525,10 @@ pub(crate) fn struct_datum_query(
     };
     let self_ty = chalk_ir::ApplicationTy {
         name: TypeName::TypeKindId(type_ctor.to_chalk(db).into()),
-        parameters: (0..num_params).map(|i| chalk_ir::Ty::BoundVar(i).cast()).collect(),
+        parameters: (0..num_params)
+            .map(|i| chalk_ir::Ty::BoundVar(i).cast())
+            .chain(erased_lifetimes(lifetime_param_count(db, type_ctor)))
+            .collect(),
     };
     let struct_datum_bound = chalk_rust_ir::StructDatumBound {
         self_ty,
@@ -474,10 +550,15 @@ pub(crate) fn impl_datum_query(
     let impl_block: ImplBlock = from_chalk(db, impl_id);
     let generic_params = impl_block.generic_params(db);
     let bound_vars = Substs::bound_vars(&generic_params);
-    let trait_ref = impl_block
-        .target_trait_ref(db)
-        .expect("FIXME handle unresolved impl block trait ref")
-        .subst(&bound_vars);
+    let trait_ref = match impl_block.target_trait_ref(db) {
+        Some(trait_ref) => trait_ref.subst(&bound_vars),
+        None => {
+            // this can happen if the trait can be resolved from one crate, but
+            // not from another; just treat the impl as not applying
+            debug!("impl {:?} has unresolved trait ref", impl_id);
+            return invalid_impl_datum();
+        }
+    };
     let impl_type = if impl_block.module().krate(db) == Some(krate) {
         chalk_rust_ir::ImplType::Local
     } else {
@@ -530,6 +611,21 @@ pub(crate) fn impl_datum_query(
     Arc::new(impl_datum)
 }
 
+fn invalid_impl_datum() -> Arc<ImplDatum> {
+    let trait_ref = chalk_ir::TraitRef {
+        trait_id: UNKNOWN_TRAIT,
+        parameters: vec![chalk_ir::Ty::BoundVar(0).cast()],
+    };
+    let impl_datum_bound = chalk_rust_ir::ImplDatumBound {
+        trait_ref: chalk_rust_ir::PolarizedTraitRef::Positive(trait_ref),
+        where_clauses: Vec::new(),
+        associated_ty_values: Vec::new(),
+        impl_type: chalk_rust_ir::ImplType::External,
+    };
+    let impl_datum = ImplDatum { binders: make_binders(impl_datum_bound, 1) };
+    Arc::new(impl_datum)
+}
+
 fn id_from_chalk<T: InternKey>(chalk_id: chalk_ir::RawId) -> T {
     T::from_intern_id(InternId::from(chalk_id.index))
 }
@@ -2,11 +2,13 @@
 
 use std::sync::Arc;
 
-use ra_syntax::ast::NameOwner;
+use ra_syntax::ast::{NameOwner, TypeBoundsOwner};
 
 use crate::{
     db::{AstDatabase, DefDatabase},
+    generics::trait_path_from_bound,
     name::{AsName, Name},
+    path::Path,
     type_ref::TypeRef,
     HasSource, TypeAlias,
 };
@@ -15,6 +17,9 @@ use crate::{
 pub struct TypeAliasData {
     pub(crate) name: Name,
     pub(crate) type_ref: Option<TypeRef>,
+    /// The trait bounds of an associated type declaration, e.g. the `Display`
+    /// in `type Item: Display;`.
+    pub(crate) bounds: Vec<Path>,
 }
 
 pub(crate) fn type_alias_data_query(
@@ -24,5 +29,11 @@ pub(crate) fn type_alias_data_query(
     let node = typ.source(db).ast;
     let name = node.name().map_or_else(Name::missing, |n| n.as_name());
     let type_ref = node.type_ref().map(TypeRef::from_ast);
-    Arc::new(TypeAliasData { name, type_ref })
+    let bounds = node
+        .type_bound_list()
+        .iter()
+        .flat_map(|l| l.bounds())
+        .filter_map(trait_path_from_bound)
+        .collect();
+    Arc::new(TypeAliasData { name, type_ref, bounds })
 }
@@ -1,9 +1,12 @@
 //! HIR for references to types. Paths in these are not yet resolved. They can
 //! be directly created from an ast::TypeRef, without further queries.
 
-use ra_syntax::ast::{self, TypeAscriptionOwner};
+use ra_syntax::{
+    ast::{self, LiteralKind, TypeAscriptionOwner},
+    SyntaxToken,
+};
 
-use crate::Path;
+use crate::{expr::parse_int_literal, Name, Path};
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum Mutability {
@@ -43,8 +46,8 @@ pub enum TypeRef {
     Tuple(Vec<TypeRef>),
     Path(Path),
     RawPtr(Box<TypeRef>, Mutability),
-    Reference(Box<TypeRef>, Mutability),
-    Array(Box<TypeRef> /*, Expr*/),
+    Reference(Box<TypeRef>, Mutability, LifetimeRef),
+    Array(Box<TypeRef>, ArrayLen),
     Slice(Box<TypeRef>),
     /// A fn pointer. Last element of the vector is the return type.
     Fn(Vec<TypeRef>),
@@ -54,6 +57,61 @@ pub enum TypeRef {
     Error,
 }
 
+/// A reference to a lifetime, like the `'a` in `Foo<'a>`.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum LifetimeRef {
+    Named(Name),
+    Static,
+    /// `'_`, or a lifetime that was left out.
+    Elided,
+}
+
+impl LifetimeRef {
+    pub(crate) fn from_token(lt: SyntaxToken) -> Self {
+        match lt.text().as_str() {
+            "'static" => LifetimeRef::Static,
+            "'_" => LifetimeRef::Elided,
+            _ => LifetimeRef::Named(Name::new_lifetime(lt)),
+        }
+    }
+
+    pub(crate) fn from_token_opt(lt: Option<SyntaxToken>) -> Self {
+        lt.map_or(LifetimeRef::Elided, LifetimeRef::from_token)
+    }
+}
+
+/// The length of an array type, like the `32` in `[u8; 32]`. Type references
+/// don't have a body to put expressions into, so only literals and paths (to
+/// constants) are kept here.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ArrayLen {
+    Literal(u64),
+    Path(Path),
+    Unknown,
+}
+
+impl ArrayLen {
+    fn from_ast_opt(node: Option<&ast::Expr>) -> Self {
+        let node = match node {
+            Some(it) => it,
+            None => return ArrayLen::Unknown,
+        };
+        match node.kind() {
+            ast::ExprKind::Literal(lit) => match lit.kind() {
+                LiteralKind::IntNumber { suffix } => {
+                    parse_int_literal(lit.token().text(), suffix.as_ref())
+                        .map_or(ArrayLen::Unknown, ArrayLen::Literal)
+                }
+                _ => ArrayLen::Unknown,
+            },
+            ast::ExprKind::PathExpr(path_expr) => {
+                path_expr.path().and_then(Path::from_ast).map_or(ArrayLen::Unknown, ArrayLen::Path)
+            }
+            _ => ArrayLen::Unknown,
+        }
+    }
+}
+
 impl TypeRef {
     /// Converts an `ast::TypeRef` to a `hir::TypeRef`.
     pub(crate) fn from_ast(node: &ast::TypeRef) -> Self {
@@ -70,12 +128,16 @@ impl TypeRef {
                 let mutability = Mutability::from_mutable(inner.is_mut());
                 TypeRef::RawPtr(Box::new(inner_ty), mutability)
             }
-            ArrayType(inner) => TypeRef::Array(Box::new(TypeRef::from_ast_opt(inner.type_ref()))),
+            ArrayType(inner) => {
+                let inner_ty = TypeRef::from_ast_opt(inner.type_ref());
+                TypeRef::Array(Box::new(inner_ty), ArrayLen::from_ast_opt(inner.expr()))
+            }
             SliceType(inner) => TypeRef::Slice(Box::new(TypeRef::from_ast_opt(inner.type_ref()))),
             ReferenceType(inner) => {
                 let inner_ty = TypeRef::from_ast_opt(inner.type_ref());
                 let mutability = Mutability::from_mutable(inner.is_mut());
-                TypeRef::Reference(Box::new(inner_ty), mutability)
+                let lifetime = LifetimeRef::from_token_opt(inner.lifetime_token());
+                TypeRef::Reference(Box::new(inner_ty), mutability, lifetime)
             }
             PlaceholderType(_inner) => TypeRef::Placeholder,
             FnPointerType(inner) => {
@@ -0,0 +1,162 @@
+//! Visibility of items, and the paths through which they can be referred to
+//! from other modules.
+
+use ra_syntax::{
+    ast::{self, AstNode, VisibilityOwner},
+    SyntaxKind::*,
+};
+
+use crate::{path::PathSegment, HasSource, HirDatabase, Module, ModuleDef, Name, Path, PathKind};
+
+/// The declared visibility of an item. `pub(in path)` is not supported yet
+/// and is conservatively treated as private.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Visibility {
+    Public,
+    Crate,
+    Super,
+    Private,
+}
+
+impl Visibility {
+    pub fn from_ast(visibility: Option<&ast::Visibility>) -> Visibility {
+        let visibility = match visibility {
+            Some(it) => it,
+            None => return Visibility::Private,
+        };
+        let kinds = visibility
+            .syntax()
+            .children_with_tokens()
+            .map(|it| it.kind())
+            .filter(|&kind| !kind.is_trivia() && kind != L_PAREN && kind != R_PAREN)
+            .collect::<Vec<_>>();
+        match kinds.as_slice() {
+            [PUB_KW] => Visibility::Public,
+            [PUB_KW, CRATE_KW] | [CRATE_KW] => Visibility::Crate,
+            [PUB_KW, SUPER_KW] => Visibility::Super,
+            _ => Visibility::Private,
+        }
+    }
+
+    /// Whether an item with this visibility, declared in `owner`, can be
+    /// referred to from `from`.
+    pub fn is_visible_from(self, db: &impl HirDatabase, owner: Module, from: Module) -> bool {
+        let is_inside = |module: Module| from.path_to_root(db).contains(&module);
+        match self {
+            Visibility::Public => true,
+            Visibility::Crate => owner.krate(db) == from.krate(db),
+            Visibility::Super => owner.parent(db).map_or(false, is_inside),
+            Visibility::Private => is_inside(owner),
+        }
+    }
+}
+
+impl Module {
+    /// The visibility of the `mod` item declaring this module. Crate roots
+    /// have none.
+    pub fn visibility(self, db: &impl HirDatabase) -> Option<Visibility> {
+        let src = self.declaration_source(db)?;
+        Some(Visibility::from_ast(src.ast.visibility()))
+    }
+}
+
+impl ModuleDef {
+    /// The module declaring this item. Enum variants are declared in the
+    /// module of their enum.
+    pub fn module(self, db: &impl HirDatabase) -> Option<Module> {
+        let module = match self {
+            ModuleDef::Module(it) => return it.parent(db),
+            ModuleDef::Function(it) => it.module(db),
+            ModuleDef::Struct(it) => it.module(db),
+            ModuleDef::Union(it) => it.module(db),
+            ModuleDef::Enum(it) => it.module(db),
+            ModuleDef::EnumVariant(it) => it.module(db),
+            ModuleDef::Const(it) => it.module(db),
+            ModuleDef::Static(it) => it.module(db),
+            ModuleDef::Trait(it) => it.module(db),
+            ModuleDef::TypeAlias(it) => it.module(db),
+            ModuleDef::BuiltinType(_) => return None,
+        };
+        Some(module)
+    }
+
+    /// The visibility of this item. Enum variants are as visible as their
+    /// enum.
+    pub fn visibility(self, db: &impl HirDatabase) -> Option<Visibility> {
+        let visibility = match self {
+            ModuleDef::Module(it) => return it.visibility(db),
+            ModuleDef::Function(it) => it.source(db).ast.visibility(),
+            ModuleDef::Struct(it) => it.source(db).ast.visibility(),
+            ModuleDef::Union(it) => it.source(db).ast.visibility(),
+            ModuleDef::Enum(it) => it.source(db).ast.visibility(),
+            ModuleDef::EnumVariant(it) => {
+                return ModuleDef::Enum(it.parent_enum(db)).visibility(db)
+            }
+            ModuleDef::Const(it) => it.source(db).ast.visibility(),
+            ModuleDef::Static(it) => it.source(db).ast.visibility(),
+            ModuleDef::Trait(it) => it.source(db).ast.visibility(),
+            ModuleDef::TypeAlias(it) => it.source(db).ast.visibility(),
+            ModuleDef::BuiltinType(_) => return None,
+        };
+        Some(Visibility::from_ast(visibility))
+    }
+
+    /// The path of this item from the root of its crate, as written in `from`:
+    /// starting with `crate` for items of the same crate, or with the name of
+    /// the dependency otherwise. Returns `None` if the item or one of the
+    /// modules on the way is not visible from `from`.
+    pub fn path_from(self, db: &impl HirDatabase, from: Module) -> Option<Path> {
+        let module = self.module(db)?;
+        if !self.visibility(db)?.is_visible_from(db, module, from) {
+            return None;
+        }
+        let mut names = Vec::new();
+        match self {
+            ModuleDef::EnumVariant(it) => {
+                names.push(it.name(db)?);
+                names.push(it.parent_enum(db).name(db)?);
+            }
+            _ => names.push(self.name(db)?),
+        }
+        // The last module of the path to the root is the crate root itself
+        let path_to_root = module.path_to_root(db);
+        for &module in path_to_root.iter().take(path_to_root.len() - 1) {
+            if !module.visibility(db)?.is_visible_from(db, module.parent(db)?, from) {
+                return None;
+            }
+            names.push(module.name(db)?);
+        }
+
+        let def_crate = module.krate(db)?;
+        let krate = from.krate(db)?;
+        let kind = if def_crate == krate {
+            PathKind::Crate
+        } else {
+            let dep = krate.dependencies(db).into_iter().find(|it| it.krate == def_crate)?;
+            names.push(dep.name);
+            PathKind::Plain
+        };
+        let segments = names
+            .into_iter()
+            .rev()
+            .map(|name| PathSegment { name, args_and_bindings: None })
+            .collect();
+        Some(Path { kind, segments })
+    }
+
+    fn name(self, db: &impl HirDatabase) -> Option<Name> {
+        match self {
+            ModuleDef::Module(it) => it.name(db),
+            ModuleDef::Function(it) => Some(it.name(db)),
+            ModuleDef::Struct(it) => it.name(db),
+            ModuleDef::Union(it) => it.name(db),
+            ModuleDef::Enum(it) => it.name(db),
+            ModuleDef::EnumVariant(it) => it.name(db),
+            ModuleDef::Const(it) => Some(it.data(db).name().clone()),
+            ModuleDef::Static(it) => Some(it.data(db).name().clone()),
+            ModuleDef::Trait(it) => it.name(db),
+            ModuleDef::TypeAlias(it) => Some(it.name(db)),
+            ModuleDef::BuiltinType(_) => None,
+        }
+    }
+}
@@ -1,8 +1,11 @@
-use ra_db::{FilePosition, FileRange};
+mod convert_struct_kind;
+mod move_to_file;
+
+use ra_db::{FileId, FilePosition, FileRange};
 
 use crate::{db::RootDatabase, SourceChange, SourceFileEdit};
 
-pub use ra_assists::AssistId;
+pub use ra_assists::{AssistId, ImportGranularity};
 
 #[derive(Debug)]
 pub struct Assist {
@@ -11,7 +14,7 @@ pub struct Assist {
 }
 
 pub(crate) fn assists(db: &RootDatabase, frange: FileRange) -> Vec<Assist> {
-    ra_assists::assists(db, frange)
+    let mut res = ra_assists::assists(db, frange)
         .into_iter()
         .map(|(label, action)| {
             let file_id = frange.file_id;
@@ -22,5 +25,18 @@ pub(crate) fn assists(db: &RootDatabase, frange: FileRange) -> Vec<Assist> {
             );
             Assist { id, change }
         })
-        .collect()
+        .collect::<Vec<_>>();
+    // Assists editing several files need reference search, which lives here
+    res.extend(convert_struct_kind::convert_struct_kind(db, frange));
+    res.extend(move_to_file::move_to_file(db, frange));
+    res
+}
+
+pub(crate) fn organize_imports(
+    db: &RootDatabase,
+    file_id: FileId,
+    granularity: ImportGranularity,
+) -> Option<SourceChange> {
+    let edit = ra_assists::organize_imports_text_edit(db, file_id, granularity)?;
+    Some(SourceChange::source_file_edit_from("organize imports", file_id, edit))
 }
@@ -0,0 +1,607 @@
+//! Converts a tuple struct or variant into one with named fields and back,
+//! rewriting its constructors, patterns and field accesses in the workspace.
+//!
+//! The fields of a converted tuple struct are named `field1`, `field2` and
+//! so on. The conversion is not offered when a usage cannot be rewritten, for
+//! example a tuple struct constructor passed as a function.
+
+use ra_db::SourceDatabase;
+use ra_syntax::{
+    algo::find_node_at_offset,
+    ast::{self, ArgListOwner, AstNode, NameOwner, TypeAscriptionOwner, TypeParamsOwner},
+    SyntaxKind::*,
+    SyntaxNode, TextRange,
+};
+use rustc_hash::FxHashMap;
+
+use crate::{
+    db::RootDatabase,
+    references::{classify_name, find_refs_to_def, NameDefinition, SearchScope},
+    rewrites::{delimited_list, multiline_indent, slice, Piece, Rewrite, Rewrites},
+    symbol_index::SymbolsDatabase,
+    FileId, FileRange, SourceChange,
+};
+
+use super::{Assist, AssistId};
+
+pub(crate) fn convert_struct_kind(db: &RootDatabase, frange: FileRange) -> Option<Assist> {
+    let source_file = db.parse(frange.file_id).tree;
+    let conversion = Conversion::at(db, frange, &source_file)?;
+    let (id, label) = conversion.id_and_label();
+    let change = conversion_change(db, frange.file_id, conversion, label)?;
+    Some(Assist { id, change })
+}
+
+/// Rewrites the definition and its usages, `None` if one of the usages can't
+/// be rewritten.
+fn conversion_change(
+    db: &RootDatabase,
+    file_id: FileId,
+    conversion: Conversion,
+    label: &str,
+) -> Option<SourceChange> {
+    let Conversion { name, target, def, hir_fields } = conversion;
+    let files = SearchScope::default().files(db, file_id);
+    let text = db.file_text(file_id);
+
+    let mut rewrites = Rewrites::default();
+    let (to_named, names) = match target.kind() {
+        ast::StructKind::Tuple(field_list) => {
+            let names = (1..=hir_fields.len()).map(|it| format!("field{}", it)).collect::<Vec<_>>();
+            rewrites.add(file_id, tuple_def_to_named(&text, target, field_list, &names)?);
+            if let Target::Struct(_) = target {
+                tuple_field_accesses(db, &files, &hir_fields, &names, &mut rewrites);
+            }
+            (true, names)
+        }
+        ast::StructKind::Named(field_list) => {
+            let names = hir_fields.iter().map(|it| it.name(db).to_string()).collect::<Vec<_>>();
+            rewrites.add(file_id, named_def_to_tuple(&text, target, field_list)?);
+            for (idx, field) in hir_fields.iter().enumerate() {
+                let field_name = field.name(db).to_string();
+                let def = NameDefinition::Field(*field);
+                for reference in find_refs_to_def(db, def, &field_name, files.clone()) {
+                    let FileRange { file_id, range } = reference.file_range;
+                    let source_file = db.parse(file_id).tree;
+                    // Fields of struct literals and patterns are rewritten with them
+                    let is_access =
+                        find_node_at_offset::<ast::NameRef>(source_file.syntax(), range.start())
+                            .and_then(|it| it.syntax().parent())
+                            .map_or(false, |it| it.kind() == FIELD_EXPR);
+                    if is_access {
+                        rewrites.add(file_id, Rewrite::text(range, idx.to_string()));
+                    }
+                }
+            }
+            (false, names)
+        }
+        ast::StructKind::Unit => return None,
+    };
+
+    let mut references = find_refs_to_def(db, def, name.text().as_str(), files.clone());
+    if let Target::Struct(_) = target {
+        references.extend(find_refs_to_def(db, def, "Self", files));
+    }
+    for reference in references {
+        let FileRange { file_id, range } = reference.file_range;
+        let source_file = db.parse(file_id).tree;
+        let text = db.file_text(file_id);
+        let path = match find_node_at_offset::<ast::NameRef>(source_file.syntax(), range.start())
+            .and_then(|it| it.syntax().parent())
+            .and_then(ast::PathSegment::cast)
+        {
+            Some(segment) => segment.parent_path(),
+            None => continue,
+        };
+        let parent = match path.syntax().parent() {
+            Some(it) => it,
+            None => continue,
+        };
+        let rewrite = match (parent.kind(), to_named) {
+            (PATH_EXPR, true) => {
+                let call = parent
+                    .parent()
+                    .and_then(ast::CallExpr::cast)
+                    .filter(|call| call.expr().map(AstNode::syntax) == Some(parent))?;
+                call_to_named(&text, path, call, &names)?
+            }
+            (TUPLE_STRUCT_PAT, true) => {
+                tuple_pat_to_named(&text, path, ast::TupleStructPat::cast(parent)?, &names)?
+            }
+            // `Foo { 0: x }` would have to be renamed as well
+            (STRUCT_LIT, true) => return None,
+            (STRUCT_LIT, false) => {
+                struct_lit_to_tuple(&text, path, ast::StructLit::cast(parent)?, &names)?
+            }
+            (STRUCT_PAT, false) => {
+                struct_pat_to_tuple(&text, path, ast::StructPat::cast(parent)?, &names)?
+            }
+            _ => continue,
+        };
+        rewrites.add(file_id, rewrite);
+    }
+
+    Some(SourceChange::source_file_edits(label, rewrites.into_edits(db)))
+}
+
+/// The struct or variant at the cursor, found without searching the
+/// workspace.
+struct Conversion<'a> {
+    name: &'a ast::Name,
+    target: Target<'a>,
+    def: NameDefinition,
+    hir_fields: Vec<hir::StructField>,
+}
+
+impl<'a> Conversion<'a> {
+    fn at(
+        db: &RootDatabase,
+        frange: FileRange,
+        source_file: &'a ast::SourceFile,
+    ) -> Option<Conversion<'a>> {
+        let file_id = frange.file_id;
+        // Only offered on the name, as every file of the workspace is searched
+        let name = find_node_at_offset::<ast::Name>(source_file.syntax(), frange.range.start())?;
+        let parent = name.syntax().parent()?;
+        let target = match ast::EnumVariant::cast(parent) {
+            Some(variant) => Target::Variant(variant),
+            None => {
+                let struct_def = ast::StructDef::cast(parent)?;
+                // Where clauses come before named fields but after tuple fields
+                if struct_def.is_union() || struct_def.where_clause().is_some() {
+                    return None;
+                }
+                Target::Struct(struct_def)
+            }
+        };
+        if let ast::StructKind::Unit = target.kind() {
+            return None;
+        }
+        if !db.local_roots().contains(&db.file_source_root(file_id)) {
+            return None;
+        }
+        let def = classify_name(db, file_id, name)?;
+        let hir_fields = match def {
+            NameDefinition::ModuleDef(hir::ModuleDef::Struct(it)) => it.fields(db),
+            NameDefinition::ModuleDef(hir::ModuleDef::EnumVariant(it)) => it.fields(db),
+            _ => return None,
+        };
+        Some(Conversion { name, target, def, hir_fields })
+    }
+
+    fn id_and_label(&self) -> (AssistId, &'static str) {
+        let to_named = match self.target.kind() {
+            ast::StructKind::Tuple(_) => true,
+            _ => false,
+        };
+        let (id, label) = match (self.target, to_named) {
+            (Target::Struct(_), true) => ("convert_to_named_struct", "convert to named struct"),
+            (Target::Struct(_), false) => ("convert_to_tuple_struct", "convert to tuple struct"),
+            (Target::Variant(_), true) => ("convert_to_named_variant", "convert to named variant"),
+            (Target::Variant(_), false) => ("convert_to_tuple_variant", "convert to tuple variant"),
+        };
+        (AssistId(id), label)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Target<'a> {
+    Struct(&'a ast::StructDef),
+    Variant(&'a ast::EnumVariant),
+}
+
+impl<'a> Target<'a> {
+    fn kind(self) -> ast::StructKind<'a> {
+        match self {
+            Target::Struct(it) => it.kind(),
+            Target::Variant(it) => it.kind(),
+        }
+    }
+}
+
+fn field_list(named: bool, items: Vec<Vec<Piece>>, indent: Option<String>) -> Vec<Piece> {
+    let delimiters = if named { (" {", "}") } else { ("(", ")") };
+    delimited_list(delimiters, named, items, indent)
+}
+
+/// The range from the end of the path, to replace the fields which follow it.
+fn fields_range(path: &ast::Path, node: &SyntaxNode) -> TextRange {
+    TextRange::from_to(path.syntax().range().end(), node.range().end())
+}
+
+fn tuple_def_to_named(
+    text: &str,
+    target: Target,
+    field_list: &ast::PosFieldDefList,
+    names: &[String],
+) -> Option<Rewrite> {
+    let mut items = Vec::new();
+    for (field, name) in field_list.fields().zip(names) {
+        let ty = field.type_ref()?.syntax().range();
+        let prefix = slice(text, TextRange::from_to(field.syntax().range().start(), ty.start()));
+        items.push(vec![Piece::Text(format!("{}{}: {}", prefix, name, slice(text, ty)))]);
+    }
+    let list_range = field_list.syntax().range();
+    // The `;` after the fields of a tuple struct goes away
+    let end = match target {
+        Target::Struct(it) => it.syntax().range().end(),
+        Target::Variant(_) => list_range.end(),
+    };
+    let pieces = field_list(true, items, multiline_indent(text, list_range));
+    Some(Rewrite { range: TextRange::from_to(list_range.start(), end), pieces })
+}
+
+fn named_def_to_tuple(
+    text: &str,
+    target: Target,
+    field_list: &ast::NamedFieldDefList,
+) -> Option<Rewrite> {
+    let mut items = Vec::new();
+    for field in field_list.fields() {
+        let name = field.name()?.syntax().range();
+        let ty = field.ascribed_type()?;
+        let prefix = slice(text, TextRange::from_to(field.syntax().range().start(), name.start()));
+        items.push(vec![Piece::Text(format!("{}{}", prefix, ty.syntax().text()))]);
+    }
+    let list_range = field_list.syntax().range();
+    // `Foo {` becomes `Foo(`
+    let mut start = list_range.start();
+    let mut prev = field_list.syntax().prev_sibling_or_token();
+    while let Some(element) = prev {
+        if element.kind() != WHITESPACE {
+            break;
+        }
+        start = element.range().start();
+        prev = element.prev_sibling_or_token();
+    }
+    let mut pieces = field_list(false, items, multiline_indent(text, list_range));
+    if let Target::Struct(_) = target {
+        pieces.push(Piece::Text(";".to_string()));
+    }
+    Some(Rewrite { range: TextRange::from_to(start, list_range.end()), pieces })
+}
+
+/// `.0` on the struct becomes `.field1`. The fields are found by resolving
+/// the field expressions, as indices are not names to search for. The
+/// inference of each body is looked up once, for the indices of the fields.
+fn tuple_field_accesses(
+    db: &RootDatabase,
+    files: &[FileId],
+    hir_fields: &[hir::StructField],
+    names: &[String],
+    rewrites: &mut Rewrites,
+) {
+    for &file_id in files {
+        let text = db.file_text(file_id);
+        if !(0..hir_fields.len()).any(|idx| text.contains(&format!(".{}", idx))) {
+            continue;
+        }
+        let source_file = db.parse(file_id).tree;
+        let mut analyzers: FxHashMap<TextRange, hir::SourceAnalyzer> = FxHashMap::default();
+        for field_expr in source_file.syntax().descendants().filter_map(ast::FieldExpr::cast) {
+            let index = match field_expr.index_token() {
+                Some(it) => it,
+                None => continue,
+            };
+            match index.text().parse::<usize>() {
+                Ok(idx) if idx < hir_fields.len() => (),
+                _ => continue,
+            }
+            let body =
+                match field_expr.syntax().ancestors().find(|it| {
+                    it.kind() == FN_DEF || it.kind() == CONST_DEF || it.kind() == STATIC_DEF
+                }) {
+                    Some(it) => it,
+                    None => continue,
+                };
+            let analyzer = analyzers.entry(body.range()).or_insert_with(|| {
+                hir::SourceAnalyzer::new(db, file_id, field_expr.syntax(), None)
+            });
+            let idx = match analyzer
+                .resolve_field(field_expr)
+                .and_then(|field| hir_fields.iter().position(|it| *it == field))
+            {
+                Some(it) => it,
+                None => continue,
+            };
+            rewrites.add(file_id, Rewrite::text(index.range(), names[idx].clone()));
+        }
+    }
+}
+
+fn call_to_named(
+    text: &str,
+    path: &ast::Path,
+    call: &ast::CallExpr,
+    names: &[String],
+) -> Option<Rewrite> {
+    let arg_list = call.arg_list()?;
+    let args = arg_list.args().collect::<Vec<_>>();
+    if args.len() != names.len() {
+        return None;
+    }
+    let items = args
+        .into_iter()
+        .zip(names)
+        .map(|(arg, name)| {
+            vec![Piece::Text(format!("{}: ", name)), Piece::Source(arg.syntax().range())]
+        })
+        .collect();
+    let range = fields_range(path, call.syntax());
+    let pieces = field_list(true, items, multiline_indent(text, arg_list.syntax().range()));
+    Some(Rewrite { range, pieces })
+}
+
+/// `Foo(a, .., b)` becomes `Foo { field1: a, field3: b, .. }`.
+fn tuple_pat_to_named(
+    text: &str,
+    path: &ast::Path,
+    pat: &ast::TupleStructPat,
+    names: &[String],
+) -> Option<Rewrite> {
+    let mut before = Vec::new();
+    let mut after = Vec::new();
+    let mut has_rest = false;
+    for element in pat.syntax().children_with_tokens() {
+        if element.kind() == DOTDOT {
+            has_rest = true;
+        } else if let Some(arg) = element.as_node().and_then(ast::Pat::cast) {
+            if has_rest {
+                after.push(arg);
+            } else {
+                before.push(arg);
+            }
+        }
+    }
+    let count = before.len() + after.len();
+    if count > names.len() || (!has_rest && count != names.len()) {
+        return None;
+    }
+    let indices = (0..before.len()).chain(names.len() - after.len()..names.len());
+    let mut items = before
+        .into_iter()
+        .chain(after)
+        .zip(indices)
+        .map(|(arg, idx)| {
+            vec![Piece::Text(format!("{}: ", names[idx])), Piece::Source(arg.syntax().range())]
+        })
+        .collect::<Vec<_>>();
+    if has_rest {
+        items.push(vec![Piece::Text("..".to_string())]);
+    }
+    let range = fields_range(path, pat.syntax());
+    let pieces = field_list(true, items, multiline_indent(text, range));
+    Some(Rewrite { range, pieces })
+}
+
+/// Fields are passed in the order they are declared in, shorthands become
+/// plain arguments.
+fn struct_lit_to_tuple(
+    text: &str,
+    path: &ast::Path,
+    lit: &ast::StructLit,
+    names: &[String],
+) -> Option<Rewrite> {
+    let field_list = lit.named_field_list()?;
+    // `..base` has no equivalent for tuple structs
+    if field_list.spread().is_some() || field_list.fields().count() != names.len() {
+        return None;
+    }
+    let mut items = Vec::new();
+    for name in names {
+        let field = field_list
+            .fields()
+            .find(|it| it.name_ref().map_or(false, |it| it.text().as_str() == name.as_str()))?;
+        let item = match field.expr() {
+            Some(expr) => Piece::Source(expr.syntax().range()),
+            None => Piece::Text(name.clone()),
+        };
+        items.push(vec![item]);
+    }
+    let range = fields_range(path, lit.syntax());
+    let pieces = field_list(false, items, multiline_indent(text, range));
+    Some(Rewrite { range, pieces })
+}
+
+/// Fields missing from the pattern become `_`, or `..` if they are the last
+/// ones.
+fn struct_pat_to_tuple(
+    text: &str,
+    path: &ast::Path,
+    pat: &ast::StructPat,
+    names: &[String],
+) -> Option<Rewrite> {
+    let field_pat_list = pat.field_pat_list()?;
+    let has_rest = field_pat_list.syntax().children_with_tokens().any(|it| it.kind() == DOTDOT);
+    let mut args = Vec::new();
+    for name in names {
+        let is_name =
+            |it: Option<&ast::Name>| it.map_or(false, |it| it.text().as_str() == name.as_str());
+        let arg = match field_pat_list.field_pats().find(|it| is_name(it.name())) {
+            Some(field_pat) => Some(field_pat.pat()?.syntax().range()),
+            None => field_pat_list
+                .bind_pats()
+                .find(|it| is_name(it.name()))
+                .map(|it| it.syntax().range()),
+        };
+        args.push(arg);
+    }
+    if !has_rest && args.iter().any(Option::is_none) {
+        return None;
+    }
+    let last_present = args.iter().rposition(Option::is_some).map_or(0, |it| it + 1);
+    let mut items = args[..last_present]
+        .iter()
+        .map(|arg| match arg {
+            Some(range) => vec![Piece::Source(*range)],
+            None => vec![Piece::Text("_".to_string())],
+        })
+        .collect::<Vec<_>>();
+    if last_present < args.len() {
+        items.push(vec![Piece::Text("..".to_string())]);
+    }
+    let range = fields_range(path, pat.syntax());
+    let pieces = field_list(false, items, multiline_indent(text, range));
+    Some(Rewrite { range, pieces })
+}
+
+#[cfg(test)]
+mod tests {
+    use ra_syntax::TextRange;
+
+    use crate::{
+        mock_analysis::{check_source_change, MockAnalysis},
+        FileRange,
+    };
+
+    fn check_convert(id: &str, fixture: &str, expected: &[(&str, &str)]) {
+        check_source_change(
+            fixture,
+            |analysis, position| {
+                let frange = FileRange {
+                    file_id: position.file_id,
+                    range: TextRange::offset_len(position.offset, 0.into()),
+                };
+                let assists = analysis.assists(frange).unwrap();
+                assists.into_iter().find(|it| it.id.0 == id).unwrap().change
+            },
+            expected,
+        );
+    }
+
+    fn check_not_applicable(id: &str, fixture: &str) {
+        let (mock, position) = MockAnalysis::with_files_and_position(fixture);
+        let analysis = mock.analysis();
+        let frange = FileRange {
+            file_id: position.file_id,
+            range: TextRange::offset_len(position.offset, 0.into()),
+        };
+        assert!(analysis.assists(frange).unwrap().iter().all(|it| it.id.0 != id));
+    }
+
+    #[test]
+    fn convert_tuple_struct_to_named() {
+        check_convert(
+            "convert_to_named_struct",
+            "
+            //- /lib.rs
+            mod foo;
+            pub struct Point<|>(pub i32, i32);
+            impl Point {
+                fn origin() -> Self { Self(0, 0) }
+            }
+            //- /foo.rs
+            use crate::Point;
+            fn f(p: Point) -> i32 {
+                let Point(x, ..) = p;
+                Point(p.0, Point(1, 2).1).0 + x
+            }
+            ",
+            &[
+                (
+                    "/lib.rs",
+                    "mod foo;
+pub struct Point { pub field1: i32, field2: i32 }
+impl Point {
+    fn origin() -> Self { Self { field1: 0, field2: 0 } }
+}",
+                ),
+                (
+                    "/foo.rs",
+                    "use crate::Point;
+fn f(p: Point) -> i32 {
+    let Point { field1: x, .. } = p;
+    Point { field1: p.field1, field2: Point { field1: 1, field2: 2 }.field2 }.field1 + x
+}",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn convert_named_struct_to_tuple() {
+        check_convert(
+            "convert_to_tuple_struct",
+            "
+            //- /lib.rs
+            struct Point<|> {
+                x: i32,
+                y: i32,
+            }
+            fn f(p: Point) -> Point {
+                let Point { y, .. } = p;
+                let Point { x: a, .. } = p;
+                Point { y: Point { x: a, y }.y, x: p.x }
+            }
+            ",
+            &[(
+                "/lib.rs",
+                "struct Point(
+    i32,
+    i32,
+);
+fn f(p: Point) -> Point {
+    let Point(_, y) = p;
+    let Point(a, ..) = p;
+    Point(p.0, Point(a, y).1)
+}",
+            )],
+        );
+    }
+
+    #[test]
+    fn convert_variant() {
+        check_convert(
+            "convert_to_named_variant",
+            "
+            //- /lib.rs
+            enum E { A<|>(u32), B }
+            fn f(e: E) -> u32 {
+                match e {
+                    E::A(x) => x,
+                    E::B => E::A(0).into(),
+                }
+            }
+            ",
+            &[(
+                "/lib.rs",
+                "enum E { A { field1: u32 }, B }
+fn f(e: E) -> u32 {
+    match e {
+        E::A { field1: x } => x,
+        E::B => E::A { field1: 0 }.into(),
+    }
+}",
+            )],
+        );
+        check_convert(
+            "convert_to_tuple_variant",
+            "
+            //- /lib.rs
+            enum E { A<|> { x: u32 } }
+            fn f() -> E { E::A { x: 0 } }
+            ",
+            &[("/lib.rs", "enum E { A(u32) }\nfn f() -> E { E::A(0) }")],
+        );
+    }
+
+    #[test]
+    fn convert_not_applicable_to_constructor_as_function() {
+        check_not_applicable(
+            "convert_to_named_struct",
+            "
+            //- /lib.rs
+            struct Wrapper<|>(u32);
+            fn f() -> Vec<Wrapper> { vec![1, 2].into_iter().map(Wrapper).collect() }
+            ",
+        );
+        check_not_applicable(
+            "convert_to_tuple_struct",
+            "
+            //- /lib.rs
+            struct S<|> { x: u32 }
+            fn f(s: S) -> S { S { ..s } }
+            ",
+        );
+    }
+}
@@ -0,0 +1,393 @@
+//! Moves an inline module, or an item into a new module, to a file of its own.
+//!
+//! An item moved into a new module is made visible to the module it was
+//! declared in, together with the fields of a struct, and the paths to it are
+//! qualified with the new module.
+
+use std::collections::BTreeMap;
+
+use hir::source_binder;
+use ra_db::SourceDatabase;
+use ra_syntax::{
+    algo::find_node_at_offset,
+    ast::{self, AstNode, NameOwner},
+    SyntaxKind::*,
+    SyntaxNode, TextRange, TextUnit,
+};
+use ra_text_edit::TextEditBuilder;
+use relative_path::RelativePathBuf;
+
+use crate::{
+    db::RootDatabase,
+    references::{classify_name, find_refs_to_def, NameDefinition, SearchScope},
+    symbol_index::SymbolsDatabase,
+    FileId, FileRange, FileSystemEdit, SourceChange, SourceFileEdit,
+};
+
+use super::{Assist, AssistId};
+
+pub(crate) fn move_to_file(db: &RootDatabase, frange: FileRange) -> Option<Assist> {
+    let file_id = frange.file_id;
+    let source_file = db.parse(file_id).tree;
+    let (module, name, item) = item_at(db, frange, &source_file)?;
+    match ast::Module::cast(item) {
+        Some(inline_module) => move_module(db, file_id, module, inline_module),
+        None => move_item(db, file_id, module, name, item),
+    }
+}
+
+/// The named item at the cursor, with the module it is declared in.
+fn item_at<'a>(
+    db: &RootDatabase,
+    frange: FileRange,
+    source_file: &'a ast::SourceFile,
+) -> Option<(hir::Module, &'a ast::Name, &'a SyntaxNode)> {
+    let file_id = frange.file_id;
+    // Only offered on the name, as the workspace is searched for the paths to
+    // a moved item
+    let name = find_node_at_offset::<ast::Name>(source_file.syntax(), frange.range.start())?;
+    let item = name.syntax().parent()?;
+    // The files of modules nested in inline modules are not handled
+    if item.parent()?.kind() != SOURCE_FILE {
+        return None;
+    }
+    if !db.local_roots().contains(&db.file_source_root(file_id)) {
+        return None;
+    }
+    let module = source_binder::module_from_file_id(db, file_id)?;
+    Some((module, name, item))
+}
+
+/// `mod foo { ... }` becomes `mod foo;`.
+fn move_module(
+    db: &RootDatabase,
+    file_id: FileId,
+    module: hir::Module,
+    inline_module: &ast::Module,
+) -> Option<Assist> {
+    let name = inline_module.name()?;
+    let item_list = inline_module.item_list()?;
+    let path = new_module_path(db, file_id, module, name.text())?;
+
+    let contents = item_list
+        .syntax()
+        .children_with_tokens()
+        .filter(|it| it.kind() != L_CURLY && it.kind() != R_CURLY)
+        .map(|it| it.range())
+        .collect::<Vec<_>>();
+    let text = match (contents.first(), contents.last()) {
+        (Some(first), Some(last)) => {
+            let range = TextRange::from_to(first.start(), last.end());
+            dedent(&db.file_text(file_id)[range])
+        }
+        _ => String::new(),
+    };
+
+    let mut edit = TextEditBuilder::default();
+    let range =
+        TextRange::from_to(name.syntax().range().end(), inline_module.syntax().range().end());
+    edit.replace(range, ";".to_string());
+    let source_root = db.file_source_root(file_id);
+    let change = SourceChange::from_edits(
+        "move module to file",
+        vec![SourceFileEdit { file_id, edit: edit.finish() }],
+        vec![FileSystemEdit::CreateFile { source_root, path, text }],
+    );
+    Some(Assist { id: AssistId("move_module_to_file"), change })
+}
+
+/// `struct FooBar {}` becomes `mod foo_bar;`, and the paths to it become
+/// `foo_bar::FooBar`.
+fn move_item(
+    db: &RootDatabase,
+    file_id: FileId,
+    module: hir::Module,
+    name: &ast::Name,
+    item: &SyntaxNode,
+) -> Option<Assist> {
+    let (def, mod_name, path) = new_item_module(db, file_id, module, name, item)?;
+    let scope = module.scope(db);
+
+    // The item stays visible to the module it is moved out of
+    let mut visibility_edit = TextEditBuilder::default();
+    let item_start = item.range().start();
+    if let Some(offset) = missing_visibility(item) {
+        visibility_edit.insert(offset - item_start, "pub(super) ".to_string());
+    }
+    if item.kind() == STRUCT_DEF {
+        let fields = item
+            .descendants()
+            .filter(|it| it.kind() == NAMED_FIELD_DEF || it.kind() == POS_FIELD_DEF);
+        for offset in fields.filter_map(missing_visibility) {
+            visibility_edit.insert(offset - item_start, "pub(super) ".to_string());
+        }
+    }
+    let mut text = visibility_edit.finish().apply(&item.text().to_string());
+    // Names of the old module are imported if the item refers to them
+    let scope_names = scope
+        .entries()
+        .filter(|(_, res)| match res.def.types {
+            Some(hir::ModuleDef::BuiltinType(_)) => false,
+            _ => true,
+        })
+        .map(|(name, _)| name.to_string())
+        .collect::<Vec<_>>();
+    let refers_to_scope = item
+        .descendants()
+        .filter_map(ast::NameRef::cast)
+        .any(|it| it.text() != name.text() && scope_names.contains(&it.text().to_string()));
+    if refers_to_scope {
+        text = format!("use super::*;\n\n{}", text);
+    }
+    text.push('\n');
+
+    let mut edits: BTreeMap<FileId, TextEditBuilder> = BTreeMap::new();
+    let visibility = item
+        .children()
+        .find(|it| it.kind() == VISIBILITY)
+        .map_or(String::new(), |it| format!("{} ", it.text()));
+    edits
+        .entry(file_id)
+        .or_default()
+        .replace(item.range(), format!("{}mod {};", visibility, mod_name));
+
+    let files = SearchScope::default().files(db, file_id);
+    for reference in find_refs_to_def(db, def, name.text(), files) {
+        let FileRange { file_id: ref_file_id, range } = reference.file_range;
+        // References in the item itself are moved with it
+        if ref_file_id == file_id && range.is_subrange(&item.range()) {
+            continue;
+        }
+        let source_file = db.parse(ref_file_id).tree;
+        let name_ref =
+            match find_node_at_offset::<ast::NameRef>(source_file.syntax(), range.start()) {
+                Some(it) => it,
+                None => continue,
+            };
+        let path = match name_ref.syntax().parent().and_then(ast::PathSegment::cast) {
+            Some(segment) => segment.parent_path(),
+            None => continue,
+        };
+        // Only the module of the item refers to it by its name alone, other
+        // modules import it, and `use m::{Item}` is qualified by the use tree.
+        let in_use_tree = path.syntax().ancestors().any(|it| it.kind() == USE_TREE);
+        if path.qualifier().is_none() && !in_use_tree {
+            let ref_module =
+                source_binder::module_from_child_node(db, ref_file_id, name_ref.syntax());
+            if ref_module != Some(module) {
+                continue;
+            }
+        }
+        edits.entry(ref_file_id).or_default().insert(range.start(), format!("{}::", mod_name));
+    }
+
+    let source_root = db.file_source_root(file_id);
+    let source_file_edits = edits
+        .into_iter()
+        .map(|(file_id, edit)| SourceFileEdit { file_id, edit: edit.finish() })
+        .collect();
+    let change = SourceChange::from_edits(
+        "move item to new module file",
+        source_file_edits,
+        vec![FileSystemEdit::CreateFile { source_root, path, text }],
+    );
+    Some(Assist { id: AssistId("move_item_to_file"), change })
+}
+
+/// The definition of the item, and the name and file path of the module it
+/// is moved to. `None` if the module can't be created.
+fn new_item_module(
+    db: &RootDatabase,
+    file_id: FileId,
+    module: hir::Module,
+    name: &ast::Name,
+    item: &SyntaxNode,
+) -> Option<(NameDefinition, String, RelativePathBuf)> {
+    match item.kind() {
+        FN_DEF | STRUCT_DEF | ENUM_DEF | TRAIT_DEF | TYPE_ALIAS_DEF | CONST_DEF | STATIC_DEF => (),
+        _ => return None,
+    }
+    let def = classify_name(db, file_id, name)?;
+    let mod_name = to_snake_case(name.text());
+    let scope = module.scope(db);
+    if scope.entries().any(|(name, res)| name.to_string() == mod_name && res.def.types.is_some()) {
+        return None;
+    }
+    let path = new_module_path(db, file_id, module, &mod_name)?;
+    Some((def, mod_name, path))
+}
+
+/// The path of the file of a new child module, the one `mod name;` resolves
+/// to. `None` if any of the files it could resolve to exists.
+fn new_module_path(
+    db: &RootDatabase,
+    file_id: FileId,
+    module: hir::Module,
+    name: &str,
+) -> Option<RelativePathBuf> {
+    let path = db.file_relative_path(file_id);
+    let root = RelativePathBuf::default();
+    let dir_path = path.parent().unwrap_or(&root);
+    let mod_name = path.file_stem()?;
+    let is_dir_owner = module.parent(db).is_none() || mod_name == "mod";
+
+    let file_mod = dir_path.join(format!("{}.rs", name));
+    let dir_mod = dir_path.join(format!("{}/mod.rs", name));
+    let file_dir_mod = dir_path.join(format!("{}/{}.rs", mod_name, name));
+    let (res, candidates) = if is_dir_owner {
+        (file_mod.clone(), vec![file_mod, dir_mod])
+    } else {
+        (file_dir_mod.clone(), vec![file_dir_mod])
+    };
+    let source_root = db.source_root(db.file_source_root(file_id));
+    if candidates.iter().any(|it| source_root.files.contains_key(it)) {
+        return None;
+    }
+    Some(res)
+}
+
+/// Where `pub(super)` goes for an item or a field without a visibility: after
+/// the attributes and comments.
+fn missing_visibility(node: &SyntaxNode) -> Option<TextUnit> {
+    let first = node
+        .children_with_tokens()
+        .find(|it| it.kind() != ATTR && it.kind() != COMMENT && it.kind() != WHITESPACE)?;
+    if first.kind() == VISIBILITY {
+        return None;
+    }
+    Some(first.range().start())
+}
+
+fn to_snake_case(name: &str) -> String {
+    let mut res = String::with_capacity(name.len());
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c.is_uppercase() && prev_lower {
+            res.push('_');
+        }
+        prev_lower = c.is_lowercase() || c.is_numeric();
+        res.extend(c.to_lowercase());
+    }
+    res
+}
+
+/// Removes the common indentation of the lines and the surrounding blank lines.
+fn dedent(text: &str) -> String {
+    let indent = text
+        .lines()
+        .filter(|it| !it.trim().is_empty())
+        .map(|it| it.len() - it.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    let lines = text
+        .lines()
+        .map(|it| if it.len() >= indent { &it[indent..] } else { it.trim_start() })
+        .collect::<Vec<_>>();
+    format!("{}\n", lines.join("\n").trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use ra_syntax::TextRange;
+    use test_utils::assert_eq_text;
+
+    use crate::{mock_analysis::check_source_change, FileRange, FileSystemEdit};
+
+    /// Checks the edited files and the path and text of the created file.
+    fn check_move(id: &str, fixture: &str, expected: &[(&str, &str)], created: (&str, &str)) {
+        let change = check_source_change(
+            fixture,
+            |analysis, position| {
+                let frange = FileRange {
+                    file_id: position.file_id,
+                    range: TextRange::offset_len(position.offset, 0.into()),
+                };
+                let assist =
+                    analysis.assists(frange).unwrap().into_iter().find(|it| it.id.0 == id).unwrap();
+                assist.change
+            },
+            expected,
+        );
+        match change.file_system_edits.as_slice() {
+            [FileSystemEdit::CreateFile { path, text, .. }] => {
+                assert_eq!(path.as_str(), created.0);
+                assert_eq_text!(created.1, text);
+            }
+            edits => panic!("expected a single new file, got {:?}", edits),
+        }
+    }
+
+    #[test]
+    fn move_inline_module_to_file() {
+        check_move(
+            "move_module_to_file",
+            "
+            //- /lib.rs
+            mod bar;
+            //- /bar.rs
+            pub mod foo<|> {
+                pub fn f() {
+                    g()
+                }
+
+                fn g() {}
+            }
+            ",
+            &[("/bar.rs", "pub mod foo;")],
+            ("bar/foo.rs", "pub fn f() {\n    g()\n}\n\nfn g() {}\n"),
+        );
+    }
+
+    #[test]
+    fn move_inline_module_of_mod_rs_to_file() {
+        check_move(
+            "move_module_to_file",
+            "
+            //- /lib.rs
+            mod bar;
+            //- /bar/mod.rs
+            mod foo<|> { struct S; }
+            ",
+            &[("/bar/mod.rs", "mod foo;")],
+            ("bar/foo.rs", "struct S;\n"),
+        );
+    }
+
+    #[test]
+    fn move_struct_to_new_module_file() {
+        check_move(
+            "move_item_to_file",
+            "
+            //- /lib.rs
+            mod util;
+            use std::fmt;
+            /// A point
+            #[derive(Debug)]
+            struct FancyPoint<|>(u32, pub(crate) u32);
+            impl fmt::Display for FancyPoint {}
+            fn origin() -> FancyPoint { FancyPoint(0, 0) }
+            //- /util.rs
+            use crate::FancyPoint;
+            fn f(p: super::FancyPoint) {}
+            ",
+            &[
+                (
+                    "/lib.rs",
+                    "mod util;
+use std::fmt;
+mod fancy_point;
+impl fmt::Display for fancy_point::FancyPoint {}
+fn origin() -> fancy_point::FancyPoint { fancy_point::FancyPoint(0, 0) }",
+                ),
+                (
+                    "/util.rs",
+                    "use crate::fancy_point::FancyPoint;\nfn f(p: super::fancy_point::FancyPoint) {}",
+                ),
+            ],
+            (
+                "fancy_point.rs",
+                "/// A point\n#[derive(Debug)]\npub(super) struct FancyPoint(pub(super) u32, pub(crate) u32);\n",
+            ),
+        );
+    }
+}
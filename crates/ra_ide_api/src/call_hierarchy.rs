@@ -0,0 +1,263 @@
+use hir::{source_binder, HasSource};
+use ra_db::SourceDatabase;
+use ra_syntax::{
+    algo::find_node_at_offset,
+    ast::{self, AstNode},
+    TextRange,
+};
+
+use crate::{
+    db::RootDatabase,
+    references::{self, NameDefinition},
+    FilePosition, NavigationTarget,
+};
+
+/// A function taking part in a call hierarchy, along with the ranges of the
+/// calls which connect it to the function the hierarchy was requested for.
+///
+/// For incoming calls, the ranges are in `target`'s file. For outgoing calls,
+/// they are in the file of the requested function.
+#[derive(Debug, Clone)]
+pub struct CallItem {
+    pub target: NavigationTarget,
+    pub ranges: Vec<TextRange>,
+}
+
+/// Finds the function at `position`, either by its name or by a reference to
+/// it.
+pub(crate) fn call_hierarchy(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<NavigationTarget> {
+    let function = function_at(db, position)?;
+    Some(NavigationTarget::from_def_source(db, function))
+}
+
+/// Lists the functions which call the function at `position`. References
+/// which do not call the function, like taking it as a function pointer, are
+/// skipped.
+pub(crate) fn incoming_calls(db: &RootDatabase, position: FilePosition) -> Option<Vec<CallItem>> {
+    function_at(db, position)?;
+    let refs = references::find_all_refs(db, position, None)?;
+
+    let mut calls = CallLocations::default();
+    for reference in refs.references() {
+        let file_id = reference.file_range.file_id;
+        let file = db.parse(file_id).tree;
+        let name_ref = match find_node_at_offset::<ast::NameRef>(
+            file.syntax(),
+            reference.file_range.range.start(),
+        ) {
+            Some(it) => it,
+            None => continue,
+        };
+        if !is_callee(name_ref) {
+            continue;
+        }
+        let fn_def = match name_ref.syntax().ancestors().find_map(ast::FnDef::cast) {
+            Some(it) => it,
+            None => continue,
+        };
+        let module = match source_binder::module_from_child_node(db, file_id, fn_def.syntax()) {
+            Some(it) => it,
+            None => continue,
+        };
+        let caller = source_binder::function_from_module(db, module, fn_def);
+        calls.add(NavigationTarget::from_def_source(db, caller), reference.file_range.range);
+    }
+    Some(calls.into_items())
+}
+
+/// Lists the functions called by the function at `position`.
+pub(crate) fn outgoing_calls(db: &RootDatabase, position: FilePosition) -> Option<Vec<CallItem>> {
+    let function = function_at(db, position)?;
+    let file_id = function.source(db).file_id;
+    let original_file_id = file_id.original_file(db);
+    // The calls of functions defined by macros have no source in the file
+    if file_id != hir::HirFileId::from(original_file_id) {
+        return Some(Vec::new());
+    }
+    let file = db.parse(original_file_id).tree;
+
+    let mut callees = function.calls(db);
+    callees.sort_by_key(|(_, ptr)| ptr.range().start());
+
+    let mut calls = CallLocations::default();
+    for (callee, ptr) in callees {
+        let node = ptr.to_node(file.syntax());
+        // Point at the name of the called function rather than at the whole call
+        let range = if let Some(call) = ast::CallExpr::cast(node) {
+            call.expr().map(|it| it.syntax().range())
+        } else {
+            node.children().find_map(ast::NameRef::cast).map(|it| it.syntax().range())
+        };
+        calls.add(
+            NavigationTarget::from_def_source(db, callee),
+            range.unwrap_or_else(|| ptr.range()),
+        );
+    }
+    Some(calls.into_items())
+}
+
+/// Checks if `name_ref` names the function called by a call or method call
+/// expression.
+fn is_callee(name_ref: &ast::NameRef) -> bool {
+    if let Some(call) = name_ref.syntax().parent().and_then(ast::MethodCallExpr::cast) {
+        return call.name_ref().map_or(false, |it| it.syntax() == name_ref.syntax());
+    }
+    let path_expr = match name_ref.syntax().ancestors().find_map(ast::PathExpr::cast) {
+        Some(it) => it,
+        None => return false,
+    };
+    // Only the last segment names the function, `foo` in `foo::bar()` does not
+    let is_last_segment = path_expr
+        .path()
+        .and_then(|it| it.segment())
+        .and_then(|it| it.name_ref())
+        .map_or(false, |it| it.syntax() == name_ref.syntax());
+    let call = match path_expr.syntax().parent().and_then(ast::CallExpr::cast) {
+        Some(it) => it,
+        None => return false,
+    };
+    is_last_segment && call.expr().map_or(false, |it| it.syntax() == path_expr.syntax())
+}
+
+fn function_at(db: &RootDatabase, position: FilePosition) -> Option<hir::Function> {
+    let file = db.parse(position.file_id).tree;
+    match references::find_definition(db, &file, position)? {
+        NameDefinition::ModuleDef(hir::ModuleDef::Function(it)) => Some(it),
+        _ => None,
+    }
+}
+
+/// Groups the call ranges by the function they are made from or to.
+#[derive(Default)]
+struct CallLocations {
+    items: Vec<CallItem>,
+}
+
+impl CallLocations {
+    fn add(&mut self, target: NavigationTarget, range: TextRange) {
+        let existing = self.items.iter_mut().find(|it| {
+            it.target.file_id() == target.file_id() && it.target.full_range() == target.full_range()
+        });
+        match existing {
+            Some(item) => item.ranges.push(range),
+            None => self.items.push(CallItem { target, ranges: vec![range] }),
+        }
+    }
+
+    fn into_items(self) -> Vec<CallItem> {
+        self.items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock_analysis::analysis_and_position;
+
+    fn check_hierarchy(
+        fixture: &str,
+        expected: &str,
+        expected_incoming: &[&str],
+        expected_outgoing: &[&str],
+    ) {
+        let (analysis, pos) = analysis_and_position(fixture);
+
+        let nav = analysis.call_hierarchy(pos).unwrap().unwrap();
+        nav.assert_match(expected);
+
+        let render = |items: Vec<crate::CallItem>| -> Vec<String> {
+            items
+                .into_iter()
+                .map(|it| format!("{} : {:?}", it.target.debug_render(), it.ranges))
+                .collect()
+        };
+        let incoming = render(analysis.incoming_calls(pos).unwrap().unwrap());
+        assert_eq!(incoming, expected_incoming);
+        let outgoing = render(analysis.outgoing_calls(pos).unwrap().unwrap());
+        assert_eq!(outgoing, expected_outgoing);
+    }
+
+    #[test]
+    fn test_call_hierarchy_on_declaration() {
+        check_hierarchy(
+            r#"
+            //- /lib.rs
+            fn callee() {}
+            fn call<|>er() {
+                callee();
+                callee();
+            }
+            fn caller2() {
+                caller();
+            }
+            "#,
+            "caller FN_DEF FileId(1) [15; 58) [18; 24)",
+            &["caller2 FN_DEF FileId(1) [59; 89) [62; 69) : [[78; 84)]"],
+            &["callee FN_DEF FileId(1) [0; 14) [3; 9) : [[33; 39), [47; 53)]"],
+        );
+    }
+
+    #[test]
+    fn test_call_hierarchy_on_reference() {
+        check_hierarchy(
+            r#"
+            //- /lib.rs
+            fn callee() {}
+            fn caller() {
+                call<|>ee();
+            }
+            "#,
+            "callee FN_DEF FileId(1) [0; 14) [3; 9)",
+            &["caller FN_DEF FileId(1) [15; 44) [18; 24) : [[33; 39)]"],
+            &[],
+        );
+    }
+
+    #[test]
+    fn test_call_hierarchy_skips_references_which_are_not_calls() {
+        check_hierarchy(
+            r#"
+            //- /lib.rs
+            fn call<|>ee() {}
+            fn caller() {
+                let f = callee;
+                f();
+                callee();
+            }
+            "#,
+            "callee FN_DEF FileId(1) [0; 14) [3; 9)",
+            &["caller FN_DEF FileId(1) [15; 73) [18; 24) : [[62; 68)]"],
+            &[],
+        );
+    }
+
+    #[test]
+    fn test_call_hierarchy_with_methods_across_files() {
+        check_hierarchy(
+            r#"
+            //- /lib.rs
+            mod foo;
+            struct S;
+            impl S {
+                fn method(&self) {}
+            }
+            fn cal<|>ler() {
+                S.method();
+                foo::bar();
+            }
+            //- /foo.rs
+            pub fn bar() {
+                crate::caller();
+            }
+            "#,
+            "caller FN_DEF FileId(1) [54; 101) [57; 63)",
+            &["bar FN_DEF FileId(2) [0; 37) [7; 10) : [[26; 32)]"],
+            &[
+                "method FN_DEF FileId(1) [32; 51) [35; 41) : [[74; 80)]",
+                "bar FN_DEF FileId(2) [0; 37) [7; 10) : [[88; 96)]",
+            ],
+        );
+    }
+}
@@ -280,6 +280,7 @@ impl RootDatabase {
             hir::db::LangItemsQuery
             hir::db::LangItemQuery
             hir::db::DocumentationQuery
+            hir::db::IsDeprecatedQuery
             hir::db::ExprScopesQuery
             hir::db::InferQuery
             hir::db::TypeForDefQuery
@@ -287,10 +288,12 @@ impl RootDatabase {
             hir::db::CallableItemSignatureQuery
             hir::db::GenericPredicatesQuery
             hir::db::GenericDefaultsQuery
+            hir::db::ConstValueQuery
             hir::db::BodyWithSourceMapQuery
             hir::db::BodyHirQuery
             hir::db::ImplsInCrateQuery
             hir::db::ImplsForTraitQuery
+            hir::db::UnusedImportsQuery
             hir::db::AssociatedTyDataQuery
             hir::db::TraitDatumQuery
             hir::db::StructDatumQuery
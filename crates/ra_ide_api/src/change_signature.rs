@@ -0,0 +1,298 @@
+//! Reorders, removes and adds parameters of a function. The definition is
+//! changed together with the related methods of a trait and its impls, and
+//! all the calls to them, including method calls and UFCS.
+
+use std::fmt;
+
+use hir::HasSource;
+use ra_db::SourceDatabase;
+use ra_syntax::{
+    algo::find_node_at_offset,
+    ast::{self, ArgListOwner},
+    AstNode,
+};
+
+use crate::{
+    db::RootDatabase,
+    references::{
+        find_definition, find_refs_to_def, related_functions, NameDefinition, SearchScope,
+    },
+    rewrites::{delimited_list, multiline_indent, Piece, Rewrite, Rewrites},
+    symbol_index::SymbolsDatabase,
+    FilePosition, FileRange, SourceChange,
+};
+
+/// The parameters of a function after the change. Parameters of the old
+/// signature which are not listed are removed, `self` is always kept.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureChange {
+    pub params: Vec<ParamChange>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamChange {
+    /// The parameter at this index of the old signature, not counting `self`.
+    Existing(usize),
+    /// A new parameter. Calls pass `value` for it, or `unimplemented!()` if
+    /// there is no value.
+    Added { name: String, ty: String, value: Option<String> },
+}
+
+/// The reason a signature change was refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureChangeError(pub(crate) String);
+
+impl fmt::Display for SignatureChangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+pub(crate) fn change_signature(
+    db: &RootDatabase,
+    position: FilePosition,
+    change: &SignatureChange,
+) -> Result<Option<SourceChange>, SignatureChangeError> {
+    let source_file = db.parse(position.file_id).tree;
+    let func = match find_definition(db, &source_file, position) {
+        Some(NameDefinition::ModuleDef(hir::ModuleDef::Function(it))) => it,
+        _ => return Ok(None),
+    };
+    let name = func.name(db).to_string();
+    let error = |reason: &str| {
+        Err(SignatureChangeError(format!("Cannot change the signature of `{}`: {}", name, reason)))
+    };
+    let param_count = match func.source(db).ast.param_list() {
+        Some(it) => it.params().count(),
+        None => return Ok(None),
+    };
+    let mut existing = Vec::new();
+    for param in change.params.iter() {
+        if let ParamChange::Existing(idx) = *param {
+            if idx >= param_count || existing.contains(&idx) {
+                return error(&format!("invalid parameter index {}", idx));
+            }
+            existing.push(idx);
+        }
+    }
+
+    let mut rewrites = Rewrites::default();
+    for func in related_functions(db, func) {
+        let src = func.source(db);
+        let file_id = src.file_id.original_file(db);
+        if src.file_id != file_id.into() {
+            return error("it is defined by a macro");
+        }
+        if !db.local_roots().contains(&db.file_source_root(file_id)) {
+            return error("it is defined outside of the workspace");
+        }
+        let param_list = match src.ast.param_list() {
+            Some(it) => it,
+            None => continue,
+        };
+        let params = param_list.params().collect::<Vec<_>>();
+        if params.len() != param_count {
+            return error("the trait and its impls declare different parameters");
+        }
+        let has_self = param_list.self_param().is_some();
+
+        let mut items = Vec::new();
+        if let Some(self_param) = param_list.self_param() {
+            items.push(vec![Piece::Text(self_param.syntax().text().to_string())]);
+        }
+        for param in change.params.iter() {
+            let text = match param {
+                ParamChange::Existing(idx) => params[*idx].syntax().text().to_string(),
+                ParamChange::Added { name, ty, .. } => format!("{}: {}", name, ty),
+            };
+            items.push(vec![Piece::Text(text)]);
+        }
+        let text = db.file_text(file_id);
+        let range = param_list.syntax().range();
+        let pieces = delimited_list(("(", ")"), false, items, multiline_indent(&text, range));
+        rewrites.add(file_id, Rewrite { range, pieces });
+
+        let def = NameDefinition::ModuleDef(func.into());
+        let files = SearchScope::default().files(db, file_id);
+        for reference in find_refs_to_def(db, def, &name, files) {
+            let FileRange { file_id, range } = reference.file_range;
+            let source_file = db.parse(file_id).tree;
+            let parent =
+                match find_node_at_offset::<ast::NameRef>(source_file.syntax(), range.start())
+                    .and_then(|it| it.syntax().parent())
+                {
+                    Some(it) => it,
+                    None => continue,
+                };
+            // `Foo::f(foo, x)` passes the receiver as the first argument
+            let (arg_list, receiver_count) = if let Some(call) = ast::MethodCallExpr::cast(parent) {
+                (call.arg_list(), 0)
+            } else if let Some(segment) = ast::PathSegment::cast(parent) {
+                let path_expr =
+                    match segment.parent_path().syntax().parent().and_then(ast::PathExpr::cast) {
+                        Some(it) => it,
+                        None => continue,
+                    };
+                let call = path_expr
+                    .syntax()
+                    .parent()
+                    .and_then(ast::CallExpr::cast)
+                    .filter(|it| it.expr().map(AstNode::syntax) == Some(path_expr.syntax()));
+                match call {
+                    Some(call) => (call.arg_list(), if has_self { 1 } else { 0 }),
+                    None => return error("it is used as a value"),
+                }
+            } else {
+                continue;
+            };
+            let arg_list = match arg_list {
+                Some(it) => it,
+                None => continue,
+            };
+            let args = arg_list.args().collect::<Vec<_>>();
+            // Calls which do not compile are left alone
+            if args.len() != receiver_count + param_count {
+                continue;
+            }
+
+            let mut items = args[..receiver_count]
+                .iter()
+                .map(|it| vec![Piece::Source(it.syntax().range())])
+                .collect::<Vec<_>>();
+            for param in change.params.iter() {
+                let item = match param {
+                    ParamChange::Existing(idx) => {
+                        Piece::Source(args[receiver_count + idx].syntax().range())
+                    }
+                    ParamChange::Added { value, .. } => {
+                        Piece::Text(value.clone().unwrap_or_else(|| "unimplemented!()".to_string()))
+                    }
+                };
+                items.push(vec![item]);
+            }
+            let text = db.file_text(file_id);
+            let range = arg_list.syntax().range();
+            let pieces = delimited_list(("(", ")"), false, items, multiline_indent(&text, range));
+            rewrites.add(file_id, Rewrite { range, pieces });
+        }
+    }
+
+    Ok(Some(SourceChange::source_file_edits("change signature", rewrites.into_edits(db))))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        mock_analysis::{check_source_change, single_file_with_position},
+        ParamChange, SignatureChange,
+    };
+
+    fn check_change_signature(fixture: &str, params: Vec<ParamChange>, expected: &[(&str, &str)]) {
+        let change = SignatureChange { params };
+        check_source_change(
+            fixture,
+            |analysis, position| {
+                analysis.change_signature(position, change).unwrap().unwrap().unwrap()
+            },
+            expected,
+        );
+    }
+
+    #[test]
+    fn test_change_signature_reorders_and_removes() {
+        check_change_signature(
+            "
+            //- /lib.rs
+            mod foo;
+            pub fn add<|>(a: u32, b: u32, unused: bool) -> u32 { a + b }
+            //- /foo.rs
+            fn f() -> u32 {
+                crate::add(1, crate::add(2, 3, true), false)
+            }
+            ",
+            vec![ParamChange::Existing(1), ParamChange::Existing(0)],
+            &[
+                ("/lib.rs", "mod foo;\npub fn add(b: u32, a: u32) -> u32 { a + b }"),
+                ("/foo.rs", "fn f() -> u32 {\n    crate::add(crate::add(3, 2), 1)\n}"),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_change_signature_of_trait_method() {
+        check_change_signature(
+            "
+            //- /lib.rs
+            trait Shape {
+                fn scale<|>(&mut self, factor: f32);
+            }
+            struct Square(f32);
+            impl Shape for Square {
+                fn scale(&mut self, by: f32) { self.0 *= by }
+            }
+            fn f(mut square: Square) {
+                square.scale(2.0);
+                Shape::scale(&mut square, 0.5);
+            }
+            ",
+            vec![
+                ParamChange::Existing(0),
+                ParamChange::Added {
+                    name: "times".to_string(),
+                    ty: "u32".to_string(),
+                    value: Some("1".to_string()),
+                },
+            ],
+            &[(
+                "/lib.rs",
+                "trait Shape {
+    fn scale(&mut self, factor: f32, times: u32);
+}
+struct Square(f32);
+impl Shape for Square {
+    fn scale(&mut self, by: f32, times: u32) { self.0 *= by }
+}
+fn f(mut square: Square) {
+    square.scale(2.0, 1);
+    Shape::scale(&mut square, 0.5, 1);
+}",
+            )],
+        );
+    }
+
+    #[test]
+    fn test_change_signature_adds_placeholder() {
+        check_change_signature(
+            "
+            //- /lib.rs
+            fn log<|>(
+                message: &str,
+            ) {}
+            fn f() { log(\"hi\") }
+            ",
+            vec![
+                ParamChange::Added { name: "level".to_string(), ty: "u8".to_string(), value: None },
+                ParamChange::Existing(0),
+            ],
+            &[(
+                "/lib.rs",
+                "fn log(
+    level: u8,
+    message: &str,
+) {}
+fn f() { log(unimplemented!(), \"hi\") }",
+            )],
+        );
+    }
+
+    #[test]
+    fn test_change_signature_invalid_index() {
+        let (analysis, position) = single_file_with_position("fn f<|>(a: u32) {}");
+        let change = SignatureChange { params: vec![ParamChange::Existing(1)] };
+        let err = analysis.change_signature(position, change).unwrap().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Cannot change the signature of `f`: invalid parameter index 1"
+        );
+    }
+}
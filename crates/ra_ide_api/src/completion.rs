@@ -10,6 +10,7 @@ mod complete_keyword;
 mod complete_snippet;
 mod complete_path;
 mod complete_scope;
+mod complete_import;
 mod complete_postfix;
 
 use ra_db::SourceDatabase;
@@ -25,7 +26,8 @@ use crate::{
 };
 
 pub use crate::completion::completion_item::{
-    CompletionItem, CompletionItemKind, InsertTextFormat,
+    CompletionItem, CompletionItemKind, CompletionRelevance, Deferred, InsertTextFormat,
+    ResolvedCompletion,
 };
 
 /// Main entry point for completion. We run completion as a two-phase process.
@@ -49,23 +51,51 @@ pub use crate::completion::completion_item::{
 ///
 /// `foo` *should* be present among the completion variants. Filtering by
 /// identifier prefix/fuzzy match should be done higher in the stack, together
-/// with ordering of completions (currently this is done by the client).
+/// with ordering of completions (done by the client, using the relevance of the
+/// items).
+///
+/// Documentation, signatures and additional edits are only computed for the
+/// item selected in the editor, by `resolve_completion`.
 pub(crate) fn completions(db: &db::RootDatabase, position: FilePosition) -> Option<Completions> {
     let original_parse = db.parse(position.file_id);
     let ctx = CompletionContext::new(db, &original_parse, position)?;
+    Some(complete(&ctx))
+}
+
+/// Computes the `deferred` parts of a completion item made at `position`. Only
+/// the selected item is looked at, the other completions are not recomputed.
+pub(crate) fn resolve_completion(
+    db: &db::RootDatabase,
+    position: FilePosition,
+    deferred: &Deferred,
+) -> Option<ResolvedCompletion> {
+    let res = match deferred {
+        Deferred::Docs(frange) => presentation::resolve_definition(db, *frange, false)?,
+        Deferred::Signature(frange) => presentation::resolve_definition(db, *frange, true)?,
+        Deferred::Import(path) => {
+            let original_parse = db.parse(position.file_id);
+            let ctx = CompletionContext::new(db, &original_parse, position)?;
+            let additional_edits = complete_import::import_edit(&ctx, path);
+            ResolvedCompletion { additional_edits, ..Default::default() }
+        }
+    };
+    Some(res)
+}
 
+fn complete(ctx: &CompletionContext) -> Completions {
     let mut acc = Completions::default();
 
-    complete_fn_param::complete_fn_param(&mut acc, &ctx);
-    complete_keyword::complete_expr_keyword(&mut acc, &ctx);
-    complete_keyword::complete_use_tree_keyword(&mut acc, &ctx);
-    complete_snippet::complete_expr_snippet(&mut acc, &ctx);
-    complete_snippet::complete_item_snippet(&mut acc, &ctx);
-    complete_path::complete_path(&mut acc, &ctx);
-    complete_scope::complete_scope(&mut acc, &ctx);
-    complete_dot::complete_dot(&mut acc, &ctx);
-    complete_struct_literal::complete_struct_literal(&mut acc, &ctx);
-    complete_pattern::complete_pattern(&mut acc, &ctx);
-    complete_postfix::complete_postfix(&mut acc, &ctx);
-    Some(acc)
+    complete_fn_param::complete_fn_param(&mut acc, ctx);
+    complete_keyword::complete_expr_keyword(&mut acc, ctx);
+    complete_keyword::complete_use_tree_keyword(&mut acc, ctx);
+    complete_snippet::complete_expr_snippet(&mut acc, ctx);
+    complete_snippet::complete_item_snippet(&mut acc, ctx);
+    complete_path::complete_path(&mut acc, ctx);
+    complete_scope::complete_scope(&mut acc, ctx);
+    complete_import::complete_import(&mut acc, ctx);
+    complete_dot::complete_dot(&mut acc, ctx);
+    complete_struct_literal::complete_struct_literal(&mut acc, ctx);
+    complete_pattern::complete_pattern(&mut acc, ctx);
+    complete_postfix::complete_postfix(&mut acc, ctx);
+    acc
 }
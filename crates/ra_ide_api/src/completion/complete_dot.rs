@@ -204,6 +204,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_primitive_method_completion() {
+        assert_debug_snapshot_matches!(
+            do_ref_completion(
+                r#"
+            #[lang = "slice"]
+            impl<T> [T] {
+                fn len(&self) -> usize {}
+            }
+            fn foo() {
+               [1, 2].<|>
+            }
+            "#,
+            ),
+            @r###"
+       ⋮[
+       ⋮    CompletionItem {
+       ⋮        label: "len",
+       ⋮        source_range: [158; 158),
+       ⋮        delete: [158; 158),
+       ⋮        insert: "len()$0",
+       ⋮        kind: Method,
+       ⋮        detail: "fn len(&self) -> usize",
+       ⋮    },
+       ⋮]
+        "###
+        );
+    }
+
     #[test]
     fn test_trait_method_completion() {
         assert_debug_snapshot_matches!(
@@ -0,0 +1,203 @@
+use ra_assists::auto_import;
+use ra_db::SourceDatabase;
+use ra_syntax::{ast, AstNode, SmolStr, SyntaxKind::*};
+use ra_text_edit::{TextEdit, TextEditBuilder};
+use rustc_hash::FxHashSet;
+
+use crate::{
+    completion::{
+        CompletionContext, CompletionItem, CompletionItemKind, CompletionKind, Completions,
+        Deferred,
+    },
+    references::{classify_name, NameDefinition},
+    symbol_index::{self, FileSymbol},
+    Query,
+};
+
+/// Searching for a one-letter prefix would match most of the index.
+const MIN_PREFIX_LEN: usize = 2;
+/// Maximum number of symbols looked at, both in the workspace and in libraries.
+const MAX_CANDIDATES: usize = 40;
+
+/// Completes the items of the workspace and of the dependencies which are not
+/// in scope yet, adding a `use` for the selected one.
+pub(super) fn complete_import(acc: &mut Completions, ctx: &CompletionContext) {
+    if !ctx.is_trivial_path {
+        return;
+    }
+    let module = match ctx.module {
+        Some(it) => it,
+        None => return,
+    };
+    // We fetch ident from the original file, because we need to pre-filter auto-imports
+    if ast::NameRef::cast(ctx.token.parent()).is_none() {
+        return;
+    }
+    let prefix = ctx.token.text();
+    if prefix.len() < MIN_PREFIX_LEN {
+        return;
+    }
+
+    let in_scope: FxHashSet<String> =
+        ctx.analyzer.all_names(ctx.db).keys().map(|name| name.to_string()).collect();
+    let mut candidates = Vec::new();
+    for &libs in &[false, true] {
+        let mut query = Query::new(prefix.to_string());
+        if libs {
+            query.libs();
+        }
+        query.limit(MAX_CANDIDATES);
+        candidates.extend(symbol_index::world_symbols(ctx.db, query));
+    }
+
+    for symbol in candidates {
+        if in_scope.contains(symbol.name.as_str()) {
+            continue;
+        }
+        let kind = match completion_item_kind(&symbol) {
+            Some(it) => it,
+            None => continue,
+        };
+        let path = match import_path(ctx, module, &symbol) {
+            Some(it) => it,
+            None => continue,
+        };
+
+        CompletionItem::new(CompletionKind::Import, ctx.source_range(), symbol.name.as_str())
+            .kind(kind)
+            .detail(path.join("::"))
+            .defer(Deferred::Import(path))
+            .add_to(acc);
+    }
+}
+
+/// Adds a `use` of `path`. Computed when the item is resolved, as finding where
+/// to insert the `use` is not free.
+pub(super) fn import_edit(ctx: &CompletionContext, path: &[SmolStr]) -> Option<TextEdit> {
+    let mut builder = TextEditBuilder::default();
+    auto_import::auto_import_text_edit(ctx.token.parent(), ctx.token.parent(), path, &mut builder);
+    let edit = builder.finish();
+
+    // The edit is sent as additional edits of the completion, which must not
+    // touch the completed range.
+    let is_valid =
+        edit.as_atoms().iter().all(|atom| ctx.source_range().intersection(&atom.delete).is_none());
+    if is_valid {
+        Some(edit)
+    } else {
+        None
+    }
+}
+
+fn completion_item_kind(symbol: &FileSymbol) -> Option<CompletionItemKind> {
+    let kind = match symbol.ptr.kind() {
+        FN_DEF => CompletionItemKind::Function,
+        STRUCT_DEF => CompletionItemKind::Struct,
+        ENUM_DEF => CompletionItemKind::Enum,
+        TRAIT_DEF => CompletionItemKind::Trait,
+        MODULE => CompletionItemKind::Module,
+        TYPE_ALIAS_DEF => CompletionItemKind::TypeAlias,
+        CONST_DEF => CompletionItemKind::Const,
+        STATIC_DEF => CompletionItemKind::Static,
+        _ => return None,
+    };
+    Some(kind)
+}
+
+/// Computes the path to `use` the symbol from `module`, starting with `crate`
+/// or with the name of the dependency defining the symbol, if the symbol and
+/// its modules are visible there.
+fn import_path(
+    ctx: &CompletionContext,
+    module: hir::Module,
+    symbol: &FileSymbol,
+) -> Option<Vec<SmolStr>> {
+    let db = ctx.db;
+    let file = db.parse(symbol.file_id).tree;
+    let node = symbol.ptr.to_node(file.syntax());
+    // Functions of impls and nested items can't be imported
+    let parent = node.parent()?;
+    let is_module_item = match parent.kind() {
+        SOURCE_FILE => true,
+        ITEM_LIST => parent.parent().map_or(false, |it| it.kind() == MODULE),
+        _ => false,
+    };
+    if !is_module_item {
+        return None;
+    }
+
+    let name = node.children().find_map(ast::Name::cast)?;
+    let def = match classify_name(db, symbol.file_id, name)? {
+        NameDefinition::ModuleDef(it) => it,
+        _ => return None,
+    };
+    Some(def.path_from(db, module)?.segment_texts())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::completion::{do_completion, CompletionKind};
+
+    fn check_import_completion(code: &str, text: &str, expected: &[(&str, &str, &str)]) {
+        let completions = do_completion(code, CompletionKind::Import);
+        let actual = completions
+            .iter()
+            .map(|it| (it.label(), it.detail().unwrap(), it.text_edit().apply(text)))
+            .collect::<Vec<_>>();
+        let expected = expected
+            .iter()
+            .map(|&(label, detail, after)| (label, detail, after.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn completes_items_not_in_scope() {
+        check_import_completion(
+            r"
+            //- /lib.rs
+            mod foo;
+            fn main() { Ba<|> }
+            //- /foo.rs
+            pub struct Bar;
+            impl Bar {
+                fn bark(&self) {}
+            }
+            struct Bay;
+            //- /other_crate/lib.rs
+            pub mod m {
+                pub struct Baz;
+                struct Bad;
+            }
+            mod private {
+                pub struct Bat;
+            }
+            ",
+            "mod foo;\nfn main() { Ba }\n",
+            &[
+                ("Bar", "crate::foo::Bar", "use crate::foo::Bar;\n\nmod foo;\nfn main() { Bar }\n"),
+                (
+                    "Baz",
+                    "other_crate::m::Baz",
+                    "use other_crate::m::Baz;\n\nmod foo;\nfn main() { Baz }\n",
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn does_not_complete_items_in_scope() {
+        check_import_completion(
+            r"
+            //- /lib.rs
+            mod foo;
+            use foo::Bar;
+            fn main() { Ba<|> }
+            //- /foo.rs
+            pub struct Bar;
+            ",
+            "mod foo;\nuse foo::Bar;\nfn main() { Ba }\n",
+            &[],
+        );
+    }
+}
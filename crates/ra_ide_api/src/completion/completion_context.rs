@@ -1,7 +1,8 @@
-use hir::source_binder;
+use hir::{db::HirDatabase, source_binder, HasSource};
 use ra_syntax::{
     algo::{find_covering_element, find_node_at_offset, find_token_at_offset},
-    ast, AstNode, Parse, SourceFile,
+    ast::{self, NameOwner, TypeAscriptionOwner},
+    AstNode, Parse, SourceFile,
     SyntaxKind::*,
     SyntaxNode, SyntaxToken, TextRange, TextUnit,
 };
@@ -38,6 +39,13 @@ pub(crate) struct CompletionContext<'a> {
     pub(super) dot_receiver: Option<&'a ast::Expr>,
     /// If this is a call (method or function) in particular, i.e. the () are already there.
     pub(super) is_call: bool,
+    /// The type expected for the completed expression: the type of the
+    /// parameter for an argument, of the annotation for the initializer of a
+    /// `let`, of the field in a struct literal, or the return type.
+    pub(super) expected_type: Option<hir::Ty>,
+    /// The name of the parameter or of the field the completed expression is
+    /// for.
+    pub(super) expected_name: Option<String>,
 }
 
 impl<'a> CompletionContext<'a> {
@@ -69,6 +77,8 @@ impl<'a> CompletionContext<'a> {
             is_new_item: false,
             dot_receiver: None,
             is_call: false,
+            expected_type: None,
+            expected_name: None,
         };
         ctx.fill(&original_parse, position.offset);
         Some(ctx)
@@ -153,6 +163,10 @@ impl<'a> CompletionContext<'a> {
             None => return,
         };
 
+        if let Some(expr) = completed_expr(name_ref) {
+            self.fill_expected_type(original_file, expr);
+        }
+
         if let Some(segment) = ast::PathSegment::cast(parent) {
             let path = segment.parent_path();
             self.is_call = path
@@ -220,6 +234,131 @@ impl<'a> CompletionContext<'a> {
             self.is_call = true;
         }
     }
+
+    fn fill_expected_type(
+        &mut self,
+        original_file: &'a SourceFile,
+        expr: &SyntaxNode,
+    ) -> Option<()> {
+        let parent = expr.parent()?;
+        match parent.kind() {
+            ARG_LIST => {
+                let index =
+                    ast::ArgList::cast(parent)?.args().position(|it| it.syntax() == expr)?;
+                let arg_list = find_node_at_start(original_file.syntax(), parent.range().start())?;
+                self.fill_expected_argument(arg_list, index)?;
+            }
+            LET_STMT => {
+                let let_stmt = ast::LetStmt::cast(parent)?;
+                if let_stmt.ascribed_type().is_none() {
+                    return None;
+                }
+                let let_stmt = find_node_at_start::<ast::LetStmt>(
+                    original_file.syntax(),
+                    parent.range().start(),
+                )?;
+                self.expected_type = self.analyzer.type_of_pat(self.db, let_stmt.pat()?);
+            }
+            NAMED_FIELD => {
+                let field_name = ast::NamedField::cast(parent)?.name_ref()?.text().to_string();
+                let struct_lit = parent.ancestors().find_map(ast::StructLit::cast)?;
+                let struct_lit = find_node_at_start::<ast::StructLit>(
+                    original_file.syntax(),
+                    struct_lit.syntax().range().start(),
+                )?;
+                let struct_ty = self.analyzer.type_of(self.db, struct_lit.into())?;
+                let (adt, substs) = struct_ty.as_adt()?;
+                let field = match adt {
+                    hir::AdtDef::Struct(it) => it
+                        .fields(self.db)
+                        .into_iter()
+                        .find(|it| it.name(self.db).to_string() == field_name)?,
+                    _ => return None,
+                };
+                self.expected_type = Some(field.ty(self.db).subst(substs));
+                self.expected_name = Some(field_name);
+            }
+            RETURN_EXPR => self.fill_expected_return_type(expr)?,
+            BLOCK if parent.parent()?.kind() == FN_DEF => {
+                if ast::Block::cast(parent)?.expr()?.syntax() == expr {
+                    self.fill_expected_return_type(expr)?;
+                }
+            }
+            _ => (),
+        }
+        Some(())
+    }
+
+    fn fill_expected_argument(&mut self, arg_list: &ast::ArgList, index: usize) -> Option<()> {
+        let db = self.db;
+        let call = arg_list.syntax().parent()?;
+        if let Some(call) = ast::MethodCallExpr::cast(call) {
+            let function = self.analyzer.resolve_method_call(call)?;
+            let method_ty = self.analyzer.type_of_method_call(call)?;
+            let (callable_def, substs) = method_ty.as_callable()?;
+            let self_params = if function.data(db).has_self_param() { 1 } else { 0 };
+            let sig = db.callable_item_signature(callable_def).subst(substs);
+            self.expected_type = sig.params().get(self_params + index).cloned();
+            self.expected_name = param_name(db, function, index);
+        } else {
+            let callee_ty = self.analyzer.type_of(db, ast::CallExpr::cast(call)?.expr()?)?;
+            let (callable_def, substs) = callee_ty.as_callable()?;
+            let sig = db.callable_item_signature(callable_def).subst(substs);
+            self.expected_type = sig.params().get(index).cloned();
+            if let hir::CallableDef::Function(function) = callable_def {
+                self.expected_name = param_name(db, function, index);
+            }
+        }
+        Some(())
+    }
+
+    fn fill_expected_return_type(&mut self, expr: &SyntaxNode) -> Option<()> {
+        // Closures have return types of their own
+        let owner = expr.ancestors().find(|it| it.kind() == FN_DEF || it.kind() == LAMBDA_EXPR)?;
+        if owner.kind() != FN_DEF {
+            return None;
+        }
+        let function =
+            source_binder::function_from_module(self.db, self.module?, self.function_syntax?);
+        let sig = self.db.callable_item_signature(function.into());
+        self.expected_type = Some(sig.ret().clone());
+        Some(())
+    }
+}
+
+/// The expression whose value is completed: the path, or the call if the path
+/// is called, or the field access or method call.
+fn completed_expr(name_ref: &ast::NameRef) -> Option<&SyntaxNode> {
+    let parent = name_ref.syntax().parent()?;
+    let expr = match parent.kind() {
+        FIELD_EXPR | METHOD_CALL_EXPR => parent,
+        PATH_SEGMENT => {
+            let path = ast::PathSegment::cast(parent)?.parent_path();
+            let path_expr = path.syntax().parent().and_then(ast::PathExpr::cast)?;
+            match path_expr.syntax().parent().and_then(ast::CallExpr::cast) {
+                Some(call) if call.expr()?.syntax() == path_expr.syntax() => call.syntax(),
+                _ => path_expr.syntax(),
+            }
+        }
+        _ => return None,
+    };
+    Some(expr)
+}
+
+fn param_name(db: &db::RootDatabase, function: hir::Function, index: usize) -> Option<String> {
+    let src = function.source(db);
+    let param = src.ast.param_list()?.params().nth(index)?;
+    match param.pat()?.kind() {
+        ast::PatKind::BindPat(it) => Some(it.name()?.text().to_string()),
+        _ => None,
+    }
+}
+
+/// Finds the node of the original file corresponding to a node of the file with
+/// the fake ident, which starts before the ident.
+fn find_node_at_start<N: AstNode>(syntax: &SyntaxNode, start: TextUnit) -> Option<&N> {
+    let token = find_token_at_offset(syntax, start).right_biased()?;
+    token.parent().ancestors().filter_map(N::cast).find(|it| it.syntax().range().start() == start)
 }
 
 fn find_node_with_range<N: AstNode>(syntax: &SyntaxNode, range: TextRange) -> Option<&N> {
@@ -1,7 +1,8 @@
 use std::fmt;
 
 use hir::Documentation;
-use ra_syntax::TextRange;
+use ra_db::FileRange;
+use ra_syntax::{SmolStr, TextRange};
 use ra_text_edit::{TextEdit, TextEditBuilder};
 
 /// `CompletionItem` describes a single completion variant in the editor pop-up.
@@ -42,6 +43,12 @@ pub struct CompletionItem {
     /// Additional info to show in the UI pop up.
     detail: Option<String>,
     documentation: Option<Documentation>,
+
+    /// How well the item fits at the cursor, used to sort the items.
+    relevance: CompletionRelevance,
+
+    /// What is left to compute when the item is resolved.
+    deferred: Option<Deferred>,
 }
 
 // We use custom debug for CompletionItem to make `insta`'s diffs more readable.
@@ -101,11 +108,72 @@ pub(crate) enum CompletionKind {
     Reference,
     /// "Secret sauce" completions.
     Magic,
+    /// Items which are not in scope yet, completed along with a `use`.
+    Import,
     Snippet,
     Postfix,
     BuiltinType,
 }
 
+/// What makes a completion item more relevant than others at the cursor.
+/// Clients sort the items by `score` rather than alphabetically.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CompletionRelevance {
+    /// The type of the item unifies with the type expected at the cursor: the
+    /// type of a parameter, of a `let` annotation, of a field or the return
+    /// type of the function.
+    pub type_match: bool,
+    /// The name of the item is the name of the expected parameter or field.
+    pub exact_name_match: bool,
+    /// The item is a local binding.
+    pub is_local: bool,
+    /// The item is marked as `#[deprecated]`.
+    pub is_deprecated: bool,
+}
+
+impl CompletionRelevance {
+    /// Greater is better. Deprecated items always come last.
+    pub fn score(self) -> u32 {
+        if self.is_deprecated {
+            return 0;
+        }
+        let mut score = 1;
+        if self.type_match {
+            score += 4;
+        }
+        if self.exact_name_match {
+            score += 2;
+        }
+        if self.is_local {
+            score += 1;
+        }
+        score
+    }
+}
+
+/// The expensive parts of a completion item, only computed for the item
+/// selected in the pop-up by `Analysis::resolve_completion`. It identifies the
+/// item by what it completes rather than by its place among the completions, so
+/// that resolving it does not need the other completions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Deferred {
+    /// Documentation of the definition at the given range.
+    Docs(FileRange),
+    /// Documentation and signature of the definition at the given range.
+    Signature(FileRange),
+    /// `use` of the given path, for items which are not in scope yet.
+    Import(Vec<SmolStr>),
+}
+
+/// The deferred parts of a completion item, computed when it is resolved.
+#[derive(Debug, Default)]
+pub struct ResolvedCompletion {
+    pub documentation: Option<Documentation>,
+    pub detail: Option<String>,
+    /// Edits to make besides completing the item itself, like adding a `use`.
+    pub additional_edits: Option<TextEdit>,
+}
+
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum InsertTextFormat {
     PlainText,
@@ -130,6 +198,8 @@ impl CompletionItem {
             lookup: None,
             kind: None,
             text_edit: None,
+            relevance: CompletionRelevance::default(),
+            deferred: None,
         }
     }
     /// What user sees in pop-up in the UI.
@@ -164,6 +234,35 @@ impl CompletionItem {
     pub fn kind(&self) -> Option<CompletionItemKind> {
         self.kind
     }
+
+    pub fn relevance(&self) -> CompletionRelevance {
+        self.relevance
+    }
+
+    /// What is left to compute when the item is selected, see
+    /// `Analysis::resolve_completion`.
+    pub fn deferred(&self) -> Option<&Deferred> {
+        self.deferred.as_ref()
+    }
+
+    #[cfg(test)]
+    fn apply_resolved(mut self, resolved: ResolvedCompletion) -> CompletionItem {
+        self.deferred = None;
+        if resolved.documentation.is_some() {
+            self.documentation = resolved.documentation;
+        }
+        if resolved.detail.is_some() {
+            self.detail = resolved.detail;
+        }
+        if let Some(edit) = resolved.additional_edits {
+            let mut builder = TextEditBuilder::default();
+            for atom in self.text_edit.as_atoms().iter().chain(edit.as_atoms()) {
+                builder.replace(atom.delete, atom.insert.clone());
+            }
+            self.text_edit = builder.finish();
+        }
+        self
+    }
 }
 
 /// A helper to make `CompletionItem`s.
@@ -179,6 +278,8 @@ pub(crate) struct Builder {
     lookup: Option<String>,
     kind: Option<CompletionItemKind>,
     text_edit: Option<TextEdit>,
+    relevance: CompletionRelevance,
+    deferred: Option<Deferred>,
 }
 
 impl Builder {
@@ -208,6 +309,8 @@ impl Builder {
             lookup: self.lookup,
             kind: self.kind,
             completion_kind: self.completion_kind,
+            relevance: self.relevance,
+            deferred: self.deferred,
         }
     }
     pub(crate) fn lookup_by(mut self, lookup: impl Into<String>) -> Builder {
@@ -250,6 +353,14 @@ impl Builder {
         self.documentation = docs.map(Into::into);
         self
     }
+    pub(crate) fn set_relevance(mut self, relevance: CompletionRelevance) -> Builder {
+        self.relevance = relevance;
+        self
+    }
+    pub(crate) fn defer(mut self, deferred: Deferred) -> Builder {
+        self.deferred = Some(deferred);
+        self
+    }
 }
 
 impl<'a> Into<CompletionItem> for Builder {
@@ -285,7 +396,7 @@ impl Into<Vec<CompletionItem>> for Completions {
 
 #[cfg(test)]
 pub(crate) fn do_completion(code: &str, kind: CompletionKind) -> Vec<CompletionItem> {
-    use crate::completion::completions;
+    use crate::completion::{completions, resolve_completion};
     use crate::mock_analysis::{analysis_and_position, single_file_with_position};
     let (analysis, position) = if code.contains("//-") {
         analysis_and_position(code)
@@ -294,8 +405,17 @@ pub(crate) fn do_completion(code: &str, kind: CompletionKind) -> Vec<CompletionI
     };
     let completions = completions(&analysis.db, position).unwrap();
     let completion_items: Vec<CompletionItem> = completions.into();
-    let mut kind_completions: Vec<CompletionItem> =
-        completion_items.into_iter().filter(|c| c.completion_kind == kind).collect();
+    let mut kind_completions: Vec<CompletionItem> = completion_items
+        .into_iter()
+        .filter(|c| c.completion_kind == kind)
+        .map(|c| match c.deferred() {
+            Some(deferred) => {
+                let resolved = resolve_completion(&analysis.db, position, deferred).unwrap();
+                c.apply_resolved(resolved)
+            }
+            None => c,
+        })
+        .collect();
     kind_completions.sort_by_key(|c| c.label.clone());
     kind_completions
 }
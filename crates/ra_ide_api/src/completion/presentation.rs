@@ -1,14 +1,26 @@
 //! This modules takes care of rendering various defenitions as completion items.
-use hir::{Docs, HasSource, HirDisplay, PerNs, Resolution};
+use hir::{db::HirDatabase, DocDef, Docs, HasSource, HirDisplay, HirFileId, PerNs, Resolution};
 use join_to_string::join;
-use ra_syntax::ast::NameOwner;
+use ra_db::{FileRange, SourceDatabase};
+use ra_syntax::{
+    algo::{
+        find_covering_element,
+        visit::{visitor, Visitor},
+    },
+    ast::{self, NameOwner},
+    AstNode, SyntaxElement, TextRange, TreeArc,
+};
 use test_utils::tested_by;
 
 use crate::completion::{
-    CompletionContext, CompletionItem, CompletionItemKind, CompletionKind, Completions,
+    completion_item::Builder, CompletionContext, CompletionItem, CompletionItemKind,
+    CompletionKind, CompletionRelevance, Completions, Deferred, ResolvedCompletion,
 };
 
-use crate::display::{const_label, function_label, type_label};
+use crate::{
+    db::RootDatabase,
+    display::{const_label, function_label, type_label},
+};
 
 impl Completions {
     pub(crate) fn add_field(
@@ -17,21 +29,23 @@ impl Completions {
         field: hir::StructField,
         substs: &hir::Substs,
     ) {
-        CompletionItem::new(
-            CompletionKind::Reference,
-            ctx.source_range(),
-            field.name(ctx.db).to_string(),
-        )
-        .kind(CompletionItemKind::Field)
-        .detail(field.ty(ctx.db).subst(substs).display(ctx.db).to_string())
-        .set_documentation(field.docs(ctx.db))
-        .add_to(self);
+        let name = field.name(ctx.db).to_string();
+        let ty = field.ty(ctx.db).subst(substs);
+        let relevance = relevance(ctx, &name, Some(&ty), Some(field.into()));
+        let builder = CompletionItem::new(CompletionKind::Reference, ctx.source_range(), name)
+            .kind(CompletionItemKind::Field)
+            .detail(ty.display(ctx.db).to_string())
+            .set_relevance(relevance);
+        defer_docs(ctx, builder, field.into(), false).add_to(self);
     }
 
     pub(crate) fn add_pos_field(&mut self, ctx: &CompletionContext, field: usize, ty: &hir::Ty) {
-        CompletionItem::new(CompletionKind::Reference, ctx.source_range(), field.to_string())
+        let name = field.to_string();
+        let relevance = relevance(ctx, &name, Some(ty), None);
+        CompletionItem::new(CompletionKind::Reference, ctx.source_range(), name)
             .kind(CompletionItemKind::Field)
             .detail(ty.display(ctx.db).to_string())
+            .set_relevance(relevance)
             .add_to(self);
     }
 
@@ -56,19 +70,23 @@ impl Completions {
             Some(it) => it,
         };
         let mut completion_kind = CompletionKind::Reference;
-        let (kind, docs) = match def {
-            Resolution::Def(Module(it)) => (CompletionItemKind::Module, it.docs(ctx.db)),
+        let (kind, doc_def) = match def {
+            Resolution::Def(Module(it)) => (CompletionItemKind::Module, Some(DocDef::from(*it))),
             Resolution::Def(Function(func)) => {
                 return self.add_function_with_name(ctx, Some(local_name), *func);
             }
-            Resolution::Def(Struct(it)) => (CompletionItemKind::Struct, it.docs(ctx.db)),
-            Resolution::Def(Union(it)) => (CompletionItemKind::Struct, it.docs(ctx.db)),
-            Resolution::Def(Enum(it)) => (CompletionItemKind::Enum, it.docs(ctx.db)),
-            Resolution::Def(EnumVariant(it)) => (CompletionItemKind::EnumVariant, it.docs(ctx.db)),
-            Resolution::Def(Const(it)) => (CompletionItemKind::Const, it.docs(ctx.db)),
-            Resolution::Def(Static(it)) => (CompletionItemKind::Static, it.docs(ctx.db)),
-            Resolution::Def(Trait(it)) => (CompletionItemKind::Trait, it.docs(ctx.db)),
-            Resolution::Def(TypeAlias(it)) => (CompletionItemKind::TypeAlias, it.docs(ctx.db)),
+            Resolution::Def(Struct(it)) => (CompletionItemKind::Struct, Some(DocDef::from(*it))),
+            Resolution::Def(Union(it)) => (CompletionItemKind::Struct, Some(DocDef::from(*it))),
+            Resolution::Def(Enum(it)) => (CompletionItemKind::Enum, Some(DocDef::from(*it))),
+            Resolution::Def(EnumVariant(it)) => {
+                (CompletionItemKind::EnumVariant, Some(DocDef::from(*it)))
+            }
+            Resolution::Def(Const(it)) => (CompletionItemKind::Const, Some(DocDef::from(*it))),
+            Resolution::Def(Static(it)) => (CompletionItemKind::Static, Some(DocDef::from(*it))),
+            Resolution::Def(Trait(it)) => (CompletionItemKind::Trait, Some(DocDef::from(*it))),
+            Resolution::Def(TypeAlias(it)) => {
+                (CompletionItemKind::TypeAlias, Some(DocDef::from(*it)))
+            }
             Resolution::Def(BuiltinType(..)) => {
                 completion_kind = CompletionKind::BuiltinType;
                 (CompletionItemKind::BuiltinType, None)
@@ -80,10 +98,27 @@ impl Completions {
                 None,
             ),
         };
-        CompletionItem::new(completion_kind, ctx.source_range(), local_name)
+        let ty = match def {
+            Resolution::Def(EnumVariant(it)) => Some(it.parent_enum(ctx.db).ty(ctx.db)),
+            Resolution::Def(Const(it)) => Some(it.ty(ctx.db)),
+            Resolution::Def(Static(it)) => Some(it.ty(ctx.db)),
+            Resolution::LocalBinding(pat) => ctx.analyzer.type_of_binding(*pat),
+            _ => None,
+        };
+        let relevance = CompletionRelevance {
+            is_local: match def {
+                Resolution::LocalBinding(..) => true,
+                _ => false,
+            },
+            ..relevance(ctx, &local_name, ty.as_ref(), doc_def)
+        };
+        let mut builder = CompletionItem::new(completion_kind, ctx.source_range(), local_name)
             .kind(kind)
-            .set_documentation(docs)
-            .add_to(self)
+            .set_relevance(relevance);
+        if let Some(doc_def) = doc_def {
+            builder = defer_docs(ctx, builder, doc_def, false);
+        }
+        builder.add_to(self)
     }
 
     pub(crate) fn add_function(&mut self, ctx: &CompletionContext, func: hir::Function) {
@@ -98,8 +133,8 @@ impl Completions {
     ) {
         let data = func.data(ctx.db);
         let name = name.unwrap_or_else(|| data.name().to_string());
-        let ast_node = func.source(ctx.db).ast;
-        let detail = function_label(&ast_node);
+        let sig = ctx.db.callable_item_signature(func.into());
+        let relevance = relevance(ctx, &name, Some(sig.ret()), Some(func.into()));
 
         let mut builder = CompletionItem::new(CompletionKind::Reference, ctx.source_range(), name)
             .kind(if data.has_self_param() {
@@ -107,8 +142,8 @@ impl Completions {
             } else {
                 CompletionItemKind::Function
             })
-            .set_documentation(func.docs(ctx.db))
-            .detail(detail);
+            .set_relevance(relevance);
+        builder = defer_docs(ctx, builder, func.into(), true);
         // If not an import, add parenthesis automatically.
         if ctx.use_item_syntax.is_none() && !ctx.is_call {
             tested_by!(inserts_parens_for_function_calls);
@@ -129,13 +164,17 @@ impl Completions {
             Some(name) => name,
             _ => return,
         };
-        let detail = const_label(&ast_node);
+        let relevance =
+            relevance(ctx, name.text(), Some(&constant.ty(ctx.db)), Some(constant.into()));
 
-        CompletionItem::new(CompletionKind::Reference, ctx.source_range(), name.text().to_string())
-            .kind(CompletionItemKind::Const)
-            .set_documentation(constant.docs(ctx.db))
-            .detail(detail)
-            .add_to(self);
+        let builder = CompletionItem::new(
+            CompletionKind::Reference,
+            ctx.source_range(),
+            name.text().to_string(),
+        )
+        .kind(CompletionItemKind::Const)
+        .set_relevance(relevance);
+        defer_docs(ctx, builder, constant.into(), true).add_to(self);
     }
 
     pub(crate) fn add_type_alias(&mut self, ctx: &CompletionContext, type_alias: hir::TypeAlias) {
@@ -144,13 +183,16 @@ impl Completions {
             Some(name) => name,
             _ => return,
         };
-        let detail = type_label(&type_def);
+        let relevance = relevance(ctx, name.text(), None, Some(type_alias.into()));
 
-        CompletionItem::new(CompletionKind::Reference, ctx.source_range(), name.text().to_string())
-            .kind(CompletionItemKind::TypeAlias)
-            .set_documentation(type_alias.docs(ctx.db))
-            .detail(detail)
-            .add_to(self);
+        let builder = CompletionItem::new(
+            CompletionKind::Reference,
+            ctx.source_range(),
+            name.text().to_string(),
+        )
+        .kind(CompletionItemKind::TypeAlias)
+        .set_relevance(relevance);
+        defer_docs(ctx, builder, type_alias.into(), true).add_to(self);
     }
 
     pub(crate) fn add_enum_variant(&mut self, ctx: &CompletionContext, variant: hir::EnumVariant) {
@@ -158,25 +200,190 @@ impl Completions {
             Some(it) => it,
             None => return,
         };
+        let name = name.to_string();
+        let ty = variant.parent_enum(ctx.db).ty(ctx.db);
+        let relevance = relevance(ctx, &name, Some(&ty), Some(variant.into()));
+
+        // Unlike the other signatures, this one needs the types of the fields
         let detail_types = variant.fields(ctx.db).into_iter().map(|field| field.ty(ctx.db));
         let detail = join(detail_types.map(|t| t.display(ctx.db).to_string()))
             .separator(", ")
             .surround_with("(", ")")
             .to_string();
-
-        CompletionItem::new(CompletionKind::Reference, ctx.source_range(), name.to_string())
+        let builder = CompletionItem::new(CompletionKind::Reference, ctx.source_range(), name)
             .kind(CompletionItemKind::EnumVariant)
-            .set_documentation(variant.docs(ctx.db))
             .detail(detail)
-            .add_to(self);
+            .set_relevance(relevance);
+        defer_docs(ctx, builder, variant.into(), false).add_to(self);
+    }
+}
+
+/// Computes the relevance of the item called `name`, whose values have type
+/// `ty`. `def` is the definition of the item, if any.
+fn relevance(
+    ctx: &CompletionContext,
+    name: &str,
+    ty: Option<&hir::Ty>,
+    def: Option<DocDef>,
+) -> CompletionRelevance {
+    let type_match = match (&ctx.expected_type, ty) {
+        (Some(expected), Some(ty)) => could_unify(expected, ty),
+        _ => false,
+    };
+    CompletionRelevance {
+        type_match,
+        exact_name_match: ctx.expected_name.as_ref().map_or(false, |it| it == name),
+        is_local: false,
+        is_deprecated: def.map_or(false, |it| it.is_deprecated(ctx.db)),
+    }
+}
+
+/// Checks whether values of type `ty` may be used where `expected` is expected.
+/// This is an approximation: unknown types and unnormalized projections nested
+/// in the types match anything. Type parameters left in the types are the ones
+/// of the current function, which only match themselves.
+fn could_unify(expected: &hir::Ty, ty: &hir::Ty) -> bool {
+    fn types_match(expected: &hir::Ty, ty: &hir::Ty) -> bool {
+        match (expected, ty) {
+            (hir::Ty::Apply(expected), hir::Ty::Apply(ty)) => {
+                expected.ctor == ty.ctor
+                    && expected.parameters.len() == ty.parameters.len()
+                    && expected
+                        .parameters
+                        .iter()
+                        .zip(ty.parameters.iter())
+                        .all(|(expected, ty)| types_match(expected, ty))
+            }
+            (hir::Ty::Unknown, _) | (_, hir::Ty::Unknown) => true,
+            (hir::Ty::Infer(_), _) | (_, hir::Ty::Infer(_)) => true,
+            (hir::Ty::Projection(_), _) | (_, hir::Ty::Projection(_)) => true,
+            (expected, ty) => expected == ty,
+        }
+    }
+
+    match (expected, ty) {
+        (hir::Ty::Unknown, _) | (_, hir::Ty::Unknown) => false,
+        _ => types_match(expected, ty),
+    }
+}
+
+/// Defers computing the documentation of `def`, and its signature if
+/// `signature` is set, to when the item is resolved.
+fn defer_docs(ctx: &CompletionContext, builder: Builder, def: DocDef, signature: bool) -> Builder {
+    match definition_range(ctx.db, def) {
+        Some(frange) if signature => builder.defer(Deferred::Signature(frange)),
+        Some(frange) => builder.defer(Deferred::Docs(frange)),
+        // Definitions expanded from macros can't be found by their range later
+        None => {
+            let builder = builder.set_documentation(def.docs(ctx.db));
+            if signature {
+                builder.set_detail(def_detail(ctx.db, def))
+            } else {
+                builder
+            }
+        }
+    }
+}
+
+/// The range of the definition of `def`, if it is written in a file.
+fn definition_range(db: &impl HirDatabase, def: DocDef) -> Option<FileRange> {
+    fn range<N: AstNode>(src: hir::Source<TreeArc<N>>) -> (HirFileId, TextRange) {
+        (src.file_id, src.ast.syntax().range())
+    }
+
+    let (file_id, range) = match def {
+        DocDef::Module(it) => range(it.declaration_source(db)?),
+        DocDef::StructField(it) => {
+            let src = it.source(db);
+            match src.ast {
+                hir::FieldSource::Named(named) => (src.file_id, named.syntax().range()),
+                hir::FieldSource::Pos(pos) => (src.file_id, pos.syntax().range()),
+            }
+        }
+        DocDef::Struct(it) => range(it.source(db)),
+        DocDef::Enum(it) => range(it.source(db)),
+        DocDef::EnumVariant(it) => range(it.source(db)),
+        DocDef::Static(it) => range(it.source(db)),
+        DocDef::Const(it) => range(it.source(db)),
+        DocDef::Function(it) => range(it.source(db)),
+        DocDef::Union(it) => range(it.source(db)),
+        DocDef::Trait(it) => range(it.source(db)),
+        DocDef::TypeAlias(it) => range(it.source(db)),
+        DocDef::MacroDef(it) => range(it.source(db)),
+    };
+    let original_file = file_id.original_file(db);
+    if file_id != original_file.into() {
+        return None;
+    }
+    Some(FileRange { file_id: original_file, range })
+}
+
+/// Computes the documentation, and the signature if `signature` is set, of the
+/// definition at `frange`.
+pub(super) fn resolve_definition(
+    db: &RootDatabase,
+    frange: FileRange,
+    signature: bool,
+) -> Option<ResolvedCompletion> {
+    fn docs<N: ast::DocCommentsOwner>(node: &N) -> Option<hir::Documentation> {
+        hir::docs_from_ast(node)
+    }
+
+    let file = db.parse(frange.file_id).tree;
+    // The file may have changed since the item was computed
+    if !frange.range.is_subrange(&file.syntax().range()) {
+        return None;
+    }
+    let node = match find_covering_element(file.syntax(), frange.range) {
+        SyntaxElement::Node(it) => it,
+        SyntaxElement::Token(it) => it.parent(),
+    };
+    let node = node.ancestors().find(|it| it.range() == frange.range)?;
+
+    let documentation = visitor()
+        .visit(docs::<ast::Module>)
+        .visit(docs::<ast::NamedFieldDef>)
+        .visit(|_: &ast::PosFieldDef| None)
+        .visit(docs::<ast::StructDef>)
+        .visit(docs::<ast::EnumDef>)
+        .visit(docs::<ast::EnumVariant>)
+        .visit(docs::<ast::StaticDef>)
+        .visit(docs::<ast::ConstDef>)
+        .visit(docs::<ast::FnDef>)
+        .visit(docs::<ast::TraitDef>)
+        .visit(docs::<ast::TypeAliasDef>)
+        .visit(docs::<ast::MacroCall>)
+        .accept(node)?;
+    let detail = if signature { detail_from_node(node) } else { None };
+    Some(ResolvedCompletion { documentation, detail, additional_edits: None })
+}
+
+/// The signature shown next to a resolved completion item.
+fn def_detail(db: &impl HirDatabase, def: DocDef) -> Option<String> {
+    match def {
+        DocDef::Function(it) => detail_from_node(it.source(db).ast.syntax()),
+        DocDef::Const(it) => detail_from_node(it.source(db).ast.syntax()),
+        DocDef::TypeAlias(it) => detail_from_node(it.source(db).ast.syntax()),
+        _ => None,
     }
 }
 
+fn detail_from_node(node: &ra_syntax::SyntaxNode) -> Option<String> {
+    visitor()
+        .visit(|it: &ast::FnDef| function_label(it))
+        .visit(|it: &ast::ConstDef| const_label(it))
+        .visit(|it: &ast::TypeAliasDef| type_label(it))
+        .accept(node)
+}
+
 #[cfg(test)]
 mod tests {
+    use ra_syntax::TextRange;
     use test_utils::covers;
 
     use crate::completion::{check_completion, CompletionKind};
+    use crate::mock_analysis::single_file_with_position;
+    use crate::{Deferred, FileRange};
 
     fn check_reference_completion(code: &str, expected_completions: &str) {
         check_completion(code, expected_completions, CompletionKind::Reference);
@@ -250,4 +457,136 @@ mod tests {
         )
     }
 
+    #[test]
+    fn defers_documentation_and_signature() {
+        let (analysis, position) = single_file_with_position(
+            r"
+            /// Does nothing.
+            fn no_args() {}
+            fn main() { no_<|> }
+            ",
+        );
+        let items = analysis.completions(position).unwrap().unwrap();
+        let item = items.iter().find(|it| it.label() == "no_args").unwrap();
+        assert_eq!(item.detail(), None);
+        assert!(item.documentation().is_none());
+
+        let deferred = item.deferred().unwrap();
+        let resolved = analysis.resolve_completion(position, deferred).unwrap().unwrap();
+        assert_eq!(resolved.detail.as_ref().map(String::as_str), Some("fn no_args()"));
+        assert_eq!(resolved.documentation.unwrap().as_str(), "Does nothing.");
+        assert!(resolved.additional_edits.is_none());
+    }
+
+    #[test]
+    fn does_not_resolve_ranges_outside_of_the_file() {
+        let (analysis, position) = single_file_with_position("fn main() { <|> }");
+        let range = TextRange::from_to(10.into(), 100.into());
+        let deferred = Deferred::Docs(FileRange { file_id: position.file_id, range });
+        assert!(analysis.resolve_completion(position, &deferred).unwrap().is_none());
+    }
+
+    /// Checks the labels of the completions with the greatest score.
+    fn check_most_relevant(code: &str, expected: &[&str]) {
+        let (analysis, position) = single_file_with_position(code);
+        let items = analysis.completions(position).unwrap().unwrap();
+        let max_score = items.iter().map(|it| it.relevance().score()).max().unwrap();
+        let mut actual = items
+            .iter()
+            .filter(|it| it.relevance().score() == max_score)
+            .map(|it| it.label())
+            .collect::<Vec<_>>();
+        actual.sort();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn prefers_locals_of_the_argument_type() {
+        check_most_relevant(
+            r"
+            struct A;
+            struct B;
+            fn take_a(a: A) {}
+            fn main(x: A, y: B) { take_a(<|>) }
+            ",
+            &["x"],
+        );
+    }
+
+    #[test]
+    fn prefers_the_argument_type_of_generic_methods() {
+        check_most_relevant(
+            r"
+            struct Wrapper<T>(T);
+            impl<T> Wrapper<T> { fn set(&mut self, value: T) {} }
+            fn main(mut w: Wrapper<u32>, flag: bool, n: u32) { w.set(<|>) }
+            ",
+            &["n"],
+        );
+    }
+
+    #[test]
+    fn does_not_match_other_type_parameters() {
+        check_most_relevant(
+            r"
+            fn same<T>(a: T, b: T) {}
+            fn main<T, U>(t: T, u: U) { same(t, <|>) }
+            ",
+            &["t"],
+        );
+    }
+
+    #[test]
+    fn prefers_items_named_as_the_parameter() {
+        check_most_relevant(
+            r"
+            fn add(count: u32, total: u32) {}
+            fn main(count: u32, other: u32) { add(<|>) }
+            ",
+            &["count"],
+        );
+    }
+
+    #[test]
+    fn prefers_the_type_of_the_let_annotation_and_puts_deprecated_items_last() {
+        let (analysis, position) = single_file_with_position(
+            r"
+            #[deprecated]
+            fn old() -> u32 { 0 }
+            fn new() -> u32 { 0 }
+            fn main() { let n: u32 = <|> }
+            ",
+        );
+        let items = analysis.completions(position).unwrap().unwrap();
+        let score =
+            |label: &str| items.iter().find(|it| it.label() == label).unwrap().relevance().score();
+        assert!(score("new") > score("main"));
+        assert!(score("main") > score("old"));
+    }
+
+    #[test]
+    fn prefers_the_type_of_the_field() {
+        check_most_relevant(
+            r"
+            struct Meters(u32);
+            struct Point { x: Meters }
+            fn meters() -> Meters { Meters(0) }
+            fn other() -> u32 { 0 }
+            fn main() -> Point { Point { x: <|> } }
+            ",
+            &["meters"],
+        );
+    }
+
+    #[test]
+    fn prefers_the_return_type() {
+        check_most_relevant(
+            r"
+            struct Meters(u32);
+            fn other() -> u32 { 0 }
+            fn meters() -> Meters { <|> }
+            ",
+            &["meters"],
+        );
+    }
 }
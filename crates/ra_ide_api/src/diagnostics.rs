@@ -5,12 +5,17 @@ use hir::{
     source_binder,
 };
 use itertools::Itertools;
-use ra_assists::ast_editor::{AstBuilder, AstEditor};
+use ra_assists::{
+    ast_editor::{AstBuilder, AstEditor},
+    fill_match_arms_text_edit,
+};
 use ra_db::SourceDatabase;
 use ra_prof::profile;
 use ra_syntax::{
-    ast::{self, AstNode, NamedField},
-    Location, SyntaxNode, TextRange, T,
+    ast::{self, AstNode, NameOwner, NamedField},
+    Direction, Location,
+    SyntaxKind::{self, *},
+    SyntaxNode, TextRange, T,
 };
 use ra_text_edit::{TextEdit, TextEditBuilder};
 
@@ -49,7 +54,11 @@ pub(crate) fn diagnostics(db: &RootDatabase, file_id: FileId) -> Vec<Diagnostic>
     })
     .on::<hir::diagnostics::UnresolvedModule, _>(|d| {
         let source_root = db.file_source_root(d.file().original_file(db));
-        let create_file = FileSystemEdit::CreateFile { source_root, path: d.candidate.clone() };
+        let create_file = FileSystemEdit::CreateFile {
+            source_root,
+            path: d.candidate.clone(),
+            text: String::new(),
+        };
         let fix = SourceChange::file_system_edit("create module", create_file);
         res.borrow_mut().push(Diagnostic {
             range: d.highlight_range(),
@@ -75,6 +84,53 @@ pub(crate) fn diagnostics(db: &RootDatabase, file_id: FileId) -> Vec<Diagnostic>
             severity: Severity::Error,
             fix: Some(fix),
         })
+    })
+    .on::<hir::diagnostics::MissingMatchArms, _>(|d| {
+        let arm_list = d.ast(db);
+        let edit = fill_match_arms_text_edit(&arm_list, &d.missed_patterns);
+        let fix = SourceChange::source_file_edit_from("fill match arms", file_id, edit);
+        res.borrow_mut().push(Diagnostic {
+            range: d.highlight_range(),
+            message: d.message(),
+            severity: Severity::Error,
+            fix: Some(fix),
+        })
+    })
+    .on::<hir::diagnostics::UnusedImport, _>(|d| {
+        let use_tree = d.ast(db);
+        let fix = range_to_remove_use_tree(&use_tree).map(|range| {
+            let mut builder = TextEditBuilder::default();
+            builder.delete(range);
+            SourceChange::source_file_edit_from("Remove unused import", file_id, builder.finish())
+        });
+        res.borrow_mut().push(Diagnostic {
+            range: d.highlight_range(),
+            message: d.message(),
+            severity: Severity::WeakWarning,
+            fix,
+        })
+    })
+    .on::<hir::diagnostics::UnusedVariable, _>(|d| {
+        let bind_pat = d.ast(db);
+        let fix = bind_pat.name().map(|name| {
+            let mut builder = TextEditBuilder::default();
+            // `S { x }` binds the field `x`, which must stay named
+            if bind_pat.syntax().parent().map(|it| it.kind()) == Some(FIELD_PAT_LIST) {
+                builder.insert(bind_pat.syntax().range().start(), format!("{}: ", name.text()));
+            }
+            builder.insert(name.syntax().range().start(), "_".to_string());
+            SourceChange::source_file_edit_from(
+                "Prefix variable with an underscore",
+                file_id,
+                builder.finish(),
+            )
+        });
+        res.borrow_mut().push(Diagnostic {
+            range: d.highlight_range(),
+            message: d.message(),
+            severity: Severity::WeakWarning,
+            fix,
+        })
     });
     if let Some(m) = source_binder::module_from_file_id(db, file_id) {
         m.diagnostics(db, &mut sink);
@@ -89,6 +145,41 @@ fn location_to_range(location: Location) -> TextRange {
     }
 }
 
+/// The range of the use tree together with its separator, or of the whole
+/// `use` item if nothing else remains of it.
+fn range_to_remove_use_tree(use_tree: &ast::UseTree) -> Option<TextRange> {
+    let node = use_tree.syntax();
+    let parent = node.parent()?;
+    if let Some(use_item) = ast::UseItem::cast(parent) {
+        let node = use_item.syntax();
+        let end = match node.next_sibling_or_token() {
+            Some(ws) if ws.kind() == WHITESPACE => ws.range().end(),
+            _ => node.range().end(),
+        };
+        return Some(TextRange::from_to(node.range().start(), end));
+    }
+    let use_tree_list = ast::UseTreeList::cast(parent)?;
+    if use_tree_list.use_trees().count() == 1 {
+        return range_to_remove_use_tree(ast::UseTree::cast(use_tree_list.syntax().parent()?)?);
+    }
+    let is_separator = |kind: SyntaxKind| kind == WHITESPACE || kind == T![,];
+    let after = node
+        .siblings_with_tokens(Direction::Next)
+        .skip(1)
+        .take_while(|it| is_separator(it.kind()))
+        .collect::<Vec<_>>();
+    if after.iter().any(|it| it.kind() == T![,]) {
+        return Some(TextRange::from_to(node.range().start(), after.last()?.range().end()));
+    }
+    let before = node
+        .siblings_with_tokens(Direction::Prev)
+        .skip(1)
+        .take_while(|it| is_separator(it.kind()))
+        .last();
+    let start = before.map_or(node.range().start(), |it| it.range().start());
+    Some(TextRange::from_to(start, node.range().end()))
+}
+
 fn check_unnecessary_braces_in_use_statement(
     acc: &mut Vec<Diagnostic>,
     file_id: FileId,
@@ -203,9 +294,17 @@ mod tests {
         assert_eq_text!(after, &actual);
     }
 
+    /// Unused code is reported alongside most fixtures, so it is only the
+    /// diagnostic under test when nothing else is reported.
+    fn is_unused_code(diagnostic: &Diagnostic) -> bool {
+        diagnostic.message.starts_with("unused ")
+    }
+
     fn check_apply_diagnostic_fix(before: &str, after: &str) {
         let (analysis, file_id) = single_file(before);
-        let diagnostic = analysis.diagnostics(file_id).unwrap().pop().unwrap();
+        let (mut unused, mut others): (Vec<_>, Vec<_>) =
+            analysis.diagnostics(file_id).unwrap().into_iter().partition(is_unused_code);
+        let diagnostic = others.pop().or_else(|| unused.pop()).unwrap();
         let mut fix = diagnostic.fix.unwrap();
         let edit = fix.source_file_edits.pop().unwrap().edit;
         let actual = edit.apply(&before);
@@ -215,7 +314,7 @@ mod tests {
     fn check_no_diagnostic(content: &str) {
         let (analysis, file_id) = single_file(content);
         let diagnostics = analysis.diagnostics(file_id).unwrap();
-        assert_eq!(diagnostics.len(), 0);
+        assert_eq!(diagnostics.iter().filter(|it| !is_unused_code(it)).count(), 0);
     }
 
     #[test]
@@ -302,6 +401,58 @@ mod tests {
         check_no_diagnostic(content);
     }
 
+    #[test]
+    fn test_fill_missing_match_arms() {
+        let before = r"
+            enum Option<T> { Some(T), None }
+            use Option::*;
+
+            fn test_fn(x: Option<(bool, u32)>) {
+                match x {
+                    Some((true, _)) => (),
+                    // no value
+                    None if true => {}
+                }
+            }
+        ";
+        let after = r"
+            enum Option<T> { Some(T), None }
+            use Option::*;
+
+            fn test_fn(x: Option<(bool, u32)>) {
+                match x {
+                    Some((true, _)) => (),
+                    // no value
+                    None if true => {}
+                    Some((false, _)) => (),
+                    None => (),
+                }
+            }
+        ";
+        check_apply_diagnostic_fix(before, after);
+    }
+
+    #[test]
+    fn test_missing_match_arms_no_diagnostic() {
+        let content = r"
+            enum E { A { x: bool }, B }
+
+            fn test_fn(e: &E, n: u32) {
+                match e {
+                    E::A { x: true } | E::B => (),
+                    &E::A { .. } => (),
+                }
+                match (n, e) {
+                    (0, _) => (),
+                    (_, _) => (),
+                }
+                if let E::B = e {}
+            }
+        ";
+
+        check_no_diagnostic(content);
+    }
+
     #[test]
     fn test_unresolved_module_diagnostic() {
         let (analysis, file_id) = single_file("mod foo;");
@@ -321,6 +472,7 @@ mod tests {
        ⋮                            0,
        ⋮                        ),
        ⋮                        path: "foo.rs",
+       ⋮                        text: "",
        ⋮                    },
        ⋮                ],
        ⋮                cursor_position: None,
@@ -332,6 +484,38 @@ mod tests {
         "###);
     }
 
+    #[test]
+    fn test_remove_unused_import() {
+        let before = r"
+            mod foo { pub struct Bar; pub struct Baz; }
+            use foo::{Baz, Bar};
+            fn f(_: Bar) {}
+        ";
+        let after = r"
+            mod foo { pub struct Bar; pub struct Baz; }
+            use foo::{Bar};
+            fn f(_: Bar) {}
+        ";
+        check_apply_diagnostic_fix(before, after);
+    }
+
+    #[test]
+    fn test_prefix_unused_variable() {
+        let before = r"
+            struct S { x: i32 }
+            fn f(s: S) {
+                let S { x } = s;
+            }
+        ";
+        let after = r"
+            struct S { x: i32 }
+            fn f(s: S) {
+                let S { x: _x } = s;
+            }
+        ";
+        check_apply_diagnostic_fix(before, after);
+    }
+
     #[test]
     fn test_check_unnecessary_braces_in_use_statement() {
         check_not_applicable(
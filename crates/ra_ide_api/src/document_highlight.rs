@@ -0,0 +1,296 @@
+use ra_db::SourceDatabase;
+use ra_syntax::{
+    algo::find_token_at_offset,
+    ast::{self, AstNode},
+    SmolStr, SyntaxKind,
+    SyntaxKind::*,
+    SyntaxNode, SyntaxToken, TextRange, T,
+};
+
+use crate::{
+    db::RootDatabase,
+    references::{self, SearchScope},
+    FilePosition, FileRange, Reference,
+};
+
+/// Finds the ranges of the current file related to the cursor: the exit points
+/// of a function when on `fn`, `return` or `?`, the break points of a loop when
+/// on `loop`, `while`, `for` or `break`, and the declaration and references of
+/// a name otherwise.
+pub(crate) fn document_highlight(
+    db: &RootDatabase,
+    position: FilePosition,
+) -> Option<Vec<Reference>> {
+    let file = db.parse(position.file_id).tree;
+    let related =
+        find_token_at_offset(file.syntax(), position.offset).find_map(|token| match token.kind() {
+            T![fn] | T![return] | T![?] => exit_points(token),
+            T![loop] | T![while] | T![for] | T![break] => break_points(token),
+            _ => None,
+        });
+    if let Some(ranges) = related {
+        let highlights = ranges
+            .into_iter()
+            .map(|range| Reference {
+                file_range: FileRange { file_id: position.file_id, range },
+                access: None,
+            })
+            .collect();
+        return Some(highlights);
+    }
+
+    let refs = references::find_all_refs(db, position, Some(SearchScope::File(position.file_id)))?;
+    let declaration = refs.declaration();
+    let mut highlights = Vec::new();
+    if declaration.file_id() == position.file_id {
+        highlights.push(Reference {
+            file_range: FileRange { file_id: position.file_id, range: declaration.range() },
+            access: refs.declaration_access(),
+        });
+    }
+    highlights.extend(
+        refs.references().iter().filter(|it| it.file_range.file_id == position.file_id).cloned(),
+    );
+    Some(highlights)
+}
+
+/// The `fn` keyword, the `return`s, the `?`s and the tail expression of the
+/// function or closure containing `token`.
+fn exit_points(token: SyntaxToken) -> Option<Vec<TextRange>> {
+    let expected_parent = match token.kind() {
+        T![fn] => FN_DEF,
+        T![return] => RETURN_EXPR,
+        _ => TRY_EXPR,
+    };
+    if token.parent().kind() != expected_parent {
+        return None;
+    }
+    let owner =
+        token.parent().ancestors().find(|it| it.kind() == FN_DEF || it.kind() == LAMBDA_EXPR)?;
+
+    let mut ranges = Vec::new();
+    let body = if let Some(fn_def) = ast::FnDef::cast(owner) {
+        ranges.extend(find_keyword(owner, |kind| kind == T![fn]));
+        fn_def.body()?.syntax()
+    } else {
+        ast::LambdaExpr::cast(owner)?.body()?.syntax()
+    };
+    collect_exit_points(body, &mut ranges);
+    if let Some(tail) = tail_expr(body) {
+        if tail.kind() != RETURN_EXPR {
+            ranges.push(tail.range());
+        }
+    }
+    ranges.sort_by_key(|it| it.start());
+    Some(ranges)
+}
+
+fn collect_exit_points(node: &SyntaxNode, ranges: &mut Vec<TextRange>) {
+    for child in node.children() {
+        match child.kind() {
+            // Nested functions and closures have exit points of their own
+            FN_DEF | LAMBDA_EXPR => continue,
+            RETURN_EXPR => ranges.extend(find_keyword(child, |kind| kind == T![return])),
+            TRY_EXPR => ranges.extend(find_keyword(child, |kind| kind == T![?])),
+            _ => (),
+        }
+        collect_exit_points(child, ranges);
+    }
+}
+
+fn tail_expr(body: &SyntaxNode) -> Option<&SyntaxNode> {
+    let block =
+        ast::Block::cast(body).or_else(|| ast::BlockExpr::cast(body).and_then(|it| it.block()));
+    match block {
+        Some(block) => block.expr().map(AstNode::syntax),
+        None => Some(body),
+    }
+}
+
+/// The keyword of the loop related to `token`, and the `break`s which exit
+/// from it.
+fn break_points(token: SyntaxToken) -> Option<Vec<TextRange>> {
+    let parent = token.parent();
+    let loop_ = match parent.kind() {
+        BREAK_EXPR => break_target(parent)?,
+        LOOP_EXPR | WHILE_EXPR | FOR_EXPR if token.kind() != T![break] => parent,
+        _ => return None,
+    };
+    let label = loop_label(loop_);
+
+    let mut ranges = Vec::new();
+    ranges.extend(find_keyword(loop_, is_loop_keyword));
+    collect_break_points(loop_, label.as_ref(), true, &mut ranges);
+    ranges.sort_by_key(|it| it.start());
+    Some(ranges)
+}
+
+/// Collects the `break`s targeting a loop labeled `label`. Unlabeled `break`s
+/// only count when they are not inside of a nested loop.
+fn collect_break_points(
+    node: &SyntaxNode,
+    label: Option<&SmolStr>,
+    is_innermost: bool,
+    ranges: &mut Vec<TextRange>,
+) {
+    for child in node.children() {
+        let is_innermost = match child.kind() {
+            FN_DEF | LAMBDA_EXPR => continue,
+            LOOP_EXPR | WHILE_EXPR | FOR_EXPR => false,
+            BREAK_EXPR => {
+                let targets_loop = match break_label(child) {
+                    Some(break_label) => label == Some(&break_label),
+                    None => is_innermost,
+                };
+                if targets_loop {
+                    ranges.extend(find_keyword(child, |kind| kind == T![break]));
+                }
+                is_innermost
+            }
+            _ => is_innermost,
+        };
+        collect_break_points(child, label, is_innermost, ranges);
+    }
+}
+
+/// Finds the loop a `break` exits from.
+fn break_target(break_expr: &SyntaxNode) -> Option<&SyntaxNode> {
+    let label = break_label(break_expr);
+    for node in break_expr.ancestors().skip(1) {
+        match node.kind() {
+            FN_DEF | LAMBDA_EXPR => return None,
+            LOOP_EXPR | WHILE_EXPR | FOR_EXPR => {
+                if label.is_none() || loop_label(node) == label {
+                    return Some(node);
+                }
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+fn loop_label(loop_: &SyntaxNode) -> Option<SmolStr> {
+    let label = loop_.children().find(|it| it.kind() == LABEL)?;
+    lifetime_text(label)
+}
+
+fn break_label(break_expr: &SyntaxNode) -> Option<SmolStr> {
+    lifetime_text(break_expr)
+}
+
+fn lifetime_text(node: &SyntaxNode) -> Option<SmolStr> {
+    let lifetime = node.children_with_tokens().find(|it| it.kind() == LIFETIME)?;
+    Some(lifetime.as_token()?.text().clone())
+}
+
+fn is_loop_keyword(kind: SyntaxKind) -> bool {
+    kind == T![loop] || kind == T![while] || kind == T![for]
+}
+
+fn find_keyword(node: &SyntaxNode, is_keyword: impl Fn(SyntaxKind) -> bool) -> Option<TextRange> {
+    node.children_with_tokens().find(|it| is_keyword(it.kind())).map(|it| it.range())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{mock_analysis::single_file_with_position, ReferenceAccess};
+
+    fn check_highlights(code: &str, expected: &[(&str, Option<ReferenceAccess>)]) {
+        let (analysis, position) = single_file_with_position(code);
+        let text = analysis.file_text(position.file_id);
+        let highlights = analysis.document_highlight(position).unwrap().unwrap();
+        let actual =
+            highlights.iter().map(|it| (&text[it.file_range.range], it.access)).collect::<Vec<_>>();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_document_highlight_local_access() {
+        check_highlights(
+            r#"
+            fn main() {
+                let mut x<|> = 1;
+                let y = &mut x;
+                x += 1;
+                let z = x;
+            }
+            "#,
+            &[
+                ("x", Some(ReferenceAccess::Write)),
+                ("x", Some(ReferenceAccess::Write)),
+                ("x", Some(ReferenceAccess::Write)),
+                ("x", Some(ReferenceAccess::Read)),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_document_highlight_uninitialized_local() {
+        check_highlights(
+            r#"
+            struct S { field: u32 }
+            fn main() {
+                let s<|>: S;
+                s.field = 1;
+                foo(s.field);
+            }
+            "#,
+            &[("s", None), ("s", Some(ReferenceAccess::Write)), ("s", Some(ReferenceAccess::Read))],
+        );
+    }
+
+    #[test]
+    fn test_document_highlight_exit_points() {
+        check_highlights(
+            r#"
+            fn foo(x: Option<u32>) -> Option<u32> {
+                if x.is_none() {
+                    ret<|>urn None;
+                }
+                let f = || { return 0; };
+                let y = x?;
+                Some(y)
+            }
+            "#,
+            &[("fn", None), ("return", None), ("?", None), ("Some(y)", None)],
+        );
+    }
+
+    #[test]
+    fn test_document_highlight_break_points() {
+        check_highlights(
+            r#"
+            fn main() {
+                'outer: lo<|>op {
+                    loop {
+                        break;
+                        break 'outer;
+                    }
+                    if true {
+                        break;
+                    }
+                }
+            }
+            "#,
+            &[("loop", None), ("break", None), ("break", None)],
+        );
+    }
+
+    #[test]
+    fn test_document_highlight_on_break() {
+        check_highlights(
+            r#"
+            fn main() {
+                while true {
+                    for x in y {
+                        break;
+                    }
+                    br<|>eak;
+                }
+            }
+            "#,
+            &[("while", None), ("break", None)],
+        );
+    }
+}
@@ -280,6 +280,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn goto_definition_works_for_trait_items_of_type_params() {
+        check_goto(
+            "
+            //- /lib.rs
+            trait Default {
+                fn default() -> Self;
+            }
+
+            fn bar<T: Default>() {
+                T::default<|>();
+            }
+            ",
+            "default FN_DEF FileId(1) [20; 41) [23; 30)",
+        );
+    }
+
     #[test]
     fn goto_definition_works_for_fields() {
         covers!(goto_definition_works_for_fields);
@@ -117,7 +117,7 @@ pub(crate) fn hover(db: &RootDatabase, position: FilePosition) -> Option<RangeIn
             }
             Some(AssocItem(it)) => res.extend(match it {
                 hir::ImplItem::Method(it) => from_def_source(db, it),
-                hir::ImplItem::Const(it) => from_def_source(db, it),
+                hir::ImplItem::Const(it) => from_const(db, it),
                 hir::ImplItem::TypeAlias(it) => from_def_source(db, it),
             }),
             Some(Def(it)) => {
@@ -132,7 +132,7 @@ pub(crate) fn hover(db: &RootDatabase, position: FilePosition) -> Option<RangeIn
                     hir::ModuleDef::Union(it) => res.extend(from_def_source(db, it)),
                     hir::ModuleDef::Enum(it) => res.extend(from_def_source(db, it)),
                     hir::ModuleDef::EnumVariant(it) => res.extend(from_def_source(db, it)),
-                    hir::ModuleDef::Const(it) => res.extend(from_def_source(db, it)),
+                    hir::ModuleDef::Const(it) => res.extend(from_const(db, it)),
                     hir::ModuleDef::Static(it) => res.extend(from_def_source(db, it)),
                     hir::ModuleDef::Trait(it) => res.extend(from_def_source(db, it)),
                     hir::ModuleDef::TypeAlias(it) => res.extend(from_def_source(db, it)),
@@ -190,7 +190,14 @@ pub(crate) fn hover(db: &RootDatabase, position: FilePosition) -> Option<RangeIn
                     hover_text(node.doc_comment_text(), node.short_label())
                 })
                 .visit(|node: &ast::ConstDef| {
-                    hover_text(node.doc_comment_text(), node.short_label())
+                    let konst = hir::source_binder::module_from_child_node(
+                        db,
+                        position.file_id,
+                        node.syntax(),
+                    )
+                    .map(|module| hir::source_binder::const_from_module(db, module, node));
+                    let label = node.short_label().map(|label| with_const_value(db, label, konst));
+                    hover_text(node.doc_comment_text(), label)
                 })
                 .visit(|node: &ast::StaticDef| {
                     hover_text(node.doc_comment_text(), node.short_label())
@@ -238,6 +245,20 @@ pub(crate) fn hover(db: &RootDatabase, position: FilePosition) -> Option<RangeIn
         let src = def.source(db);
         hover_text(src.ast.doc_comment_text(), src.ast.short_label())
     }
+
+    fn from_const(db: &RootDatabase, konst: hir::Const) -> Option<String> {
+        let src = konst.source(db);
+        let label = src.ast.short_label().map(|label| with_const_value(db, label, Some(konst)));
+        hover_text(src.ast.doc_comment_text(), label)
+    }
+}
+
+/// Appends the value of the constant to its label, e.g. `const FOO: u32 = 42`.
+fn with_const_value(db: &RootDatabase, mut label: String, konst: Option<hir::Const>) -> String {
+    if let Some(value) = konst.and_then(|it| it.value(db)) {
+        label.push_str(&format!(" = {}", value));
+    }
+    label
 }
 
 pub(crate) fn type_of(db: &RootDatabase, frange: FileRange) -> Option<String> {
@@ -421,7 +442,20 @@ mod tests {
                 const foo<|>: u32 = 0;
             }
         "#,
-            &["const foo: u32"],
+            &["const foo: u32 = 0"],
+        );
+
+        check_hover_result(
+            r#"
+            //- /main.rs
+            const BAR: usize = 4;
+            const FOO: usize = BAR * 2 + 1;
+
+            fn main() {
+                let x = FO<|>O;
+            }
+        "#,
+            &["const FOO: usize = 9"],
         );
 
         check_hover_result(
@@ -464,6 +498,21 @@ mod tests {
         assert_eq!(trim_markup_opt(hover.info.first()), Some("Option<i32>"));
     }
 
+    #[test]
+    fn hover_shows_erased_lifetimes() {
+        let (analysis, position) = single_file_with_position(
+            "
+            struct Ref<'a, T> { t: &'a T }
+
+            fn main() {
+                let r<|> = Ref { t: &1u32 };
+            }
+            ",
+        );
+        let hover = analysis.hover(position).unwrap().unwrap();
+        assert_eq!(trim_markup_opt(hover.info.first()), Some("Ref<'_, u32>"));
+    }
+
     #[test]
     fn hover_enum_variant() {
         check_hover_result(
@@ -0,0 +1,294 @@
+use hir::{HirDisplay, SourceAnalyzer, Ty};
+use ra_syntax::{
+    algo::visit::{visitor, Visitor},
+    ast::{self, ArgListOwner, AstNode, TypeAscriptionOwner},
+    SmolStr, SourceFile, SyntaxKind, SyntaxNode, TextRange,
+};
+
+use crate::{db::RootDatabase, FileId, FunctionSignature};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum InlayKind {
+    TypeHint,
+    ParameterHint,
+}
+
+#[derive(Debug)]
+pub struct InlayHint {
+    pub range: TextRange,
+    pub kind: InlayKind,
+    pub label: SmolStr,
+}
+
+pub(crate) fn inlay_hints(
+    db: &RootDatabase,
+    file_id: FileId,
+    file: &SourceFile,
+    max_inlay_hint_length: Option<usize>,
+    parameter_hints: bool,
+) -> Vec<InlayHint> {
+    file.syntax()
+        .descendants()
+        .flat_map(|node| {
+            get_inlay_hints(db, file_id, node, max_inlay_hint_length, parameter_hints)
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+fn get_inlay_hints(
+    db: &RootDatabase,
+    file_id: FileId,
+    node: &SyntaxNode,
+    max_inlay_hint_length: Option<usize>,
+    parameter_hints: bool,
+) -> Option<Vec<InlayHint>> {
+    let analyzer = || SourceAnalyzer::new(db, file_id, node, None);
+    visitor()
+        .visit(|let_statement: &ast::LetStmt| {
+            if let_statement.ascribed_type().is_some() {
+                return None;
+            }
+            let pat = let_statement.pat()?;
+            Some(get_pat_type_hints(db, &analyzer(), pat, max_inlay_hint_length))
+        })
+        .visit(|closure_parameter: &ast::LambdaExpr| {
+            let analyzer = analyzer();
+            let hints = closure_parameter
+                .param_list()?
+                .params()
+                .filter(|param| param.ascribed_type().is_none())
+                .filter_map(|param| param.pat())
+                .flat_map(|pat| get_pat_type_hints(db, &analyzer, pat, max_inlay_hint_length))
+                .collect();
+            Some(hints)
+        })
+        .visit(|for_expression: &ast::ForExpr| {
+            let pat = for_expression.pat()?;
+            Some(get_pat_type_hints(db, &analyzer(), pat, max_inlay_hint_length))
+        })
+        .visit(|if_expr: &ast::IfExpr| {
+            let pat = if_expr.condition()?.pat()?;
+            Some(get_pat_type_hints(db, &analyzer(), pat, max_inlay_hint_length))
+        })
+        .visit(|while_expr: &ast::WhileExpr| {
+            let pat = while_expr.condition()?.pat()?;
+            Some(get_pat_type_hints(db, &analyzer(), pat, max_inlay_hint_length))
+        })
+        .visit(|match_arm_list: &ast::MatchArmList| {
+            let analyzer = analyzer();
+            let hints = match_arm_list
+                .arms()
+                .flat_map(|arm| arm.pats())
+                .flat_map(|pat| get_pat_type_hints(db, &analyzer, pat, max_inlay_hint_length))
+                .collect();
+            Some(hints)
+        })
+        .visit(|call: &ast::CallExpr| {
+            if !parameter_hints {
+                return None;
+            }
+            let analyzer = analyzer();
+            let (callable_def, _subst) = analyzer.type_of(db, call.expr()?)?.as_callable()?;
+            let function = match callable_def {
+                hir::CallableDef::Function(it) => it,
+                _ => return None,
+            };
+            Some(get_param_name_hints(db, function, call.arg_list()?, false))
+        })
+        .visit(|method_call: &ast::MethodCallExpr| {
+            let analyzer = analyzer();
+            let mut hints = Vec::new();
+            hints.extend(get_chain_hint(db, &analyzer, method_call, max_inlay_hint_length));
+            if !parameter_hints {
+                return Some(hints);
+            }
+            if let (Some(function), Some(arg_list)) =
+                (analyzer.resolve_method_call(method_call), method_call.arg_list())
+            {
+                hints.extend(get_param_name_hints(db, function, arg_list, true));
+            }
+            Some(hints)
+        })
+        .accept(node)?
+}
+
+/// Hints the type of every binding in `root_pat`, skipping the ones whose type
+/// could not be inferred.
+fn get_pat_type_hints(
+    db: &RootDatabase,
+    analyzer: &SourceAnalyzer,
+    root_pat: &ast::Pat,
+    max_inlay_hint_length: Option<usize>,
+) -> Vec<InlayHint> {
+    root_pat
+        .syntax()
+        .descendants()
+        .filter_map(ast::BindPat::cast)
+        .filter_map(|pat| {
+            let ty = analyzer.type_of_pat(db, pat.into())?;
+            if let Ty::Unknown = ty {
+                return None;
+            }
+            Some(InlayHint {
+                range: pat.syntax().range(),
+                kind: InlayKind::TypeHint,
+                label: ty.display_truncated(db, max_inlay_hint_length).to_string().into(),
+            })
+        })
+        .collect()
+}
+
+/// Hints the type of a method call which is the receiver of another call on
+/// the next line, so that each step of a method chain gets a type.
+fn get_chain_hint(
+    db: &RootDatabase,
+    analyzer: &SourceAnalyzer,
+    method_call: &ast::MethodCallExpr,
+    max_inlay_hint_length: Option<usize>,
+) -> Option<InlayHint> {
+    let parent = method_call.syntax().parent().and_then(ast::MethodCallExpr::cast)?;
+    if parent.expr()?.syntax() != method_call.syntax() {
+        return None;
+    }
+    let next = method_call.syntax().next_sibling_or_token()?;
+    if next.kind() != SyntaxKind::WHITESPACE || !next.as_token()?.text().contains('\n') {
+        return None;
+    }
+    let ty = analyzer.type_of(db, method_call.into())?;
+    if let Ty::Unknown = ty {
+        return None;
+    }
+    Some(InlayHint {
+        range: method_call.syntax().range(),
+        kind: InlayKind::TypeHint,
+        label: ty.display_truncated(db, max_inlay_hint_length).to_string().into(),
+    })
+}
+
+/// Hints the name of the parameter each argument is passed to, unless the
+/// argument already spells it out.
+fn get_param_name_hints(
+    db: &RootDatabase,
+    function: hir::Function,
+    arg_list: &ast::ArgList,
+    is_method_call: bool,
+) -> Vec<InlayHint> {
+    let signature = FunctionSignature::from_hir(db, function);
+    // The receiver of a method call is not part of the argument list
+    let skip = if is_method_call && function.data(db).has_self_param() { 1 } else { 0 };
+    signature
+        .parameter_names
+        .iter()
+        .skip(skip)
+        .zip(arg_list.args())
+        .filter(|(name, arg)| {
+            !name.is_empty() && !name.starts_with('_') && arg.syntax().text() != name.as_str()
+        })
+        .map(|(name, arg)| InlayHint {
+            range: arg.syntax().range(),
+            kind: InlayKind::ParameterHint,
+            label: name.as_str().into(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use insta::assert_debug_snapshot_matches;
+
+    use crate::{mock_analysis::single_file, InlayKind};
+
+    #[test]
+    fn let_and_pattern_hints() {
+        let (analysis, file_id) = single_file(
+            r#"
+enum CustomOption<T> { None, Some(T) }
+struct Test { a: CustomOption<u32>, b: u8 }
+fn main() {
+    let test = Test { a: CustomOption::Some(1), b: 2 };
+    let (c, d) = (1u32, 'c');
+    let annotated: u8 = 3;
+    if let CustomOption::Some(x) = test.a {}
+    match test.a { CustomOption::Some(y) => {}, _ => {} }
+}"#,
+        );
+
+        assert_debug_snapshot_matches!(
+            "inlay_hints_let_and_pattern",
+            analysis.inlay_hints(file_id, None, true).unwrap()
+        );
+    }
+
+    #[test]
+    fn truncated_type_hints() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct Wrapper<T>(T);
+fn main() {
+    let nested = Wrapper(Wrapper(Wrapper(1u32)));
+}"#,
+        );
+
+        assert_debug_snapshot_matches!(
+            "inlay_hints_truncated",
+            analysis.inlay_hints(file_id, Some(12), true).unwrap()
+        );
+    }
+
+    #[test]
+    fn parameter_name_hints() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct S;
+impl S { fn method(&self, param: u8) {} }
+fn foo(first: u32, second: u32, _ignored: u32) {}
+fn main() {
+    let second = 2;
+    foo(1, second, 3);
+    S.method(4);
+}"#,
+        );
+
+        assert_debug_snapshot_matches!(
+            "inlay_hints_parameter_names",
+            analysis.inlay_hints(file_id, None, true).unwrap()
+        );
+    }
+
+    #[test]
+    fn no_parameter_name_hints_when_disabled() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct S;
+impl S { fn method(&self, param: u8) {} }
+fn foo(first: u32) {}
+fn main() {
+    foo(1);
+    S.method(4);
+}"#,
+        );
+
+        let hints = analysis.inlay_hints(file_id, None, false).unwrap();
+        assert!(hints.iter().all(|hint| hint.kind != InlayKind::ParameterHint));
+    }
+
+    #[test]
+    fn method_chain_hints() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct A; struct B; struct C;
+impl A { fn into_b(&self) -> B { B } }
+impl B { fn into_c(&self) -> C { C } }
+fn main() {
+    A.into_b()
+        .into_c();
+}"#,
+        );
+
+        assert_debug_snapshot_matches!(
+            "inlay_hints_method_chain",
+            analysis.inlay_hints(file_id, None, true).unwrap()
+        );
+    }
+}
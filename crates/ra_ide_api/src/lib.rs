@@ -24,9 +24,14 @@ mod goto_type_definition;
 mod extend_selection;
 mod hover;
 mod call_info;
+mod call_hierarchy;
+mod change_signature;
 mod syntax_highlighting;
+mod type_hierarchy;
 mod parent_module;
 mod references;
+mod rewrites;
+mod document_highlight;
 mod impls;
 mod assists;
 mod diagnostics;
@@ -38,6 +43,7 @@ mod join_lines;
 mod typing;
 mod matching_brace;
 mod display;
+mod inlay_hints;
 
 #[cfg(test)]
 mod marks;
@@ -57,18 +63,27 @@ use relative_path::RelativePathBuf;
 use crate::{db::LineIndexDatabase, symbol_index::FileSymbol};
 
 pub use crate::{
-    assists::{Assist, AssistId},
+    assists::{Assist, AssistId, ImportGranularity},
+    call_hierarchy::CallItem,
     change::{AnalysisChange, LibraryData},
-    completion::{CompletionItem, CompletionItemKind, InsertTextFormat},
+    change_signature::{ParamChange, SignatureChange, SignatureChangeError},
+    completion::{
+        CompletionItem, CompletionItemKind, CompletionRelevance, Deferred, InsertTextFormat,
+        ResolvedCompletion,
+    },
     diagnostics::Severity,
     display::{file_structure, FunctionSignature, NavigationTarget, StructureNode},
     folding_ranges::{Fold, FoldKind},
     hover::HoverResult,
+    inlay_hints::{InlayHint, InlayKind},
     line_index::{LineCol, LineIndex},
     line_index_utils::translate_offset_with_edit,
-    references::ReferenceSearchResult,
+    references::{Reference, ReferenceAccess, ReferenceSearchResult, RenameError, SearchScope},
     runnables::{Runnable, RunnableKind},
-    syntax_highlighting::HighlightedRange,
+    syntax_highlighting::{
+        Highlight, HighlightModifier, HighlightModifiers, HighlightTag, HighlightedRange,
+    },
+    type_hierarchy::TypeHierarchy,
 };
 
 pub use hir::Documentation;
@@ -167,8 +182,17 @@ pub struct SourceFileEdit {
 
 #[derive(Debug)]
 pub enum FileSystemEdit {
-    CreateFile { source_root: SourceRootId, path: RelativePathBuf },
-    MoveFile { src: FileId, dst_source_root: SourceRootId, dst_path: RelativePathBuf },
+    /// Creates a file with `text` as its contents.
+    CreateFile {
+        source_root: SourceRootId,
+        path: RelativePathBuf,
+        text: String,
+    },
+    MoveFile {
+        src: FileId,
+        dst_source_root: SourceRootId,
+        dst_path: RelativePathBuf,
+    },
 }
 
 #[derive(Debug)]
@@ -394,6 +418,26 @@ impl Analysis {
         file_structure(&file)
     }
 
+    /// Returns a list of the places in the file where type hints and, if
+    /// `parameter_hints` is set, parameter names can be displayed. Types
+    /// longer than `max_inlay_hint_length` characters are truncated.
+    pub fn inlay_hints(
+        &self,
+        file_id: FileId,
+        max_inlay_hint_length: Option<usize>,
+        parameter_hints: bool,
+    ) -> Cancelable<Vec<InlayHint>> {
+        self.with_db(|db| {
+            inlay_hints::inlay_hints(
+                db,
+                file_id,
+                &db.parse(file_id).tree,
+                max_inlay_hint_length,
+                parameter_hints,
+            )
+        })
+    }
+
     /// Returns the set of folding ranges.
     pub fn folding_ranges(&self, file_id: FileId) -> Vec<Fold> {
         let file = self.db.parse(file_id).tree;
@@ -431,12 +475,28 @@ impl Analysis {
         self.with_db(|db| goto_type_definition::goto_type_definition(db, position))
     }
 
+    /// Returns the supertraits, subtraits and implementors of the trait at
+    /// position, or the traits implemented by the type at position.
+    pub fn type_hierarchy(&self, position: FilePosition) -> Cancelable<Option<TypeHierarchy>> {
+        self.with_db(|db| type_hierarchy::type_hierarchy(db, position))
+    }
+
     /// Finds all usages of the reference at point.
+    ///
+    /// Items are searched for in `search_scope`, which defaults to the whole
+    /// workspace. Locals are always searched for in their own body only.
     pub fn find_all_refs(
         &self,
         position: FilePosition,
+        search_scope: Option<SearchScope>,
     ) -> Cancelable<Option<ReferenceSearchResult>> {
-        self.with_db(|db| references::find_all_refs(db, position))
+        self.with_db(|db| references::find_all_refs(db, position, search_scope))
+    }
+
+    /// Finds the ranges related to the cursor in the current file, along with
+    /// whether each of them reads or writes the value at the cursor.
+    pub fn document_highlight(&self, position: FilePosition) -> Cancelable<Option<Vec<Reference>>> {
+        self.with_db(|db| document_highlight::document_highlight(db, position))
     }
 
     /// Returns a short text describing element at position.
@@ -449,6 +509,22 @@ impl Analysis {
         self.with_db(|db| call_info::call_info(db, position))
     }
 
+    /// Finds the function at position to build a call hierarchy from.
+    pub fn call_hierarchy(&self, position: FilePosition) -> Cancelable<Option<NavigationTarget>> {
+        self.with_db(|db| call_hierarchy::call_hierarchy(db, position))
+    }
+
+    /// Lists the functions calling the function at position, across the
+    /// workspace.
+    pub fn incoming_calls(&self, position: FilePosition) -> Cancelable<Option<Vec<CallItem>>> {
+        self.with_db(|db| call_hierarchy::incoming_calls(db, position))
+    }
+
+    /// Lists the functions called by the function at position.
+    pub fn outgoing_calls(&self, position: FilePosition) -> Cancelable<Option<Vec<CallItem>>> {
+        self.with_db(|db| call_hierarchy::outgoing_calls(db, position))
+    }
+
     /// Returns a `mod name;` declaration which created the current module.
     pub fn parent_module(&self, position: FilePosition) -> Cancelable<Vec<NavigationTarget>> {
         self.with_db(|db| parent_module::parent_module(db, position))
@@ -484,12 +560,32 @@ impl Analysis {
         self.with_db(|db| completion::completions(db, position).map(Into::into))
     }
 
+    /// Computes the documentation, the signature and the additional edits
+    /// which were deferred for a completion item computed at the given position.
+    pub fn resolve_completion(
+        &self,
+        position: FilePosition,
+        deferred: &Deferred,
+    ) -> Cancelable<Option<ResolvedCompletion>> {
+        self.with_db(|db| completion::resolve_completion(db, position, deferred))
+    }
+
     /// Computes assists (aka code actions aka intentions) for the given
     /// position.
     pub fn assists(&self, frange: FileRange) -> Cancelable<Vec<Assist>> {
         self.with_db(|db| assists::assists(db, frange))
     }
 
+    /// Merges, groups and sorts the imports of the given file, removing the
+    /// unused ones.
+    pub fn organize_imports(
+        &self,
+        file_id: FileId,
+        granularity: ImportGranularity,
+    ) -> Cancelable<Option<SourceChange>> {
+        self.with_db(|db| assists::organize_imports(db, file_id, granularity))
+    }
+
     /// Computes the set of diagnostics for the given file.
     pub fn diagnostics(&self, file_id: FileId) -> Cancelable<Vec<Diagnostic>> {
         self.with_db(|db| diagnostics::diagnostics(db, file_id))
@@ -501,15 +597,28 @@ impl Analysis {
     }
 
     /// Returns the edit required to rename reference at the position to the new
-    /// name.
+    /// name, across all files of the workspace.
+    ///
+    /// Fails if the new name is not a valid identifier or is already used in
+    /// the scope of the renamed item.
     pub fn rename(
         &self,
         position: FilePosition,
         new_name: &str,
-    ) -> Cancelable<Option<SourceChange>> {
+    ) -> Cancelable<Result<Option<SourceChange>, RenameError>> {
         self.with_db(|db| references::rename(db, position, new_name))
     }
 
+    /// Reorders, removes and adds parameters of the function at `position`,
+    /// updating the related trait methods and all the calls to them.
+    pub fn change_signature(
+        &self,
+        position: FilePosition,
+        change: SignatureChange,
+    ) -> Cancelable<Result<Option<SourceChange>, SignatureChangeError>> {
+        self.with_db(|db| change_signature::change_signature(db, position, &change))
+    }
+
     fn with_db<F: FnOnce(&db::RootDatabase) -> T + std::panic::UnwindSafe, T>(
         &self,
         f: F,
@@ -1,11 +1,11 @@
 use std::sync::Arc;
 
 use relative_path::RelativePathBuf;
-use test_utils::{extract_offset, extract_range, parse_fixture, CURSOR_MARKER};
+use test_utils::{assert_eq_text, extract_offset, extract_range, parse_fixture, CURSOR_MARKER};
 
 use crate::{
     Analysis, AnalysisChange, AnalysisHost, CrateGraph, Edition::Edition2018, FileId, FilePosition,
-    FileRange, SourceRootId,
+    FileRange, SourceChange, SourceRootId,
 };
 
 /// Mock analysis is used in test to bootstrap an AnalysisHost/Analysis
@@ -117,6 +117,27 @@ pub fn analysis_and_position(fixture: &str) -> (Analysis, FilePosition) {
     (mock.analysis(), position)
 }
 
+/// Computes a change at the position marked with <|> in a multi-file
+/// fixture, and checks the text of the `expected` files once it is applied.
+pub fn check_source_change(
+    fixture: &str,
+    compute: impl FnOnce(&Analysis, FilePosition) -> SourceChange,
+    expected: &[(&str, &str)],
+) -> SourceChange {
+    let (mock, position) = MockAnalysis::with_files_and_position(fixture);
+    let file_ids = expected.iter().map(|(path, _)| mock.id_of(path)).collect::<Vec<_>>();
+    let analysis = mock.analysis();
+    let change = compute(&analysis, position);
+    for ((_, expected), file_id) in expected.iter().zip(file_ids) {
+        let mut text = analysis.file_text(file_id).to_string();
+        for edit in change.source_file_edits.iter().filter(|it| it.file_id == file_id) {
+            text = edit.edit.apply(&text);
+        }
+        assert_eq_text!(*expected, text.trim());
+    }
+    change
+}
+
 /// Creates analysis for a single file.
 pub fn single_file(code: &str) -> (Analysis, FileId) {
     let mut mock = MockAnalysis::new();
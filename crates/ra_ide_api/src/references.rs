@@ -1,17 +1,47 @@
-use hir::{source_binder, Either, ModuleSource};
-use ra_db::SourceDatabase;
-use ra_syntax::{algo::find_node_at_offset, ast, AstNode, SourceFile, SyntaxNode};
-use relative_path::{RelativePath, RelativePathBuf};
+mod classify;
+mod rename;
+mod search_scope;
+
+use hir::Either;
+use ra_db::{CheckCanceled, SourceDatabase};
+use ra_syntax::{
+    algo::find_node_at_offset,
+    ast::{self, BinOp},
+    AstNode, SourceFile, SyntaxKind,
+};
 
 use crate::{
-    db::RootDatabase, FileId, FilePosition, FileRange, FileSystemEdit, NavigationTarget,
-    SourceChange, SourceFileEdit, TextRange,
+    db::RootDatabase, symbol_index::SymbolsDatabase, FileId, FilePosition, FileRange,
+    NavigationTarget,
+};
+
+use self::classify::classify_reference;
+
+pub(crate) use self::{
+    classify::{classify_name, NameDefinition},
+    rename::{related_functions, rename},
 };
+pub use self::{rename::RenameError, search_scope::SearchScope};
 
 #[derive(Debug, Clone)]
 pub struct ReferenceSearchResult {
     declaration: NavigationTarget,
-    references: Vec<FileRange>,
+    declaration_access: Option<ReferenceAccess>,
+    references: Vec<Reference>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Reference {
+    pub file_range: FileRange,
+    /// Whether the referenced value is read or written, `None` if the
+    /// reference is not to a variable, a field or a static.
+    pub access: Option<ReferenceAccess>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceAccess {
+    Read,
+    Write,
 }
 
 impl ReferenceSearchResult {
@@ -19,7 +49,13 @@ impl ReferenceSearchResult {
         &self.declaration
     }
 
-    pub fn references(&self) -> &[FileRange] {
+    /// Whether the declaration writes a value, as a `let` with an
+    /// initializer does.
+    pub fn declaration_access(&self) -> Option<ReferenceAccess> {
+        self.declaration_access
+    }
+
+    pub fn references(&self) -> &[Reference] {
         &self.references
     }
 
@@ -37,10 +73,10 @@ impl IntoIterator for ReferenceSearchResult {
     type Item = FileRange;
     type IntoIter = std::vec::IntoIter<FileRange>;
 
-    fn into_iter(mut self) -> Self::IntoIter {
+    fn into_iter(self) -> Self::IntoIter {
         let mut v = Vec::with_capacity(self.len());
         v.push(FileRange { file_id: self.declaration.file_id(), range: self.declaration.range() });
-        v.append(&mut self.references);
+        v.extend(self.references.into_iter().map(|it| it.file_range));
         v.into_iter()
     }
 }
@@ -48,163 +84,190 @@ impl IntoIterator for ReferenceSearchResult {
 pub(crate) fn find_all_refs(
     db: &RootDatabase,
     position: FilePosition,
+    search_scope: Option<SearchScope>,
 ) -> Option<ReferenceSearchResult> {
     let file = db.parse(position.file_id).tree;
-    let (binding, analyzer) = find_binding(db, &file, position)?;
-    let declaration = NavigationTarget::from_bind_pat(position.file_id, binding);
-
-    let references = analyzer
-        .find_all_refs(binding)
-        .into_iter()
-        .map(move |ref_desc| FileRange { file_id: position.file_id, range: ref_desc.range })
-        .collect::<Vec<_>>();
-
-    return Some(ReferenceSearchResult { declaration, references });
-
-    fn find_binding<'a>(
-        db: &RootDatabase,
-        source_file: &'a SourceFile,
-        position: FilePosition,
-    ) -> Option<(&'a ast::BindPat, hir::SourceAnalyzer)> {
-        let syntax = source_file.syntax();
-        if let Some(binding) = find_node_at_offset::<ast::BindPat>(syntax, position.offset) {
-            let analyzer = hir::SourceAnalyzer::new(db, position.file_id, binding.syntax(), None);
-            return Some((binding, analyzer));
+
+    // Locals never escape their body, so they are searched for by the analyzer
+    // instead of going through the files in scope.
+    if let Some((binding, analyzer)) = find_binding(db, &file, position) {
+        let declaration = NavigationTarget::from_bind_pat(position.file_id, binding);
+        // Every binding is initialized when it is declared, except for `let x;`
+        let declaration_access = match binding.syntax().ancestors().find_map(ast::LetStmt::cast) {
+            Some(let_stmt) if let_stmt.initializer().is_none() => None,
+            _ => Some(ReferenceAccess::Write),
         };
-        let name_ref = find_node_at_offset::<ast::NameRef>(syntax, position.offset)?;
-        let analyzer = hir::SourceAnalyzer::new(db, position.file_id, name_ref.syntax(), None);
-        let resolved = analyzer.resolve_local_name(name_ref)?;
-        if let Either::A(ptr) = resolved.ptr() {
-            if let ast::PatKind::BindPat(binding) = ptr.to_node(source_file.syntax()).kind() {
-                return Some((binding, analyzer));
-            }
-        }
-        None
+        let references = analyzer
+            .find_all_refs(binding)
+            .into_iter()
+            .map(|ref_desc| {
+                let access =
+                    find_node_at_offset::<ast::NameRef>(file.syntax(), ref_desc.range.start())
+                        .map(reference_access);
+                Reference {
+                    file_range: FileRange { file_id: position.file_id, range: ref_desc.range },
+                    access,
+                }
+            })
+            .collect::<Vec<_>>();
+        return Some(ReferenceSearchResult { declaration, declaration_access, references });
     }
-}
-
-pub(crate) fn rename(
-    db: &RootDatabase,
-    position: FilePosition,
-    new_name: &str,
-) -> Option<SourceChange> {
-    let source_file = db.parse(position.file_id).tree;
-    let syntax = source_file.syntax();
 
-    if let Some((ast_name, ast_module)) = find_name_and_module_at_offset(syntax, position) {
-        rename_mod(db, ast_name, ast_module, position, new_name)
-    } else {
-        rename_reference(db, position, new_name)
+    let def = find_definition(db, &file, position)?;
+    let declaration = def.declaration(db)?;
+    let name = declaration.name().clone();
+    if name.is_empty() {
+        return None;
     }
+
+    let files = search_scope.unwrap_or_default().files(db, declaration.file_id());
+    let references = find_refs_to_def(db, def, &name, files);
+    let declaration_access = match def {
+        NameDefinition::ModuleDef(hir::ModuleDef::Static(_)) => Some(ReferenceAccess::Write),
+        _ => None,
+    };
+
+    Some(ReferenceSearchResult { declaration, declaration_access, references })
 }
 
-fn find_name_and_module_at_offset(
-    syntax: &SyntaxNode,
+fn find_binding<'a>(
+    db: &RootDatabase,
+    source_file: &'a SourceFile,
     position: FilePosition,
-) -> Option<(&ast::Name, &ast::Module)> {
-    let ast_name = find_node_at_offset::<ast::Name>(syntax, position.offset);
-    let ast_name_parent = ast::Module::cast(ast_name?.syntax().parent()?);
-
-    if let (Some(ast_module), Some(name)) = (ast_name_parent, ast_name) {
-        return Some((name, ast_module));
+) -> Option<(&'a ast::BindPat, hir::SourceAnalyzer)> {
+    let syntax = source_file.syntax();
+    if let Some(binding) = find_node_at_offset::<ast::BindPat>(syntax, position.offset) {
+        let analyzer = hir::SourceAnalyzer::new(db, position.file_id, binding.syntax(), None);
+        return Some((binding, analyzer));
+    };
+    let name_ref = find_node_at_offset::<ast::NameRef>(syntax, position.offset)?;
+    // Field and method names are never locals, even if a local shares the name
+    name_ref.syntax().parent().and_then(ast::PathSegment::cast)?;
+    let analyzer = hir::SourceAnalyzer::new(db, position.file_id, name_ref.syntax(), None);
+    let resolved = analyzer.resolve_local_name(name_ref)?;
+    if let Either::A(ptr) = resolved.ptr() {
+        if let ast::PatKind::BindPat(binding) = ptr.to_node(source_file.syntax()).kind() {
+            return Some((binding, analyzer));
+        }
     }
     None
 }
 
-fn source_edit_from_fileid_range(
-    file_id: FileId,
-    range: TextRange,
-    new_name: &str,
-) -> SourceFileEdit {
-    SourceFileEdit {
-        file_id,
-        edit: {
-            let mut builder = ra_text_edit::TextEditBuilder::default();
-            builder.replace(range, new_name.into());
-            builder.finish()
-        },
+pub(crate) fn find_definition(
+    db: &RootDatabase,
+    source_file: &SourceFile,
+    position: FilePosition,
+) -> Option<NameDefinition> {
+    let syntax = source_file.syntax();
+    if let Some(name) = find_node_at_offset::<ast::Name>(syntax, position.offset) {
+        return classify_name(db, position.file_id, name);
     }
+    let name_ref = find_node_at_offset::<ast::NameRef>(syntax, position.offset)?;
+    classify_reference(db, position.file_id, name_ref)
 }
 
-fn rename_mod(
+/// Skips the files which don't refer to `name` at all, using the name ref
+/// index, and only resolves the name references in the remaining ones.
+pub(crate) fn find_refs_to_def(
     db: &RootDatabase,
-    ast_name: &ast::Name,
-    ast_module: &ast::Module,
-    position: FilePosition,
-    new_name: &str,
-) -> Option<SourceChange> {
-    let mut source_file_edits = Vec::new();
-    let mut file_system_edits = Vec::new();
-    if let Some(module) = source_binder::module_from_declaration(db, position.file_id, &ast_module)
-    {
-        let src = module.definition_source(db);
-        let file_id = src.file_id.as_original_file();
-        match src.ast {
-            ModuleSource::SourceFile(..) => {
-                let mod_path: RelativePathBuf = db.file_relative_path(file_id);
-                // mod is defined in path/to/dir/mod.rs
-                let dst_path = if mod_path.file_stem() == Some("mod") {
-                    mod_path
-                        .parent()
-                        .and_then(|p| p.parent())
-                        .or_else(|| Some(RelativePath::new("")))
-                        .map(|p| p.join(new_name).join("mod.rs"))
-                } else {
-                    Some(mod_path.with_file_name(new_name).with_extension("rs"))
-                };
-                if let Some(path) = dst_path {
-                    let move_file = FileSystemEdit::MoveFile {
-                        src: file_id,
-                        dst_source_root: db.file_source_root(position.file_id),
-                        dst_path: path,
-                    };
-                    file_system_edits.push(move_file);
-                }
+    def: NameDefinition,
+    name: &str,
+    files: Vec<FileId>,
+) -> Vec<Reference> {
+    let mut refs = Vec::new();
+    for file_id in files {
+        db.check_canceled();
+        if !db.file_name_refs(file_id).contains(name) {
+            continue;
+        }
+        let source_file = db.parse(file_id).tree;
+        let name_refs = source_file.syntax().descendants().filter_map(ast::NameRef::cast);
+        for name_ref in name_refs.filter(|it| it.text() == name) {
+            let range = name_ref.syntax().range();
+            if classify_reference(db, file_id, name_ref) != Some(def) {
+                continue;
             }
-            ModuleSource::Module(..) => {}
+            let access = if !def.has_access() {
+                None
+            } else if name_ref.syntax().parent().and_then(ast::NamedField::cast).is_some() {
+                // Initializing a field in a struct literal writes to it
+                Some(ReferenceAccess::Write)
+            } else {
+                Some(reference_access(name_ref))
+            };
+            refs.push(Reference { file_range: FileRange { file_id, range }, access });
         }
     }
+    refs
+}
 
-    let edit = SourceFileEdit {
-        file_id: position.file_id,
-        edit: {
-            let mut builder = ra_text_edit::TextEditBuilder::default();
-            builder.replace(ast_name.syntax().range(), new_name.into());
-            builder.finish()
-        },
+/// Classifies a reference in expression position. The places which are
+/// assigned to or borrowed mutably are written, including through fields and
+/// indexing.
+fn reference_access(name_ref: &ast::NameRef) -> ReferenceAccess {
+    let expr = name_ref
+        .syntax()
+        .ancestors()
+        .skip(1)
+        .find(|it| it.kind() != SyntaxKind::PATH_SEGMENT && it.kind() != SyntaxKind::PATH)
+        .and_then(ast::Expr::cast);
+    let mut expr = match expr {
+        Some(expr) => expr,
+        None => return ReferenceAccess::Read,
     };
-    source_file_edits.push(edit);
-
-    Some(SourceChange::from_edits("rename", source_file_edits, file_system_edits))
+    // `a.b = c` and `a[i] = c` write to `a` as well
+    while let Some(parent) = expr.syntax().parent().and_then(ast::Expr::cast) {
+        let is_base = match parent.kind() {
+            ast::ExprKind::FieldExpr(it) => it.expr().map(AstNode::syntax) == Some(expr.syntax()),
+            ast::ExprKind::IndexExpr(it) => it.syntax().children().next() == Some(expr.syntax()),
+            _ => false,
+        };
+        if !is_base {
+            break;
+        }
+        expr = parent;
+    }
+    let parent = match expr.syntax().parent() {
+        Some(it) => it,
+        None => return ReferenceAccess::Read,
+    };
+    let is_write = if let Some(bin_expr) = ast::BinExpr::cast(parent) {
+        bin_expr.lhs().map(AstNode::syntax) == Some(expr.syntax()) && is_assignment(bin_expr)
+    } else if let Some(ref_expr) = ast::RefExpr::cast(parent) {
+        ref_expr.is_mut()
+    } else {
+        false
+    };
+    if is_write {
+        ReferenceAccess::Write
+    } else {
+        ReferenceAccess::Read
+    }
 }
 
-fn rename_reference(
-    db: &RootDatabase,
-    position: FilePosition,
-    new_name: &str,
-) -> Option<SourceChange> {
-    let refs = find_all_refs(db, position)?;
-
-    let edit = refs
-        .into_iter()
-        .map(|range| source_edit_from_fileid_range(range.file_id, range.range, new_name))
-        .collect::<Vec<_>>();
-
-    if edit.is_empty() {
-        return None;
+fn is_assignment(bin_expr: &ast::BinExpr) -> bool {
+    match bin_expr.op_kind() {
+        Some(BinOp::Assignment)
+        | Some(BinOp::AddAssign)
+        | Some(BinOp::DivAssign)
+        | Some(BinOp::MulAssign)
+        | Some(BinOp::RemAssign)
+        | Some(BinOp::ShrAssign)
+        | Some(BinOp::ShlAssign)
+        | Some(BinOp::SubAssign)
+        | Some(BinOp::BitOrAssign)
+        | Some(BinOp::BitAndAssign)
+        | Some(BinOp::BitXorAssign) => true,
+        _ => false,
     }
-
-    Some(SourceChange::source_file_edits("rename", edit))
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        mock_analysis::analysis_and_position, mock_analysis::single_file_with_position, FileId,
-        ReferenceSearchResult,
+        mock_analysis::analysis_and_position, mock_analysis::single_file_with_position,
+        ReferenceAccess, ReferenceSearchResult, SearchScope,
     };
-    use insta::assert_debug_snapshot_matches;
-    use test_utils::assert_eq_text;
 
     #[test]
     fn test_find_all_refs_for_local() {
@@ -247,135 +310,112 @@ mod tests {
         assert_eq!(refs.len(), 2);
     }
 
-    fn get_all_refs(text: &str) -> ReferenceSearchResult {
-        let (analysis, position) = single_file_with_position(text);
-        analysis.find_all_refs(position).unwrap().unwrap()
-    }
-
     #[test]
-    fn test_rename_for_local() {
-        test_rename(
-            r#"
+    fn test_find_all_refs_for_local_access() {
+        let code = r#"
     fn main() {
         let mut i = 1;
         let j = 1;
         i = i<|> + j;
 
         {
-            i = 0;
-        }
-
-        i = 5;
-    }"#,
-            "k",
-            r#"
-    fn main() {
-        let mut k = 1;
-        let j = 1;
-        k = k + j;
-
-        {
-            k = 0;
+            i += 0;
         }
+    }"#;
 
-        k = 5;
-    }"#,
-        );
+        let refs = get_all_refs(code);
+        let writes =
+            refs.references().iter().filter(|r| r.access == Some(ReferenceAccess::Write)).count();
+        assert_eq!(refs.len(), 4);
+        assert_eq!(writes, 2);
     }
 
     #[test]
-    fn test_rename_for_param_inside() {
-        test_rename(
-            r#"
-    fn foo(i : u32) -> u32 {
-        i<|>
-    }"#,
-            "j",
-            r#"
-    fn foo(j : u32) -> u32 {
-        j
-    }"#,
-        );
-    }
+    fn test_find_all_refs_for_struct_across_files() {
+        let (analysis, position) = analysis_and_position(
+            "
+            //- /lib.rs
+            mod foo;
+            pub struct Foo<|> {}
+            fn new() -> Foo { Foo {} }
 
-    #[test]
-    fn test_rename_refs_for_fn_param() {
-        test_rename(
-            r#"
-    fn foo(i<|> : u32) -> u32 {
-        i
-    }"#,
-            "new_name",
-            r#"
-    fn foo(new_name : u32) -> u32 {
-        new_name
-    }"#,
+            //- /foo.rs
+            use crate::Foo;
+            fn take(foo: Foo) {}
+            ",
         );
+        let refs = analysis.find_all_refs(position, None).unwrap().unwrap();
+        assert_eq!(refs.len(), 5);
+        assert!(refs.references().iter().all(|r| r.access.is_none()));
+        let other_file =
+            refs.references().iter().filter(|r| r.file_range.file_id != position.file_id).count();
+        assert_eq!(other_file, 2);
     }
 
     #[test]
-    fn test_rename_for_mut_param() {
-        test_rename(
-            r#"
-    fn foo(mut i<|> : u32) -> u32 {
-        i
-    }"#,
-            "new_name",
-            r#"
-    fn foo(mut new_name : u32) -> u32 {
-        new_name
-    }"#,
+    fn test_find_all_refs_for_field() {
+        let code = r#"
+    struct S { field<|>: u32 }
+    fn f(s: &mut S) -> u32 {
+        let t = S { field: 0 };
+        s.field = t.field;
+        s.field
+    }"#;
+
+        let refs = get_all_refs(code);
+        let access = refs.references().iter().map(|r| r.access).collect::<Vec<_>>();
+        assert_eq!(
+            access,
+            vec![
+                Some(ReferenceAccess::Write),
+                Some(ReferenceAccess::Write),
+                Some(ReferenceAccess::Read),
+                Some(ReferenceAccess::Read),
+            ]
         );
     }
 
     #[test]
-    fn test_rename_mod() {
-        let (analysis, position) = analysis_and_position(
-            "
-            //- /lib.rs
-            mod bar;
-
-            //- /bar.rs
-            mod foo<|>;
+    fn test_find_all_refs_for_method() {
+        let code = r#"
+    struct Foo;
+    impl Foo {
+        fn frobnicate(&self) {}
+    }
+    fn main() {
+        let foo = Foo;
+        foo.frobnicate<|>();
+        Foo::frobnicate(&foo);
+    }"#;
 
-            //- /bar/foo.rs
-            // emtpy
-            ",
-        );
-        let new_name = "foo2";
-        let source_change = analysis.rename(position, new_name).unwrap();
-        assert_debug_snapshot_matches!("rename_mod", &source_change);
+        let refs = get_all_refs(code);
+        assert_eq!(refs.declaration().name(), "frobnicate");
+        assert_eq!(refs.len(), 3);
     }
 
     #[test]
-    fn test_rename_mod_in_dir() {
+    fn test_find_all_refs_respects_search_scope() {
         let (analysis, position) = analysis_and_position(
             "
             //- /lib.rs
-            mod fo<|>o;
-            //- /foo/mod.rs
-            // emtpy
+            mod foo;
+            pub enum E { A<|>, B }
+            fn a() -> E { E::A }
+
+            //- /foo.rs
+            use crate::E;
+            fn a() -> E { E::A }
             ",
         );
-        let new_name = "foo2";
-        let source_change = analysis.rename(position, new_name).unwrap();
-        assert_debug_snapshot_matches!("rename_mod_in_dir", &source_change);
+        let file_scope = SearchScope::File(position.file_id);
+        let refs = analysis.find_all_refs(position, Some(file_scope)).unwrap().unwrap();
+        assert_eq!(refs.len(), 2);
+        let refs = analysis.find_all_refs(position, Some(SearchScope::Crate)).unwrap().unwrap();
+        assert_eq!(refs.len(), 3);
     }
 
-    fn test_rename(text: &str, new_name: &str, expected: &str) {
+    fn get_all_refs(text: &str) -> ReferenceSearchResult {
         let (analysis, position) = single_file_with_position(text);
-        let source_change = analysis.rename(position, new_name).unwrap();
-        let mut text_edit_builder = ra_text_edit::TextEditBuilder::default();
-        let mut file_id: Option<FileId> = None;
-        if let Some(change) = source_change {
-            for edit in change.source_file_edits {
-                file_id = Some(edit.file_id);
-                for atom in edit.edit.as_atoms() {
-                    text_edit_builder.replace(atom.delete, atom.insert.clone());
-                }
-            }
-        }
-        let result = text_edit_builder.finish().apply(&*analysis.file_text(file_id.unwrap()));
-        assert_eq_text!(expected, &*result);
+        analysis.find_all_refs(position, None).unwrap().unwrap()
     }
 }
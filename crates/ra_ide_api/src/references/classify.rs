@@ -0,0 +1,156 @@
+use hir::source_binder;
+use ra_syntax::{
+    algo::visit::{visitor, Visitor},
+    ast::{self, DocCommentsOwner},
+    AstNode,
+};
+
+use crate::{
+    db::RootDatabase,
+    name_ref_kind::{classify_name_ref, NameRefKind},
+    FileId, NavigationTarget,
+};
+
+/// An item, field or macro whose references can be searched for across files.
+///
+/// Locals are handled separately, as their references never leave the body
+/// they are defined in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum NameDefinition {
+    Macro(hir::MacroDef),
+    Field(hir::StructField),
+    ModuleDef(hir::ModuleDef),
+}
+
+impl NameDefinition {
+    pub(crate) fn declaration(&self, db: &RootDatabase) -> Option<NavigationTarget> {
+        match *self {
+            NameDefinition::Macro(it) => Some(NavigationTarget::from_macro_def(db, it)),
+            NameDefinition::Field(it) => Some(NavigationTarget::from_field(db, it)),
+            NameDefinition::ModuleDef(hir::ModuleDef::Module(it)) => {
+                // Point at the name in `mod foo;`, the crate root has no name to search for
+                let src = it.declaration_source(db)?;
+                Some(NavigationTarget::from_named(
+                    src.file_id.as_original_file(),
+                    &*src.ast,
+                    src.ast.doc_comment_text(),
+                    None,
+                ))
+            }
+            NameDefinition::ModuleDef(it) => NavigationTarget::from_def(db, it),
+        }
+    }
+
+    /// Only fields and statics can be written to, references to anything
+    /// else are neither reads nor writes.
+    pub(crate) fn has_access(&self) -> bool {
+        match self {
+            NameDefinition::Field(_) | NameDefinition::ModuleDef(hir::ModuleDef::Static(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Classifies the definition introduced by `name`.
+pub(crate) fn classify_name(
+    db: &RootDatabase,
+    file_id: FileId,
+    name: &ast::Name,
+) -> Option<NameDefinition> {
+    let parent = name.syntax().parent()?;
+    let module = source_binder::module_from_child_node(db, file_id, parent)?;
+    let text = name.text();
+
+    let find_variant = |variant: &ast::EnumVariant| -> Option<hir::EnumVariant> {
+        let enum_def = variant.syntax().ancestors().find_map(ast::EnumDef::cast)?;
+        let variant_name = variant.name()?;
+        source_binder::enum_from_module(db, module, enum_def)
+            .variants(db)
+            .into_iter()
+            .find(|it| it.name(db).map_or(false, |n| n.as_smolstr() == variant_name.text()))
+    };
+
+    visitor()
+        .visit(|it: &ast::Module| {
+            let module = source_binder::module_from_declaration(db, file_id, it)?;
+            Some(NameDefinition::ModuleDef(module.into()))
+        })
+        .visit(|it: &ast::FnDef| {
+            let def = source_binder::function_from_module(db, module, it);
+            Some(NameDefinition::ModuleDef(def.into()))
+        })
+        .visit(|it: &ast::StructDef| {
+            let def = source_binder::struct_from_module(db, module, it);
+            Some(NameDefinition::ModuleDef(def.into()))
+        })
+        .visit(|it: &ast::EnumDef| {
+            let def = source_binder::enum_from_module(db, module, it);
+            Some(NameDefinition::ModuleDef(def.into()))
+        })
+        .visit(|it: &ast::TraitDef| {
+            let def = source_binder::trait_from_module(db, module, it);
+            Some(NameDefinition::ModuleDef(def.into()))
+        })
+        .visit(|it: &ast::ConstDef| {
+            let def = source_binder::const_from_module(db, module, it);
+            Some(NameDefinition::ModuleDef(def.into()))
+        })
+        .visit(|it: &ast::StaticDef| {
+            let def = source_binder::static_from_module(db, module, it);
+            Some(NameDefinition::ModuleDef(def.into()))
+        })
+        .visit(|it: &ast::TypeAliasDef| {
+            let def = source_binder::type_alias_from_module(db, module, it);
+            Some(NameDefinition::ModuleDef(def.into()))
+        })
+        .visit(|it: &ast::EnumVariant| {
+            let def = find_variant(it)?;
+            Some(NameDefinition::ModuleDef(def.into()))
+        })
+        .visit(|it: &ast::MacroCall| {
+            let def = source_binder::macro_from_definition(db, file_id, it)?;
+            Some(NameDefinition::Macro(def))
+        })
+        .visit(|it: &ast::NamedFieldDef| {
+            let fields = match it.syntax().ancestors().find_map(ast::EnumVariant::cast) {
+                Some(variant) => find_variant(variant)?.fields(db),
+                None => {
+                    let struct_def = it.syntax().ancestors().find_map(ast::StructDef::cast)?;
+                    source_binder::struct_from_module(db, module, struct_def).fields(db)
+                }
+            };
+            let field = fields.into_iter().find(|f| f.name(db).as_smolstr() == text)?;
+            Some(NameDefinition::Field(field))
+        })
+        .accept(parent)?
+}
+
+/// Classifies the definition `name_ref` refers to, if it is an item, a field
+/// or a macro.
+pub(crate) fn classify_reference(
+    db: &RootDatabase,
+    file_id: FileId,
+    name_ref: &ast::NameRef,
+) -> Option<NameDefinition> {
+    let analyzer = hir::SourceAnalyzer::new(db, file_id, name_ref.syntax(), None);
+    let def = match classify_name_ref(db, &analyzer, name_ref)? {
+        NameRefKind::Method(it) => NameDefinition::ModuleDef(it.into()),
+        NameRefKind::Macro(it) => NameDefinition::Macro(it),
+        NameRefKind::FieldAccess(it) => NameDefinition::Field(it),
+        NameRefKind::AssocItem(it) => NameDefinition::ModuleDef(match it {
+            hir::ImplItem::Method(it) => it.into(),
+            hir::ImplItem::Const(it) => it.into(),
+            hir::ImplItem::TypeAlias(it) => it.into(),
+        }),
+        NameRefKind::Def(it) => NameDefinition::ModuleDef(it),
+        NameRefKind::SelfType(ty) => match ty.as_adt()?.0 {
+            hir::AdtDef::Struct(it) => NameDefinition::ModuleDef(it.into()),
+            hir::AdtDef::Union(it) => NameDefinition::ModuleDef(it.into()),
+            hir::AdtDef::Enum(it) => NameDefinition::ModuleDef(it.into()),
+        },
+        NameRefKind::Pat(_) | NameRefKind::SelfParam(_) | NameRefKind::GenericParam(_) => {
+            return None
+        }
+    };
+    Some(def)
+}
@@ -0,0 +1,625 @@
+use std::{collections::BTreeMap, fmt};
+
+use hir::ModuleSource;
+use ra_db::SourceDatabase;
+use ra_syntax::{algo::find_node_at_offset, ast, tokenize, AstNode, SourceFile, SyntaxKind};
+use ra_text_edit::TextEditBuilder;
+use relative_path::{RelativePath, RelativePathBuf};
+
+use crate::{
+    db::RootDatabase, symbol_index::SymbolsDatabase, FileId, FilePosition, FileRange,
+    FileSystemEdit, SourceChange, SourceFileEdit,
+};
+
+use super::{
+    classify::NameDefinition, find_binding, find_definition, find_refs_to_def, SearchScope,
+};
+
+/// The reason a rename was refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenameError(pub(crate) String);
+
+impl fmt::Display for RenameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+pub(crate) fn rename(
+    db: &RootDatabase,
+    position: FilePosition,
+    new_name: &str,
+) -> Result<Option<SourceChange>, RenameError> {
+    check_identifier(new_name)?;
+
+    let source_file = db.parse(position.file_id).tree;
+    if let Some((binding, analyzer)) = find_binding(db, &source_file, position) {
+        return rename_local(db, position.file_id, &source_file, binding, &analyzer, new_name)
+            .map(Some);
+    }
+
+    match find_definition(db, &source_file, position) {
+        Some(def) => rename_def(db, def, new_name),
+        None => Ok(None),
+    }
+}
+
+fn check_identifier(new_name: &str) -> Result<(), RenameError> {
+    let tokens = tokenize(new_name);
+    match tokens.as_slice() {
+        [token] if token.kind == SyntaxKind::IDENT => Ok(()),
+        _ => Err(RenameError(format!("Invalid name `{}`: not an identifier", new_name))),
+    }
+}
+
+fn name_conflict(new_name: &str) -> RenameError {
+    RenameError(format!("Cannot rename to `{}`: the name is already used in this scope", new_name))
+}
+
+fn rename_local(
+    db: &RootDatabase,
+    file_id: FileId,
+    source_file: &SourceFile,
+    binding: &ast::BindPat,
+    analyzer: &hir::SourceAnalyzer,
+    new_name: &str,
+) -> Result<SourceChange, RenameError> {
+    let name = match binding.name() {
+        Some(name) => name,
+        None => return Err(RenameError("Cannot rename a binding without a name".to_string())),
+    };
+    let old_name = name.text();
+    let mut edit = TextEditBuilder::default();
+
+    if binding.syntax().parent().and_then(ast::FieldPatList::cast).is_some() {
+        // `Foo { x }` binds the field of the same name, so it has to be spelled out
+        let pat_range = binding.syntax().range();
+        let pat_text = binding.syntax().text().to_string();
+        let start = (name.syntax().range().start() - pat_range.start()).to_usize();
+        let end = (name.syntax().range().end() - pat_range.start()).to_usize();
+        let new_pat = format!("{}{}{}", &pat_text[..start], new_name, &pat_text[end..]);
+        edit.replace(pat_range, format!("{}: {}", old_name, new_pat));
+    } else {
+        edit.replace(name.syntax().range(), new_name.to_string());
+    }
+
+    for reference in analyzer.find_all_refs(binding) {
+        let name_ref = match find_node_at_offset::<ast::NameRef>(
+            source_file.syntax(),
+            reference.range.start(),
+        ) {
+            Some(name_ref) => name_ref,
+            None => continue,
+        };
+        // Another local with the new name would capture this reference
+        let analyzer = hir::SourceAnalyzer::new(db, file_id, name_ref.syntax(), None);
+        let captured = analyzer.all_names(db).into_iter().any(|(name, res)| {
+            name.as_smolstr() == new_name
+                && match res.values {
+                    Some(hir::Resolution::LocalBinding(_)) => true,
+                    _ => false,
+                }
+        });
+        if captured {
+            return Err(name_conflict(new_name));
+        }
+
+        let replacement = if is_shorthand_field(name_ref) {
+            format!("{}: {}", old_name, new_name)
+        } else {
+            new_name.to_string()
+        };
+        edit.replace(reference.range, replacement);
+    }
+
+    let edit = SourceFileEdit { file_id, edit: edit.finish() };
+    Ok(SourceChange::source_file_edit("rename", edit))
+}
+
+fn rename_def(
+    db: &RootDatabase,
+    def: NameDefinition,
+    new_name: &str,
+) -> Result<Option<SourceChange>, RenameError> {
+    check_conflicts(db, def, new_name)?;
+
+    // Methods of a trait and of its impls have to be renamed together
+    let defs = match def {
+        NameDefinition::ModuleDef(hir::ModuleDef::Function(func)) => related_functions(db, func)
+            .into_iter()
+            .map(|it| NameDefinition::ModuleDef(it.into()))
+            .collect(),
+        _ => vec![def],
+    };
+
+    let mut edits: BTreeMap<FileId, TextEditBuilder> = BTreeMap::new();
+    let mut file_system_edits = Vec::new();
+    for def in defs {
+        let declaration = match def.declaration(db) {
+            Some(it) => it,
+            None => continue,
+        };
+        let old_name = declaration.name().clone();
+        if old_name.is_empty() {
+            return Ok(None);
+        }
+        let decl_file = declaration.file_id();
+        if !db.local_roots().contains(&db.file_source_root(decl_file)) {
+            return Err(RenameError(format!(
+                "Cannot rename `{}`: it is defined outside of the workspace",
+                old_name
+            )));
+        }
+
+        edits.entry(decl_file).or_default().replace(declaration.range(), new_name.to_string());
+
+        let files = SearchScope::default().files(db, decl_file);
+        for reference in find_refs_to_def(db, def, &old_name, files) {
+            let FileRange { file_id, range } = reference.file_range;
+            let source_file = db.parse(file_id).tree;
+            let shorthand = match def {
+                NameDefinition::Field(_) => {
+                    find_node_at_offset::<ast::NameRef>(source_file.syntax(), range.start())
+                        .map_or(false, is_shorthand_field)
+                }
+                _ => false,
+            };
+            let replacement = if shorthand {
+                format!("{}: {}", new_name, old_name)
+            } else {
+                new_name.to_string()
+            };
+            edits.entry(file_id).or_default().replace(range, replacement);
+        }
+
+        if let NameDefinition::ModuleDef(hir::ModuleDef::Module(module)) = def {
+            file_system_edits.extend(move_module_file(db, module, new_name));
+        }
+    }
+
+    if edits.is_empty() {
+        return Ok(None);
+    }
+    let source_file_edits = edits
+        .into_iter()
+        .map(|(file_id, edit)| SourceFileEdit { file_id, edit: edit.finish() })
+        .collect();
+    Ok(Some(SourceChange::from_edits("rename", source_file_edits, file_system_edits)))
+}
+
+/// `Foo { x }` in a struct literal refers both to the field and to a local.
+fn is_shorthand_field(name_ref: &ast::NameRef) -> bool {
+    name_ref
+        .syntax()
+        .parent()
+        .and_then(ast::NamedField::cast)
+        .map_or(false, |field| field.expr().is_none())
+}
+
+fn check_conflicts(
+    db: &RootDatabase,
+    def: NameDefinition,
+    new_name: &str,
+) -> Result<(), RenameError> {
+    let is_new_name =
+        |name: Option<hir::Name>| name.map_or(false, |it| it.as_smolstr() == new_name);
+    let conflict = match def {
+        NameDefinition::Macro(_) => false,
+        NameDefinition::Field(field) => {
+            let fields = match field.parent_def(db) {
+                hir::VariantDef::Struct(it) => it.fields(db),
+                hir::VariantDef::EnumVariant(it) => it.fields(db),
+            };
+            fields.into_iter().any(|it| is_new_name(Some(it.name(db))))
+        }
+        NameDefinition::ModuleDef(hir::ModuleDef::EnumVariant(variant)) => {
+            variant.parent_enum(db).variants(db).into_iter().any(|it| is_new_name(it.name(db)))
+        }
+        NameDefinition::ModuleDef(module_def) => {
+            let container = match module_def {
+                hir::ModuleDef::Function(it) => it.container(db),
+                hir::ModuleDef::Const(it) => it.impl_block(db).map(hir::Container::from),
+                hir::ModuleDef::TypeAlias(it) => it.container(db),
+                _ => None,
+            };
+            match container {
+                Some(hir::Container::ImplBlock(impl_block)) => {
+                    impl_block.items(db).into_iter().any(|it| is_new_name(impl_item_name(db, it)))
+                }
+                Some(hir::Container::Trait(trait_)) => {
+                    trait_.items(db).into_iter().any(|it| is_new_name(trait_item_name(db, it)))
+                }
+                None => match module_of(db, module_def) {
+                    Some(module) => {
+                        module.scope(db).entries().any(|(name, _)| is_new_name(Some(name.clone())))
+                    }
+                    None => false,
+                },
+            }
+        }
+    };
+    if conflict {
+        Err(name_conflict(new_name))
+    } else {
+        Ok(())
+    }
+}
+
+fn module_of(db: &RootDatabase, def: hir::ModuleDef) -> Option<hir::Module> {
+    let module = match def {
+        hir::ModuleDef::Module(it) => return it.parent(db),
+        hir::ModuleDef::Function(it) => it.module(db),
+        hir::ModuleDef::Struct(it) => it.module(db),
+        hir::ModuleDef::Union(it) => it.module(db),
+        hir::ModuleDef::Enum(it) => it.module(db),
+        hir::ModuleDef::EnumVariant(it) => it.module(db),
+        hir::ModuleDef::Const(it) => it.module(db),
+        hir::ModuleDef::Static(it) => it.module(db),
+        hir::ModuleDef::Trait(it) => it.module(db),
+        hir::ModuleDef::TypeAlias(it) => it.module(db),
+        hir::ModuleDef::BuiltinType(_) => return None,
+    };
+    Some(module)
+}
+
+fn impl_item_name(db: &RootDatabase, item: hir::ImplItem) -> Option<hir::Name> {
+    match item {
+        hir::ImplItem::Method(it) => Some(it.name(db)),
+        hir::ImplItem::Const(it) => Some(it.data(db).name().clone()),
+        hir::ImplItem::TypeAlias(it) => Some(it.name(db)),
+    }
+}
+
+fn trait_item_name(db: &RootDatabase, item: hir::TraitItem) -> Option<hir::Name> {
+    match item {
+        hir::TraitItem::Function(it) => Some(it.name(db)),
+        hir::TraitItem::Const(it) => Some(it.data(db).name().clone()),
+        hir::TraitItem::TypeAlias(it) => Some(it.name(db)),
+    }
+}
+
+/// For a method of a trait or of a trait impl, returns the method of the
+/// trait together with the methods of all its impls in the workspace.
+pub(crate) fn related_functions(db: &RootDatabase, func: hir::Function) -> Vec<hir::Function> {
+    let trait_ = func.parent_trait(db).or_else(|| {
+        let impl_block = func.impl_block(db)?;
+        Some(impl_block.target_trait_ref(db)?.trait_)
+    });
+    let trait_ = match trait_ {
+        Some(it) => it,
+        None => return vec![func],
+    };
+    let name = func.name(db);
+
+    let mut res = Vec::new();
+    for item in trait_.items(db) {
+        if let hir::TraitItem::Function(it) = item {
+            if it.name(db) == name {
+                res.push(it);
+            }
+        }
+    }
+
+    // Impls of the trait are found by looking at the modules which mention it
+    let trait_def = NameDefinition::ModuleDef(trait_.into());
+    if let Some(declaration) = trait_def.declaration(db) {
+        let files = SearchScope::default().files(db, declaration.file_id());
+        let mut modules = Vec::new();
+        for reference in find_refs_to_def(db, trait_def, declaration.name(), files) {
+            let FileRange { file_id, range } = reference.file_range;
+            let source_file = db.parse(file_id).tree;
+            let module = find_node_at_offset::<ast::NameRef>(source_file.syntax(), range.start())
+                .and_then(|it| {
+                    hir::source_binder::module_from_child_node(db, file_id, it.syntax())
+                });
+            if let Some(module) = module {
+                if !modules.contains(&module) {
+                    modules.push(module);
+                }
+            }
+        }
+        for impl_block in modules.into_iter().flat_map(|it| it.impl_blocks(db)) {
+            if impl_block.target_trait_ref(db).map(|it| it.trait_) != Some(trait_) {
+                continue;
+            }
+            for item in impl_block.items(db) {
+                if let hir::ImplItem::Method(it) = item {
+                    if it.name(db) == name && !res.contains(&it) {
+                        res.push(it);
+                    }
+                }
+            }
+        }
+    }
+
+    if !res.contains(&func) {
+        res.push(func);
+    }
+    res
+}
+
+fn move_module_file(
+    db: &RootDatabase,
+    module: hir::Module,
+    new_name: &str,
+) -> Option<FileSystemEdit> {
+    let src = module.definition_source(db);
+    let file_id = src.file_id.as_original_file();
+    match src.ast {
+        ModuleSource::SourceFile(..) => {
+            let mod_path: RelativePathBuf = db.file_relative_path(file_id);
+            // mod is defined in path/to/dir/mod.rs
+            let dst_path = if mod_path.file_stem() == Some("mod") {
+                mod_path
+                    .parent()
+                    .and_then(|p| p.parent())
+                    .or_else(|| Some(RelativePath::new("")))
+                    .map(|p| p.join(new_name).join("mod.rs"))?
+            } else {
+                mod_path.with_file_name(new_name).with_extension("rs")
+            };
+            Some(FileSystemEdit::MoveFile {
+                src: file_id,
+                dst_source_root: db.file_source_root(file_id),
+                dst_path,
+            })
+        }
+        ModuleSource::Module(..) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock_analysis::{
+        analysis_and_position, check_source_change, single_file_with_position,
+    };
+    use crate::FileId;
+    use insta::assert_debug_snapshot_matches;
+    use test_utils::assert_eq_text;
+
+    #[test]
+    fn test_rename_for_local() {
+        test_rename(
+            r#"
+    fn main() {
+        let mut i = 1;
+        let j = 1;
+        i = i<|> + j;
+
+        {
+            i = 0;
+        }
+
+        i = 5;
+    }"#,
+            "k",
+            r#"
+    fn main() {
+        let mut k = 1;
+        let j = 1;
+        k = k + j;
+
+        {
+            k = 0;
+        }
+
+        k = 5;
+    }"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_for_param_inside() {
+        test_rename(
+            r#"
+    fn foo(i : u32) -> u32 {
+        i<|>
+    }"#,
+            "j",
+            r#"
+    fn foo(j : u32) -> u32 {
+        j
+    }"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_refs_for_fn_param() {
+        test_rename(
+            r#"
+    fn foo(i<|> : u32) -> u32 {
+        i
+    }"#,
+            "new_name",
+            r#"
+    fn foo(new_name : u32) -> u32 {
+        new_name
+    }"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_for_mut_param() {
+        test_rename(
+            r#"
+    fn foo(mut i<|> : u32) -> u32 {
+        i
+    }"#,
+            "new_name",
+            r#"
+    fn foo(mut new_name : u32) -> u32 {
+        new_name
+    }"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_mod() {
+        let (analysis, position) = analysis_and_position(
+            "
+            //- /lib.rs
+            mod bar;
+
+            //- /bar.rs
+            mod foo<|>;
+
+            //- /bar/foo.rs
+            // emtpy
+            ",
+        );
+        let new_name = "foo2";
+        let source_change = analysis.rename(position, new_name).unwrap().unwrap();
+        assert_debug_snapshot_matches!("rename_mod", &source_change);
+    }
+
+    #[test]
+    fn test_rename_mod_in_dir() {
+        let (analysis, position) = analysis_and_position(
+            "
+            //- /lib.rs
+            mod fo<|>o;
+            //- /foo/mod.rs
+            // emtpy
+            ",
+        );
+        let new_name = "foo2";
+        let source_change = analysis.rename(position, new_name).unwrap().unwrap();
+        assert_debug_snapshot_matches!("rename_mod_in_dir", &source_change);
+    }
+
+    #[test]
+    fn test_rename_struct_across_files() {
+        check_rename_in_files(
+            "
+            //- /lib.rs
+            mod foo;
+            pub struct Foo<|> {}
+            fn new() -> Foo { Foo {} }
+            //- /foo.rs
+            use crate::Foo;
+            fn take(foo: Foo) {}
+            ",
+            "Bar",
+            &[
+                ("/lib.rs", "mod foo;\npub struct Bar {}\nfn new() -> Bar { Bar {} }"),
+                ("/foo.rs", "use crate::Bar;\nfn take(foo: Bar) {}"),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_rename_field_in_shorthand() {
+        test_rename(
+            r#"
+    struct Foo { i<|>: u32 }
+    fn new(i: u32) -> Foo { Foo { i } }
+    fn get(foo: Foo) -> u32 { foo.i }"#,
+            "j",
+            r#"
+    struct Foo { j: u32 }
+    fn new(i: u32) -> Foo { Foo { j: i } }
+    fn get(foo: Foo) -> u32 { foo.j }"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_local_in_shorthand() {
+        test_rename(
+            r#"
+    struct Foo { i: u32 }
+    fn new(i<|>: u32) -> Foo { Foo { i } }"#,
+            "j",
+            r#"
+    struct Foo { i: u32 }
+    fn new(j: u32) -> Foo { Foo { i: j } }"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_trait_method_with_impls() {
+        test_rename(
+            r#"
+    trait Foo { fn foo(&self); }
+    struct S;
+    impl Foo for S { fn foo<|>(&self) {} }
+    fn f(s: S) { s.foo() }"#,
+            "bar",
+            r#"
+    trait Foo { fn bar(&self); }
+    struct S;
+    impl Foo for S { fn bar(&self) {} }
+    fn f(s: S) { s.bar() }"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_enum_variant() {
+        test_rename(
+            r#"
+    enum E { A<|>, B }
+    fn f() -> E { E::A }"#,
+            "C",
+            r#"
+    enum E { C, B }
+    fn f() -> E { E::C }"#,
+        );
+    }
+
+    #[test]
+    fn test_rename_refuses_invalid_identifier() {
+        let (analysis, position) = single_file_with_position(
+            r#"
+    fn main() {
+        let i<|> = 1;
+    }"#,
+        );
+        assert!(analysis.rename(position, "fn").unwrap().is_err());
+        assert!(analysis.rename(position, "1i").unwrap().is_err());
+        assert!(analysis.rename(position, "a b").unwrap().is_err());
+    }
+
+    #[test]
+    fn test_rename_refuses_conflicts() {
+        let (analysis, position) = single_file_with_position(
+            r#"
+    struct Foo;
+    struct Bar<|>;"#,
+        );
+        assert!(analysis.rename(position, "Foo").unwrap().is_err());
+
+        let (analysis, position) = single_file_with_position(
+            r#"
+    fn main() {
+        let a = 1;
+        let b<|> = 2;
+        a + b;
+    }"#,
+        );
+        assert!(analysis.rename(position, "a").unwrap().is_err());
+    }
+
+    fn test_rename(text: &str, new_name: &str, expected: &str) {
+        let (analysis, position) = single_file_with_position(text);
+        let source_change = analysis.rename(position, new_name).unwrap().unwrap();
+        let mut text_edit_builder = ra_text_edit::TextEditBuilder::default();
+        let mut file_id: Option<FileId> = None;
+        if let Some(change) = source_change {
+            for edit in change.source_file_edits {
+                file_id = Some(edit.file_id);
+                for atom in edit.edit.as_atoms() {
+                    text_edit_builder.replace(atom.delete, atom.insert.clone());
+                }
+            }
+        }
+        let result = text_edit_builder.finish().apply(&*analysis.file_text(file_id.unwrap()));
+        assert_eq_text!(expected, &*result);
+    }
+
+    fn check_rename_in_files(fixture: &str, new_name: &str, expected: &[(&str, &str)]) {
+        check_source_change(
+            fixture,
+            |analysis, position| analysis.rename(position, new_name).unwrap().unwrap().unwrap(),
+            expected,
+        );
+    }
+}
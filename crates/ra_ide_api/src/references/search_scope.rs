@@ -0,0 +1,66 @@
+use hir::source_binder;
+use ra_db::{SourceDatabase, SourceRootId};
+
+use crate::{db::RootDatabase, symbol_index::SymbolsDatabase, FileId};
+
+/// Which files to look at when searching for references to an item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchScope {
+    /// Only the given file.
+    File(FileId),
+    /// All files of the crate the item is declared in.
+    Crate,
+    /// All files of the workspace.
+    Workspace,
+    /// All files of the workspace and of its dependencies, including the sysroot.
+    WorkspaceAndLibraries,
+}
+
+impl Default for SearchScope {
+    fn default() -> SearchScope {
+        SearchScope::Workspace
+    }
+}
+
+impl SearchScope {
+    /// Lists the files to search for an item declared in `decl_file`, sorted
+    /// to keep the order of the results stable.
+    pub(crate) fn files(self, db: &RootDatabase, decl_file: FileId) -> Vec<FileId> {
+        let mut res = match self {
+            SearchScope::File(file_id) => return vec![file_id],
+            SearchScope::Crate => {
+                let krate = match crate_of_file(db, decl_file) {
+                    Some(krate) => krate,
+                    None => return vec![decl_file],
+                };
+                let source_root = db.source_root(db.file_source_root(decl_file));
+                source_root
+                    .files
+                    .values()
+                    .copied()
+                    .filter(|&file_id| crate_of_file(db, file_id) == Some(krate))
+                    .collect()
+            }
+            SearchScope::Workspace => files_of_roots(db, &db.local_roots()),
+            SearchScope::WorkspaceAndLibraries => {
+                let mut files = files_of_roots(db, &db.local_roots());
+                files.extend(files_of_roots(db, &db.library_roots()));
+                files
+            }
+        };
+        res.sort();
+        res
+    }
+}
+
+fn crate_of_file(db: &RootDatabase, file_id: FileId) -> Option<hir::Crate> {
+    source_binder::module_from_file_id(db, file_id)?.krate(db)
+}
+
+fn files_of_roots(db: &RootDatabase, roots: &[SourceRootId]) -> Vec<FileId> {
+    let mut files = Vec::new();
+    for &root in roots {
+        files.extend(db.source_root(root).files.values().copied());
+    }
+    files
+}
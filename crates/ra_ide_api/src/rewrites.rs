@@ -0,0 +1,139 @@
+//! Edits for refactorings which rewrite usages of an item across files.
+//!
+//! A usage may contain another usage, as in `Foo(Foo(1).0)` or
+//! `f(f(1, 2), 3)`, so a rewrite refers to the original text it keeps by
+//! range. The rewrites nested in such a range are applied when the outer
+//! rewrite is rendered, which keeps the edits of a file disjoint.
+
+use std::{cmp::Reverse, collections::BTreeMap};
+
+use ra_db::SourceDatabase;
+use ra_syntax::{TextRange, TextUnit};
+use ra_text_edit::TextEditBuilder;
+
+use crate::{db::RootDatabase, FileId, SourceFileEdit};
+
+pub(crate) enum Piece {
+    Text(String),
+    /// The original text of the range, with the rewrites inside of it applied.
+    Source(TextRange),
+}
+
+pub(crate) struct Rewrite {
+    pub(crate) range: TextRange,
+    pub(crate) pieces: Vec<Piece>,
+}
+
+impl Rewrite {
+    pub(crate) fn text(range: TextRange, text: String) -> Rewrite {
+        Rewrite { range, pieces: vec![Piece::Text(text)] }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Rewrites(BTreeMap<FileId, Vec<Rewrite>>);
+
+impl Rewrites {
+    pub(crate) fn add(&mut self, file_id: FileId, rewrite: Rewrite) {
+        self.0.entry(file_id).or_default().push(rewrite);
+    }
+
+    pub(crate) fn into_edits(self, db: &RootDatabase) -> Vec<SourceFileEdit> {
+        self.0
+            .into_iter()
+            .map(|(file_id, mut rewrites)| {
+                let text = db.file_text(file_id);
+                rewrites.sort_by_key(|it| (it.range.start(), Reverse(it.range.end())));
+                let mut edit = TextEditBuilder::default();
+                let mut end = TextUnit::from(0);
+                for rewrite in rewrites.iter() {
+                    // Nested rewrites are applied while rendering the outer one
+                    if rewrite.range.start() < end {
+                        continue;
+                    }
+                    edit.replace(rewrite.range, render(&text, &rewrite.pieces, &rewrites));
+                    end = rewrite.range.end();
+                }
+                SourceFileEdit { file_id, edit: edit.finish() }
+            })
+            .collect()
+    }
+}
+
+fn render(text: &str, pieces: &[Piece], rewrites: &[Rewrite]) -> String {
+    let mut buf = String::new();
+    for piece in pieces {
+        let range = match piece {
+            Piece::Text(it) => {
+                buf.push_str(it);
+                continue;
+            }
+            Piece::Source(range) => *range,
+        };
+        let mut pos = range.start();
+        for rewrite in rewrites.iter().filter(|it| it.range.is_subrange(&range)) {
+            if rewrite.range.start() < pos {
+                continue;
+            }
+            buf.push_str(slice(text, TextRange::from_to(pos, rewrite.range.start())));
+            buf.push_str(&render(text, &rewrite.pieces, rewrites));
+            pos = rewrite.range.end();
+        }
+        buf.push_str(slice(text, TextRange::from_to(pos, range.end())));
+    }
+    buf
+}
+
+pub(crate) fn slice(text: &str, range: TextRange) -> &str {
+    &text[range.start().to_usize()..range.end().to_usize()]
+}
+
+/// The indentation of the line the range starts on, if the range spans
+/// several lines.
+pub(crate) fn multiline_indent(text: &str, range: TextRange) -> Option<String> {
+    if !slice(text, range).contains('\n') {
+        return None;
+    }
+    let line_start = text[..range.start().to_usize()].rfind('\n').map_or(0, |it| it + 1);
+    let indent = text[line_start..].chars().take_while(|it| *it == ' ' || *it == '\t').collect();
+    Some(indent)
+}
+
+/// Lays out a delimited list on a single line, or on a line each if the
+/// original list spans several lines. Padded lists have a space inside of the
+/// delimiters, as in `{ a, b }`.
+pub(crate) fn delimited_list(
+    (open, close): (&str, &str),
+    padded: bool,
+    items: Vec<Vec<Piece>>,
+    indent: Option<String>,
+) -> Vec<Piece> {
+    let mut res = vec![Piece::Text(open.to_string())];
+    match indent {
+        Some(indent) if !items.is_empty() => {
+            for item in items {
+                res.push(Piece::Text(format!("\n{}    ", indent)));
+                res.extend(item);
+                res.push(Piece::Text(",".to_string()));
+            }
+            res.push(Piece::Text(format!("\n{}{}", indent, close)));
+        }
+        _ => {
+            let is_empty = items.is_empty();
+            for (idx, item) in items.into_iter().enumerate() {
+                let separator = match idx {
+                    0 if padded => " ",
+                    0 => "",
+                    _ => ", ",
+                };
+                res.push(Piece::Text(separator.to_string()));
+                res.extend(item);
+            }
+            if padded && !is_empty {
+                res.push(Piece::Text(" ".to_string()));
+            }
+            res.push(Piece::Text(close.to_string()));
+        }
+    }
+    res
+}
@@ -45,6 +45,9 @@ use crate::{db::RootDatabase, FileId, Query};
 #[salsa::query_group(SymbolsDatabaseStorage)]
 pub(crate) trait SymbolsDatabase: hir::db::HirDatabase {
     fn file_symbols(&self, file_id: FileId) -> Arc<SymbolIndex>;
+    /// The names referred to in a file, so that searching for the references
+    /// to an item only needs to parse the files which mention its name.
+    fn file_name_refs(&self, file_id: FileId) -> Arc<NameRefIndex>;
     #[salsa::input]
     fn library_symbols(&self, id: SourceRootId) -> Arc<SymbolIndex>;
     /// The set of "local" (that is, from the current workspace) roots.
@@ -68,6 +71,20 @@ fn file_symbols(db: &impl SymbolsDatabase, file_id: FileId) -> Arc<SymbolIndex>
     Arc::new(SymbolIndex::new(symbols))
 }
 
+fn file_name_refs(db: &impl SymbolsDatabase, file_id: FileId) -> Arc<NameRefIndex> {
+    db.check_canceled();
+    let source_file = db.parse(file_id).tree;
+    let mut names = source_file
+        .syntax()
+        .descendants()
+        .filter_map(ast::NameRef::cast)
+        .map(|name_ref| name_ref.text().clone())
+        .collect::<Vec<_>>();
+    names.sort();
+    names.dedup();
+    Arc::new(NameRefIndex { names })
+}
+
 pub(crate) fn world_symbols(db: &RootDatabase, query: Query) -> Vec<FileSymbol> {
     /// Need to wrap Snapshot to provide `Clone` impl for `map_with`
     struct Snap(salsa::Snapshot<RootDatabase>);
@@ -104,6 +121,18 @@ pub(crate) fn index_resolve(db: &RootDatabase, name_ref: &ast::NameRef) -> Vec<F
     crate::symbol_index::world_symbols(db, query)
 }
 
+/// The sorted, deduplicated names of the `NameRef`s in a file.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct NameRefIndex {
+    names: Vec<SmolStr>,
+}
+
+impl NameRefIndex {
+    pub(crate) fn contains(&self, name: &str) -> bool {
+        self.names.binary_search_by(|it| it.as_str().cmp(name)).is_ok()
+    }
+}
+
 #[derive(Default)]
 pub(crate) struct SymbolIndex {
     symbols: Vec<FileSymbol>,
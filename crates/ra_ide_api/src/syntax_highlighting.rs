@@ -1,20 +1,194 @@
+use std::fmt;
+
+use hir::{HasSource, HirFileId};
 use rustc_hash::{FxHashMap, FxHashSet};
 
 use ra_db::SourceDatabase;
 use ra_prof::profile;
 use ra_syntax::{
-    ast, AstNode, Direction, SmolStr, SyntaxElement, SyntaxKind, SyntaxKind::*, TextRange, T,
+    ast::{self, AttrsOwner},
+    AstNode, Direction, SmolStr, SyntaxElement, SyntaxKind,
+    SyntaxKind::*,
+    SyntaxNode, SyntaxToken, TextRange, TreeArc, T,
 };
 
-use crate::{db::RootDatabase, FileId};
+use crate::{
+    db::RootDatabase,
+    name_ref_kind::{classify_name_ref, NameRefKind},
+    references::{classify_name, NameDefinition},
+    FileId,
+};
 
 #[derive(Debug)]
 pub struct HighlightedRange {
     pub range: TextRange,
-    pub tag: &'static str,
+    pub highlight: Highlight,
     pub binding_hash: Option<u64>,
 }
 
+/// How a range should be highlighted: what it is, plus any modifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Highlight {
+    pub tag: HighlightTag,
+    pub modifiers: HighlightModifiers,
+}
+
+/// What a highlighted range is. Names are classified by what they resolve to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HighlightTag {
+    Attribute,
+    BuiltinType,
+    Comment,
+    Constant,
+    Enum,
+    EnumVariant,
+    Field,
+    Function,
+    Keyword,
+    KeywordControl,
+    KeywordUnsafe,
+    Lifetime,
+    Literal,
+    Local,
+    Macro,
+    Method,
+    Module,
+    Parameter,
+    SelfKeyword,
+    Static,
+    String,
+    Struct,
+    Trait,
+    TypeAlias,
+    TypeParam,
+    Union,
+    Unresolved,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HighlightModifier {
+    /// A mutable binding, `self` or static.
+    Mutable,
+    /// A call to an unsafe function or method.
+    UnsafeCall,
+    /// The name being defined, rather than a reference to it.
+    Declaration,
+    /// An item defined in a library, outside of the workspace.
+    Library,
+    /// An item marked with `#[deprecated]`.
+    Deprecated,
+}
+
+/// A set of `HighlightModifier`s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct HighlightModifiers(u8);
+
+impl Highlight {
+    fn new(tag: HighlightTag, modifiers: HighlightModifiers) -> Highlight {
+        Highlight { tag, modifiers }
+    }
+}
+
+impl From<HighlightTag> for Highlight {
+    fn from(tag: HighlightTag) -> Highlight {
+        Highlight::new(tag, HighlightModifiers::default())
+    }
+}
+
+impl fmt::Display for Highlight {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.tag)?;
+        for modifier in self.modifiers.iter() {
+            write!(f, " {}", modifier)?;
+        }
+        Ok(())
+    }
+}
+
+impl HighlightTag {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HighlightTag::Attribute => "attribute",
+            HighlightTag::BuiltinType => "builtin_type",
+            HighlightTag::Comment => "comment",
+            HighlightTag::Constant => "constant",
+            HighlightTag::Enum => "enum",
+            HighlightTag::EnumVariant => "enum_variant",
+            HighlightTag::Field => "field",
+            HighlightTag::Function => "function",
+            HighlightTag::Keyword => "keyword",
+            HighlightTag::KeywordControl => "keyword.control",
+            HighlightTag::KeywordUnsafe => "keyword.unsafe",
+            HighlightTag::Lifetime => "lifetime",
+            HighlightTag::Literal => "literal",
+            HighlightTag::Local => "local",
+            HighlightTag::Macro => "macro",
+            HighlightTag::Method => "method",
+            HighlightTag::Module => "module",
+            HighlightTag::Parameter => "parameter",
+            HighlightTag::SelfKeyword => "self_keyword",
+            HighlightTag::Static => "static",
+            HighlightTag::String => "string",
+            HighlightTag::Struct => "struct",
+            HighlightTag::Trait => "trait",
+            HighlightTag::TypeAlias => "type_alias",
+            HighlightTag::TypeParam => "type_param",
+            HighlightTag::Union => "union",
+            HighlightTag::Unresolved => "unresolved",
+        }
+    }
+}
+
+impl fmt::Display for HighlightTag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl HighlightModifier {
+    const ALL: &'static [HighlightModifier] = &[
+        HighlightModifier::Mutable,
+        HighlightModifier::UnsafeCall,
+        HighlightModifier::Declaration,
+        HighlightModifier::Library,
+        HighlightModifier::Deprecated,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HighlightModifier::Mutable => "mutable",
+            HighlightModifier::UnsafeCall => "unsafe_call",
+            HighlightModifier::Declaration => "declaration",
+            HighlightModifier::Library => "library",
+            HighlightModifier::Deprecated => "deprecated",
+        }
+    }
+
+    fn mask(self) -> u8 {
+        1 << (self as u8)
+    }
+}
+
+impl fmt::Display for HighlightModifier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl HighlightModifiers {
+    pub fn contains(self, modifier: HighlightModifier) -> bool {
+        self.0 & modifier.mask() != 0
+    }
+
+    pub fn insert(&mut self, modifier: HighlightModifier) {
+        self.0 |= modifier.mask();
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = HighlightModifier> {
+        HighlightModifier::ALL.iter().cloned().filter(move |it| self.contains(*it))
+    }
+}
+
 fn is_control_keyword(kind: SyntaxKind) -> bool {
     match kind {
         T![for]
@@ -56,97 +230,66 @@ pub(crate) fn highlight(db: &RootDatabase, file_id: FileId) -> Vec<HighlightedRa
             continue;
         }
         let mut binding_hash = None;
-        let tag = match node.kind() {
-            COMMENT => "comment",
-            STRING | RAW_STRING | RAW_BYTE_STRING | BYTE_STRING => "string",
-            ATTR => "attribute",
+        let highlight: Highlight = match node.kind() {
+            COMMENT => HighlightTag::Comment.into(),
+            STRING | RAW_STRING | RAW_BYTE_STRING | BYTE_STRING => HighlightTag::String.into(),
+            ATTR => HighlightTag::Attribute.into(),
             NAME_REF => {
                 if let Some(name_ref) = node.as_node().and_then(ast::NameRef::cast) {
-                    // FIXME: revisit this after #1340
-                    use crate::name_ref_kind::{classify_name_ref, NameRefKind::*};
-                    use hir::{ImplItem, ModuleDef};
-
                     // FIXME: try to reuse the SourceAnalyzers
                     let analyzer = hir::SourceAnalyzer::new(db, file_id, name_ref.syntax(), None);
                     match classify_name_ref(db, &analyzer, name_ref) {
-                        Some(Method(_)) => "function",
-                        Some(Macro(_)) => "macro",
-                        Some(FieldAccess(_)) => "field",
-                        Some(AssocItem(ImplItem::Method(_))) => "function",
-                        Some(AssocItem(ImplItem::Const(_))) => "constant",
-                        Some(AssocItem(ImplItem::TypeAlias(_))) => "type",
-                        Some(Def(ModuleDef::Module(_))) => "module",
-                        Some(Def(ModuleDef::Function(_))) => "function",
-                        Some(Def(ModuleDef::Struct(_))) => "type",
-                        Some(Def(ModuleDef::Union(_))) => "type",
-                        Some(Def(ModuleDef::Enum(_))) => "type",
-                        Some(Def(ModuleDef::EnumVariant(_))) => "constant",
-                        Some(Def(ModuleDef::Const(_))) => "constant",
-                        Some(Def(ModuleDef::Static(_))) => "constant",
-                        Some(Def(ModuleDef::Trait(_))) => "type",
-                        Some(Def(ModuleDef::TypeAlias(_))) => "type",
-                        Some(Def(ModuleDef::BuiltinType(_))) => "type",
-                        Some(SelfType(_)) => "type",
-                        Some(Pat(ptr)) => {
+                        Some(NameRefKind::Pat(ptr)) => {
+                            let pat = ptr.to_node(source_file.syntax());
                             binding_hash = Some({
-                                let text = ptr
-                                    .syntax_node_ptr()
-                                    .to_node(&source_file.syntax())
-                                    .text()
-                                    .to_smol_string();
+                                let text = pat.syntax().text().to_smol_string();
                                 let shadow_count =
                                     bindings_shadow_count.entry(text.clone()).or_default();
                                 calc_binding_hash(file_id, &text, *shadow_count)
                             });
-
-                            "variable"
+                            highlight_pat(pat)
                         }
-                        Some(SelfParam(_)) => "type",
-                        Some(GenericParam(_)) => "type",
-                        None => "text",
+                        Some(kind) => highlight_name_ref_kind(db, name_ref, kind),
+                        None => HighlightTag::Unresolved.into(),
                     }
                 } else {
-                    "text"
+                    HighlightTag::Unresolved.into()
                 }
             }
             NAME => {
                 if let Some(name) = node.as_node().and_then(ast::Name::cast) {
-                    if name.syntax().ancestors().any(|x| ast::BindPat::cast(x).is_some()) {
-                        binding_hash = Some({
-                            let text = name.syntax().text().to_smol_string();
-                            let shadow_count =
-                                bindings_shadow_count.entry(text.clone()).or_insert(0);
-                            *shadow_count += 1;
-                            calc_binding_hash(file_id, &text, *shadow_count)
-                        });
-                        "variable"
-                    } else if name
-                        .syntax()
-                        .parent()
-                        .map(|x| {
-                            x.kind() == TYPE_PARAM
-                                || x.kind() == STRUCT_DEF
-                                || x.kind() == ENUM_DEF
-                                || x.kind() == TRAIT_DEF
-                                || x.kind() == TYPE_ALIAS_DEF
-                        })
-                        .unwrap_or(false)
-                    {
-                        "type"
-                    } else {
-                        "function"
-                    }
+                    let mut highlight =
+                        if let Some(pat) = name.syntax().parent().and_then(ast::BindPat::cast) {
+                            binding_hash = Some({
+                                let text = name.syntax().text().to_smol_string();
+                                let shadow_count =
+                                    bindings_shadow_count.entry(text.clone()).or_insert(0);
+                                *shadow_count += 1;
+                                calc_binding_hash(file_id, &text, *shadow_count)
+                            });
+                            highlight_pat(pat.into())
+                        } else {
+                            match classify_name(db, file_id, name) {
+                                Some(def) => highlight_definition(db, def),
+                                None => highlight_name_by_syntax(name).into(),
+                            }
+                        };
+                    highlight.modifiers.insert(HighlightModifier::Declaration);
+                    highlight
                 } else {
-                    "text"
+                    HighlightTag::Unresolved.into()
                 }
             }
-            INT_NUMBER | FLOAT_NUMBER | CHAR | BYTE => "literal",
-            LIFETIME => "parameter",
-            T![unsafe] => "keyword.unsafe",
-            k if is_control_keyword(k) => "keyword.control",
-            k if k.is_keyword() => "keyword",
+            INT_NUMBER | FLOAT_NUMBER | CHAR | BYTE => HighlightTag::Literal.into(),
+            LIFETIME => HighlightTag::Lifetime.into(),
+            T![self] => match node.as_token() {
+                Some(token) => highlight_self(db, file_id, source_file.syntax(), token),
+                None => HighlightTag::SelfKeyword.into(),
+            },
+            T![unsafe] => HighlightTag::KeywordUnsafe.into(),
+            k if is_control_keyword(k) => HighlightTag::KeywordControl.into(),
+            k if k.is_keyword() => HighlightTag::Keyword.into(),
             _ => {
-                // let analyzer = hir::SourceAnalyzer::new(db, file_id, name_ref.syntax(), None);
                 if let Some(macro_call) = node.as_node().and_then(ast::MacroCall::cast) {
                     if let Some(path) = macro_call.path() {
                         if let Some(segment) = path.segment() {
@@ -160,9 +303,19 @@ pub(crate) fn highlight(db: &RootDatabase, file_id: FileId) -> Vec<HighlightedRa
                                         _ => (),
                                     }
                                 }
+                                let analyzer = hir::SourceAnalyzer::new(
+                                    db,
+                                    file_id,
+                                    macro_call.syntax(),
+                                    None,
+                                );
+                                let modifiers = match analyzer.resolve_macro_call(db, macro_call) {
+                                    Some(it) => item_modifiers(db, it),
+                                    None => HighlightModifiers::default(),
+                                };
                                 res.push(HighlightedRange {
                                     range: TextRange::from_to(range_start, range_end),
-                                    tag: "macro",
+                                    highlight: Highlight::new(HighlightTag::Macro, modifiers),
                                     binding_hash: None,
                                 })
                             }
@@ -172,11 +325,236 @@ pub(crate) fn highlight(db: &RootDatabase, file_id: FileId) -> Vec<HighlightedRa
                 continue;
             }
         };
-        res.push(HighlightedRange { range: node.range(), tag, binding_hash })
+        res.push(HighlightedRange { range: node.range(), highlight, binding_hash })
     }
     res
 }
 
+/// Highlights a local binding, telling parameters apart from other locals.
+fn highlight_pat(pat: &ast::Pat) -> Highlight {
+    let is_param = pat.syntax().ancestors().any(|it| it.kind() == PARAM);
+    let tag = if is_param { HighlightTag::Parameter } else { HighlightTag::Local };
+    let mut modifiers = HighlightModifiers::default();
+    if let Some(bind_pat) = ast::BindPat::cast(pat.syntax()) {
+        if bind_pat.is_mutable() {
+            modifiers.insert(HighlightModifier::Mutable);
+        }
+    }
+    Highlight::new(tag, modifiers)
+}
+
+fn highlight_name_ref_kind(
+    db: &RootDatabase,
+    name_ref: &ast::NameRef,
+    kind: NameRefKind,
+) -> Highlight {
+    match kind {
+        NameRefKind::Method(it) => highlight_function(db, it, Some(name_ref)),
+        NameRefKind::Macro(it) => highlight_definition(db, NameDefinition::Macro(it)),
+        NameRefKind::FieldAccess(it) => highlight_definition(db, NameDefinition::Field(it)),
+        NameRefKind::AssocItem(hir::ImplItem::Method(it)) => {
+            highlight_function(db, it, Some(name_ref))
+        }
+        NameRefKind::AssocItem(hir::ImplItem::Const(it)) => highlight_module_def(db, it.into()),
+        NameRefKind::AssocItem(hir::ImplItem::TypeAlias(it)) => highlight_module_def(db, it.into()),
+        NameRefKind::Def(hir::ModuleDef::Function(it)) => {
+            highlight_function(db, it, Some(name_ref))
+        }
+        NameRefKind::Def(it) => highlight_module_def(db, it),
+        NameRefKind::SelfType(ty) => match ty.as_adt() {
+            Some((hir::AdtDef::Struct(it), _)) => highlight_module_def(db, it.into()),
+            Some((hir::AdtDef::Union(it), _)) => highlight_module_def(db, it.into()),
+            Some((hir::AdtDef::Enum(it), _)) => highlight_module_def(db, it.into()),
+            None => HighlightTag::SelfKeyword.into(),
+        },
+        NameRefKind::GenericParam(_) => HighlightTag::TypeParam.into(),
+        NameRefKind::SelfParam(_) => HighlightTag::SelfKeyword.into(),
+        // Bindings are highlighted by the caller, which tracks their shadowing
+        NameRefKind::Pat(_) => HighlightTag::Local.into(),
+    }
+}
+
+fn highlight_definition(db: &RootDatabase, def: NameDefinition) -> Highlight {
+    match def {
+        NameDefinition::Macro(it) => Highlight::new(HighlightTag::Macro, item_modifiers(db, it)),
+        NameDefinition::Field(it) => {
+            let src = it.source(db);
+            let mut modifiers = HighlightModifiers::default();
+            if is_library(db, src.file_id) {
+                modifiers.insert(HighlightModifier::Library);
+            }
+            let is_deprecated = match &src.ast {
+                hir::FieldSource::Named(it) => is_deprecated(&**it),
+                hir::FieldSource::Pos(it) => is_deprecated(&**it),
+            };
+            if is_deprecated {
+                modifiers.insert(HighlightModifier::Deprecated);
+            }
+            Highlight::new(HighlightTag::Field, modifiers)
+        }
+        NameDefinition::ModuleDef(hir::ModuleDef::Function(it)) => highlight_function(db, it, None),
+        NameDefinition::ModuleDef(it) => highlight_module_def(db, it),
+    }
+}
+
+fn highlight_module_def(db: &RootDatabase, def: hir::ModuleDef) -> Highlight {
+    let (tag, modifiers) = match def {
+        hir::ModuleDef::Module(it) => {
+            let mut modifiers = HighlightModifiers::default();
+            if is_library(db, it.definition_source(db).file_id) {
+                modifiers.insert(HighlightModifier::Library);
+            }
+            (HighlightTag::Module, modifiers)
+        }
+        hir::ModuleDef::Function(it) => return highlight_function(db, it, None),
+        hir::ModuleDef::Struct(it) => (HighlightTag::Struct, item_modifiers(db, it)),
+        hir::ModuleDef::Union(it) => (HighlightTag::Union, item_modifiers(db, it)),
+        hir::ModuleDef::Enum(it) => (HighlightTag::Enum, item_modifiers(db, it)),
+        hir::ModuleDef::EnumVariant(it) => (HighlightTag::EnumVariant, item_modifiers(db, it)),
+        hir::ModuleDef::Const(it) => (HighlightTag::Constant, item_modifiers(db, it)),
+        hir::ModuleDef::Static(it) => {
+            let mut modifiers = item_modifiers(db, it);
+            if it.source(db).ast.is_mut() {
+                modifiers.insert(HighlightModifier::Mutable);
+            }
+            (HighlightTag::Static, modifiers)
+        }
+        hir::ModuleDef::Trait(it) => (HighlightTag::Trait, item_modifiers(db, it)),
+        hir::ModuleDef::TypeAlias(it) => (HighlightTag::TypeAlias, item_modifiers(db, it)),
+        hir::ModuleDef::BuiltinType(_) => {
+            (HighlightTag::BuiltinType, HighlightModifiers::default())
+        }
+    };
+    Highlight::new(tag, modifiers)
+}
+
+/// Highlights a function or a method. When highlighting a reference which
+/// calls the function, `name_ref` is used to tell whether the call is unsafe.
+fn highlight_function(
+    db: &RootDatabase,
+    function: hir::Function,
+    name_ref: Option<&ast::NameRef>,
+) -> Highlight {
+    let tag = if function.data(db).has_self_param() {
+        HighlightTag::Method
+    } else {
+        HighlightTag::Function
+    };
+    let mut modifiers = item_modifiers(db, function);
+    if name_ref.map_or(false, is_call) && function.source(db).ast.is_unsafe() {
+        modifiers.insert(HighlightModifier::UnsafeCall);
+    }
+    Highlight::new(tag, modifiers)
+}
+
+/// Checks whether `name_ref` is the name of the function of a call.
+fn is_call(name_ref: &ast::NameRef) -> bool {
+    let parent = match name_ref.syntax().parent() {
+        Some(it) => it,
+        None => return false,
+    };
+    if ast::MethodCallExpr::cast(parent).is_some() {
+        return true;
+    }
+    let path = name_ref
+        .syntax()
+        .ancestors()
+        .skip(1)
+        .take_while(|it| it.kind() == PATH_SEGMENT || it.kind() == PATH)
+        .last();
+    let path_expr = match path.and_then(SyntaxNode::parent).and_then(ast::PathExpr::cast) {
+        Some(it) => it,
+        None => return false,
+    };
+    path_expr
+        .syntax()
+        .parent()
+        .and_then(ast::CallExpr::cast)
+        .and_then(|call| call.expr())
+        .map_or(false, |callee| callee.syntax() == path_expr.syntax())
+}
+
+/// Highlights the `self` keyword, which is mutable if its parameter is.
+fn highlight_self(
+    db: &RootDatabase,
+    file_id: FileId,
+    root: &SyntaxNode,
+    token: SyntaxToken,
+) -> Highlight {
+    let mut highlight: Highlight = HighlightTag::SelfKeyword.into();
+    let parent = token.parent();
+    let self_param = if let Some(self_param) = ast::SelfParam::cast(parent) {
+        highlight.modifiers.insert(HighlightModifier::Declaration);
+        Some(self_param)
+    } else if let Some(path) = parent.parent().and_then(ast::Path::cast) {
+        let analyzer = hir::SourceAnalyzer::new(db, file_id, path.syntax(), None);
+        match analyzer.resolve_path(db, path) {
+            Some(hir::PathResolution::LocalBinding(hir::Either::B(ptr))) => Some(ptr.to_node(root)),
+            _ => None,
+        }
+    } else {
+        None
+    };
+    if let Some(self_param) = self_param {
+        if self_param.syntax().children_with_tokens().any(|it| it.kind() == T![mut]) {
+            highlight.modifiers.insert(HighlightModifier::Mutable);
+        }
+    }
+    highlight
+}
+
+/// Highlights a name which could not be resolved from the syntax alone.
+fn highlight_name_by_syntax(name: &ast::Name) -> HighlightTag {
+    let parent = match name.syntax().parent() {
+        Some(it) => it,
+        None => return HighlightTag::Unresolved,
+    };
+    match parent.kind() {
+        STRUCT_DEF => match ast::StructDef::cast(parent) {
+            Some(it) if it.is_union() => HighlightTag::Union,
+            _ => HighlightTag::Struct,
+        },
+        ENUM_DEF => HighlightTag::Enum,
+        ENUM_VARIANT => HighlightTag::EnumVariant,
+        TRAIT_DEF => HighlightTag::Trait,
+        TYPE_ALIAS_DEF => HighlightTag::TypeAlias,
+        TYPE_PARAM => HighlightTag::TypeParam,
+        NAMED_FIELD_DEF => HighlightTag::Field,
+        FN_DEF => HighlightTag::Function,
+        CONST_DEF => HighlightTag::Constant,
+        STATIC_DEF => HighlightTag::Static,
+        MODULE => HighlightTag::Module,
+        MACRO_CALL => HighlightTag::Macro,
+        _ => HighlightTag::Unresolved,
+    }
+}
+
+/// Computes the `Library` and `Deprecated` modifiers of an item.
+fn item_modifiers<D, N>(db: &RootDatabase, def: D) -> HighlightModifiers
+where
+    D: HasSource<Ast = TreeArc<N>>,
+    N: AttrsOwner,
+{
+    let src = def.source(db);
+    let mut modifiers = HighlightModifiers::default();
+    if is_library(db, src.file_id) {
+        modifiers.insert(HighlightModifier::Library);
+    }
+    if is_deprecated(&*src.ast) {
+        modifiers.insert(HighlightModifier::Deprecated);
+    }
+    modifiers
+}
+
+fn is_library(db: &RootDatabase, file_id: HirFileId) -> bool {
+    let file_id = file_id.original_file(db);
+    db.source_root(db.file_source_root(file_id)).is_library
+}
+
+fn is_deprecated(node: &impl AttrsOwner) -> bool {
+    node.attrs().filter_map(|it| it.as_named()).any(|it| it == "deprecated")
+}
+
 pub(crate) fn highlight_as_html(db: &RootDatabase, file_id: FileId, rainbow: bool) -> String {
     let source_file = db.parse(file_id).tree;
 
@@ -219,7 +597,8 @@ pub(crate) fn highlight_as_html(db: &RootDatabase, file_id: FileId, rainbow: boo
         if ranges.is_empty() {
             buf.push_str(&text);
         } else {
-            let classes = ranges.iter().map(|x| x.tag).collect::<Vec<_>>().join(" ");
+            let classes =
+                ranges.iter().map(|x| x.highlight.to_string()).collect::<Vec<_>>().join(" ");
             let binding_hash = ranges.first().and_then(|x| x.binding_hash);
             let color = match (rainbow, binding_hash) {
                 (true, Some(hash)) => format!(
@@ -246,25 +625,30 @@ const STYLE: &str = "
 body       { margin: 0; }
 pre        { color: #DCDCCC; background: #3F3F3F; font-size: 22px; padding: 0.4em; }
 
-.comment   { color: #7F9F7F; }
-.string    { color: #CC9393; }
-.function  { color: #93E0E3; }
-.parameter { color: #94BFF3; }
-.builtin   { color: #DD6718; }
-.text      { color: #DCDCCC; }
-.attribute { color: #BFEBBF; }
-.literal   { color: #DFAF8F; }
-.macro     { color: #DFAF8F; }
+.comment      { color: #7F9F7F; }
+.string       { color: #CC9393; }
+.function     { color: #93E0E3; }
+.method       { color: #93E0E3; }
+.parameter    { color: #94BFF3; }
+.builtin_type { color: #DD6718; }
+.unresolved   { color: #DCDCCC; }
+.attribute    { color: #BFEBBF; }
+.literal      { color: #DFAF8F; }
+.macro        { color: #DFAF8F; }
 
 .keyword           { color: #F0DFAF; }
 .keyword\\.unsafe  { color: #F0DFAF; font-weight: bold; }
 .keyword\\.control { color: #DC8CC3; }
+
+.mutable     { text-decoration: underline; }
+.unsafe_call { font-weight: bold; }
+.deprecated  { text-decoration: line-through; }
 </style>
 ";
 
 #[cfg(test)]
 mod tests {
-    use crate::mock_analysis::single_file;
+    use crate::{mock_analysis::single_file, HighlightTag};
     use test_utils::{assert_eq_text, project_dir, read_text};
 
     #[test]
@@ -324,4 +708,65 @@ fn main() {
         std::fs::write(dst_file, &actual_html).unwrap();
         assert_eq_text!(expected_html, actual_html);
     }
+
+    #[test]
+    fn test_highlighting_by_resolved_definition() {
+        let (analysis, file_id) = single_file(
+            r#"
+struct S;
+impl S {
+    fn method(&mut self) {}
+}
+#[deprecated]
+fn old() {}
+unsafe fn danger() {}
+static mut COUNTER: u32 = 0;
+fn main(param: u32) {
+    let mut s = S;
+    s.method();
+    old();
+    unsafe { danger(); COUNTER += param; }
+}
+"#,
+        );
+        let text = analysis.file_text(file_id);
+        let actual = analysis
+            .highlight(file_id)
+            .unwrap()
+            .into_iter()
+            .filter(|it| match it.highlight.tag {
+                HighlightTag::Keyword
+                | HighlightTag::KeywordControl
+                | HighlightTag::KeywordUnsafe
+                | HighlightTag::Attribute
+                | HighlightTag::Literal => false,
+                _ => true,
+            })
+            .map(|it| format!("{}: {}", &text[it.range], it.highlight))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actual,
+            vec![
+                "S: struct declaration",
+                "S: struct",
+                "method: method declaration",
+                "self: self_keyword mutable declaration",
+                "old: function declaration deprecated",
+                "danger: function declaration",
+                "COUNTER: static mutable declaration",
+                "u32: builtin_type",
+                "main: function declaration",
+                "param: parameter declaration",
+                "u32: builtin_type",
+                "s: local mutable declaration",
+                "S: struct",
+                "s: local mutable",
+                "method: method",
+                "old: function deprecated",
+                "danger: function unsafe_call",
+                "COUNTER: static mutable",
+                "param: parameter",
+            ]
+        );
+    }
 }
@@ -0,0 +1,220 @@
+use ra_db::SourceDatabase;
+use ra_syntax::{
+    algo::find_node_at_offset,
+    ast::{self, AstNode},
+};
+
+use crate::{
+    db::RootDatabase,
+    references::{self, NameDefinition},
+    FilePosition, NavigationTarget,
+};
+
+/// The traits and types related to a trait or to a type.
+#[derive(Debug, Clone)]
+pub struct TypeHierarchy {
+    /// The trait or type the hierarchy was requested for.
+    pub item: NavigationTarget,
+    /// For a trait, its supertraits. For a type, the traits it implements.
+    pub supertypes: Vec<NavigationTarget>,
+    /// For a trait, its subtraits and the types implementing it. Blanket impls
+    /// are listed as impl blocks. Always empty for a type.
+    pub subtypes: Vec<NavigationTarget>,
+}
+
+pub(crate) fn type_hierarchy(db: &RootDatabase, position: FilePosition) -> Option<TypeHierarchy> {
+    let file = db.parse(position.file_id).tree;
+    let def = match references::find_definition(db, &file, position)? {
+        NameDefinition::ModuleDef(it) => it,
+        _ => return None,
+    };
+    let item = NavigationTarget::from_def(db, def)?;
+    match def {
+        hir::ModuleDef::Trait(it) => Some(trait_hierarchy(db, position, it, item)),
+        hir::ModuleDef::Struct(it) => type_hierarchy_for_adt(db, position, it.into(), item),
+        hir::ModuleDef::Enum(it) => type_hierarchy_for_adt(db, position, it.into(), item),
+        hir::ModuleDef::Union(it) => type_hierarchy_for_adt(db, position, it.into(), item),
+        _ => None,
+    }
+}
+
+fn trait_hierarchy(
+    db: &RootDatabase,
+    position: FilePosition,
+    trait_: hir::Trait,
+    item: NavigationTarget,
+) -> TypeHierarchy {
+    let supertypes = trait_
+        .super_traits(db)
+        .into_iter()
+        .filter_map(|it| NavigationTarget::from_def(db, it.into()))
+        .collect();
+
+    // Subtraits and impls both mention the trait, so they are found among its
+    // references
+    let mut subtraits = Vec::new();
+    let mut modules = Vec::new();
+    let refs = references::find_all_refs(db, position, None);
+    for reference in refs.iter().flat_map(|it| it.references()) {
+        let file_id = reference.file_range.file_id;
+        let file = db.parse(file_id).tree;
+        let name_ref = match find_node_at_offset::<ast::NameRef>(
+            file.syntax(),
+            reference.file_range.range.start(),
+        ) {
+            Some(it) => it,
+            None => continue,
+        };
+        let module =
+            match hir::source_binder::module_from_child_node(db, file_id, name_ref.syntax()) {
+                Some(it) => it,
+                None => continue,
+            };
+        if !modules.contains(&module) {
+            modules.push(module);
+        }
+        let trait_def = name_ref.syntax().ancestors().find_map(ast::TraitDef::cast);
+        if let Some(trait_def) = trait_def {
+            let subtrait = hir::source_binder::trait_from_module(db, module, trait_def);
+            if subtrait != trait_
+                && !subtraits.contains(&subtrait)
+                && subtrait.super_traits(db).contains(&trait_)
+            {
+                subtraits.push(subtrait);
+            }
+        }
+    }
+
+    let mut subtypes: Vec<NavigationTarget> =
+        subtraits.into_iter().filter_map(|it| NavigationTarget::from_def(db, it.into())).collect();
+    for impl_block in modules.into_iter().flat_map(|it| it.impl_blocks(db)) {
+        if impl_block.target_trait_ref(db).map(|it| it.trait_) != Some(trait_) {
+            continue;
+        }
+        let nav = match impl_block.target_ty(db).as_adt() {
+            Some((adt_def, _)) => NavigationTarget::from_adt_def(db, adt_def),
+            None => NavigationTarget::from_impl_block(db, impl_block),
+        };
+        let is_duplicate = subtypes
+            .iter()
+            .any(|it| it.file_id() == nav.file_id() && it.full_range() == nav.full_range());
+        if !is_duplicate {
+            subtypes.push(nav);
+        }
+    }
+
+    TypeHierarchy { item, supertypes, subtypes }
+}
+
+fn type_hierarchy_for_adt(
+    db: &RootDatabase,
+    position: FilePosition,
+    adt_def: hir::AdtDef,
+    item: NavigationTarget,
+) -> Option<TypeHierarchy> {
+    let krate = hir::source_binder::module_from_position(db, position)?.krate(db)?;
+    let ty = match adt_def {
+        hir::AdtDef::Struct(it) => it.ty(db),
+        hir::AdtDef::Union(it) => it.ty(db),
+        hir::AdtDef::Enum(it) => it.ty(db),
+    };
+    let mut supertypes: Vec<NavigationTarget> = ty
+        .implemented_traits(db, krate)
+        .into_iter()
+        .filter_map(|it| NavigationTarget::from_def(db, it.into()))
+        .collect();
+    supertypes.sort_by_key(|it| (it.file_id(), it.full_range().start()));
+    Some(TypeHierarchy { item, supertypes, subtypes: Vec::new() })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock_analysis::analysis_and_position;
+
+    fn check_hierarchy(
+        fixture: &str,
+        expected: &str,
+        expected_supertypes: &[&str],
+        expected_subtypes: &[&str],
+    ) {
+        let (analysis, pos) = analysis_and_position(fixture);
+
+        let hierarchy = analysis.type_hierarchy(pos).unwrap().unwrap();
+        hierarchy.item.assert_match(expected);
+        let render = |navs: Vec<crate::NavigationTarget>| -> Vec<String> {
+            navs.into_iter().map(|it| it.debug_render()).collect()
+        };
+        assert_eq!(render(hierarchy.supertypes), expected_supertypes);
+        assert_eq!(render(hierarchy.subtypes), expected_subtypes);
+    }
+
+    #[test]
+    fn test_type_hierarchy_for_trait() {
+        check_hierarchy(
+            r#"
+            //- /lib.rs
+            trait Base {}
+            trait Other {}
+            trait Tr<|>ait: Base where Self: Other {}
+            trait Sub: Trait {}
+            struct S;
+            impl Trait for S {}
+            impl<T: Base + Other> Trait for T {}
+            "#,
+            "Trait TRAIT_DEF FileId(1) [29; 67) [35; 40)",
+            &[
+                "Base TRAIT_DEF FileId(1) [0; 13) [6; 10)",
+                "Other TRAIT_DEF FileId(1) [14; 28) [20; 25)",
+            ],
+            &[
+                "Sub TRAIT_DEF FileId(1) [68; 87) [74; 77)",
+                "S STRUCT_DEF FileId(1) [88; 97) [95; 96)",
+                "impl IMPL_BLOCK FileId(1) [118; 154)",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_type_hierarchy_for_type() {
+        check_hierarchy(
+            r#"
+            //- /lib.rs
+            trait Direct {}
+            trait Blanket {}
+            trait Unrelated {}
+            struct Fo<|>o;
+            struct Bar;
+            impl Direct for Foo {}
+            impl<T: Direct> Blanket for T {}
+            impl Unrelated for Bar {}
+            "#,
+            "Foo STRUCT_DEF FileId(1) [52; 63) [59; 62)",
+            &[
+                "Direct TRAIT_DEF FileId(1) [0; 15) [6; 12)",
+                "Blanket TRAIT_DEF FileId(1) [16; 32) [22; 29)",
+            ],
+            &[],
+        );
+    }
+
+    #[test]
+    fn test_type_hierarchy_for_type_with_impls_in_other_crates() {
+        check_hierarchy(
+            r#"
+            //- /lib.rs
+            trait Downstream {}
+            impl Downstream for foo::Fo<|>o {}
+            //- /foo/lib.rs
+            pub trait Upstream {}
+            pub struct Foo;
+            impl Upstream for Foo {}
+            "#,
+            "Foo STRUCT_DEF FileId(2) [22; 37) [33; 36)",
+            &[
+                "Downstream TRAIT_DEF FileId(1) [0; 19) [6; 16)",
+                "Upstream TRAIT_DEF FileId(2) [0; 21) [10; 18)",
+            ],
+            &[],
+        );
+    }
+}
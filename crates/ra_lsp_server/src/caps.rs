@@ -2,12 +2,23 @@ use lsp_types::{
     CodeActionProviderCapability, CodeLensOptions, CompletionOptions,
     DocumentOnTypeFormattingOptions, ExecuteCommandOptions, FoldingRangeProviderCapability,
     GenericCapability, ImplementationProviderCapability, RenameOptions, RenameProviderCapability,
-    ServerCapabilities, SignatureHelpOptions, TextDocumentSyncCapability, TextDocumentSyncKind,
+    SignatureHelpOptions, TextDocumentSyncCapability, TextDocumentSyncKind,
     TextDocumentSyncOptions, TypeDefinitionProviderCapability,
 };
+use serde::Serialize;
+
+/// `lsp_types::ServerCapabilities` extended with the capabilities of proposed
+/// protocol features it does not support yet.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerCapabilities {
+    #[serde(flatten)]
+    pub lsp: lsp_types::ServerCapabilities,
+    pub call_hierarchy_provider: bool,
+}
 
 pub fn server_capabilities() -> ServerCapabilities {
-    ServerCapabilities {
+    let lsp = lsp_types::ServerCapabilities {
         text_document_sync: Some(TextDocumentSyncCapability::Options(TextDocumentSyncOptions {
             open_close: Some(true),
             change: Some(TextDocumentSyncKind::Full),
@@ -17,7 +28,7 @@ pub fn server_capabilities() -> ServerCapabilities {
         })),
         hover_provider: Some(true),
         completion_provider: Some(CompletionOptions {
-            resolve_provider: None,
+            resolve_provider: Some(true),
             trigger_characters: Some(vec![":".to_string(), ".".to_string()]),
         }),
         signature_help_provider: Some(SignatureHelpOptions {
@@ -48,5 +59,6 @@ pub fn server_capabilities() -> ServerCapabilities {
             commands: vec!["apply_code_action".to_string()],
         }),
         workspace: None,
-    }
+    };
+    ServerCapabilities { lsp, call_hierarchy_provider: true }
 }
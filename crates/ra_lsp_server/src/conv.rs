@@ -1,13 +1,13 @@
 use lsp_types::{
-    self, CreateFile, DocumentChangeOperation, DocumentChanges, Documentation, Location,
-    LocationLink, MarkupContent, MarkupKind, Position, Range, RenameFile, ResourceOp, SymbolKind,
-    TextDocumentEdit, TextDocumentIdentifier, TextDocumentItem, TextDocumentPositionParams, Url,
-    VersionedTextDocumentIdentifier, WorkspaceEdit,
+    self, CreateFile, DocumentChangeOperation, DocumentChanges, DocumentHighlightKind,
+    Documentation, Location, LocationLink, MarkupContent, MarkupKind, Position, Range, RenameFile,
+    ResourceOp, SymbolKind, TextDocumentEdit, TextDocumentIdentifier, TextDocumentItem,
+    TextDocumentPositionParams, Url, VersionedTextDocumentIdentifier, WorkspaceEdit,
 };
 use ra_ide_api::{
     translate_offset_with_edit, CompletionItem, CompletionItemKind, FileId, FilePosition,
-    FileRange, FileSystemEdit, InsertTextFormat, LineCol, LineIndex, NavigationTarget, RangeInfo,
-    SourceChange, SourceFileEdit,
+    FileRange, FileSystemEdit, HighlightTag, InsertTextFormat, LineCol, LineIndex,
+    NavigationTarget, RangeInfo, ReferenceAccess, SourceChange, SourceFileEdit,
 };
 use ra_syntax::{SyntaxKind, TextRange, TextUnit};
 use ra_text_edit::{AtomTextEdit, TextEdit};
@@ -79,6 +79,53 @@ impl Conv for CompletionItemKind {
     }
 }
 
+/// References which neither read nor write a value are highlighted as text.
+impl Conv for Option<ReferenceAccess> {
+    type Output = DocumentHighlightKind;
+
+    fn conv(self) -> DocumentHighlightKind {
+        match self {
+            Some(ReferenceAccess::Read) => DocumentHighlightKind::Read,
+            Some(ReferenceAccess::Write) => DocumentHighlightKind::Write,
+            None => DocumentHighlightKind::Text,
+        }
+    }
+}
+
+/// Decorations use the tags understood by the editor extensions, which are
+/// coarser than `HighlightTag`.
+impl Conv for HighlightTag {
+    type Output = &'static str;
+
+    fn conv(self) -> &'static str {
+        match self {
+            HighlightTag::Local | HighlightTag::Parameter => "variable",
+            HighlightTag::Function | HighlightTag::Method => "function",
+            HighlightTag::Constant | HighlightTag::Static | HighlightTag::EnumVariant => "constant",
+            HighlightTag::Struct
+            | HighlightTag::Union
+            | HighlightTag::Enum
+            | HighlightTag::Trait
+            | HighlightTag::TypeAlias
+            | HighlightTag::TypeParam
+            | HighlightTag::BuiltinType => "type",
+            HighlightTag::SelfKeyword => "keyword",
+            HighlightTag::Lifetime => "parameter",
+            HighlightTag::Unresolved => "text",
+            HighlightTag::Attribute
+            | HighlightTag::Comment
+            | HighlightTag::Field
+            | HighlightTag::Keyword
+            | HighlightTag::KeywordControl
+            | HighlightTag::KeywordUnsafe
+            | HighlightTag::Literal
+            | HighlightTag::Macro
+            | HighlightTag::Module
+            | HighlightTag::String => self.as_str(),
+        }
+    }
+}
+
 impl ConvWith for CompletionItem {
     type Ctx = LineIndex;
     type Output = ::lsp_types::CompletionItem;
@@ -113,6 +160,7 @@ impl ConvWith for CompletionItem {
             label: self.label().to_string(),
             detail: self.detail().map(|it| it.to_string()),
             filter_text: Some(self.lookup().to_string()),
+            sort_text: Some(sort_text(self.relevance().score())),
             kind: self.kind().map(|it| it.conv()),
             text_edit: Some(text_edit),
             additional_text_edits: Some(additional_text_edits),
@@ -128,6 +176,11 @@ impl ConvWith for CompletionItem {
     }
 }
 
+/// Clients sort the items by `sort_text`, more relevant items go first.
+fn sort_text(score: u32) -> String {
+    format!("{:08x}", u32::max_value() - score)
+}
+
 impl ConvWith for Position {
     type Ctx = LineIndex;
     type Output = TextUnit;
@@ -327,8 +380,23 @@ impl TryConvWith for SourceChange {
             }
         };
         let mut document_changes: Vec<DocumentChangeOperation> = Vec::new();
-        for resource_op in self.file_system_edits.try_conv_with(world)? {
+        for file_system_edit in self.file_system_edits {
+            // The contents of a new file are inserted once it is created
+            let contents = match &file_system_edit {
+                FileSystemEdit::CreateFile { source_root, path, text } if !text.is_empty() => {
+                    let text_document = VersionedTextDocumentIdentifier {
+                        uri: world.path_to_uri(*source_root, path)?,
+                        version: None,
+                    };
+                    let start = Position::new(0, 0);
+                    let edit = lsp_types::TextEdit::new(Range::new(start, start), text.clone());
+                    Some(TextDocumentEdit { text_document, edits: vec![edit] })
+                }
+                _ => None,
+            };
+            let resource_op = file_system_edit.try_conv_with(world)?;
             document_changes.push(DocumentChangeOperation::Op(resource_op));
+            document_changes.extend(contents.map(DocumentChangeOperation::Edit));
         }
         for text_document_edit in self.source_file_edits.try_conv_with(world)? {
             document_changes.push(DocumentChangeOperation::Edit(text_document_edit));
@@ -360,7 +428,7 @@ impl TryConvWith for FileSystemEdit {
     type Output = ResourceOp;
     fn try_conv_with(self, world: &WorldSnapshot) -> Result<ResourceOp> {
         let res = match self {
-            FileSystemEdit::CreateFile { source_root, path } => {
+            FileSystemEdit::CreateFile { source_root, path, .. } => {
                 let uri = world.path_to_uri(source_root, &path)?;
                 ResourceOp::Create(CreateFile { uri, options: None })
             }
@@ -19,6 +19,42 @@ pub struct InitializationOptions {
     pub show_workspace_loaded: bool,
 
     pub lru_capacity: Option<usize>,
+
+    /// Maximum length of the types displayed in inlay hints, longer types are
+    /// truncated
+    ///
+    /// Defaults to `None`, which means no limit
+    pub max_inlay_hint_length: Option<usize>,
+
+    /// Whether inlay hints show the names of the parameters the arguments of
+    /// calls are passed to
+    ///
+    /// Defaults to `true`
+    #[serde(deserialize_with = "nullable_bool_true")]
+    pub parameter_hints: bool,
+
+    /// How organizing imports merges `use` items
+    ///
+    /// Defaults to `None`, which means one `use` item per crate
+    pub import_granularity: Option<ImportGranularity>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportGranularity {
+    Item,
+    Module,
+    Crate,
+}
+
+impl From<ImportGranularity> for ra_ide_api::ImportGranularity {
+    fn from(granularity: ImportGranularity) -> ra_ide_api::ImportGranularity {
+        match granularity {
+            ImportGranularity::Item => ra_ide_api::ImportGranularity::Item,
+            ImportGranularity::Module => ra_ide_api::ImportGranularity::Module,
+            ImportGranularity::Crate => ra_ide_api::ImportGranularity::Crate,
+        }
+    }
 }
 
 impl Default for InitializationOptions {
@@ -27,6 +63,9 @@ impl Default for InitializationOptions {
             publish_decorations: false,
             show_workspace_loaded: true,
             lru_capacity: None,
+            max_inlay_hint_length: None,
+            parameter_hints: true,
+            import_granularity: None,
         }
     }
 }
@@ -61,7 +100,8 @@ mod test {
         assert_eq!(
             default,
             serde_json::from_str(
-                r#"{"publishDecorations":null, "showWorkspaceLoaded":null, "lruCapacity":null}"#
+                r#"{"publishDecorations":null, "showWorkspaceLoaded":null, "lruCapacity":null,
+                    "maxInlayHintLength":null, "parameterHints":null, "importGranularity":null}"#
             )
             .unwrap()
         );
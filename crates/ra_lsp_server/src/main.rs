@@ -1,5 +1,11 @@
+use crossbeam_channel::{Receiver, Sender};
 use flexi_logger::{Duplicate, Logger};
-use gen_lsp_server::{run_server, stdio_transport};
+use gen_lsp_server::{stdio_transport, RawMessage, RawResponse};
+use lsp_types::{
+    notification::{Exit, Initialized},
+    request::Initialize,
+    InitializeParams,
+};
 use serde::Deserialize;
 
 use ra_lsp_server::{InitializationOptions, Result};
@@ -32,29 +38,60 @@ fn main() -> Result<()> {
 fn main_inner() -> Result<()> {
     let (receiver, sender, threads) = stdio_transport();
     let cwd = std::env::current_dir()?;
-    run_server(ra_lsp_server::server_capabilities(), receiver, sender, |params, r, s| {
-        let root = params.root_uri.and_then(|it| it.to_file_path().ok()).unwrap_or(cwd);
+    log::info!("lsp server initializes");
+    let params = initialize(&receiver, &sender)?;
+    log::info!("lsp server initialized, serving requests");
+    let root = params.root_uri.and_then(|it| it.to_file_path().ok()).unwrap_or(cwd);
 
-        let workspace_roots = params
-            .workspace_folders
-            .map(|workspaces| {
-                workspaces
-                    .into_iter()
-                    .filter_map(|it| it.uri.to_file_path().ok())
-                    .collect::<Vec<_>>()
-            })
-            .filter(|workspaces| !workspaces.is_empty())
-            .unwrap_or_else(|| vec![root]);
+    let workspace_roots = params
+        .workspace_folders
+        .map(|workspaces| {
+            workspaces.into_iter().filter_map(|it| it.uri.to_file_path().ok()).collect::<Vec<_>>()
+        })
+        .filter(|workspaces| !workspaces.is_empty())
+        .unwrap_or_else(|| vec![root]);
 
-        let opts = params
-            .initialization_options
-            .and_then(|v| InitializationOptions::deserialize(v).ok())
-            .unwrap_or_default();
+    let opts = params
+        .initialization_options
+        .and_then(|v| InitializationOptions::deserialize(v).ok())
+        .unwrap_or_default();
 
-        ra_lsp_server::main_loop(workspace_roots, opts, r, s)
-    })?;
+    ra_lsp_server::main_loop(workspace_roots, opts, &receiver, &sender)?;
+    log::info!("lsp server waiting for exit notification");
+    match receiver.recv() {
+        Ok(RawMessage::Notification(n)) => n
+            .cast::<Exit>()
+            .map_err(|n| format!("unexpected notification during shutdown: {:?}", n))?,
+        m => Err(format!("unexpected message during shutdown: {:?}", m))?,
+    }
     log::info!("shutting down IO...");
     threads.join()?;
     log::info!("... IO is down");
     Ok(())
 }
+
+/// Initializes the connection like `gen_lsp_server::run_server` does, but
+/// answers with capabilities of proposed features `lsp_types` doesn't know.
+fn initialize(
+    receiver: &Receiver<RawMessage>,
+    sender: &Sender<RawMessage>,
+) -> Result<InitializeParams> {
+    let (id, params) = match receiver.recv() {
+        Ok(RawMessage::Request(req)) => match req.cast::<Initialize>() {
+            Err(req) => Err(format!("expected initialize request, got {:?}", req))?,
+            Ok(req) => req,
+        },
+        msg => Err(format!("expected initialize request, got {:?}", msg))?,
+    };
+    let capabilities = ra_lsp_server::server_capabilities();
+    let result = serde_json::json!({ "capabilities": capabilities });
+    let resp = RawResponse { id, result: Some(result), error: None };
+    sender.send(RawMessage::Response(resp)).unwrap();
+    match receiver.recv() {
+        Ok(RawMessage::Notification(n)) => {
+            n.cast::<Initialized>().map_err(|_| "expected initialized notification")?;
+        }
+        _ => Err("expected initialized notification".to_string())?,
+    }
+    Ok(params)
+}
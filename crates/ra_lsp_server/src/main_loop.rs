@@ -22,7 +22,7 @@ use crate::{
     },
     project_model::workspace_loader,
     req,
-    world::{WorldSnapshot, WorldState},
+    world::{Options, WorldSnapshot, WorldState},
     InitializationOptions, Result,
 };
 
@@ -77,7 +77,16 @@ pub fn main_loop(
         loaded_workspaces
     };
 
-    let mut state = WorldState::new(ws_roots, workspaces, options.lru_capacity);
+    let mut state = WorldState::new(
+        ws_roots,
+        workspaces,
+        options.lru_capacity,
+        Options {
+            max_inlay_hint_length: options.max_inlay_hint_length,
+            parameter_hints: options.parameter_hints,
+            import_granularity: options.import_granularity.map(Into::into).unwrap_or_default(),
+        },
+    );
 
     let pool = ThreadPool::new(THREADPOOL_SIZE);
     let (task_sender, task_receiver) = unbounded::<Task>();
@@ -339,6 +348,7 @@ fn on_request(
         .on::<req::Runnables>(handlers::handle_runnables)?
         .on::<req::DecorationsRequest>(handlers::handle_decorations)?
         .on::<req::Completion>(handlers::handle_completion)?
+        .on::<req::ResolveCompletionItem>(handlers::handle_completion_resolve)?
         .on::<req::CodeActionRequest>(handlers::handle_code_action)?
         .on::<req::CodeLensRequest>(handlers::handle_code_lens)?
         .on::<req::CodeLensResolve>(handlers::handle_code_lens_resolve)?
@@ -347,9 +357,15 @@ fn on_request(
         .on::<req::HoverRequest>(handlers::handle_hover)?
         .on::<req::PrepareRenameRequest>(handlers::handle_prepare_rename)?
         .on::<req::Rename>(handlers::handle_rename)?
+        .on::<req::ChangeSignature>(handlers::handle_change_signature)?
         .on::<req::References>(handlers::handle_references)?
         .on::<req::Formatting>(handlers::handle_formatting)?
         .on::<req::DocumentHighlightRequest>(handlers::handle_document_highlight)?
+        .on::<req::InlayHints>(handlers::handle_inlay_hints)?
+        .on::<req::CallHierarchyPrepare>(handlers::handle_call_hierarchy_prepare)?
+        .on::<req::CallHierarchyIncomingCalls>(handlers::handle_call_hierarchy_incoming)?
+        .on::<req::CallHierarchyOutgoingCalls>(handlers::handle_call_hierarchy_outgoing)?
+        .on::<req::TypeHierarchy>(handlers::handle_type_hierarchy)?
         .finish();
     Ok(())
 }
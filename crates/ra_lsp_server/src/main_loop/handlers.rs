@@ -9,11 +9,11 @@ use lsp_types::{
     TextEdit, WorkspaceEdit,
 };
 use ra_ide_api::{
-    AssistId, Cancelable, FileId, FilePosition, FileRange, FoldKind, Query, RangeInfo,
-    RunnableKind, Severity,
+    AssistId, Cancelable, Deferred, FileId, FilePosition, FileRange, FoldKind, InlayKind,
+    NavigationTarget, ParamChange, Query, RangeInfo, RunnableKind, Severity, SignatureChange,
 };
 use ra_prof::profile;
-use ra_syntax::{AstNode, SyntaxKind, TextRange, TextUnit};
+use ra_syntax::{AstNode, SmolStr, SyntaxKind, TextRange, TextUnit};
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
 use serde_json::to_value;
@@ -440,11 +440,99 @@ pub fn handle_completion(
         Some(items) => items,
     };
     let line_index = world.analysis().file_line_index(position.file_id);
-    let items = items.into_iter().map(|item| item.conv_with(&line_index)).collect();
+    let position_params =
+        req::TextDocumentPositionParams::new(params.text_document, params.position);
+    let items = items
+        .into_iter()
+        .map(|item| {
+            let data = item.deferred().map(|deferred| {
+                let data = CompletionResolveData {
+                    position: position_params.clone(),
+                    deferred: deferred.into(),
+                };
+                to_value(data).unwrap()
+            });
+            lsp_types::CompletionItem { data, ..item.conv_with(&line_index) }
+        })
+        .collect();
 
     Ok(Some(req::CompletionResponse::Array(items)))
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CompletionResolveData {
+    position: req::TextDocumentPositionParams,
+    deferred: DeferredData,
+}
+
+/// `Deferred` in a form which can be sent to the client and back.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum DeferredData {
+    Docs { file_id: u32, range: TextRange },
+    Signature { file_id: u32, range: TextRange },
+    Import { path: Vec<SmolStr> },
+}
+
+impl From<&Deferred> for DeferredData {
+    fn from(deferred: &Deferred) -> DeferredData {
+        match deferred {
+            Deferred::Docs(frange) => {
+                DeferredData::Docs { file_id: frange.file_id.0, range: frange.range }
+            }
+            Deferred::Signature(frange) => {
+                DeferredData::Signature { file_id: frange.file_id.0, range: frange.range }
+            }
+            Deferred::Import(path) => DeferredData::Import { path: path.clone() },
+        }
+    }
+}
+
+impl From<DeferredData> for Deferred {
+    fn from(data: DeferredData) -> Deferred {
+        match data {
+            DeferredData::Docs { file_id, range } => {
+                Deferred::Docs(FileRange { file_id: FileId(file_id), range })
+            }
+            DeferredData::Signature { file_id, range } => {
+                Deferred::Signature(FileRange { file_id: FileId(file_id), range })
+            }
+            DeferredData::Import { path } => Deferred::Import(path),
+        }
+    }
+}
+
+/// Fills in the parts of `item` which were deferred when it was completed. The
+/// rest of the item is kept as the client sent it.
+pub fn handle_completion_resolve(
+    world: WorldSnapshot,
+    mut item: lsp_types::CompletionItem,
+) -> Result<lsp_types::CompletionItem> {
+    let _p = profile("handle_completion_resolve");
+    let data: CompletionResolveData = match item.data.take() {
+        Some(data) => serde_json::from_value(data)?,
+        None => return Ok(item),
+    };
+    let position = data.position.try_conv_with(&world)?;
+    let resolved = match world.analysis().resolve_completion(position, &data.deferred.into())? {
+        Some(it) => it,
+        None => return Ok(item),
+    };
+    if let Some(documentation) = resolved.documentation {
+        item.documentation = Some(documentation.conv());
+    }
+    if let Some(detail) = resolved.detail {
+        item.detail = Some(detail);
+    }
+    if let Some(edit) = resolved.additional_edits {
+        let line_index = world.analysis().file_line_index(position.file_id);
+        let edits = item.additional_text_edits.get_or_insert_with(Vec::new);
+        edits.extend(edit.as_atoms().iter().map(|atom| atom.conv_with(&line_index)));
+    }
+    Ok(item)
+}
+
 pub fn handle_folding_range(
     world: WorldSnapshot,
     params: FoldingRangeParams,
@@ -528,16 +616,21 @@ pub fn handle_prepare_rename(
 
     // We support renaming references like handle_rename does.
     // In the future we may want to reject the renaming of things like keywords here too.
-    let refs = match world.analysis().find_all_refs(position)? {
+    let refs = match world.analysis().find_all_refs(position, None)? {
         None => return Ok(None),
         Some(refs) => refs,
     };
 
-    // Refs should always have a declaration
-    let r = refs.declaration();
-    let file_id = params.text_document.try_conv_with(&world)?;
-    let line_index = world.analysis().file_line_index(file_id);
-    let loc = to_location(r.file_id(), r.range(), &world, &line_index)?;
+    // The declaration may live in another file, so look for the reference under the cursor
+    let range = match refs
+        .into_iter()
+        .find(|r| r.file_id == position.file_id && r.range.contains_inclusive(position.offset))
+    {
+        None => return Ok(None),
+        Some(r) => r.range,
+    };
+    let line_index = world.analysis().file_line_index(position.file_id);
+    let loc = to_location(position.file_id, range, &world, &line_index)?;
 
     Ok(Some(PrepareRenameResponse::Range(loc.range)))
 }
@@ -555,8 +648,10 @@ pub fn handle_rename(world: WorldSnapshot, params: RenameParams) -> Result<Optio
         .into());
     }
 
-    let optional_change =
-        world.analysis().rename(FilePosition { file_id, offset }, &*params.new_name)?;
+    let optional_change = world
+        .analysis()
+        .rename(FilePosition { file_id, offset }, &*params.new_name)?
+        .map_err(|err| LspError::new(ErrorCode::InvalidParams as i32, err.to_string()))?;
     let change = match optional_change {
         None => return Ok(None),
         Some(it) => it,
@@ -567,6 +662,33 @@ pub fn handle_rename(world: WorldSnapshot, params: RenameParams) -> Result<Optio
     Ok(Some(source_change_req.workspace_edit))
 }
 
+pub fn handle_change_signature(
+    world: WorldSnapshot,
+    params: req::ChangeSignatureParams,
+) -> Result<Option<req::SourceChange>> {
+    let _p = profile("handle_change_signature");
+    let file_id = params.text_document.try_conv_with(&world)?;
+    let line_index = world.analysis().file_line_index(file_id);
+    let offset = params.position.conv_with(&line_index);
+    let params = params
+        .params
+        .into_iter()
+        .map(|it| match it {
+            req::ParamChange::Existing(idx) => ParamChange::Existing(idx),
+            req::ParamChange::Added { name, ty, value } => ParamChange::Added { name, ty, value },
+        })
+        .collect();
+
+    let change = world
+        .analysis()
+        .change_signature(FilePosition { file_id, offset }, SignatureChange { params })?
+        .map_err(|err| LspError::new(ErrorCode::InvalidParams as i32, err.to_string()))?;
+    match change {
+        None => Ok(None),
+        Some(it) => Ok(Some(it.try_conv_with(&world)?)),
+    }
+}
+
 pub fn handle_references(
     world: WorldSnapshot,
     params: req::ReferenceParams,
@@ -575,21 +697,21 @@ pub fn handle_references(
     let line_index = world.analysis().file_line_index(file_id);
     let offset = params.position.conv_with(&line_index);
 
-    let refs = match world.analysis().find_all_refs(FilePosition { file_id, offset })? {
+    let refs = match world.analysis().find_all_refs(FilePosition { file_id, offset }, None)? {
         None => return Ok(None),
         Some(refs) => refs,
     };
 
+    // References may be spread over several files, each with its own line index
+    let location = |r: FileRange| {
+        let line_index = world.analysis().file_line_index(r.file_id);
+        to_location(r.file_id, r.range, &world, &line_index).ok()
+    };
     let locations = if params.context.include_declaration {
-        refs.into_iter()
-            .filter_map(|r| to_location(r.file_id, r.range, &world, &line_index).ok())
-            .collect()
+        refs.into_iter().filter_map(location).collect()
     } else {
         // Only iterate over the references if include_declaration was false
-        refs.references()
-            .iter()
-            .filter_map(|r| to_location(r.file_id, r.range, &world, &line_index).ok())
-            .collect()
+        refs.references().iter().filter_map(|r| location(r.file_range)).collect()
     };
 
     Ok(Some(locations))
@@ -662,11 +784,15 @@ pub fn handle_code_action(
     let file_id = params.text_document.try_conv_with(&world)?;
     let line_index = world.analysis().file_line_index(file_id);
     let range = params.range.conv_with(&line_index);
-
-    let assists = world.analysis().assists(FileRange { file_id, range })?.into_iter();
-    let diagnostics = world.analysis().diagnostics(file_id)?;
+    // Kinds are hierarchical: `source` requests `source.organizeImports`
+    let is_requested = |kind: &str| match &params.context.only {
+        None => true,
+        Some(only) => only.iter().any(|it| kind == it || kind.starts_with(&format!("{}.", it))),
+    };
     let mut res = CodeActionResponse::default();
 
+    let diagnostics =
+        if is_requested("quickfix") { world.analysis().diagnostics(file_id)? } else { Vec::new() };
     let fixes_from_diagnostics = diagnostics
         .into_iter()
         .filter_map(|d| Some((d.range, d.fix?)))
@@ -692,6 +818,11 @@ pub fn handle_code_action(
         res.push(action.into());
     }
 
+    let assists = if is_requested("refactor") {
+        world.analysis().assists(FileRange { file_id, range })?
+    } else {
+        Vec::new()
+    };
     for assist in assists {
         let title = assist.change.label.clone();
         let edit = assist.change.try_conv_with(&world)?;
@@ -714,6 +845,34 @@ pub fn handle_code_action(
         res.push(action.into());
     }
 
+    // Source actions apply to the whole file, so they are only computed when
+    // the client asks for them
+    let wants_organize_imports =
+        params.context.only.is_some() && is_requested("source.organizeImports");
+    let organize_imports = if wants_organize_imports {
+        world.analysis().organize_imports(file_id, world.options.import_granularity)?
+    } else {
+        None
+    };
+    if let Some(change) = organize_imports {
+        let title = change.label.clone();
+        let edit = change.try_conv_with(&world)?;
+
+        let command = Command {
+            title,
+            command: "rust-analyzer.applySourceChange".to_string(),
+            arguments: Some(vec![to_value(edit).unwrap()]),
+        };
+        let action = CodeAction {
+            title: command.title.clone(),
+            kind: Some("source.organizeImports".to_string()),
+            diagnostics: None,
+            edit: None,
+            command: Some(command),
+        };
+        res.push(action.into());
+    }
+
     Ok(Some(res))
 }
 
@@ -846,18 +1005,163 @@ pub fn handle_document_highlight(
     let file_id = params.text_document.try_conv_with(&world)?;
     let line_index = world.analysis().file_line_index(file_id);
 
-    let refs = match world.analysis().find_all_refs(params.try_conv_with(&world)?)? {
+    let highlights = match world.analysis().document_highlight(params.try_conv_with(&world)?)? {
         None => return Ok(None),
-        Some(refs) => refs,
+        Some(highlights) => highlights,
     };
 
     Ok(Some(
-        refs.into_iter()
-            .map(|r| DocumentHighlight { range: r.range.conv_with(&line_index), kind: None })
+        highlights
+            .into_iter()
+            .map(|it| DocumentHighlight {
+                range: it.file_range.range.conv_with(&line_index),
+                kind: Some(it.access.conv()),
+            })
             .collect(),
     ))
 }
 
+pub fn handle_inlay_hints(
+    world: WorldSnapshot,
+    params: req::InlayHintsParams,
+) -> Result<Vec<req::InlayHint>> {
+    let file_id = params.text_document.try_conv_with(&world)?;
+    let analysis = world.analysis();
+    let line_index = analysis.file_line_index(file_id);
+    Ok(analysis
+        .inlay_hints(file_id, world.options.max_inlay_hint_length, world.options.parameter_hints)?
+        .into_iter()
+        .map(|api_type| req::InlayHint {
+            label: api_type.label.to_string(),
+            range: api_type.range.conv_with(&line_index),
+            kind: match api_type.kind {
+                InlayKind::TypeHint => req::InlayKind::TypeHint,
+                InlayKind::ParameterHint => req::InlayKind::ParameterHint,
+            },
+        })
+        .collect())
+}
+
+pub fn handle_call_hierarchy_prepare(
+    world: WorldSnapshot,
+    params: req::TextDocumentPositionParams,
+) -> Result<Option<Vec<req::CallHierarchyItem>>> {
+    let position = params.try_conv_with(&world)?;
+    let nav = match world.analysis().call_hierarchy(position)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+    Ok(Some(vec![to_call_hierarchy_item(&world, &nav)?]))
+}
+
+pub fn handle_call_hierarchy_incoming(
+    world: WorldSnapshot,
+    params: req::CallHierarchyCallsParams,
+) -> Result<Option<Vec<req::CallHierarchyIncomingCall>>> {
+    let position = call_hierarchy_item_position(&world, &params.item)?;
+    let calls = match world.analysis().incoming_calls(position)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+    let mut res = Vec::new();
+    for call in calls {
+        // The calls are made from the caller's file
+        let line_index = world.analysis().file_line_index(call.target.file_id());
+        res.push(req::CallHierarchyIncomingCall {
+            from: to_call_hierarchy_item(&world, &call.target)?,
+            from_ranges: call.ranges.into_iter().map_conv_with(&line_index).collect(),
+        });
+    }
+    Ok(Some(res))
+}
+
+pub fn handle_call_hierarchy_outgoing(
+    world: WorldSnapshot,
+    params: req::CallHierarchyCallsParams,
+) -> Result<Option<Vec<req::CallHierarchyOutgoingCall>>> {
+    let position = call_hierarchy_item_position(&world, &params.item)?;
+    let calls = match world.analysis().outgoing_calls(position)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+    // The calls are made from the file of the requested function
+    let line_index = world.analysis().file_line_index(position.file_id);
+    let mut res = Vec::new();
+    for call in calls {
+        res.push(req::CallHierarchyOutgoingCall {
+            to: to_call_hierarchy_item(&world, &call.target)?,
+            from_ranges: call.ranges.into_iter().map_conv_with(&line_index).collect(),
+        });
+    }
+    Ok(Some(res))
+}
+
+fn to_call_hierarchy_item(
+    world: &WorldSnapshot,
+    nav: &NavigationTarget,
+) -> Result<req::CallHierarchyItem> {
+    let line_index = world.analysis().file_line_index(nav.file_id());
+    Ok(req::CallHierarchyItem {
+        name: nav.name().to_string(),
+        kind: nav.kind().conv(),
+        detail: nav.description().map(|it| it.to_string()),
+        uri: world.file_id_to_uri(nav.file_id())?,
+        range: nav.full_range().conv_with(&line_index),
+        selection_range: nav.range().conv_with(&line_index),
+    })
+}
+
+fn call_hierarchy_item_position(
+    world: &WorldSnapshot,
+    item: &req::CallHierarchyItem,
+) -> Result<FilePosition> {
+    let file_id = world.uri_to_file_id(&item.uri)?;
+    let line_index = world.analysis().file_line_index(file_id);
+    Ok(FilePosition { file_id, offset: item.selection_range.start.conv_with(&line_index) })
+}
+
+pub fn handle_type_hierarchy(
+    world: WorldSnapshot,
+    params: req::TextDocumentPositionParams,
+) -> Result<Option<req::TypeHierarchyItem>> {
+    let position = params.try_conv_with(&world)?;
+    let hierarchy = match world.analysis().type_hierarchy(position)? {
+        None => return Ok(None),
+        Some(it) => it,
+    };
+    let parents = hierarchy
+        .supertypes
+        .iter()
+        .map(|nav| to_type_hierarchy_item(&world, nav))
+        .collect::<Result<Vec<_>>>()?;
+    let children = hierarchy
+        .subtypes
+        .iter()
+        .map(|nav| to_type_hierarchy_item(&world, nav))
+        .collect::<Result<Vec<_>>>()?;
+    let mut item = to_type_hierarchy_item(&world, &hierarchy.item)?;
+    item.parents = Some(parents);
+    item.children = Some(children);
+    Ok(Some(item))
+}
+
+fn to_type_hierarchy_item(
+    world: &WorldSnapshot,
+    nav: &NavigationTarget,
+) -> Result<req::TypeHierarchyItem> {
+    let line_index = world.analysis().file_line_index(nav.file_id());
+    Ok(req::TypeHierarchyItem {
+        name: nav.name().to_string(),
+        kind: nav.kind().conv(),
+        detail: nav.description().map(|it| it.to_string()),
+        uri: world.file_id_to_uri(nav.file_id())?,
+        range: nav.full_range().conv_with(&line_index),
+        selection_range: nav.range().conv_with(&line_index),
+        parents: None,
+        children: None,
+    })
+}
+
 pub fn publish_diagnostics(
     world: &WorldSnapshot,
     file_id: FileId,
@@ -896,7 +1200,7 @@ fn highlight(world: &WorldSnapshot, file_id: FileId) -> Result<Vec<Decoration>>
         .into_iter()
         .map(|h| Decoration {
             range: h.range.conv_with(&line_index),
-            tag: h.tag,
+            tag: h.highlight.tag.conv(),
             binding_hash: h.binding_hash.map(|x| x.to_string()),
         })
         .collect();
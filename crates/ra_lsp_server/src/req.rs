@@ -1,4 +1,4 @@
-use lsp_types::{Location, Position, Range, TextDocumentIdentifier, Url};
+use lsp_types::{Location, Position, Range, SymbolKind, TextDocumentIdentifier, Url};
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
 use url_serde;
@@ -154,6 +154,35 @@ pub struct JoinLinesParams {
     pub range: Range,
 }
 
+pub enum ChangeSignature {}
+
+impl Request for ChangeSignature {
+    type Params = ChangeSignatureParams;
+    type Result = Option<SourceChange>;
+    const METHOD: &'static str = "rust-analyzer/changeSignature";
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeSignatureParams {
+    pub text_document: TextDocumentIdentifier,
+    pub position: Position,
+    /// The new parameters, `self` excluded
+    pub params: Vec<ParamChange>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum ParamChange {
+    /// Index of the parameter in the old signature
+    Existing(usize),
+    Added {
+        name: String,
+        ty: String,
+        value: Option<String>,
+    },
+}
+
 pub enum OnEnter {}
 
 impl Request for OnEnter {
@@ -195,3 +224,109 @@ pub struct SourceChange {
     pub workspace_edit: WorkspaceEdit,
     pub cursor_position: Option<TextDocumentPositionParams>,
 }
+
+pub enum InlayHints {}
+
+impl Request for InlayHints {
+    type Params = InlayHintsParams;
+    type Result = Vec<InlayHint>;
+    const METHOD: &'static str = "rust-analyzer/inlayHints";
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct InlayHintsParams {
+    pub text_document: TextDocumentIdentifier,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InlayKind {
+    TypeHint,
+    ParameterHint,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct InlayHint {
+    pub range: Range,
+    pub kind: InlayKind,
+    pub label: String,
+}
+
+pub enum CallHierarchyPrepare {}
+
+impl Request for CallHierarchyPrepare {
+    type Params = TextDocumentPositionParams;
+    type Result = Option<Vec<CallHierarchyItem>>;
+    const METHOD: &'static str = "textDocument/prepareCallHierarchy";
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CallHierarchyItem {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub detail: Option<String>,
+    #[serde(with = "url_serde")]
+    pub uri: Url,
+    pub range: Range,
+    pub selection_range: Range,
+}
+
+pub enum CallHierarchyIncomingCalls {}
+
+impl Request for CallHierarchyIncomingCalls {
+    type Params = CallHierarchyCallsParams;
+    type Result = Option<Vec<CallHierarchyIncomingCall>>;
+    const METHOD: &'static str = "callHierarchy/incomingCalls";
+}
+
+pub enum CallHierarchyOutgoingCalls {}
+
+impl Request for CallHierarchyOutgoingCalls {
+    type Params = CallHierarchyCallsParams;
+    type Result = Option<Vec<CallHierarchyOutgoingCall>>;
+    const METHOD: &'static str = "callHierarchy/outgoingCalls";
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CallHierarchyCallsParams {
+    pub item: CallHierarchyItem,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CallHierarchyIncomingCall {
+    pub from: CallHierarchyItem,
+    pub from_ranges: Vec<Range>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CallHierarchyOutgoingCall {
+    pub to: CallHierarchyItem,
+    pub from_ranges: Vec<Range>,
+}
+
+pub enum TypeHierarchy {}
+
+impl Request for TypeHierarchy {
+    type Params = TextDocumentPositionParams;
+    type Result = Option<TypeHierarchyItem>;
+    const METHOD: &'static str = "rust-analyzer/typeHierarchy";
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeHierarchyItem {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub detail: Option<String>,
+    #[serde(with = "url_serde")]
+    pub uri: Url,
+    pub range: Range,
+    pub selection_range: Range,
+    /// Supertraits of a trait, or traits implemented by a type
+    pub parents: Option<Vec<TypeHierarchyItem>>,
+    /// Subtraits and implementors of a trait
+    pub children: Option<Vec<TypeHierarchyItem>>,
+}
@@ -7,7 +7,8 @@ use gen_lsp_server::ErrorCode;
 use lsp_types::Url;
 use parking_lot::RwLock;
 use ra_ide_api::{
-    Analysis, AnalysisChange, AnalysisHost, CrateGraph, FileId, LibraryData, SourceRootId,
+    Analysis, AnalysisChange, AnalysisHost, CrateGraph, FileId, ImportGranularity, LibraryData,
+    SourceRootId,
 };
 use ra_vfs::{Vfs, VfsChange, VfsFile, VfsRoot};
 use relative_path::RelativePathBuf;
@@ -19,6 +20,15 @@ use crate::{
     LspError, Result,
 };
 
+/// Options which affect the results of requests, rather than the server
+/// itself.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Options {
+    pub max_inlay_hint_length: Option<usize>,
+    pub parameter_hints: bool,
+    pub import_granularity: ImportGranularity,
+}
+
 /// `WorldState` is the primary mutable state of the language server
 ///
 /// The most interesting components are `vfs`, which stores a consistent
@@ -26,6 +36,7 @@ use crate::{
 /// incremental salsa database.
 #[derive(Debug)]
 pub struct WorldState {
+    pub options: Options,
     pub roots_to_scan: usize,
     pub roots: Vec<PathBuf>,
     pub workspaces: Arc<Vec<ProjectWorkspace>>,
@@ -36,6 +47,7 @@ pub struct WorldState {
 
 /// An immutable snapshot of the world's state at a point in time.
 pub struct WorldSnapshot {
+    pub options: Options,
     pub workspaces: Arc<Vec<ProjectWorkspace>>,
     pub analysis: Analysis,
     pub vfs: Arc<RwLock<Vfs>>,
@@ -47,6 +59,7 @@ impl WorldState {
         folder_roots: Vec<PathBuf>,
         workspaces: Vec<ProjectWorkspace>,
         lru_capacity: Option<usize>,
+        options: Options,
     ) -> WorldState {
         let mut change = AnalysisChange::new();
 
@@ -78,6 +91,7 @@ impl WorldState {
         let mut analysis_host = AnalysisHost::new(lru_capacity);
         analysis_host.apply_change(change);
         WorldState {
+            options,
             roots_to_scan,
             roots: folder_roots,
             workspaces: Arc::new(workspaces),
@@ -140,6 +154,7 @@ impl WorldState {
 
     pub fn snapshot(&self) -> WorldSnapshot {
         WorldSnapshot {
+            options: self.options,
             workspaces: Arc::clone(&self.workspaces),
             analysis: self.analysis_host.analysis(),
             vfs: Arc::clone(&self.vfs),
@@ -89,6 +89,11 @@ impl ast::Attr {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PathSegmentKind<'a> {
     Name(&'a ast::NameRef),
+    /// A qualified segment like `<T>` or `<T as Trait>`.
+    Type {
+        type_ref: Option<&'a ast::TypeRef>,
+        trait_ref: Option<&'a ast::PathType>,
+    },
     SelfKw,
     SuperKw,
     CrateKw,
@@ -110,6 +115,15 @@ impl ast::PathSegment {
                 T![self] => PathSegmentKind::SelfKw,
                 T![super] => PathSegmentKind::SuperKw,
                 T![crate] => PathSegmentKind::CrateKw,
+                T![<] => {
+                    // <T> or <T as Trait>
+                    // T is any TypeRef, Trait has to be a PathType
+                    let mut type_refs = self.syntax().children().filter_map(ast::TypeRef::cast);
+                    let type_ref = type_refs.next();
+                    let trait_ref =
+                        type_refs.next().and_then(|it| ast::PathType::cast(it.syntax()));
+                    PathSegmentKind::Type { type_ref, trait_ref }
+                }
                 _ => return None,
             }
         };
@@ -237,6 +251,16 @@ impl ast::FnDef {
             .and_then(|it| it.as_token())
             .filter(|it| it.kind() == T![;])
     }
+
+    pub fn is_unsafe(&self) -> bool {
+        self.syntax().children_with_tokens().any(|it| it.kind() == T![unsafe])
+    }
+}
+
+impl ast::StaticDef {
+    pub fn is_mut(&self) -> bool {
+        self.syntax().children_with_tokens().any(|it| it.kind() == T![mut])
+    }
 }
 
 impl ast::LetStmt {
@@ -310,6 +334,13 @@ impl ast::ReferenceType {
     pub fn is_mut(&self) -> bool {
         self.syntax().children_with_tokens().any(|n| n.kind() == T![mut])
     }
+
+    pub fn lifetime_token(&self) -> Option<SyntaxToken> {
+        self.syntax()
+            .children_with_tokens()
+            .filter_map(|it| it.as_token())
+            .find(|it| it.kind() == LIFETIME)
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
@@ -331,6 +362,13 @@ impl ast::SelfParam {
             .expect("invalid tree: self param must have self")
     }
 
+    pub fn lifetime_token(&self) -> Option<SyntaxToken> {
+        self.syntax()
+            .children_with_tokens()
+            .filter_map(|it| it.as_token())
+            .find(|it| it.kind() == LIFETIME)
+    }
+
     pub fn kind(&self) -> SelfParamKind {
         let borrowed = self.syntax().children_with_tokens().any(|n| n.kind() == T![&]);
         if borrowed {
@@ -360,6 +398,15 @@ impl ast::LifetimeParam {
     }
 }
 
+impl ast::LifetimeArg {
+    pub fn lifetime_token(&self) -> Option<SyntaxToken> {
+        self.syntax()
+            .children_with_tokens()
+            .filter_map(|it| it.as_token())
+            .find(|it| it.kind() == LIFETIME)
+    }
+}
+
 impl ast::WherePred {
     pub fn lifetime_token(&self) -> Option<SyntaxToken> {
         self.syntax()